@@ -1,21 +1,58 @@
-use color_eyre::Result;
+use color_eyre::{eyre::bail, Result};
 use futures::StreamExt;
+use log::{debug, warn};
 use reqwest::Client;
-use std::{borrow::BorrowMut, collections::HashMap, path::Path};
-use tokio::io::AsyncWriteExt;
+use std::{
+    borrow::BorrowMut,
+    collections::{BTreeMap, HashMap},
+    future::Future,
+    path::Path,
+    time::Duration,
+};
+use tokio::{io::AsyncWriteExt, time::sleep};
 
 use crate::{
-    emotes::DownloadedEmotes,
+    emotes::{DownloadedEmotes, EmoteProvider},
     handlers::config::{CompleteConfig, FrontendConfig},
     twitch::oauth::{get_channel_id, get_twitch_client},
-    utils::pathing::cache_path,
+    utils::{pathing::cache_path, proxy::http_client},
 };
 
 // HashMap of emote name, emote filename, emote url, and if the emote is an overlay
 type EmoteMap = HashMap<String, (String, String, bool)>;
 
+// Same as `EmoteMap`, with the provider of each emote
+type ProviderEmoteMap = HashMap<String, (String, String, bool, EmoteProvider)>;
+
+// The URLs of the sizes that an emote comes in, by scale, such as 2 for 2x
+type SizeUrls = BTreeMap<u8, String>;
+
+/// The size of an emote closest to the preferred scale, going for smaller sizes first,
+/// since they are enough when the image is shrunk to fit one row anyway.
+fn choose_size(urls: &SizeUrls, scale: u8) -> Option<(u8, &str)> {
+    urls.range(..=scale)
+        .next_back()
+        .or_else(|| urls.range(scale..).next())
+        .map(|(&scale, url)| (scale, url.as_str()))
+}
+
+/// The filename and URL of an emote in the size closest to the preferred scale.
+///
+/// Sizes other than 1x are cached under their own name, so that changing sizes downloads them again.
+fn sized_emote(filename: &str, urls: &SizeUrls, scale: u8) -> Option<(String, String)> {
+    let (scale, url) = choose_size(urls, scale)?;
+
+    let filename = if scale == 1 {
+        filename.to_string()
+    } else {
+        format!("{scale}x-{filename}")
+    };
+
+    Some((filename, url.to_string()))
+}
+
 mod twitch {
-    use crate::emotes::downloader::EmoteMap;
+    use crate::emotes::downloader::{sized_emote, EmoteMap, SizeUrls};
     use color_eyre::Result;
     use reqwest::Client;
     use serde::Deserialize;
@@ -23,6 +60,8 @@ mod twitch {
     #[derive(Deserialize)]
     struct Image {
         url_1x: String,
+        url_2x: Option<String>,
+        url_4x: Option<String>,
     }
 
     #[derive(Deserialize)]
@@ -38,7 +77,7 @@ mod twitch {
         data: Vec<Emote>,
     }
 
-    pub async fn get_emotes(client: &Client, channel_id: i32) -> Result<EmoteMap> {
+    pub async fn get_emotes(client: &Client, channel_id: i32, scale: u8) -> Result<EmoteMap> {
         let channel_emotes = client
             .get(format!(
                 "https://api.twitch.tv/helix/chat/emotes?broadcaster_id={channel_id}",
@@ -62,26 +101,51 @@ mod twitch {
         Ok(channel_emotes
             .into_iter()
             .chain(global_emotes)
-            .map(|emote| {
-                let (id, url) = if emote.format.contains(&String::from("animated")) {
-                    (
-                        emote.id + "-animated",
-                        emote.images.url_1x.replace("/static/", "/animated/"),
-                    )
+            .filter_map(|emote| {
+                let animated = emote.format.contains(&String::from("animated"));
+
+                let Image {
+                    url_1x,
+                    url_2x,
+                    url_4x,
+                } = emote.images;
+
+                let urls = [(1, Some(url_1x)), (2, url_2x), (4, url_4x)]
+                    .into_iter()
+                    .filter_map(|(scale, url)| {
+                        let url = url?;
+
+                        Some((
+                            scale,
+                            if animated {
+                                url.replace("/static/", "/animated/")
+                            } else {
+                                url
+                            },
+                        ))
+                    })
+                    .collect::<SizeUrls>();
+
+                let id = if animated {
+                    emote.id + "-animated"
                 } else {
-                    (emote.id, emote.images.url_1x)
+                    emote.id
                 };
 
-                (emote.name, (id, url, false))
+                let (filename, url) = sized_emote(&id, &urls, scale)?;
+
+                Some((emote.name, (filename, url, false)))
             })
             .collect())
     }
 }
 
 mod betterttv {
-    use crate::emotes::downloader::EmoteMap;
+    use crate::{
+        emotes::downloader::{sized_emote, EmoteMap, SizeUrls},
+        utils::proxy::http_client,
+    };
     use color_eyre::Result;
-    use reqwest::Client;
     use serde::Deserialize;
 
     #[derive(Deserialize)]
@@ -99,8 +163,8 @@ mod betterttv {
         shared_emotes: Vec<Emote>,
     }
 
-    pub async fn get_emotes(channel_id: i32) -> Result<EmoteMap> {
-        let client = Client::new();
+    pub async fn get_emotes(channel_id: i32, scale: u8) -> Result<EmoteMap> {
+        let client = http_client().build()?;
 
         let EmoteList {
             channel_emotes,
@@ -127,20 +191,26 @@ mod betterttv {
             .into_iter()
             .chain(shared_emotes)
             .chain(global_emotes)
-            .map(
+            .filter_map(
                 |Emote {
                      code,
                      id,
                      image_type,
                  }| {
-                    (
-                        code,
-                        (
-                            format!("{id}.{image_type}"),
-                            format!("https://cdn.betterttv.net/emote/{id}/1x.{image_type}"),
-                            false,
-                        ),
-                    )
+                    let urls = (1..=3)
+                        .map(|scale| {
+                            (
+                                scale,
+                                format!(
+                                    "https://cdn.betterttv.net/emote/{id}/{scale}x.{image_type}"
+                                ),
+                            )
+                        })
+                        .collect::<SizeUrls>();
+
+                    let (filename, url) = sized_emote(&format!("{id}.{image_type}"), &urls, scale)?;
+
+                    Some((code, (filename, url, false)))
                 },
             )
             .collect())
@@ -148,9 +218,11 @@ mod betterttv {
 }
 
 mod seventv {
-    use crate::emotes::downloader::EmoteMap;
+    use crate::{
+        emotes::downloader::{sized_emote, EmoteMap, SizeUrls},
+        utils::proxy::http_client,
+    };
     use color_eyre::Result;
-    use reqwest::Client;
     use serde::Deserialize;
 
     #[derive(Deserialize)]
@@ -170,8 +242,8 @@ mod seventv {
         emote_set: EmoteList,
     }
 
-    pub async fn get_emotes(channel_id: i32) -> Result<EmoteMap> {
-        let client = Client::new();
+    pub async fn get_emotes(channel_id: i32, scale: u8) -> Result<EmoteMap> {
+        let client = http_client().build()?;
 
         let channel_emotes = client
             .get(format!("https://7tv.io/v3/users/twitch/{channel_id}",))
@@ -195,25 +267,33 @@ mod seventv {
         Ok(channel_emotes
             .into_iter()
             .chain(global_emotes)
-            .map(|Emote { name, id, flags }| {
-                (
-                    name,
-                    (
-                        format!("{id}.webp"),
-                        format!("https://cdn.7tv.app/emote/{id}/1x.webp"),
-                        flags == 1,
-                    ),
-                )
+            .filter_map(|Emote { name, id, flags }| {
+                let urls = (1..=4)
+                    .map(|scale| {
+                        (
+                            scale,
+                            format!("https://cdn.7tv.app/emote/{id}/{scale}x.webp"),
+                        )
+                    })
+                    .collect::<SizeUrls>();
+
+                let (filename, url) = sized_emote(&format!("{id}.webp"), &urls, scale)?;
+
+                Some((name, (filename, url, flags == 1)))
             })
             .collect())
     }
 }
 
 mod frankerfacez {
-    use crate::emotes::downloader::EmoteMap;
+    use std::collections::HashMap;
+
+    use crate::{
+        emotes::downloader::{sized_emote, EmoteMap, SizeUrls},
+        utils::proxy::http_client,
+    };
     use color_eyre::Result;
     use futures::StreamExt;
-    use reqwest::Client;
     use serde::Deserialize;
 
     #[derive(Deserialize)]
@@ -221,6 +301,9 @@ mod frankerfacez {
         name: String,
         id: u64,
         modifier_flags: u64,
+        /// By scale, such as "2" for 2x.
+        #[serde(default)]
+        urls: HashMap<String, String>,
     }
 
     #[derive(Deserialize)]
@@ -248,8 +331,8 @@ mod frankerfacez {
         room: SetId,
     }
 
-    pub async fn get_emotes(channel_id: i32) -> Result<EmoteMap> {
-        let client = &Client::new();
+    pub async fn get_emotes(channel_id: i32, scale: u8) -> Result<EmoteMap> {
+        let client = &http_client().build()?;
 
         let mut sets = client
             .get("https://api.frankerfacez.com/v1/_set/global")
@@ -293,68 +376,179 @@ mod frankerfacez {
         .flatten();
 
         Ok(emotes
-            .map(
+            .filter_map(
                 |Emote {
                      name,
                      id,
                      modifier_flags,
+                     urls,
                  }| {
-                    (
-                        name,
-                        (
-                            format!("ffz_{id}"),
-                            format!("https://cdn.frankerfacez.com/emote/{id}/1"),
-                            modifier_flags != 0,
-                        ),
-                    )
+                    let mut urls = urls
+                        .into_iter()
+                        .filter_map(|(scale, url)| Some((scale.parse().ok()?, url)))
+                        .collect::<SizeUrls>();
+
+                    urls.entry(1)
+                        .or_insert_with(|| format!("https://cdn.frankerfacez.com/emote/{id}/1"));
+
+                    let (filename, url) = sized_emote(&format!("ffz_{id}"), &urls, scale)?;
+
+                    Some((name, (filename, url, modifier_flags != 0)))
                 },
             )
             .collect())
     }
 }
 
-async fn download_emotes(emotes: EmoteMap) -> DownloadedEmotes {
-    let client = &Client::new();
+/// Attempts to download each emote, including the first one.
+const DOWNLOAD_ATTEMPTS: u32 = 3;
+
+/// How long to wait before the first retry, doubling with every following one.
+const RETRY_BACKOFF: Duration = Duration::from_millis(500);
+
+/// Run all futures, with at most `limit` of them running at the same time.
+///
+/// Outputs are in the order that the futures finished.
+async fn run_bounded<F: Future>(
+    futures: impl IntoIterator<Item = F>,
+    limit: usize,
+) -> Vec<F::Output> {
+    futures::stream::iter(futures)
+        .buffer_unordered(limit.max(1))
+        .collect()
+        .await
+}
+
+/// Try an operation until it succeeds, at most `attempts` times, backing off between tries.
+async fn retry<T, F, Fut>(attempts: u32, backoff: Duration, mut operation: F) -> Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    let mut delay = backoff;
+    let mut attempt = 1;
+
+    loop {
+        match operation().await {
+            Ok(value) => return Ok(value),
+            Err(err) if attempt >= attempts => return Err(err),
+            Err(err) => {
+                debug!("Attempt {attempt} of {attempts} failed, retrying in {delay:?}: {err}");
+
+                sleep(delay).await;
+                delay *= 2;
+                attempt += 1;
+            }
+        }
+    }
+}
+
+async fn download_emote(client: &Client, url: &str, path: &Path) -> Result<()> {
+    let mut res = client.get(url).send().await?.error_for_status()?;
+
+    let mut file = tokio::fs::File::create(&path).await?;
+
+    while let Some(mut item) = res.chunk().await? {
+        file.write_all_buf(item.borrow_mut()).await?;
+    }
+
+    Ok(())
+}
+
+/// Same as [`download_emote`], giving up with `false` once the file turns out to be too large.
+async fn download_capped(client: &Client, url: &str, path: &Path, max_bytes: u64) -> Result<bool> {
+    let mut res = client.get(url).send().await?.error_for_status()?;
+
+    if res
+        .content_length()
+        .is_some_and(|length| length > max_bytes)
+    {
+        return Ok(false);
+    }
+
+    let mut file = tokio::fs::File::create(&path).await?;
+    let mut written = 0;
+
+    while let Some(mut item) = res.chunk().await? {
+        written += item.len() as u64;
+
+        // The length header can be missing or wrong, so count what is actually received.
+        if written > max_bytes {
+            drop(file);
+            tokio::fs::remove_file(&path).await?;
+
+            return Ok(false);
+        }
+
+        file.write_all_buf(item.borrow_mut()).await?;
+    }
+
+    Ok(true)
+}
+
+/// Download an image that was linked in chat to the cache, unless it is already there,
+/// returning the path that it was saved to.
+pub async fn download_thumbnail(url: &str, filename: &str, max_bytes: u64) -> Result<String> {
+    let client = http_client().timeout(Duration::from_secs(10)).build()?;
+
+    let path = cache_path(filename);
+
+    if tokio::fs::metadata(&path).await.is_err()
+        && !retry(DOWNLOAD_ATTEMPTS, RETRY_BACKOFF, || {
+            download_capped(&client, url, Path::new(&path), max_bytes)
+        })
+        .await?
+    {
+        bail!("Image is larger than {max_bytes} bytes");
+    }
+
+    Ok(path)
+}
+
+async fn download_emotes(emotes: ProviderEmoteMap, concurrency: usize) -> DownloadedEmotes {
+    let client = match http_client().build() {
+        Ok(client) => client,
+        Err(err) => {
+            warn!("Unable to set up downloading emotes: {err}");
+
+            return DownloadedEmotes::default();
+        }
+    };
+    let client = &client;
 
     // We need to limit the number of concurrent connections, otherwise we might hit some system limits
     // ex: number of files/sockets open, etc.
-    futures::stream::iter(
+    run_bounded(
         emotes
             .into_iter()
-            .map(|(x, (filename, url, o))| async move {
+            .map(|(x, (filename, url, o, p))| async move {
                 let path = cache_path(&filename);
                 let path = Path::new(&path);
 
                 if tokio::fs::metadata(&path).await.is_ok() {
-                    return Ok((x, (filename, o)));
+                    return Some((x, (filename, o, p)));
                 }
 
-                let mut res = client.get(&url).send().await?.error_for_status()?;
-
-                let mut file = tokio::fs::File::create(&path).await?;
+                if let Err(err) = retry(DOWNLOAD_ATTEMPTS, RETRY_BACKOFF, || {
+                    download_emote(client, &url, path)
+                })
+                .await
+                {
+                    warn!("Unable to download emote {x}: {err}");
 
-                while let Some(mut item) = res.chunk().await? {
-                    file.write_all_buf(item.borrow_mut()).await?;
+                    return None;
                 }
 
-                Ok((x, (filename, o)))
+                Some((x, (filename, o, p)))
             }),
+        concurrency,
     )
-    .buffer_unordered(100)
-    .collect::<Vec<Result<(String, (String, bool))>>>()
     .await
     .into_iter()
     .flatten()
     .collect()
 }
 
-enum EmoteProvider {
-    Twitch,
-    BetterTTV,
-    SevenTV,
-    FrankerFaceZ,
-}
-
 fn get_enabled_emote_providers(config: &FrontendConfig) -> Vec<EmoteProvider> {
     let mut providers = Vec::with_capacity(4);
 
@@ -374,7 +568,50 @@ fn get_enabled_emote_providers(config: &FrontendConfig) -> Vec<EmoteProvider> {
     providers
 }
 
-pub async fn get_emotes(config: &CompleteConfig, channel: &str) -> Result<DownloadedEmotes> {
+/// Merge the emotes of each enabled provider into one map.
+///
+/// When several providers have an emote with the same name, the provider that comes first in
+/// the configured precedence wins, so that the result does not depend on which list arrived first.
+/// Providers missing from the precedence come after it, in the order of [`EmoteProvider::ALL`].
+fn merge_emotes(
+    emotes: Vec<(EmoteProvider, EmoteMap)>,
+    config: &FrontendConfig,
+) -> ProviderEmoteMap {
+    let enabled = get_enabled_emote_providers(config);
+
+    let rank = |provider: EmoteProvider| {
+        config
+            .emote_precedence
+            .iter()
+            .chain(EmoteProvider::ALL.iter())
+            .position(|&p| p == provider)
+    };
+
+    let mut emotes = emotes
+        .into_iter()
+        .filter(|(provider, _)| enabled.contains(provider))
+        .collect::<Vec<_>>();
+
+    emotes.sort_by_key(|(provider, _)| rank(*provider));
+
+    let mut merged = ProviderEmoteMap::new();
+
+    for (provider, emotes) in emotes {
+        for (name, (filename, url, o)) in emotes {
+            merged.entry(name).or_insert((filename, url, o, provider));
+        }
+    }
+
+    merged
+}
+
+/// The emotes of a channel from every enabled provider, in the sizes that suit rows of
+/// `cell_height` pixels if it is known.
+async fn get_emote_lists(
+    config: &CompleteConfig,
+    channel: &str,
+    cell_height: Option<f32>,
+) -> Result<ProviderEmoteMap> {
     // Reuse the same client and headers for twitch requests
     let twitch_client = get_twitch_client(config.twitch.token.clone()).await?;
 
@@ -382,25 +619,236 @@ pub async fn get_emotes(config: &CompleteConfig, channel: &str) -> Result<Downlo
 
     let enabled_emotes = get_enabled_emote_providers(&config.frontend);
 
-    let twitch_get_emotes = |c: i32| twitch::get_emotes(&twitch_client, c);
+    let twitch_get_emotes = |c: i32, scale: u8| twitch::get_emotes(&twitch_client, c, scale);
+    let sizes = &config.frontend.emote_sizes;
 
     // Concurrently get the list of emotes for each provider
     let emotes =
         futures::stream::iter(enabled_emotes.into_iter().map(|emote_provider| async move {
+            let scale = sizes.get(emote_provider).scale(cell_height);
+
             match emote_provider {
-                EmoteProvider::Twitch => twitch_get_emotes(channel_id).await,
-                EmoteProvider::BetterTTV => betterttv::get_emotes(channel_id).await,
-                EmoteProvider::SevenTV => seventv::get_emotes(channel_id).await,
-                EmoteProvider::FrankerFaceZ => frankerfacez::get_emotes(channel_id).await,
+                EmoteProvider::Twitch => twitch_get_emotes(channel_id, scale).await,
+                EmoteProvider::BetterTTV => betterttv::get_emotes(channel_id, scale).await,
+                EmoteProvider::SevenTV => seventv::get_emotes(channel_id, scale).await,
+                EmoteProvider::FrankerFaceZ => frankerfacez::get_emotes(channel_id, scale).await,
             }
+            .map(|emotes| (emote_provider, emotes))
         }))
         .buffer_unordered(4)
-        .collect::<Vec<Result<EmoteMap>>>()
+        .collect::<Vec<Result<(EmoteProvider, EmoteMap)>>>()
         .await
         .into_iter()
         .flatten()
-        .flatten()
-        .collect::<EmoteMap>();
+        .collect::<Vec<_>>();
+
+    Ok(merge_emotes(emotes, &config.frontend))
+}
+
+/// Download the emotes of a channel, in the sizes that suit rows of `cell_height` pixels if it is known.
+pub async fn get_emotes(
+    config: &CompleteConfig,
+    channel: &str,
+    cell_height: Option<f32>,
+) -> Result<DownloadedEmotes> {
+    let emotes = get_emote_lists(config, channel, cell_height).await?;
+
+    Ok(download_emotes(emotes, config.frontend.emote_download_concurrency).await)
+}
+
+/// The emotes of a channel without their images, for when those cannot be shown.
+pub async fn get_emote_names(config: &CompleteConfig, channel: &str) -> Result<DownloadedEmotes> {
+    let emotes = get_emote_lists(config, channel, None).await?;
+
+    Ok(emotes
+        .into_iter()
+        .map(|(name, (filename, _, overlay, provider))| (name, (filename, overlay, provider)))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicU32, AtomicUsize, Ordering},
+        Arc,
+    };
+
+    use color_eyre::eyre::anyhow;
+
+    use super::*;
+    use crate::handlers::config::EmoteSize;
+
+    #[tokio::test]
+    async fn test_run_bounded_never_exceeds_limit() {
+        let running = Arc::new(AtomicUsize::new(0));
+        let max_running = Arc::new(AtomicUsize::new(0));
+
+        let tasks = (0..20).map(|i| {
+            let running = running.clone();
+            let max_running = max_running.clone();
+
+            async move {
+                let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                max_running.fetch_max(now, Ordering::SeqCst);
 
-    Ok(download_emotes(emotes).await)
+                sleep(Duration::from_millis(5)).await;
+
+                running.fetch_sub(1, Ordering::SeqCst);
+
+                i
+            }
+        });
+
+        let mut outputs = run_bounded(tasks, 3).await;
+        outputs.sort_unstable();
+
+        assert_eq!(outputs, (0..20).collect::<Vec<i32>>());
+        assert_eq!(max_running.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_retry_until_success() {
+        let calls = AtomicU32::new(0);
+
+        let result = retry(3, Duration::ZERO, || async {
+            if calls.fetch_add(1, Ordering::SeqCst) < 2 {
+                Err(anyhow!("failed"))
+            } else {
+                Ok("done")
+            }
+        })
+        .await;
+
+        assert_eq!(result.unwrap(), "done");
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_retry_gives_up() {
+        let calls = AtomicU32::new(0);
+
+        let result: Result<()> = retry(3, Duration::ZERO, || async {
+            calls.fetch_add(1, Ordering::SeqCst);
+
+            Err(anyhow!("failed"))
+        })
+        .await;
+
+        assert!(result.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    fn provider_emotes(provider: &str, names: &[&str]) -> EmoteMap {
+        names
+            .iter()
+            .map(|name| {
+                (
+                    (*name).to_string(),
+                    (format!("{provider}-{name}"), String::new(), false),
+                )
+            })
+            .collect()
+    }
+
+    fn all_enabled() -> FrontendConfig {
+        FrontendConfig {
+            twitch_emotes: true,
+            betterttv_emotes: true,
+            seventv_emotes: true,
+            frankerfacez_emotes: true,
+            ..FrontendConfig::default()
+        }
+    }
+
+    fn fetched() -> Vec<(EmoteProvider, EmoteMap)> {
+        vec![
+            (
+                EmoteProvider::FrankerFaceZ,
+                provider_emotes("ffz", &["LUL", "OMEGALUL"]),
+            ),
+            (
+                EmoteProvider::SevenTV,
+                provider_emotes("7tv", &["LUL", "catJAM"]),
+            ),
+            (
+                EmoteProvider::BetterTTV,
+                provider_emotes("bttv", &["catJAM", "monkaS"]),
+            ),
+        ]
+    }
+
+    #[test]
+    fn test_merge_emotes_precedence() {
+        let mut config = all_enabled();
+        config.emote_precedence = vec![EmoteProvider::FrankerFaceZ, EmoteProvider::SevenTV];
+
+        let merged = merge_emotes(fetched(), &config);
+
+        assert_eq!(merged.len(), 4);
+        assert_eq!(merged["LUL"].3, EmoteProvider::FrankerFaceZ);
+        assert_eq!(merged["LUL"].0, "ffz-LUL");
+        // Left out of the precedence, so it comes after the listed providers.
+        assert_eq!(merged["catJAM"].3, EmoteProvider::SevenTV);
+        assert_eq!(merged["monkaS"].3, EmoteProvider::BetterTTV);
+
+        config.emote_precedence = vec![EmoteProvider::BetterTTV, EmoteProvider::SevenTV];
+
+        let merged = merge_emotes(fetched(), &config);
+
+        assert_eq!(merged["LUL"].3, EmoteProvider::SevenTV);
+        assert_eq!(merged["catJAM"].3, EmoteProvider::BetterTTV);
+    }
+
+    #[test]
+    fn test_merge_emotes_disabled_provider() {
+        let mut config = all_enabled();
+        config.frankerfacez_emotes = false;
+
+        let merged = merge_emotes(fetched(), &config);
+
+        assert!(!merged.contains_key("OMEGALUL"));
+        assert_eq!(merged["LUL"].3, EmoteProvider::SevenTV);
+        assert!(merged
+            .values()
+            .all(|(_, _, _, provider)| *provider != EmoteProvider::FrankerFaceZ));
+    }
+
+    #[test]
+    fn test_choose_size_with_fallback() {
+        let urls = [(1, "1x"), (2, "2x"), (4, "4x")]
+            .into_iter()
+            .map(|(scale, url)| (scale, url.to_string()))
+            .collect::<SizeUrls>();
+
+        assert_eq!(choose_size(&urls, 2), Some((2, "2x")));
+        // Missing sizes fall back to the closest smaller one.
+        assert_eq!(choose_size(&urls, 3), Some((2, "2x")));
+
+        let large = [(2, "2x"), (3, "3x")]
+            .into_iter()
+            .map(|(scale, url)| (scale, url.to_string()))
+            .collect::<SizeUrls>();
+
+        // Or to the closest bigger one, when there is no smaller one.
+        assert_eq!(choose_size(&large, 1), Some((2, "2x")));
+        assert_eq!(choose_size(&SizeUrls::new(), 1), None);
+
+        assert_eq!(
+            sized_emote("abc.webp", &urls, 1),
+            Some(("abc.webp".to_string(), "1x".to_string()))
+        );
+        assert_eq!(
+            sized_emote("abc.webp", &urls, 4),
+            Some(("4x-abc.webp".to_string(), "4x".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_emote_size_scale() {
+        assert_eq!(EmoteSize::Auto.scale(None), 1);
+        assert_eq!(EmoteSize::Auto.scale(Some(17.)), 1);
+        assert_eq!(EmoteSize::Auto.scale(Some(40.)), 2);
+        assert_eq!(EmoteSize::Auto.scale(Some(500.)), 4);
+        assert_eq!(EmoteSize::Three.scale(Some(17.)), 3);
+    }
 }
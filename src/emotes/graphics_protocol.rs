@@ -94,6 +94,7 @@ pub struct DecodedImage {
 pub struct DecodedEmote {
     id: u32,
     cols: u16,
+    rows: u16,
     images: Vec<DecodedImage>,
 }
 
@@ -105,6 +106,10 @@ impl DecodedEmote {
     pub const fn cols(&self) -> u16 {
         self.cols
     }
+
+    pub const fn rows(&self) -> u16 {
+        self.rows
+    }
 }
 
 impl Command for DecodedEmote {
@@ -178,8 +183,10 @@ pub struct Image {
     id: u32,
     pub width: u32,
     ratio: f32,
-    overlay: bool,
+    /// If frames are resized to the size they are shown at, instead of leaving it to the terminal.
+    resize: bool,
     pub cols: u16,
+    pub rows: u16,
     decoder: Box<dyn IntoFrames>,
 }
 
@@ -241,16 +248,31 @@ impl Image {
             id,
             width,
             ratio,
-            overlay,
+            resize: overlay,
             cols,
+            rows: 1,
             decoder,
         })
     }
 
+    /// An image that is shown over multiple rows, scaled down beforehand since it can be large.
+    pub fn thumbnail(
+        id: u32,
+        name: String,
+        path: &str,
+        (cell_w, cell_h): (f32, f32),
+        rows: u16,
+    ) -> Result<Self> {
+        let mut image = Self::new(id, name, path, true, (cell_w, cell_h * f32::from(rows)))?;
+        image.rows = rows;
+
+        Ok(image)
+    }
+
     pub fn decode(self) -> Result<DecodedEmote> {
         let frames = self.decoder.frames().map(|f| {
             let (image, delay) = f?;
-            let image = if self.overlay {
+            let image = if self.resize {
                 let (w, h) = image.dimensions();
                 image::imageops::resize(
                     &image,
@@ -298,6 +320,7 @@ impl Image {
         Ok(DecodedEmote {
             id: self.id,
             cols: self.cols,
+            rows: self.rows,
             images,
         })
     }
@@ -320,22 +343,34 @@ pub struct Display {
     id: u32,
     pid: u32,
     cols: u16,
+    rows: u16,
 }
 
 impl Display {
     pub const fn new(id: u32, pid: u32, cols: u16) -> Self {
-        Self { id, pid, cols }
+        Self {
+            id,
+            pid,
+            cols,
+            rows: 1,
+        }
+    }
+
+    pub const fn rows(mut self, rows: u16) -> Self {
+        self.rows = rows;
+        self
     }
 }
 
 impl Command for Display {
     fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
-        // r=1: Set height to 1 row
+        // r: Height in rows, c: Width in columns
         write!(
             f,
-            gp!("a=p,U=1,i={id},p={pid},r=1,c={cols},q=2;"),
+            gp!("a=p,U=1,i={id},p={pid},r={rows},c={cols},q=2;"),
             id = self.id,
             pid = self.pid,
+            rows = self.rows,
             cols = self.cols
         )
     }
@@ -473,6 +508,7 @@ mod tests {
         let emote = DecodedEmote {
             id: 1,
             cols: 3,
+            rows: 1,
             images: vec![image],
         };
         emote.write_ansi(&mut s).unwrap();
@@ -516,6 +552,7 @@ mod tests {
         let emote = DecodedEmote {
             id: 1,
             cols: 3,
+            rows: 1,
             images,
         };
 
@@ -557,6 +594,15 @@ mod tests {
         assert_eq!(s, gp!("a=p,U=1,i=1,p=2,r=1,c=3,q=2;"));
     }
 
+    #[test]
+    fn display_image_multiple_rows() {
+        let mut s = String::new();
+
+        Display::new(1, 2, 3).rows(4).write_ansi(&mut s).unwrap();
+
+        assert_eq!(s, gp!("a=p,U=1,i=1,p=2,r=4,c=3,q=2;"));
+    }
+
     #[test]
     fn overlay_image() {
         let mut s = String::new();
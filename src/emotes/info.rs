@@ -0,0 +1,222 @@
+use std::ops::Range;
+
+use crate::{
+    emotes::{DownloadedEmotes, EmoteProvider},
+    handlers::data::MessageData,
+    utils::emotes::PRIVATE_USE_UNICODE,
+};
+
+/// What is known about an emote in a message, to tell what an unfamiliar one is.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EmoteInfo {
+    pub name: String,
+    pub provider: Option<EmoteProvider>,
+    pub id: Option<String>,
+}
+
+impl EmoteInfo {
+    pub fn line(&self) -> String {
+        let mut parts = vec![self.name.clone()];
+
+        if let Some(provider) = self.provider {
+            parts.push(provider.label().to_string());
+        }
+
+        if let Some(id) = &self.id {
+            parts.push(id.clone());
+        }
+
+        parts.join(" · ")
+    }
+}
+
+/// The ID of an emote from the name of the file that it was downloaded to.
+fn emote_id(filename: &str) -> &str {
+    // Files of other sizes than the smallest start with that size.
+    let filename = filename
+        .split_once("x-")
+        .filter(|(scale, _)| scale.parse::<u8>().is_ok())
+        .map_or(filename, |(_, rest)| rest);
+
+    let filename = filename.strip_prefix("ffz_").unwrap_or(filename);
+
+    filename.rsplit_once('.').map_or(filename, |(id, _)| id)
+}
+
+/// The byte ranges of the emotes in a message, with their names and where they are known to
+/// come from. Emotes shown as images are runs of placeholders, named in order by `emote_names`.
+fn emote_spans(message: &MessageData) -> Vec<(Range<usize>, &str, Option<EmoteProvider>)> {
+    let mut spans = message
+        .emote_ranges
+        .iter()
+        .filter_map(|(range, provider)| {
+            let name = message.payload.get(range.clone())?;
+
+            Some((range.clone(), name, Some(*provider)))
+        })
+        .collect::<Vec<_>>();
+
+    let mut names = message.emote_names.iter();
+    let mut run: Option<Range<usize>> = None;
+
+    for (i, c) in message
+        .payload
+        .char_indices()
+        .chain([(message.payload.len(), ' ')])
+    {
+        if c == PRIVATE_USE_UNICODE {
+            let end = i + c.len_utf8();
+
+            run = Some(run.map_or(i..end, |run| run.start..end));
+        } else if let Some(range) = run.take() {
+            if let Some(name) = names.next() {
+                // Overlays are named after the emote that they go over.
+                let name = name.split(' ').next().unwrap_or(name);
+
+                spans.push((range, name, None));
+            }
+        }
+    }
+
+    spans.sort_by_key(|(range, _, _)| range.start);
+
+    spans
+}
+
+/// The emote at a byte position in a message, or else the one nearest to it.
+pub fn emote_info_at(
+    message: &MessageData,
+    emotes: &DownloadedEmotes,
+    position: usize,
+) -> Option<EmoteInfo> {
+    let (_, name, provider) = emote_spans(message)
+        .into_iter()
+        .min_by_key(|(range, _, _)| {
+            // Nothing if the position is in the emote.
+            range
+                .start
+                .saturating_sub(position)
+                .max(position.saturating_sub(range.end.saturating_sub(1)))
+        })?;
+
+    let downloaded = emotes.get(name);
+
+    Some(EmoteInfo {
+        name: name.to_string(),
+        provider: provider.or_else(|| downloaded.map(|(_, _, provider)| *provider)),
+        id: downloaded.map(|(filename, _, _)| emote_id(filename).to_string()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::emotes::ZERO_WIDTH_SPACE;
+
+    fn downloaded() -> DownloadedEmotes {
+        DownloadedEmotes::from([
+            (
+                "catJAM".to_string(),
+                (
+                    "2x-60ae7316f7c927fad14e6ca2.webp".to_string(),
+                    false,
+                    EmoteProvider::SevenTV,
+                ),
+            ),
+            (
+                "Kappa".to_string(),
+                ("25".to_string(), false, EmoteProvider::Twitch),
+            ),
+            (
+                "monkaS".to_string(),
+                ("ffz_130762".to_string(), false, EmoteProvider::FrankerFaceZ),
+            ),
+        ])
+    }
+
+    #[test]
+    fn test_emote_id() {
+        assert_eq!(emote_id("25"), "25");
+        assert_eq!(emote_id("ffz_130762"), "130762");
+        assert_eq!(emote_id("3x-ffz_130762"), "130762");
+        assert_eq!(
+            emote_id("5e76d338d6581c3724c0f0b2.gif"),
+            "5e76d338d6581c3724c0f0b2"
+        );
+        assert_eq!(emote_id("emotesv2_abc"), "emotesv2_abc");
+    }
+
+    #[test]
+    fn test_emote_info_at() {
+        // "Kappa" is shown as text, the two emotes after it as images.
+        let mut message = MessageData::new(
+            "viewer".to_string(),
+            None,
+            false,
+            format!(
+                "Kappa so {PRIVATE_USE_UNICODE}{PRIVATE_USE_UNICODE}{ZERO_WIDTH_SPACE}{PRIVATE_USE_UNICODE}{ZERO_WIDTH_SPACE}good"
+            ),
+            None,
+            false,
+        );
+        message.emote_ranges = vec![(0..5, EmoteProvider::Twitch)];
+        message.emote_names = vec!["catJAM".to_string(), "monkaS SteerR".to_string()];
+
+        let emotes = downloaded();
+        let at = |position| emote_info_at(&message, &emotes, position);
+
+        assert_eq!(
+            at(2),
+            Some(EmoteInfo {
+                name: "Kappa".to_string(),
+                provider: Some(EmoteProvider::Twitch),
+                id: Some("25".to_string()),
+            })
+        );
+
+        let cat_jam = at(9).unwrap();
+        assert_eq!(cat_jam.line(), "catJAM · 7TV · 60ae7316f7c927fad14e6ca2");
+
+        // Past the end is nearest to the last emote, the base of an overlay.
+        assert_eq!(
+            at(message.payload.len()).unwrap().line(),
+            "monkaS · FrankerFaceZ · 130762"
+        );
+        // Between the first two, the nearer one.
+        assert_eq!(at(6).unwrap().name, "Kappa");
+    }
+
+    #[test]
+    fn test_no_emotes() {
+        let message = MessageData::new(
+            "viewer".to_string(),
+            None,
+            false,
+            "just words".to_string(),
+            None,
+            false,
+        );
+
+        assert_eq!(emote_info_at(&message, &downloaded(), 0), None);
+    }
+
+    #[test]
+    fn test_unknown_emote() {
+        let mut message = MessageData::new(
+            "viewer".to_string(),
+            None,
+            false,
+            "LUL".to_string(),
+            None,
+            false,
+        );
+        message.emote_ranges = vec![(0..3, EmoteProvider::Twitch)];
+
+        assert_eq!(
+            emote_info_at(&message, &DownloadedEmotes::new(), 0)
+                .unwrap()
+                .line(),
+            "LUL · Twitch"
+        );
+    }
+}
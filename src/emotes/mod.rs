@@ -1,10 +1,16 @@
-use color_eyre::{eyre::anyhow, Result};
+use color_eyre::{
+    eyre::{anyhow, bail, Error},
+    Result,
+};
 use log::{error, info, warn};
+use serde::Serialize;
+use serde_with::DeserializeFromStr;
 use std::{
     cell::{OnceCell, RefCell},
     collections::{hash_map::DefaultHasher, BTreeMap, HashMap},
     hash::{Hash, Hasher},
     rc::Rc,
+    str::FromStr,
     sync::OnceLock,
 };
 use tokio::sync::{
@@ -13,7 +19,10 @@ use tokio::sync::{
 };
 
 use crate::{
-    emotes::{downloader::get_emotes, graphics_protocol::Image},
+    emotes::{
+        downloader::{get_emote_names, get_emotes},
+        graphics_protocol::Image,
+    },
     handlers::config::CompleteConfig,
     utils::{
         emotes::{emotes_enabled, get_emote_offset},
@@ -23,11 +32,56 @@ use crate::{
 
 mod downloader;
 mod graphics_protocol;
+mod info;
+mod thumbnails;
 
 pub use graphics_protocol::{support_graphics_protocol, ApplyCommand, DecodedEmote};
+pub use info::emote_info_at;
+pub use thumbnails::{Thumbnail, Thumbnails};
+
+// HashMap of emote name, emote filename, if the emote is an overlay, and where it comes from
+pub type DownloadedEmotes = BTreeMap<String, (String, bool, EmoteProvider)>;
+
+#[derive(Serialize, DeserializeFromStr, Debug, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum EmoteProvider {
+    Twitch,
+    BetterTTV,
+    SevenTV,
+    FrankerFaceZ,
+}
+
+impl EmoteProvider {
+    pub const ALL: [Self; 4] = [
+        Self::Twitch,
+        Self::BetterTTV,
+        Self::SevenTV,
+        Self::FrankerFaceZ,
+    ];
+
+    pub const fn label(self) -> &'static str {
+        match self {
+            Self::Twitch => "Twitch",
+            Self::BetterTTV => "BetterTTV",
+            Self::SevenTV => "7TV",
+            Self::FrankerFaceZ => "FrankerFaceZ",
+        }
+    }
+}
+
+impl FromStr for EmoteProvider {
+    type Err = Error;
 
-// HashMap of emote name, emote filename, and if the emote is an overlay
-pub type DownloadedEmotes = BTreeMap<String, (String, bool)>;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "twitch" => Ok(Self::Twitch),
+            "betterttv" => Ok(Self::BetterTTV),
+            "seventv" => Ok(Self::SevenTV),
+            "frankerfacez" => Ok(Self::FrankerFaceZ),
+            _ => bail!("Emote provider '{}' cannot be deserialized", s),
+        }
+    }
+}
 
 #[derive(Copy, Clone, Debug)]
 pub struct EmoteData {
@@ -91,12 +145,16 @@ impl From<LoadedEmote> for EmoteData {
     }
 }
 
-pub fn query_emotes(config: &CompleteConfig, channel: String) -> OSReceiver<DownloadedEmotes> {
+pub fn query_emotes(
+    config: &CompleteConfig,
+    channel: String,
+    cell_height: Option<f32>,
+) -> OSReceiver<DownloadedEmotes> {
     let (tx, mut rx) = tokio::sync::oneshot::channel();
 
     if emotes_enabled(&config.frontend) {
         let config = config.clone();
-        tokio::spawn(async move { send_emotes(&config, tx, channel).await });
+        tokio::spawn(async move { send_emotes(&config, tx, channel, cell_height).await });
     } else {
         rx.close();
     }
@@ -104,9 +162,22 @@ pub fn query_emotes(config: &CompleteConfig, channel: String) -> OSReceiver<Down
     rx
 }
 
-pub async fn send_emotes(config: &CompleteConfig, tx: OSSender<DownloadedEmotes>, channel: String) {
+pub async fn send_emotes(
+    config: &CompleteConfig,
+    tx: OSSender<DownloadedEmotes>,
+    channel: String,
+    cell_height: Option<f32>,
+) {
     info!("Starting emotes download.");
-    match get_emotes(config, &channel).await {
+
+    // Without a known cell size the images cannot be shown, so only the names are needed.
+    let emotes = if cell_height.is_some() {
+        get_emotes(config, &channel, cell_height).await
+    } else {
+        get_emote_names(config, &channel).await
+    };
+
+    match emotes {
         Ok(emotes) => {
             info!("Emotes downloaded.");
             if tx.send(emotes).is_err() {
@@ -230,7 +301,13 @@ pub fn load_picker_emote(
 }
 
 pub fn display_emote(id: u32, pid: u32, cols: u16) -> Result<()> {
-    graphics_protocol::Display::new(id, pid, cols).apply()
+    display_image(id, pid, cols, 1)
+}
+
+pub fn display_image(id: u32, pid: u32, cols: u16, rows: u16) -> Result<()> {
+    graphics_protocol::Display::new(id, pid, cols)
+        .rows(rows)
+        .apply()
 }
 
 pub fn overlay_emote(
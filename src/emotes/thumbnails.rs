@@ -0,0 +1,263 @@
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    hash::{Hash, Hasher},
+    sync::Arc,
+};
+
+use color_eyre::{eyre::anyhow, Result};
+use log::warn;
+use tokio::sync::{
+    mpsc::{self, UnboundedReceiver, UnboundedSender},
+    Semaphore,
+};
+
+use crate::{
+    emotes::{
+        downloader::download_thumbnail, graphics_protocol, ApplyCommand, DECODE_EMOTE_SENDER,
+    },
+    handlers::config::ThumbnailsConfig,
+    utils::{colors::u32_to_color, emotes::PRIVATE_USE_UNICODE},
+};
+
+const IMAGE_EXTENSIONS: [&str; 4] = ["png", "jpg", "jpeg", "gif"];
+
+/// Combining characters that tell the terminal which row of an image a placeholder is in.
+///
+/// These are the first entries of the graphics protocol's row and column diacritics,
+/// which also limits how many rows a thumbnail can have.
+const ROW_DIACRITICS: [char; 10] = [
+    '\u{0305}', '\u{030D}', '\u{030E}', '\u{0310}', '\u{0312}', '\u{033D}', '\u{033E}', '\u{033F}',
+    '\u{0346}', '\u{034A}',
+];
+
+/// If a link points directly at an image, judging by the extension of its path.
+pub fn is_image_url(url: &str) -> bool {
+    let Some(rest) = url
+        .strip_prefix("https://")
+        .or_else(|| url.strip_prefix("http://"))
+    else {
+        return false;
+    };
+
+    let rest = rest.split(['?', '#']).next().unwrap_or_default();
+
+    let Some((host, path)) = rest.split_once('/') else {
+        return false;
+    };
+
+    !host.is_empty()
+        && path.rsplit_once('.').is_some_and(|(name, extension)| {
+            !name.is_empty()
+                && !name.ends_with('/')
+                && IMAGE_EXTENSIONS
+                    .iter()
+                    .any(|e| extension.eq_ignore_ascii_case(e))
+        })
+}
+
+/// The first image link in a message.
+pub fn find_image_url(text: &str) -> Option<&str> {
+    text.split_whitespace().find(|word| is_image_url(word))
+}
+
+/// An image that has been sent to the terminal, to be shown below a message.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Thumbnail {
+    pub id: u32,
+    pub rows: u16,
+    pub cols: u16,
+}
+
+impl Thumbnail {
+    /// There is only ever one placement of a thumbnail, which every message linking to it shares.
+    const PLACEMENT_ID: u32 = 1;
+
+    /// The unicode placeholders of one row of the thumbnail, along with the
+    /// foreground and underline colors that encode the image and its placement.
+    pub fn placeholder_row(self, row: u16) -> (String, (tui::style::Color, tui::style::Color)) {
+        let diacritic = ROW_DIACRITICS[usize::from(row).min(ROW_DIACRITICS.len() - 1)];
+
+        let placeholder = (0..self.cols)
+            .flat_map(|_| [PRIVATE_USE_UNICODE, diacritic])
+            .collect();
+
+        (
+            placeholder,
+            (u32_to_color(self.id), u32_to_color(Self::PLACEMENT_ID)),
+        )
+    }
+}
+
+/// Downloads and loads the images linked in chat.
+pub struct Thumbnails {
+    max_rows: u16,
+    max_cols: u16,
+    max_bytes: u64,
+    /// Shared by every download, so that a flood of links cannot open too many connections.
+    limiter: Arc<Semaphore>,
+    tx: UnboundedSender<(String, Option<String>)>,
+    rx: UnboundedReceiver<(String, Option<String>)>,
+    /// Links that were requested, with their thumbnail once it has been loaded.
+    links: HashMap<String, Option<Thumbnail>>,
+}
+
+impl Thumbnails {
+    pub fn new(config: &ThumbnailsConfig, concurrency: usize) -> Self {
+        let (tx, rx) = mpsc::unbounded_channel();
+
+        Self {
+            max_rows: config.max_rows.clamp(1, ROW_DIACRITICS.len() as u16),
+            max_cols: config.max_cols.max(1),
+            max_bytes: config.max_size.saturating_mul(1000),
+            limiter: Arc::new(Semaphore::new(concurrency.max(1))),
+            tx,
+            rx,
+            links: HashMap::new(),
+        }
+    }
+
+    /// The thumbnail of the first image link in a message, if it has been loaded already.
+    /// Otherwise, it starts being downloaded, if it is not already.
+    pub fn request(&mut self, payload: &str) -> Option<Thumbnail> {
+        let url = find_image_url(payload)?;
+
+        if let Some(thumbnail) = self.links.get(url) {
+            return *thumbnail;
+        }
+
+        self.links.insert(url.to_string(), None);
+
+        let url = url.to_string();
+        let filename = format!("thumbnail-{:x}", hash(&url));
+        let (max_bytes, limiter, tx) = (self.max_bytes, self.limiter.clone(), self.tx.clone());
+
+        tokio::spawn(async move {
+            let Ok(_permit) = limiter.acquire().await else {
+                return;
+            };
+
+            let path = download_thumbnail(&url, &filename, max_bytes)
+                .await
+                .map_err(|e| warn!("Unable to download thumbnail of {url}: {e}"))
+                .ok();
+
+            tx.send((url, path)).unwrap_or_default();
+        });
+
+        None
+    }
+
+    /// Start loading the next downloaded image, returning its link and the thumbnail to show for it.
+    ///
+    /// This has to be called from the main thread, like every other graphics protocol command.
+    pub fn next_loaded(&mut self, cell_size: (f32, f32)) -> Option<(String, Thumbnail)> {
+        while let Ok((url, path)) = self.rx.try_recv() {
+            let Some(path) = path else {
+                continue;
+            };
+
+            match self.load(&url, &path, cell_size) {
+                Ok(thumbnail) => {
+                    self.links.insert(url.clone(), Some(thumbnail));
+
+                    return Some((url, thumbnail));
+                }
+                Err(e) => warn!("Unable to load thumbnail of {url}: {e}"),
+            }
+        }
+
+        None
+    }
+
+    fn load(&self, url: &str, path: &str, cell_size: (f32, f32)) -> Result<Thumbnail> {
+        // IDs are encoded on 3 bytes.
+        let id = hash(url) as u32 & 0x00FF_FFFF;
+
+        let mut image = graphics_protocol::Image::thumbnail(
+            id,
+            url.to_string(),
+            path,
+            cell_size,
+            self.max_rows,
+        )?;
+
+        // The terminal fits the image into its placement, keeping its aspect ratio.
+        image.cols = image.cols.clamp(1, self.max_cols);
+
+        let thumbnail = Thumbnail {
+            id,
+            rows: image.rows,
+            cols: image.cols,
+        };
+
+        // Decoding happens in another thread, which then has the image displayed.
+        DECODE_EMOTE_SENDER
+            .get()
+            .ok_or_else(|| anyhow!("Decoding channel has not been initialized."))?
+            .try_send(image)
+            .map_err(|e| anyhow!("Unable to send thumbnail to decoder thread. {e}"))?;
+
+        Ok(thumbnail)
+    }
+
+    /// Remove all thumbnails from the terminal.
+    pub fn unload(&mut self) {
+        for thumbnail in self.links.drain().filter_map(|(_, t)| t) {
+            graphics_protocol::Clear(thumbnail.id)
+                .apply()
+                .unwrap_or_default();
+        }
+    }
+}
+
+fn hash(url: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    url.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_image_url() {
+        assert!(is_image_url("https://i.imgur.com/abc123.png"));
+        assert!(is_image_url("http://example.com/images/cat.JPG"));
+        assert!(is_image_url("https://example.com/a.jpeg?size=large#top"));
+        assert!(is_image_url("https://media.tenor.com/x/funny.gif"));
+    }
+
+    #[test]
+    fn test_is_not_image_url() {
+        assert!(!is_image_url("https://example.com/page.html"));
+        assert!(!is_image_url("https://example.com/cat.png/view"));
+        assert!(!is_image_url("https://example.com/.png"));
+        assert!(!is_image_url("https://example.com/?file=cat.png"));
+        assert!(!is_image_url("https://cat.png"));
+        assert!(!is_image_url("ftp://example.com/cat.png"));
+        assert!(!is_image_url("cat.png"));
+    }
+
+    #[test]
+    fn test_find_image_url() {
+        assert_eq!(
+            find_image_url("look https://twitch.tv/xithrius https://i.imgur.com/a.gif wow"),
+            Some("https://i.imgur.com/a.gif")
+        );
+        assert_eq!(find_image_url("no images here"), None);
+    }
+
+    #[test]
+    fn test_placeholder_row() {
+        let thumbnail = Thumbnail {
+            id: 1,
+            rows: 2,
+            cols: 3,
+        };
+
+        let (placeholder, _) = thumbnail.placeholder_row(1);
+
+        assert_eq!(placeholder, "\u{10EEEE}\u{030D}".repeat(3));
+    }
+}
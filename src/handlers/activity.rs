@@ -0,0 +1,103 @@
+use std::time::Duration;
+
+use chrono::{DateTime, Local};
+use tui::style::{Modifier, Style};
+
+use crate::handlers::data::MessageData;
+
+/// How many messages a channel got within the last `window`, from its messages newest first.
+pub fn recent_activity<'a>(
+    messages: impl IntoIterator<Item = &'a MessageData>,
+    window: Duration,
+    now: DateTime<Local>,
+) -> usize {
+    messages
+        .into_iter()
+        .filter(|message| !message.system)
+        .take_while(|message| {
+            // Messages from after `now` have no age, and are as recent as can be.
+            now.signed_duration_since(message.time_sent)
+                .to_std()
+                .map_or(true, |age| age <= window)
+        })
+        .count()
+}
+
+/// How lively a channel is, going by its recent messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Activity {
+    Quiet,
+    Usual,
+    Busy,
+}
+
+impl Activity {
+    /// Quiet below `quiet_below` recent messages and busy from `busy_from` of them.
+    pub const fn from_count(count: usize, quiet_below: usize, busy_from: usize) -> Self {
+        if count >= busy_from {
+            Self::Busy
+        } else if count < quiet_below {
+            Self::Quiet
+        } else {
+            Self::Usual
+        }
+    }
+
+    /// The style that a channel of this activity is shown with, dimmed when quiet and bold when busy.
+    pub fn style(self) -> Style {
+        match self {
+            Self::Quiet => Style::default().add_modifier(Modifier::DIM),
+            Self::Usual => Style::default(),
+            Self::Busy => Style::default().add_modifier(Modifier::BOLD),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(now: DateTime<Local>, seconds_ago: i64, system: bool) -> MessageData {
+        let mut message = MessageData::new(
+            "xithrius".to_string(),
+            None,
+            system,
+            "hi".to_string(),
+            None,
+            false,
+        );
+        message.time_sent = now - chrono::Duration::seconds(seconds_ago);
+
+        message
+    }
+
+    #[test]
+    fn test_recent_activity() {
+        let now = Local::now();
+        let messages = [
+            message(now, 1, false),
+            message(now, 5, true),
+            message(now, 20, false),
+            message(now, 90, false),
+            message(now, 100, false),
+        ];
+
+        assert_eq!(recent_activity(&messages, Duration::from_secs(60), now), 2);
+        assert_eq!(recent_activity(&messages, Duration::from_secs(300), now), 4);
+        assert_eq!(recent_activity(&[], Duration::from_secs(60), now), 0);
+    }
+
+    #[test]
+    fn test_activity_thresholds() {
+        assert_eq!(Activity::from_count(0, 2, 20), Activity::Quiet);
+        assert_eq!(Activity::from_count(2, 2, 20), Activity::Usual);
+        assert_eq!(Activity::from_count(19, 2, 20), Activity::Usual);
+        assert_eq!(Activity::from_count(20, 2, 20), Activity::Busy);
+
+        assert_eq!(
+            Activity::Quiet.style(),
+            Style::default().add_modifier(Modifier::DIM)
+        );
+        assert_eq!(Activity::Usual.style(), Style::default());
+    }
+}
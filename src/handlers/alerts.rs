@@ -0,0 +1,325 @@
+use std::{
+    io::{stdout, Write},
+    time::{Duration, Instant},
+};
+
+use log::warn;
+
+use crate::{
+    handlers::{
+        config::{AlertsConfig, DisconnectAlert},
+        data::MessageData,
+    },
+    twitch::ConnectionState,
+};
+
+/// How long the screen stays inverted when flashing.
+pub const FLASH_DURATION: Duration = Duration::from_millis(200);
+
+/// If a message was sent by the user, going by the login of the author since the display name can differ from it.
+pub fn is_own_message(message: &MessageData, login: &str) -> bool {
+    message
+        .login
+        .as_deref()
+        .unwrap_or(&message.author)
+        .eq_ignore_ascii_case(login)
+}
+
+/// If a message should alert the user, either by mentioning them or by containing a keyword.
+///
+/// This is the single trigger that every kind of alert should go through.
+pub fn is_alert(message: &MessageData, login: &str, config: &AlertsConfig) -> bool {
+    if message.system || (!config.own_messages && is_own_message(message, login)) {
+        return false;
+    }
+
+    let payload = message.payload.to_lowercase();
+
+    (!login.is_empty() && payload.contains(&login.to_lowercase()))
+        || config
+            .keywords
+            .iter()
+            .filter(|k| !k.is_empty())
+            .any(|k| payload.contains(&k.to_lowercase()))
+}
+
+/// Only lets an alert through once per cooldown period.
+#[derive(Debug, Clone)]
+pub struct Debounce {
+    cooldown: Duration,
+    last: Option<Instant>,
+}
+
+impl Debounce {
+    pub const fn new(cooldown: Duration) -> Self {
+        Self {
+            cooldown,
+            last: None,
+        }
+    }
+
+    /// If enough time has passed since the last time this returned `true`.
+    pub fn ready(&mut self, now: Instant) -> bool {
+        if self
+            .last
+            .is_some_and(|last| now.saturating_duration_since(last) < self.cooldown)
+        {
+            return false;
+        }
+
+        self.last = Some(now);
+
+        true
+    }
+}
+
+/// Rings the terminal bell when chat mentions the user.
+pub struct SoundAlert {
+    enabled: bool,
+    debounce: Debounce,
+    muted_channels: Vec<String>,
+}
+
+impl SoundAlert {
+    pub fn new(config: &AlertsConfig) -> Self {
+        Self {
+            enabled: config.sound,
+            debounce: Debounce::new(Duration::from_secs(config.cooldown)),
+            muted_channels: config.muted_channels.clone(),
+        }
+    }
+
+    /// If alerts are muted in a channel, whatever the case of its name.
+    fn is_muted(&self, channel: &str) -> bool {
+        self.muted_channels
+            .iter()
+            .any(|muted| muted.eq_ignore_ascii_case(channel))
+    }
+
+    pub fn play(&mut self, channel: &str) {
+        if !self.enabled || self.is_muted(channel) || !self.debounce.ready(Instant::now()) {
+            return;
+        }
+
+        if let Err(err) = ring_bell() {
+            warn!("Unable to ring the terminal bell, disabling sound alerts: {err}");
+
+            self.enabled = false;
+        }
+    }
+}
+
+fn ring_bell() -> std::io::Result<()> {
+    let mut out = stdout();
+
+    out.write_all(b"\x07").and_then(|()| out.flush())
+}
+
+/// If going from one connection state to the other means the connection was lost,
+/// as opposed to it never having been made.
+pub const fn is_unexpected_disconnect(previous: ConnectionState, next: ConnectionState) -> bool {
+    matches!(previous, ConnectionState::Connected)
+        && matches!(
+            next,
+            ConnectionState::Disconnected
+                | ConnectionState::Reconnecting(_)
+                | ConnectionState::Failed
+        )
+}
+
+/// Rings the bell and/or flashes the screen when the connection drops unexpectedly.
+pub struct ConnectionAlert {
+    kind: DisconnectAlert,
+    state: ConnectionState,
+    flash_until: Option<Instant>,
+}
+
+impl ConnectionAlert {
+    pub fn new(kind: DisconnectAlert) -> Self {
+        Self {
+            kind,
+            state: ConnectionState::default(),
+            flash_until: None,
+        }
+    }
+
+    /// Follow the connection to a new state, returning if an alert was fired.
+    pub fn update(&mut self, state: ConnectionState, now: Instant) -> bool {
+        let previous = std::mem::replace(&mut self.state, state);
+
+        if self.kind == DisconnectAlert::None || !is_unexpected_disconnect(previous, state) {
+            return false;
+        }
+
+        if self.kind.bell() {
+            if let Err(err) = ring_bell() {
+                warn!("Unable to ring the terminal bell on disconnect: {err}");
+            }
+        }
+
+        if self.kind.flash() {
+            self.flash_until = Some(now + FLASH_DURATION);
+        }
+
+        true
+    }
+
+    /// If the screen should currently be inverted.
+    pub fn flashing(&self, now: Instant) -> bool {
+        self.flash_until.is_some_and(|until| now < until)
+    }
+
+    /// If a flash was shown and has just run out, so the screen has to be drawn normally again.
+    pub fn flash_ended(&mut self, now: Instant) -> bool {
+        if self.flash_until.is_some_and(|until| now >= until) {
+            self.flash_until = None;
+
+            return true;
+        }
+
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handlers::data::mention_ranges;
+
+    fn message(author: &str, payload: &str) -> MessageData {
+        MessageData::new(
+            author.to_string(),
+            None,
+            false,
+            payload.to_string(),
+            None,
+            false,
+        )
+    }
+
+    #[test]
+    fn test_debounce_gate() {
+        let mut debounce = Debounce::new(Duration::from_secs(5));
+        let start = Instant::now();
+
+        assert!(debounce.ready(start));
+        assert!(!debounce.ready(start + Duration::from_secs(1)));
+        assert!(!debounce.ready(start + Duration::from_millis(4999)));
+        assert!(debounce.ready(start + Duration::from_secs(5)));
+        assert!(!debounce.ready(start + Duration::from_secs(6)));
+    }
+
+    #[test]
+    fn test_debounce_no_cooldown() {
+        let mut debounce = Debounce::new(Duration::ZERO);
+        let start = Instant::now();
+
+        assert!(debounce.ready(start));
+        assert!(debounce.ready(start));
+    }
+
+    #[test]
+    fn test_muted_channels_ignore_case() {
+        let alert = SoundAlert::new(&AlertsConfig {
+            muted_channels: vec!["Xithrius".to_string()],
+            ..AlertsConfig::default()
+        });
+
+        assert!(alert.is_muted("xithrius"));
+        assert!(alert.is_muted("XITHRIUS"));
+        assert!(!alert.is_muted("someone"));
+    }
+
+    #[test]
+    fn test_unexpected_disconnect_transitions() {
+        use ConnectionState::{Authenticating, Connected, Disconnected, Failed, Reconnecting};
+
+        assert!(is_unexpected_disconnect(Connected, Reconnecting(1)));
+        assert!(is_unexpected_disconnect(Connected, Disconnected));
+        assert!(is_unexpected_disconnect(Connected, Failed));
+
+        assert!(!is_unexpected_disconnect(Disconnected, Authenticating));
+        assert!(!is_unexpected_disconnect(Authenticating, Connected));
+        assert!(!is_unexpected_disconnect(Authenticating, Failed));
+        assert!(!is_unexpected_disconnect(Reconnecting(1), Reconnecting(2)));
+        assert!(!is_unexpected_disconnect(Connected, Connected));
+    }
+
+    #[test]
+    fn test_connection_alert_flashes_once_per_disconnect() {
+        let mut alert = ConnectionAlert::new(DisconnectAlert::Flash);
+        let start = Instant::now();
+
+        assert!(!alert.update(ConnectionState::Authenticating, start));
+        assert!(!alert.update(ConnectionState::Connected, start));
+        assert!(!alert.flashing(start));
+
+        assert!(alert.update(ConnectionState::Reconnecting(1), start));
+        assert!(alert.flashing(start));
+        assert!(!alert.update(ConnectionState::Reconnecting(2), start));
+
+        let later = start + FLASH_DURATION;
+        assert!(!alert.flashing(later));
+        assert!(alert.flash_ended(later));
+        assert!(!alert.flash_ended(later));
+    }
+
+    #[test]
+    fn test_connection_alert_disabled() {
+        let mut alert = ConnectionAlert::new(DisconnectAlert::None);
+        let now = Instant::now();
+
+        alert.update(ConnectionState::Connected, now);
+
+        assert!(!alert.update(ConnectionState::Reconnecting(1), now));
+        assert!(!alert.flashing(now));
+    }
+
+    #[test]
+    fn test_is_alert_mention_and_keyword() {
+        let config = AlertsConfig {
+            keywords: vec!["giveaway".to_string()],
+            ..AlertsConfig::default()
+        };
+
+        assert!(is_alert(
+            &message("viewer", "hi @Xithrius"),
+            "xithrius",
+            &config
+        ));
+        assert!(is_alert(
+            &message("viewer", "GIVEAWAY time"),
+            "xithrius",
+            &config
+        ));
+        assert!(!is_alert(
+            &message("viewer", "hello chat"),
+            "xithrius",
+            &config
+        ));
+        assert!(!is_alert(
+            &message("Xithrius", "giveaway"),
+            "xithrius",
+            &config
+        ));
+    }
+
+    #[test]
+    fn test_own_mention_does_not_alert() {
+        // The display name differs from the login, so only the login tells that it is the user.
+        let mut own = message("名無し", "@nanashi and @viewer, look at this");
+        own.login = Some("nanashi".to_string());
+
+        assert!(is_own_message(&own, "nanashi"));
+        assert!(!is_alert(&own, "nanashi", &AlertsConfig::default()));
+
+        let config = AlertsConfig {
+            own_messages: true,
+            ..AlertsConfig::default()
+        };
+        assert!(is_alert(&own, "nanashi", &config));
+
+        // Mentions in it are still styled.
+        assert_eq!(mention_ranges(&own.payload).len(), 2);
+    }
+}
@@ -1,4 +1,9 @@
-use std::{cell::RefCell, collections::VecDeque, rc::Rc};
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet, VecDeque},
+    rc::Rc,
+    time::Instant,
+};
 
 use chrono::{DateTime, Local};
 use rustyline::line_buffer::LineBuffer;
@@ -10,20 +15,26 @@ use tui::{
 use crate::{
     emotes::SharedEmotes,
     handlers::{
-        config::{CompleteConfig, SharedCompleteConfig, Theme},
-        data::MessageData,
+        channel_history::{ChannelHistory, SharedChannelHistory},
+        config::{CompleteConfig, RemovedMessages, SharedCompleteConfig, Theme},
+        data::{MessageData, Removal},
         filters::{Filters, SharedFilters},
         state::State,
-        storage::{SharedStorage, Storage},
+        storage::{Session, SessionWriter, SharedStorage, Storage, SESSION_FILE},
         user_input::events::{Event, Key},
     },
     terminal::TerminalAction,
+    twitch::eventsub::Redemption,
     ui::{
-        components::{Component, Components},
+        components::{utils::ScreenLayout, Component, Components},
         statics::LINE_BUFFER_CAPACITY,
     },
+    utils::styles::HIGHLIGHTED_MESSAGE,
 };
 
+/// How many seconds before or after a redemption the message it highlights can have been sent.
+const REDEMPTION_WINDOW: i64 = 60;
+
 pub type SharedMessages = Rc<RefCell<VecDeque<MessageData>>>;
 
 pub struct App {
@@ -49,6 +60,14 @@ pub struct App {
     pub theme: Theme,
     /// Emotes
     pub emotes: SharedEmotes,
+    /// Channels in the order they were active.
+    pub channel_history: SharedChannelHistory,
+    /// Saves the session as it changes, if sessions are enabled.
+    session: Option<SessionWriter>,
+    /// Ids of users with a recently created account.
+    new_accounts: HashSet<String>,
+    /// Ids of users who redeemed a highlighted message that has not arrived yet, with when they did.
+    pending_highlights: HashMap<String, DateTime<Local>>,
 }
 
 macro_rules! shared {
@@ -65,9 +84,11 @@ impl App {
 
         let storage = shared!(Storage::new("storage.json", &shared_config_borrow.storage));
 
-        if !storage
-            .borrow()
-            .contains("channels", &config.twitch.channel)
+        // Without a channel, one is only stored once it has been picked and joined.
+        if !config.twitch.channel.is_empty()
+            && !storage
+                .borrow()
+                .contains("channels", &config.twitch.channel)
         {
             storage.borrow_mut().add("channels", config.twitch.channel);
         }
@@ -80,15 +101,44 @@ impl App {
 
         let emotes = SharedEmotes::default();
 
-        let components = Components::new(
+        let session = shared_config_borrow
+            .storage
+            .session
+            .then(|| Session::load(SESSION_FILE));
+
+        let channel_history = shared!(ChannelHistory::default());
+
+        if let Some(session) = &session {
+            for channel in &session.channels {
+                channel_history.borrow_mut().switch_to(channel);
+            }
+        }
+
+        // When starting on the dashboard, a channel is only active once it has been joined.
+        if shared_config_borrow.terminal.first_state != State::Dashboard
+            && !shared_config_borrow.twitch.channel.is_empty()
+        {
+            channel_history
+                .borrow_mut()
+                .switch_to(&shared_config_borrow.twitch.channel);
+        }
+
+        let mut components = Components::new(
             &shared_config,
             storage.clone(),
             filters.clone(),
             messages.clone(),
             &emotes,
+            channel_history.clone(),
             startup_time,
         );
 
+        if let Some(session) = &session {
+            components.chat.set_input_draft(&session.input_draft);
+        }
+
+        let session = session.map(|session| SessionWriter::new(SESSION_FILE, session));
+
         Self {
             components,
             config: shared_config.clone(),
@@ -101,21 +151,34 @@ impl App {
             buffer_suggestion: None,
             theme: shared_config_borrow.frontend.theme.clone(),
             emotes,
+            channel_history,
+            session,
+            new_accounts: HashSet::new(),
+            pending_highlights: HashMap::new(),
         }
     }
 
     pub fn draw(&mut self, f: &mut Frame) {
-        let mut size = f.size();
-
-        if self.config.borrow().frontend.state_tabs {
-            let layout = Layout::default()
-                .direction(Direction::Vertical)
-                .constraints([Constraint::Length(size.height - 1), Constraint::Length(1)])
-                .split(f.size());
+        let layout = {
+            let frontend = &self.config.borrow().frontend;
+
+            if self.state == State::Normal && self.components.chat.focused() {
+                ScreenLayout::focused(f.size(), frontend.input_position, None)
+            } else {
+                ScreenLayout::new(
+                    f.size(),
+                    frontend.input_position,
+                    frontend.margin,
+                    None,
+                    frontend.state_tabs,
+                )
+            }
+        };
 
-            size = layout[0];
+        let size = layout.content;
 
-            self.components.tabs.draw(f, Some(layout[1]), &self.state);
+        if let Some(tabs) = layout.tabs {
+            self.components.tabs.draw(f, Some(tabs), &self.state);
         }
 
         if (size.height < 10 || size.width < 60)
@@ -125,7 +188,7 @@ impl App {
         } else {
             match self.state {
                 State::Dashboard => self.components.dashboard.draw(f, None),
-                State::Normal => self.components.chat.draw(f, None),
+                State::Normal => self.components.chat.draw(f, Some(size)),
                 State::Help => self.components.help.draw(f, None),
             }
         }
@@ -140,35 +203,105 @@ impl App {
 
             self.components.debug.draw(f, Some(rect));
         }
+
+        if self.components.quit_prompt.is_focused() {
+            self.components.quit_prompt.draw(f, None);
+        }
+    }
+
+    /// If a popup or a window is open over chat.
+    pub const fn modal_open(&self) -> bool {
+        matches!(self.state, State::Help)
+            || self.components.debug.is_focused()
+            || self.components.quit_prompt.is_focused()
+            || self.components.chat.modal_open()
+    }
+
+    /// Hold back new messages in chat while something is open over it, if enabled.
+    pub fn update_scroll_lock(&mut self) {
+        let open = self.config.borrow().frontend.popup_scroll_lock && self.modal_open();
+
+        self.components.chat.set_modal(open);
     }
 
     pub async fn event(&mut self, event: &Event) -> Option<TerminalAction> {
         if let Event::Input(key) = event {
-            if self.components.debug.is_focused() {
-                return self.components.debug.event(event).await;
+            if self.components.quit_prompt.is_focused() {
+                return self.components.quit_prompt.event(event).await;
             }
 
-            match key {
-                // Global keybinds
-                Key::Ctrl('d') => {
-                    self.components.debug.toggle_focus();
-                }
-                _ => {
-                    return match self.state {
+            let action = if self.components.debug.is_focused() {
+                self.components.debug.event(event).await
+            } else {
+                match key {
+                    // Global keybinds
+                    Key::Ctrl('d') => {
+                        self.components.debug.toggle_focus();
+
+                        None
+                    }
+                    _ => match self.state {
                         State::Dashboard => self.components.dashboard.event(event).await,
                         State::Normal => self.components.chat.event(event).await,
                         State::Help => self.components.help.event(event).await,
-                    };
+                    },
                 }
+            };
+
+            // The prompt sends the quit action itself once it is answered.
+            if matches!(action, Some(TerminalAction::Quit))
+                && !self.components.quit_prompt.request()
+            {
+                return None;
             }
+
+            return action;
         }
 
         None
     }
 
-    pub fn cleanup(&self) {
+    pub fn cleanup(&mut self) {
         self.storage.borrow().dump_data();
+        self.components.chat.save_input_history();
+
+        if let Some(session) = self.current_session() {
+            if let Some(writer) = &mut self.session {
+                writer.flush(session);
+            }
+        }
+
         self.emotes.unload();
+        log::logger().flush();
+    }
+
+    /// Save the current session if it changed, at most every few seconds so that typing
+    /// does not write it on every key.
+    pub fn save_session(&mut self, now: Instant) {
+        if let Some(session) = self.current_session() {
+            if let Some(writer) = &mut self.session {
+                writer.update(session, now);
+            }
+        }
+    }
+
+    /// The session as it is now, if sessions are enabled.
+    fn current_session(&self) -> Option<Session> {
+        self.session.as_ref()?;
+
+        let chat = &self.components.chat;
+
+        // What is typed is not written anywhere while the history is ephemeral.
+        let input_draft = if chat.is_history_ephemeral() {
+            String::new()
+        } else {
+            chat.input_draft()
+        };
+
+        Some(Session {
+            channels: self.channel_history.borrow().channels().to_vec(),
+            input_draft,
+        })
     }
 
     pub fn clear_messages(&mut self) {
@@ -178,26 +311,100 @@ impl App {
     }
 
     pub fn purge_user_messages(&mut self, user_id: &str) {
-        let messages = self
-            .messages
-            .borrow_mut()
-            .iter()
-            .filter(|&m| m.user_id.clone().map_or(true, |user| user != user_id))
-            .cloned()
-            .collect::<VecDeque<MessageData>>();
+        let mode = self.config.borrow().frontend.removed_messages;
 
-        self.messages.replace(messages);
+        remove_messages(
+            &mut self.messages.borrow_mut(),
+            Removal::TimedOut,
+            mode,
+            |m| m.user_id.as_deref() == Some(user_id),
+        );
     }
 
     pub fn remove_message_with(&mut self, message_id: &str) {
-        let index = self
-            .messages
+        let mode = self.config.borrow().frontend.removed_messages;
+
+        remove_messages(
+            &mut self.messages.borrow_mut(),
+            Removal::Deleted,
+            mode,
+            |m| m.message_id.as_deref() == Some(message_id),
+        );
+    }
+
+    /// Take a message out of chat, even if removed messages are kept.
+    pub fn forget_message_with(&self, message_id: &str) {
+        self.messages
             .borrow_mut()
-            .iter()
-            .position(|f| f.message_id.clone().map_or(false, |id| id == message_id));
+            .retain(|m| m.message_id.as_deref() != Some(message_id));
+    }
 
-        if let Some(i) = index {
-            self.messages.borrow_mut().remove(i).unwrap();
+    /// Take the messages that were sent before `cutoff` out of chat, returning them oldest first.
+    pub fn age_out_messages(&mut self, cutoff: DateTime<Local>) -> Vec<MessageData> {
+        let mut messages = self.messages.borrow_mut();
+
+        let aged_out = age_out(&mut messages, cutoff);
+
+        if !aged_out.is_empty() {
+            self.components.chat.scroll_offset.clamp_to(messages.len());
+        }
+
+        aged_out
+    }
+
+    /// Mark all past and future messages of a user as coming from a recently created account.
+    pub fn mark_new_account(&mut self, user_id: String) {
+        for message in self.messages.borrow_mut().iter_mut() {
+            if message.user_id.as_ref() == Some(&user_id) {
+                message.new_account = true;
+                message.invalidate_lines();
+            }
+        }
+
+        self.new_accounts.insert(user_id);
+    }
+
+    pub fn is_new_account(&self, user_id: &str) -> bool {
+        self.new_accounts.contains(user_id)
+    }
+
+    /// Highlight the message that a highlight redemption was for, or the next message
+    /// of the user if it has not arrived yet.
+    pub fn highlight_redeemed_message(&mut self, redemption: &Redemption) {
+        if !redemption.highlight {
+            return;
+        }
+
+        let mut messages = self.messages.borrow_mut();
+
+        if let Some(i) = find_redeemed_message(
+            &messages,
+            &redemption.user_id,
+            redemption.input.as_deref(),
+            Local::now(),
+        ) {
+            highlight_message(&mut messages[i]);
+        } else {
+            self.pending_highlights
+                .insert(redemption.user_id.clone(), Local::now());
+        }
+    }
+
+    /// Highlight a new message if its author redeemed a highlight for it.
+    pub fn apply_pending_highlight(&mut self, message: &mut MessageData) {
+        if message
+            .user_id
+            .as_ref()
+            .and_then(|id| self.pending_highlights.remove(id))
+            .is_some_and(|redeemed_at| {
+                message
+                    .time_sent
+                    .signed_duration_since(redeemed_at)
+                    .num_seconds()
+                    <= REDEMPTION_WINDOW
+            })
+        {
+            highlight_message(message);
         }
     }
 
@@ -220,3 +427,217 @@ impl App {
         todo!("Rotate through different themes")
     }
 }
+
+/// Take the matching messages out of chat, or mark them as removed while keeping them.
+fn remove_messages(
+    messages: &mut VecDeque<MessageData>,
+    removal: Removal,
+    mode: RemovedMessages,
+    matches: impl Fn(&MessageData) -> bool,
+) {
+    match mode {
+        RemovedMessages::Remove => messages.retain(|m| !matches(m)),
+        RemovedMessages::Mark => {
+            for message in messages
+                .iter_mut()
+                .filter(|m| m.removed.is_none() && matches(m))
+            {
+                message.removed = Some(removal);
+                message.invalidate_lines();
+            }
+        }
+    }
+}
+
+/// Split the messages sent before `cutoff` off of the rest, which keep their order.
+/// The split off messages are returned oldest first.
+fn age_out(messages: &mut VecDeque<MessageData>, cutoff: DateTime<Local>) -> Vec<MessageData> {
+    let (kept, aged_out): (VecDeque<_>, VecDeque<_>) = messages
+        .drain(..)
+        .partition(|message| message.time_sent >= cutoff);
+
+    *messages = kept;
+
+    aged_out.into_iter().rev().collect()
+}
+
+fn highlight_message(message: &mut MessageData) {
+    message.announcement = Some(HIGHLIGHTED_MESSAGE);
+    message.invalidate_lines();
+}
+
+/// The newest recent message of a user, preferring one with the given text.
+fn find_redeemed_message(
+    messages: &VecDeque<MessageData>,
+    user_id: &str,
+    text: Option<&str>,
+    redeemed_at: DateTime<Local>,
+) -> Option<usize> {
+    let candidates = messages
+        .iter()
+        .enumerate()
+        .take_while(|(_, m)| {
+            redeemed_at.signed_duration_since(m.time_sent).num_seconds() <= REDEMPTION_WINDOW
+        })
+        .filter(|(_, m)| !m.system && m.user_id.as_deref() == Some(user_id))
+        .collect::<Vec<_>>();
+
+    candidates
+        .iter()
+        .find(|(_, m)| text.is_some_and(|t| m.payload == t))
+        .or_else(|| candidates.first())
+        .map(|(i, _)| *i)
+}
+
+#[cfg(test)]
+mod tests {
+    use tui::style::Modifier;
+
+    use chrono::Duration;
+
+    use super::*;
+    use crate::handlers::config::FrontendConfig;
+
+    fn messages() -> VecDeque<MessageData> {
+        [
+            ("1", "a", "hello"),
+            ("2", "b", "rude message"),
+            ("2", "c", "another one"),
+        ]
+        .into_iter()
+        .map(|(user_id, message_id, payload)| {
+            MessageData::new(
+                format!("user{user_id}"),
+                Some(user_id.to_string()),
+                false,
+                payload.to_string(),
+                Some(message_id.to_string()),
+                false,
+            )
+        })
+        .collect()
+    }
+
+    #[test]
+    fn test_age_out() {
+        let now = Local::now();
+        let mut messages = messages();
+
+        for (message, age) in messages.iter_mut().zip([10, 120, 600]) {
+            message.time_sent = now - Duration::seconds(age);
+        }
+
+        let aged_out = age_out(&mut messages, now - Duration::seconds(60));
+
+        assert_eq!(
+            aged_out
+                .iter()
+                .map(|m| m.payload.as_str())
+                .collect::<Vec<_>>(),
+            vec!["another one", "rude message"]
+        );
+        assert_eq!(messages.len(), 1);
+
+        // Messages are still found by their ID once the ones around them are gone.
+        remove_messages(
+            &mut messages,
+            Removal::Deleted,
+            RemovedMessages::Mark,
+            |m| m.message_id.as_deref() == Some("a"),
+        );
+
+        assert_eq!(messages[0].removed, Some(Removal::Deleted));
+        assert!(age_out(&mut messages, now - Duration::seconds(60)).is_empty());
+    }
+
+    #[test]
+    fn test_remove_timed_out_messages() {
+        let mut messages = messages();
+
+        remove_messages(
+            &mut messages,
+            Removal::TimedOut,
+            RemovedMessages::Remove,
+            |m| m.user_id.as_deref() == Some("2"),
+        );
+
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].payload, "hello");
+    }
+
+    #[test]
+    fn test_mark_timed_out_messages() {
+        let mut messages = messages();
+
+        remove_messages(
+            &mut messages,
+            Removal::TimedOut,
+            RemovedMessages::Mark,
+            |m| m.user_id.as_deref() == Some("2"),
+        );
+
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[0].removed, None);
+
+        for message in messages.iter().skip(1) {
+            assert_eq!(message.removed, Some(Removal::TimedOut));
+
+            let lines = message.to_vec(&FrontendConfig::default(), 80, None, None, false);
+            let spans = &lines[0].spans;
+
+            assert!(spans
+                .iter()
+                .any(|s| s.content == format!("{} ", Removal::TimedOut.marker())));
+
+            let body = spans.last().unwrap();
+            assert!(message.payload.ends_with(body.content.as_ref()));
+            assert!(body.style.add_modifier.contains(Modifier::DIM));
+        }
+    }
+
+    #[test]
+    fn test_mark_keeps_first_removal() {
+        let mut messages = messages();
+
+        remove_messages(
+            &mut messages,
+            Removal::Deleted,
+            RemovedMessages::Mark,
+            |m| m.message_id.as_deref() == Some("b"),
+        );
+        remove_messages(
+            &mut messages,
+            Removal::TimedOut,
+            RemovedMessages::Mark,
+            |m| m.user_id.as_deref() == Some("2"),
+        );
+
+        assert_eq!(messages[1].removed, Some(Removal::Deleted));
+        assert_eq!(messages[2].removed, Some(Removal::TimedOut));
+    }
+
+    #[test]
+    fn test_find_redeemed_message_by_user() {
+        let messages = messages();
+        let now = Local::now();
+
+        assert_eq!(find_redeemed_message(&messages, "2", None, now), Some(1));
+        assert_eq!(
+            find_redeemed_message(&messages, "2", Some("another one"), now),
+            Some(2)
+        );
+        assert_eq!(
+            find_redeemed_message(&messages, "2", Some("not sent"), now),
+            Some(1)
+        );
+        assert_eq!(find_redeemed_message(&messages, "3", None, now), None);
+    }
+
+    #[test]
+    fn test_find_redeemed_message_too_old() {
+        let messages = messages();
+        let later = Local::now() + Duration::seconds(REDEMPTION_WINDOW + 1);
+
+        assert_eq!(find_redeemed_message(&messages, "1", None, later), None);
+    }
+}
@@ -85,6 +85,15 @@ pub struct Cli {
     /// Show a warning if the screen size is too small
     #[arg(short, long)]
     pub unsupported_screen_size: bool,
+    /// Do not restore the previous session
+    #[arg(long)]
+    pub no_restore: bool,
+    /// Read chat anonymously, without logging in, where nothing can be sent
+    #[arg(long)]
+    pub anonymous: bool,
+    /// Replay raw IRC lines from a file instead of connecting to Twitch
+    #[arg(long)]
+    pub irc_capture: Option<String>,
 }
 
 pub fn merge_args_into_config(config: &mut CompleteConfig, args: Cli) {
@@ -105,11 +114,17 @@ pub fn merge_args_into_config(config: &mut CompleteConfig, args: Cli) {
         config.terminal.first_state = first_state;
     }
 
+    if args.no_restore {
+        config.storage.session = false;
+    }
+
     // Twitch arguments
     if let Some(channel) = args.channel {
         config.twitch.channel = channel;
     }
 
+    config.twitch.anonymous = config.twitch.anonymous || args.anonymous;
+
     // Frontend arguments
     config.frontend.show_datetimes = config.frontend.show_datetimes || args.show_datetimes;
 
@@ -0,0 +1,168 @@
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Local};
+
+use crate::handlers::{alerts::is_alert, config::AlertsConfig, data::MessageData};
+
+/// The ID of the line that sums up what was missed, so that there is only ever one of it.
+pub const CATCH_UP_ID: &str = "catch-up";
+
+/// What was said in chat while the user was away.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CatchUp {
+    pub messages: usize,
+    /// Messages that would have alerted the user.
+    pub mentions: usize,
+}
+
+impl CatchUp {
+    /// Count the messages sent after `since`, in a buffer that goes from newest to oldest.
+    pub fn count<'a>(
+        messages: impl IntoIterator<Item = &'a MessageData>,
+        since: DateTime<Local>,
+        login: &str,
+        alerts: &AlertsConfig,
+    ) -> Self {
+        messages
+            .into_iter()
+            .take_while(|message| message.time_sent > since)
+            .filter(|message| !message.system)
+            .fold(Self::default(), |catch_up, message| Self {
+                messages: catch_up.messages + 1,
+                mentions: catch_up.mentions + usize::from(is_alert(message, login, alerts)),
+            })
+    }
+
+    pub fn line(self) -> String {
+        let plural = |count: usize, word: &str| {
+            format!("{count} {word}{}", if count == 1 { "" } else { "s" })
+        };
+
+        let mut line = format!("{} while you were away", plural(self.messages, "message"));
+
+        if self.mentions > 0 {
+            line = format!("{line}, {}", plural(self.mentions, "mention"));
+        }
+
+        line
+    }
+}
+
+/// Tells when the user comes back after not pressing any key for a while.
+#[derive(Debug, Clone)]
+pub struct AwayTracker {
+    /// How long without input counts as being away, where there is no limit if zero.
+    idle: Duration,
+    last_input: Instant,
+    /// When the last input was, to compare against when messages were sent.
+    last_input_time: DateTime<Local>,
+}
+
+impl AwayTracker {
+    pub fn new(idle: Duration) -> Self {
+        Self {
+            idle,
+            last_input: Instant::now(),
+            last_input_time: Local::now(),
+        }
+    }
+
+    /// Note some input at `now`, returning when the user went away if they were.
+    pub fn input(&mut self, now: Instant, time: DateTime<Local>) -> Option<DateTime<Local>> {
+        let away =
+            !self.idle.is_zero() && now.saturating_duration_since(self.last_input) >= self.idle;
+        let since = self.last_input_time;
+
+        self.last_input = now;
+        self.last_input_time = time;
+
+        away.then_some(since)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(payload: &str, time_sent: DateTime<Local>, system: bool) -> MessageData {
+        let mut message = MessageData::new(
+            "viewer".to_string(),
+            None,
+            system,
+            payload.to_string(),
+            None,
+            false,
+        );
+        message.time_sent = time_sent;
+
+        message
+    }
+
+    #[test]
+    fn test_count_since_marker() {
+        let since = Local::now();
+        let after = |seconds| since + chrono::Duration::seconds(seconds);
+
+        // From newest to oldest, as chat keeps them.
+        let buffer = [
+            message("hey @nanashi", after(30), false),
+            message("giveaway soon", after(20), false),
+            message("Connected", after(15), true),
+            message("lol", after(10), false),
+            message("before leaving", since, false),
+            message(
+                "nanashi said this",
+                since - chrono::Duration::seconds(5),
+                false,
+            ),
+        ];
+
+        let alerts = AlertsConfig {
+            keywords: vec!["giveaway".to_string()],
+            ..AlertsConfig::default()
+        };
+
+        let catch_up = CatchUp::count(&buffer, since, "nanashi", &alerts);
+
+        assert_eq!(
+            catch_up,
+            CatchUp {
+                messages: 3,
+                mentions: 2,
+            }
+        );
+        assert_eq!(
+            catch_up.line(),
+            "3 messages while you were away, 2 mentions"
+        );
+
+        assert_eq!(
+            CatchUp::count(&buffer[..2], after(25), "nanashi", &AlertsConfig::default()).line(),
+            "1 message while you were away, 1 mention"
+        );
+        assert_eq!(
+            CatchUp::count(&buffer[3..], after(10), "nanashi", &alerts),
+            CatchUp::default()
+        );
+    }
+
+    #[test]
+    fn test_away_after_idle() {
+        let mut away = AwayTracker::new(Duration::from_secs(60));
+        let start = Instant::now();
+        let left = Local::now();
+
+        assert_eq!(away.input(start, left), None);
+        assert_eq!(away.input(start + Duration::from_secs(59), left), None);
+
+        let back = left + chrono::Duration::seconds(180);
+        assert_eq!(
+            away.input(start + Duration::from_secs(180), back),
+            Some(left)
+        );
+        assert_eq!(away.input(start + Duration::from_secs(181), back), None);
+
+        let mut never = AwayTracker::new(Duration::ZERO);
+        assert_eq!(never.input(start + Duration::from_secs(3600), back), None);
+    }
+}
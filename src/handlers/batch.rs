@@ -0,0 +1,94 @@
+use std::{
+    mem,
+    time::{Duration, Instant},
+};
+
+/// Collects items arriving in quick succession, so that they can be applied all at once.
+///
+/// A batch is ready once the window has passed since its first item arrived.
+#[derive(Debug, Clone)]
+pub struct Batch<T> {
+    window: Duration,
+    items: Vec<T>,
+    started: Option<Instant>,
+}
+
+impl<T> Batch<T> {
+    pub const fn new(window: Duration) -> Self {
+        Self {
+            window,
+            items: vec![],
+            started: None,
+        }
+    }
+
+    pub fn push(&mut self, item: T, now: Instant) {
+        self.started.get_or_insert(now);
+        self.items.push(item);
+    }
+
+    /// All items in the order they arrived, if the batch is ready.
+    pub fn take_ready(&mut self, now: Instant) -> Option<Vec<T>> {
+        let started = self.started?;
+
+        if now.saturating_duration_since(started) < self.window {
+            return None;
+        }
+
+        self.started = None;
+
+        Some(mem::take(&mut self.items))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_items_within_window_are_one_batch() {
+        let start = Instant::now();
+        let mut batch = Batch::new(Duration::from_millis(50));
+
+        for i in 0..10 {
+            batch.push(i, start + Duration::from_millis(i * 4));
+        }
+
+        assert_eq!(batch.take_ready(start + Duration::from_millis(49)), None);
+        assert_eq!(
+            batch.take_ready(start + Duration::from_millis(50)),
+            Some((0..10).collect())
+        );
+        assert_eq!(batch.take_ready(start + Duration::from_millis(100)), None);
+    }
+
+    #[test]
+    fn test_next_batch_starts_with_next_item() {
+        let start = Instant::now();
+        let mut batch = Batch::new(Duration::from_millis(50));
+
+        batch.push("first", start);
+        assert_eq!(
+            batch.take_ready(start + Duration::from_millis(60)),
+            Some(vec!["first"])
+        );
+
+        batch.push("second", start + Duration::from_millis(70));
+        assert_eq!(batch.take_ready(start + Duration::from_millis(100)), None);
+        assert_eq!(
+            batch.take_ready(start + Duration::from_millis(120)),
+            Some(vec!["second"])
+        );
+    }
+
+    #[test]
+    fn test_no_window_is_always_ready() {
+        let start = Instant::now();
+        let mut batch = Batch::new(Duration::ZERO);
+
+        batch.push(1, start);
+        batch.push(2, start);
+
+        assert_eq!(batch.take_ready(start), Some(vec![1, 2]));
+    }
+}
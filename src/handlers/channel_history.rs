@@ -0,0 +1,130 @@
+use std::{cell::RefCell, rc::Rc};
+
+pub type SharedChannelHistory = Rc<RefCell<ChannelHistory>>;
+
+/// How many channels are remembered, where the least recently active ones are forgotten first.
+const MAX_CHANNELS: usize = 20;
+
+/// Channels in the order they were last active, to quickly go back to the previous one.
+#[derive(Debug, Clone, Default)]
+pub struct ChannelHistory {
+    /// From least to most recently active, where the last one is the current channel.
+    channels: Vec<String>,
+}
+
+impl ChannelHistory {
+    /// Mark a channel as the active one.
+    pub fn switch_to(&mut self, channel: &str) {
+        self.channels.retain(|c| c != channel);
+        self.channels.push(channel.to_string());
+
+        let overflow = self.channels.len().saturating_sub(MAX_CHANNELS);
+        self.channels.drain(..overflow);
+    }
+
+    /// From least to most recently active.
+    pub fn channels(&self) -> &[String] {
+        &self.channels
+    }
+
+    /// The channel that was active before the current one.
+    pub fn previous(&self) -> Option<&str> {
+        self.channels
+            .len()
+            .checked_sub(2)
+            .map(|i| self.channels[i].as_str())
+    }
+
+    /// Forget about a channel, so that the one active before it takes its place.
+    pub fn close(&mut self, channel: &str) {
+        self.channels.retain(|c| c != channel);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn current(history: &ChannelHistory) -> Option<&str> {
+        history.channels().last().map(String::as_str)
+    }
+
+    fn history(channels: &[&str]) -> ChannelHistory {
+        let mut history = ChannelHistory::default();
+
+        for channel in channels {
+            history.switch_to(channel);
+        }
+
+        history
+    }
+
+    #[test]
+    fn test_toggle_between_two_channels() {
+        let mut history = history(&["xithrius", "rocketleague", "antvenom"]);
+
+        assert_eq!(current(&history), Some("antvenom"));
+        assert_eq!(history.previous(), Some("rocketleague"));
+
+        history.switch_to("rocketleague");
+        assert_eq!(current(&history), Some("rocketleague"));
+        assert_eq!(history.previous(), Some("antvenom"));
+
+        history.switch_to("antvenom");
+        assert_eq!(current(&history), Some("antvenom"));
+        assert_eq!(history.previous(), Some("rocketleague"));
+    }
+
+    #[test]
+    fn test_switch_to_older_channel() {
+        let mut history = history(&["xithrius", "rocketleague", "antvenom"]);
+
+        history.switch_to("xithrius");
+
+        assert_eq!(current(&history), Some("xithrius"));
+        assert_eq!(history.previous(), Some("antvenom"));
+    }
+
+    #[test]
+    fn test_close_falls_back_to_next_most_recent() {
+        let mut history = history(&["xithrius", "rocketleague", "antvenom"]);
+
+        history.close("rocketleague");
+        assert_eq!(current(&history), Some("antvenom"));
+        assert_eq!(history.previous(), Some("xithrius"));
+
+        // Switching back and forth skips the closed channel.
+        history.switch_to("xithrius");
+        assert_eq!(history.previous(), Some("antvenom"));
+        history.switch_to("antvenom");
+        assert_eq!(history.previous(), Some("xithrius"));
+
+        history.close("antvenom");
+        assert_eq!(current(&history), Some("xithrius"));
+        assert_eq!(history.previous(), None);
+    }
+
+    #[test]
+    fn test_oldest_channels_are_forgotten() {
+        let channels = (0..MAX_CHANNELS + 5)
+            .map(|i| format!("channel{i}"))
+            .collect::<Vec<_>>();
+
+        let mut history = history(&channels.iter().map(String::as_str).collect::<Vec<_>>());
+
+        assert_eq!(history.channels().len(), MAX_CHANNELS);
+        assert_eq!(history.channels()[0], "channel5");
+
+        // Switching back to a remembered channel does not forget another one.
+        history.switch_to("channel10");
+        assert_eq!(history.channels().len(), MAX_CHANNELS);
+        assert_eq!(current(&history), Some("channel10"));
+        assert_eq!(history.previous(), Some("channel24"));
+    }
+
+    #[test]
+    fn test_no_previous_channel() {
+        assert_eq!(ChannelHistory::default().previous(), None);
+        assert_eq!(history(&["xithrius", "xithrius"]).previous(), None);
+    }
+}
@@ -0,0 +1,131 @@
+use std::collections::HashMap;
+
+use crate::handlers::data::MessageData;
+
+/// How many of the most active chatters are shown.
+pub const TOP_TALKERS: usize = 5;
+
+/// How active chat has been, over the messages that are kept.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ChatStats {
+    pub messages: usize,
+    pub chatters: usize,
+    /// Over the time between the oldest and newest message, of at least a minute.
+    pub messages_per_minute: f64,
+    /// The chatters with the most messages and how many they sent, most first.
+    pub top_talkers: Vec<(String, usize)>,
+}
+
+impl ChatStats {
+    /// The statistics of the messages of chat, leaving out the ones of the client itself.
+    pub fn new<'a>(messages: impl IntoIterator<Item = &'a MessageData>, top: usize) -> Self {
+        let messages = messages
+            .into_iter()
+            .filter(|m| !m.system)
+            .collect::<Vec<_>>();
+
+        // Chatters are told apart by their login, the display name is kept to be shown.
+        let mut counts: HashMap<String, (&str, usize)> = HashMap::new();
+
+        for message in &messages {
+            let login = message
+                .login
+                .as_deref()
+                .unwrap_or(&message.author)
+                .to_lowercase();
+
+            counts.entry(login).or_insert((&message.author, 0)).1 += 1;
+        }
+
+        let (oldest, newest) = (
+            messages.iter().map(|m| m.time_sent).min(),
+            messages.iter().map(|m| m.time_sent).max(),
+        );
+
+        let minutes = oldest
+            .zip(newest)
+            .map_or(0.0, |(oldest, newest)| {
+                (newest - oldest).num_seconds() as f64 / 60.0
+            })
+            .max(1.0);
+
+        let chatters = counts.len();
+
+        let mut top_talkers = counts
+            .into_values()
+            .map(|(author, count)| (author.to_string(), count))
+            .collect::<Vec<_>>();
+
+        top_talkers.sort_by(|(a, a_count), (b, b_count)| b_count.cmp(a_count).then(a.cmp(b)));
+        top_talkers.truncate(top);
+
+        Self {
+            messages: messages.len(),
+            chatters,
+            messages_per_minute: messages.len() as f64 / minutes,
+            top_talkers,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+
+    use super::*;
+
+    fn message(author: &str, minutes_ago: i64) -> MessageData {
+        let mut message = MessageData::new(
+            author.to_string(),
+            None,
+            false,
+            "hello chat".to_string(),
+            None,
+            false,
+        );
+        message.login = Some(author.to_lowercase());
+        message.time_sent -= Duration::minutes(minutes_ago);
+
+        message
+    }
+
+    #[test]
+    fn test_chat_stats() {
+        let mut system = message("twitch-tui", 30);
+        system.system = true;
+
+        let messages = vec![
+            message("Alice", 0),
+            message("bob", 1),
+            message("alice", 2),
+            message("Carol", 3),
+            message("bob", 9),
+            message("Alice", 10),
+            system,
+        ];
+
+        let stats = ChatStats::new(&messages, 2);
+
+        assert_eq!(stats.messages, 6);
+        assert_eq!(stats.chatters, 3);
+        assert!((stats.messages_per_minute - 0.6).abs() < 0.01);
+        assert_eq!(
+            stats.top_talkers,
+            vec![("Alice".to_string(), 3), ("bob".to_string(), 2)]
+        );
+    }
+
+    #[test]
+    fn test_chat_stats_short_or_empty() {
+        let messages = vec![message("alice", 0), message("bob", 0)];
+
+        let stats = ChatStats::new(&messages, TOP_TALKERS);
+
+        assert!((stats.messages_per_minute - 2.0).abs() < f64::EPSILON);
+        assert_eq!(
+            stats.top_talkers,
+            vec![("alice".to_string(), 1), ("bob".to_string(), 1)]
+        );
+        assert_eq!(ChatStats::new(&[], TOP_TALKERS), ChatStats::default());
+    }
+}
@@ -3,6 +3,7 @@ use serde::{Deserialize, Serialize};
 use serde_with::DeserializeFromStr;
 use std::{
     cell::RefCell,
+    collections::HashMap,
     env,
     fs::{create_dir_all, read_to_string, File},
     io::Write,
@@ -11,18 +12,25 @@ use std::{
     str::FromStr,
 };
 use tokio::{runtime::Handle, task};
-use tui::widgets::BorderType;
+use tui::{
+    style::{Color, Modifier, Style},
+    widgets::BorderType,
+};
 
 use crate::{
-    emotes::support_graphics_protocol,
+    emotes::EmoteProvider,
     handlers::{
         args::{merge_args_into_config, Cli},
         interactive::interactive_config,
         state::State,
+        storage::{Session, SESSION_FILE},
     },
+    ui::statics::NAME_MAX_CHARACTERS,
     utils::{
-        emotes::emotes_enabled,
-        pathing::{cache_path, config_path},
+        certificates::RootCertificates,
+        pathing::config_path,
+        proxy::Proxy,
+        text::{mark_display, sanitize_display},
     },
 };
 
@@ -41,6 +49,23 @@ pub struct CompleteConfig {
     pub filters: FiltersConfig,
     /// How everything looks to the user.
     pub frontend: FrontendConfig,
+    /// Alerting the user of mentions and keywords.
+    pub alerts: AlertsConfig,
+    /// Masking out terms in messages.
+    pub censor: CensorConfig,
+    /// Acting on messages as a moderator.
+    pub moderation: ModerationConfig,
+    /// Replying to messages automatically.
+    pub responses: ResponsesConfig,
+    /// Settings for single channels, under `[channels.<name>]`.
+    pub channels: HashMap<String, ChannelConfig>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct ChannelConfig {
+    /// The color that sets the channel apart while it is joined, empty for none.
+    pub accent: String,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -54,6 +79,40 @@ pub struct TwitchConfig {
     pub server: String,
     /// The authentication token for the IRC.
     pub token: Option<String>,
+    /// If chat is read without logging in, as an anonymous `justinfan` user that cannot send anything.
+    pub anonymous: bool,
+    /// The amount of recent messages to load when joining a channel.
+    pub recent_messages: usize,
+    /// Which followed channel to join at startup, when none is given on the command line.
+    pub auto_join: AutoJoin,
+    /// If events such as Hype Trains, polls and predictions are received, to be shown with chat.
+    pub eventsub: bool,
+    /// Seconds that the clock can jump ahead before the connection is assumed dead from sleep, 0 to disable.
+    pub sleep_threshold: u64,
+    /// Seconds between the PINGs sent to check that the connection is alive, 0 to disable.
+    pub ping_interval: u64,
+    /// Seconds to wait for the PONG to a PING before the connection is assumed dead.
+    pub ping_timeout: u64,
+    /// Milliseconds to wait between joining channels, besides keeping to the join rate limit.
+    pub join_delay: u64,
+    /// The most messages that wait while disconnected to be sent on reconnect, 0 to not keep any.
+    pub queue_size: usize,
+    /// Seconds that a message waits to be sent on reconnect before it is dropped.
+    pub queue_max_age: u64,
+    /// A SOCKS5 or HTTP proxy to connect through, as a URL.
+    pub proxy: Option<String>,
+    /// A PEM file of certificates to trust besides the ones of the system.
+    pub root_certificates: Option<String>,
+    /// If `/raw` sends lines to IRC as they are. Advanced and unsafe, since nothing checks them.
+    pub raw_commands: bool,
+    /// If the moderators and VIPs of the channel are looked up on join, so that their role is known early.
+    pub cache_roles: bool,
+    /// If own messages are shown with the name and id that Twitch tells on connect, instead of the configured username.
+    pub self_identity: bool,
+    /// If the membership capability is requested, for the JOIN, PART and NAMES of other users.
+    pub membership: bool,
+    /// Minutes between looking up the moderators and VIPs again, 0 to only look them up on join.
+    pub roles_refresh: u64,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -65,19 +124,38 @@ pub struct TerminalConfig {
     pub maximum_messages: usize,
     /// The file path to log to.
     pub log_file: Option<String>,
+    /// A file that every chat event is appended to as a line of JSON, for scripts to follow.
+    pub event_file: Option<String>,
     /// if debug logging should be enabled.
     pub verbose: bool,
     /// What state the application should start in.
     pub first_state: State,
+    /// If the screen should only be redrawn when something changed, instead of on every update.
+    pub redraw_on_change: bool,
+    /// Milliseconds over which incoming messages are collected before being shown together.
+    pub batch_window: u64,
+    /// If quitting has to be confirmed first.
+    pub confirm_quit: bool,
+    /// How emotes are written when messages are logged.
+    pub log_emotes: EmoteText,
+    /// Which keys chat uses in normal mode.
+    pub key_scheme: KeyScheme,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(default)]
 pub struct StorageConfig {
     /// If previous channels switched to should be tracked.
     pub channels: bool,
     /// If previous username mentions should be tracked.
     pub mentions: bool,
+    /// If the last session should be restored on startup.
+    pub session: bool,
+    /// If sent messages are only kept in the input history until quitting, and never saved,
+    /// which is the default so that nothing typed ends up on disk without asking for it.
+    pub ephemeral_history: bool,
+    /// If a summary of chat is written to a JSON file on quitting.
+    pub session_summary: bool,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Default)]
@@ -87,6 +165,10 @@ pub struct FiltersConfig {
     pub enabled: bool,
     /// If the regex filters should be reversed.
     pub reversed: bool,
+    /// Other filter lists to add to the filters file, by their path.
+    pub include: Vec<String>,
+    /// The ISO 639-3 codes of the languages of the messages that are shown, all if empty.
+    pub languages: Vec<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -96,22 +178,47 @@ pub struct FrontendConfig {
     pub show_datetimes: bool,
     /// The format of string that will show up in the terminal.
     pub datetime_format: String,
+    /// Where the date and time go on the first row of a message.
+    pub timestamp_position: TimestampPosition,
     /// If the username should be shown.
     pub username_shown: bool,
+    /// Which of the login and display name of a user should be shown.
+    pub username_display: UsernameDisplay,
     /// The color palette.
     pub palette: Palette,
+    /// Username colors with less contrast than this against the background of the theme
+    /// are made lighter or darker, 1 to disable.
+    pub min_contrast: f64,
     /// Show Title with time and channel.
     pub title_shown: bool,
+    /// Which parts of the title are shown, in order.
+    pub status_segments: Vec<String>,
     /// The amount of space between the chat window and the terminal border.
     pub margin: u16,
+    /// The widest that chat and the input box get, where 0 lets them take up the whole terminal.
+    pub max_width: u16,
+    /// Where chat goes on a terminal that is wider than `max_width`.
+    pub column_alignment: ColumnAlignment,
     /// Show twitch badges next to usernames.
     pub badges: bool,
+    /// Show the tier and months of subscribers after their name.
+    pub sub_info: bool,
     /// Theme, being either light or dark.
     pub theme: Theme,
     /// If the username should be highlighted when it appears in chat.
     pub username_highlight: bool,
-    /// If there should be state tabs shown on the bottom of the terminal.
+    /// If `@login` mentions of users in messages are shown in bold and underlined.
+    pub style_mentions: bool,
+    /// If messages that contain links are marked.
+    pub link_marker: bool,
+    /// If the name, provider and ID of the first emote in the selected message are shown.
+    pub emote_info: bool,
+    /// If there should be state tabs shown on the edge of the terminal.
     pub state_tabs: bool,
+    /// If the latest people to talk are listed next to chat, along with their role.
+    pub speakers: bool,
+    /// If the input box goes above or below chat, along with the state tabs.
+    pub input_position: InputPosition,
     /// The shape of the cursor in insert boxes.
     pub cursor_shape: CursorType,
     /// If the cursor should be blinking.
@@ -120,14 +227,20 @@ pub struct FrontendConfig {
     pub inverted_scrolling: bool,
     /// If scroll offset integer should be shown.
     pub show_scroll_offset: bool,
-    /// If twitch emotes should be displayed (requires kitty terminal).
+    /// If a scrollbar shows where the view is in the buffer of messages.
+    pub scrollbar: bool,
+    /// If twitch emotes should be displayed (as images in the kitty terminal, or else as their names).
     pub twitch_emotes: bool,
-    /// If betterttv emotes should be displayed (requires kitty terminal).
+    /// If betterttv emotes should be displayed (as images in the kitty terminal, or else as their names).
     pub betterttv_emotes: bool,
-    /// If 7tv emotes should be displayed (requires kitty terminal).
+    /// If 7tv emotes should be displayed (as images in the kitty terminal, or else as their names).
     pub seventv_emotes: bool,
-    /// If FrankerFacez emotes should be displayed (requires kitty terminal).
+    /// If FrankerFacez emotes should be displayed (as images in the kitty terminal, or else as their names).
     pub frankerfacez_emotes: bool,
+    /// Which provider's emote is used when several have one with the same name, first to last.
+    pub emote_precedence: Vec<EmoteProvider>,
+    /// The maximum amount of emotes downloaded at the same time.
+    pub emote_download_concurrency: usize,
     /// Channels to always be displayed in the start screen.
     pub favorite_channels: Vec<String>,
     /// The amount of recent channels that should be shown on the start screen.
@@ -138,8 +251,166 @@ pub struct FrontendConfig {
     pub hide_chat_border: bool,
     /// If the usernames should be aligned to the right.
     pub right_align_usernames: bool,
+    /// If right aligned usernames are padded to the widest recent name instead of the longest possible one.
+    pub auto_name_width: bool,
+    /// The widest that the column of right aligned usernames gets when sized automatically.
+    pub max_name_width: usize,
+    /// If the channel in the status line is dimmed when chat is quiet and bold when it is busy.
+    pub activity_style: bool,
+    /// How many seconds back the messages of a channel count towards how active it is.
+    pub activity_window: u64,
+    /// Fewer recent messages than this and a channel is quiet.
+    pub quiet_activity: usize,
+    /// This many recent messages or more and a channel is busy.
+    pub busy_activity: usize,
     /// Do not display the window size warning.
     pub show_unsupported_screen_size: bool,
+    /// Messages that can be inserted into the chat input.
+    pub canned_responses: Vec<String>,
+    /// Accounts created less than this amount of days ago get a warning marker, 0 to disable.
+    pub new_account_days: u64,
+    /// Seconds within which consecutive messages from the same author are grouped, 0 to disable.
+    pub group_messages_window: u64,
+    /// Seconds without input after which what was said in chat is summed up on return, 0 to disable.
+    pub catch_up_after: u64,
+    /// Seconds after a raid during which raid notices and new chatters are condensed into one line, 0 to disable.
+    pub raid_digest_window: u64,
+    /// What happens to messages that are deleted, or whose author is timed out or banned.
+    pub removed_messages: RemovedMessages,
+    /// What happens to invisible characters in messages that could hide text or reorder it.
+    pub control_characters: ControlCharacters,
+    /// Seconds after a message is deleted within which its author reposting it is shown as an edit, 0 to disable.
+    pub edit_window: u64,
+    /// Seconds after which messages are cleared from chat, such as while sharing the screen, 0 to disable.
+    pub message_max_age: u64,
+    /// If messages that are cleared for their age are written to the log file.
+    pub log_aged_out: bool,
+    /// How alike, from 0 to 1, a repost has to be to the deleted message to count as an edit of it.
+    pub edit_similarity: f32,
+    /// If long messages made of box-drawing, block or braille characters are collapsed to one line.
+    pub collapse_ascii_art: bool,
+    /// The fraction of a message's characters that have to be drawing characters for it to be collapsed.
+    pub ascii_art_ratio: f32,
+    /// If a run of the same emote within a message is shown once, with how many times it was repeated.
+    pub collapse_emote_runs: bool,
+    /// The most emotes that are shown in a message, with the ones past it counted instead, 0 to not limit them.
+    pub max_emotes: usize,
+    /// Messages are cut short to this amount of lines until they are expanded, 0 to disable.
+    pub truncate_lines: usize,
+    /// Words that are wider than a line are broken across at most this amount of lines, 0 to not limit them.
+    pub word_lines: usize,
+    /// If messages are wrapped, or each kept to one line as on classic IRC clients.
+    pub render_mode: RenderMode,
+    /// If words that are not emotes are marked, and sending them asks for confirmation, in emote-only chats.
+    pub emote_only_warning: bool,
+    /// If chat stops following new messages while a popup is open, catching up once it closes.
+    pub popup_scroll_lock: bool,
+    /// If clicking the name of an author shows only their messages, and clicking any other part of a message selects it.
+    pub mouse_clicks: bool,
+    /// How emote names rendered as text look, per provider.
+    pub emote_styles: EmoteStylesConfig,
+    /// Which size of emote images is downloaded, per provider.
+    pub emote_sizes: EmoteSizesConfig,
+    /// Small previews of image links, below the messages they are in.
+    pub thumbnails: ThumbnailsConfig,
+    /// Messages of bots and chat commands, which are shown dimly.
+    pub dimmed: DimmedConfig,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct DimmedConfig {
+    /// Users whose messages are dimmed, such as the bots of a channel.
+    pub users: Vec<String>,
+    /// Messages starting with this are dimmed, such as `!` for bot commands, empty to disable.
+    pub command_prefix: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct ThumbnailsConfig {
+    /// Channels where image links get a thumbnail, none by default.
+    pub channels: Vec<String>,
+    /// The most rows that a thumbnail takes up.
+    pub max_rows: u16,
+    /// The most columns that a thumbnail takes up.
+    pub max_cols: u16,
+    /// Images larger than this amount of kilobytes are not downloaded.
+    pub max_size: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct EmoteStylesConfig {
+    pub twitch: EmoteStyle,
+    pub betterttv: EmoteStyle,
+    pub seventv: EmoteStyle,
+    pub frankerfacez: EmoteStyle,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct EmoteSizesConfig {
+    pub twitch: EmoteSize,
+    pub betterttv: EmoteSize,
+    pub seventv: EmoteSize,
+    pub frankerfacez: EmoteSize,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct EmoteStyle {
+    /// A color name or hex code, where "reset" keeps the color of the message.
+    pub color: String,
+    /// If the emote name should be bold.
+    pub bold: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct AlertsConfig {
+    /// If the terminal bell should ring on alerts.
+    pub sound: bool,
+    /// Words that alert the user in addition to mentions of their username.
+    pub keywords: Vec<String>,
+    /// The minimum amount of seconds between two sound alerts.
+    pub cooldown: u64,
+    /// Channels where sound alerts are never played.
+    pub muted_channels: Vec<String>,
+    /// How to alert the user when the connection drops unexpectedly.
+    pub disconnect: DisconnectAlert,
+    /// If messages of the user can alert them, such as ones sent from another client.
+    pub own_messages: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct CensorConfig {
+    /// Words or regex to be replaced with asterisks.
+    pub terms: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct ModerationConfig {
+    /// If selected messages can be acted upon with single keys.
+    pub enabled: bool,
+    /// How many seconds a user is timed out for.
+    pub timeout_duration: u64,
+    /// The key that deletes the selected message.
+    pub delete_key: char,
+    /// The key that times out the author of the selected message.
+    pub timeout_key: char,
+    /// The key that bans the author of the selected message.
+    pub ban_key: char,
+    /// What happens when an action is taken without the scope that the token needs for it.
+    pub missing_scope: MissingScope,
+    /// If recent moderation actions are shown next to chat.
+    pub feed: bool,
+    /// If raiding channels are shouted out.
+    pub auto_shoutout: bool,
+    /// Seconds after a raid before the raiding channel is shouted out.
+    pub shoutout_delay: u64,
 }
 
 impl Default for TwitchConfig {
@@ -149,6 +420,23 @@ impl Default for TwitchConfig {
             channel: String::new(),
             server: "irc.chat.twitch.tv".to_string(),
             token: None,
+            anonymous: false,
+            recent_messages: 0,
+            auto_join: AutoJoin::default(),
+            eventsub: false,
+            sleep_threshold: 30,
+            ping_interval: 30,
+            ping_timeout: 10,
+            join_delay: 500,
+            queue_size: 10,
+            queue_max_age: 60,
+            proxy: None,
+            root_certificates: None,
+            raw_commands: false,
+            cache_roles: false,
+            self_identity: true,
+            membership: false,
+            roles_refresh: 10,
         }
     }
 }
@@ -159,8 +447,88 @@ impl Default for TerminalConfig {
             delay: 30,
             maximum_messages: 500,
             log_file: None,
+            event_file: None,
             verbose: false,
             first_state: State::default(),
+            redraw_on_change: true,
+            batch_window: 50,
+            confirm_quit: true,
+            log_emotes: EmoteText::default(),
+            key_scheme: KeyScheme::default(),
+        }
+    }
+}
+
+impl Default for StorageConfig {
+    fn default() -> Self {
+        Self {
+            channels: false,
+            mentions: false,
+            session: false,
+            ephemeral_history: true,
+            session_summary: false,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct ResponsesConfig {
+    /// If messages that match a trigger are replied to.
+    pub enabled: bool,
+    /// What to reply to, and with what.
+    pub triggers: Vec<ResponseTrigger>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct ResponseTrigger {
+    /// How the pattern is matched against messages.
+    pub kind: TriggerKind,
+    /// The text, word or regex that a message has to match.
+    pub pattern: String,
+    /// The message that is sent in reply.
+    pub reply: String,
+    /// The minimum amount of seconds between two replies of this trigger.
+    pub cooldown: u64,
+}
+
+impl Default for AlertsConfig {
+    fn default() -> Self {
+        Self {
+            sound: false,
+            keywords: vec![],
+            cooldown: 5,
+            muted_channels: vec![],
+            disconnect: DisconnectAlert::default(),
+            own_messages: false,
+        }
+    }
+}
+
+impl Default for ResponseTrigger {
+    fn default() -> Self {
+        Self {
+            kind: TriggerKind::default(),
+            pattern: String::new(),
+            reply: String::new(),
+            cooldown: 60,
+        }
+    }
+}
+
+impl Default for ModerationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            timeout_duration: 600,
+            delete_key: 'd',
+            timeout_key: 't',
+            ban_key: 'b',
+            missing_scope: MissingScope::default(),
+            feed: false,
+            auto_shoutout: false,
+            shoutout_delay: 5,
         }
     }
 }
@@ -170,28 +538,152 @@ impl Default for FrontendConfig {
         Self {
             show_datetimes: true,
             datetime_format: "%a %b %e %T %Y".to_string(),
+            timestamp_position: TimestampPosition::default(),
             username_shown: true,
+            username_display: UsernameDisplay::default(),
             palette: Palette::default(),
+            min_contrast: 3.0,
             title_shown: true,
+            status_segments: [
+                "mode",
+                "connection",
+                "time",
+                "channel",
+                "filter",
+                "paused",
+                "author",
+                "modes",
+            ]
+            .map(str::to_string)
+            .to_vec(),
             margin: 0,
+            max_width: 0,
+            column_alignment: ColumnAlignment::default(),
             badges: false,
+            sub_info: false,
             theme: Theme::default(),
             username_highlight: true,
+            style_mentions: true,
+            link_marker: false,
+            emote_info: false,
             state_tabs: false,
+            speakers: false,
+            input_position: InputPosition::default(),
             cursor_shape: CursorType::default(),
             blinking_cursor: false,
             inverted_scrolling: false,
             show_scroll_offset: false,
+            scrollbar: false,
             twitch_emotes: false,
             betterttv_emotes: false,
             seventv_emotes: false,
             frankerfacez_emotes: false,
+            emote_precedence: EmoteProvider::ALL.to_vec(),
+            emote_download_concurrency: 100,
             favorite_channels: vec![],
             recent_channel_count: 5,
             border_type: Border::default(),
             hide_chat_border: false,
             right_align_usernames: false,
+            auto_name_width: false,
+            max_name_width: NAME_MAX_CHARACTERS,
+            activity_style: false,
+            activity_window: 60,
+            quiet_activity: 2,
+            busy_activity: 20,
             show_unsupported_screen_size: true,
+            canned_responses: vec![],
+            new_account_days: 0,
+            group_messages_window: 0,
+            catch_up_after: 0,
+            raid_digest_window: 0,
+            removed_messages: RemovedMessages::default(),
+            control_characters: ControlCharacters::default(),
+            edit_window: 0,
+            message_max_age: 0,
+            log_aged_out: false,
+            edit_similarity: 0.8,
+            collapse_ascii_art: false,
+            collapse_emote_runs: false,
+            max_emotes: 0,
+            ascii_art_ratio: 0.5,
+            truncate_lines: 0,
+            word_lines: 0,
+            render_mode: RenderMode::default(),
+            emote_only_warning: true,
+            popup_scroll_lock: true,
+            mouse_clicks: true,
+            emote_styles: EmoteStylesConfig::default(),
+            emote_sizes: EmoteSizesConfig::default(),
+            thumbnails: ThumbnailsConfig::default(),
+            dimmed: DimmedConfig::default(),
+        }
+    }
+}
+
+impl Default for ThumbnailsConfig {
+    fn default() -> Self {
+        Self {
+            channels: vec![],
+            max_rows: 4,
+            max_cols: 16,
+            max_size: 5000,
+        }
+    }
+}
+
+impl ThumbnailsConfig {
+    /// If image links in a channel should get a thumbnail.
+    pub fn enabled(&self, channel: &str) -> bool {
+        self.channels
+            .iter()
+            .any(|c| c.eq_ignore_ascii_case(channel))
+    }
+}
+
+impl Default for EmoteStyle {
+    fn default() -> Self {
+        Self {
+            color: "reset".to_string(),
+            bold: true,
+        }
+    }
+}
+
+impl EmoteStylesConfig {
+    pub const fn get(&self, provider: EmoteProvider) -> &EmoteStyle {
+        match provider {
+            EmoteProvider::Twitch => &self.twitch,
+            EmoteProvider::BetterTTV => &self.betterttv,
+            EmoteProvider::SevenTV => &self.seventv,
+            EmoteProvider::FrankerFaceZ => &self.frankerfacez,
+        }
+    }
+}
+
+impl EmoteSizesConfig {
+    pub const fn get(&self, provider: EmoteProvider) -> EmoteSize {
+        match provider {
+            EmoteProvider::Twitch => self.twitch,
+            EmoteProvider::BetterTTV => self.betterttv,
+            EmoteProvider::SevenTV => self.seventv,
+            EmoteProvider::FrankerFaceZ => self.frankerfacez,
+        }
+    }
+}
+
+impl EmoteStyle {
+    /// The style to patch over the style of the message, ignoring colors that cannot be parsed.
+    pub fn style(&self) -> Style {
+        let style = match Color::from_str(&self.color) {
+            Ok(Color::Reset) | Err(_) => Style::default(),
+            Ok(color) => Style::default().fg(color),
+        };
+
+        if self.bold {
+            style.add_modifier(Modifier::BOLD)
+        } else {
+            style
         }
     }
 }
@@ -225,6 +717,373 @@ impl FromStr for Palette {
     }
 }
 
+#[derive(Serialize, DeserializeFromStr, Debug, Clone, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum UsernameDisplay {
+    #[default]
+    Display,
+    Login,
+    Both,
+}
+
+impl FromStr for UsernameDisplay {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "display" => Ok(Self::Display),
+            "login" => Ok(Self::Login),
+            "both" => Ok(Self::Both),
+            _ => bail!("Username display '{}' cannot be deserialized", s),
+        }
+    }
+}
+
+#[derive(Serialize, DeserializeFromStr, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum TimestampPosition {
+    /// In front of the author.
+    #[default]
+    Left,
+    /// In a column along the right edge of chat.
+    Right,
+    Hidden,
+}
+
+impl FromStr for TimestampPosition {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "left" => Ok(Self::Left),
+            "right" => Ok(Self::Right),
+            "hidden" => Ok(Self::Hidden),
+            _ => bail!("Timestamp position '{}' cannot be deserialized", s),
+        }
+    }
+}
+
+#[derive(Serialize, DeserializeFromStr, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum InputPosition {
+    Top,
+    #[default]
+    Bottom,
+}
+
+impl FromStr for InputPosition {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "top" => Ok(Self::Top),
+            "bottom" => Ok(Self::Bottom),
+            _ => bail!("Input position '{}' cannot be deserialized", s),
+        }
+    }
+}
+
+#[derive(Serialize, DeserializeFromStr, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ColumnAlignment {
+    Left,
+    #[default]
+    Center,
+}
+
+impl FromStr for ColumnAlignment {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "left" => Ok(Self::Left),
+            "center" => Ok(Self::Center),
+            _ => bail!("Column alignment '{}' cannot be deserialized", s),
+        }
+    }
+}
+
+#[derive(Serialize, DeserializeFromStr, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum RemovedMessages {
+    #[default]
+    Remove,
+    Mark,
+}
+
+impl FromStr for RemovedMessages {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "remove" => Ok(Self::Remove),
+            "mark" => Ok(Self::Mark),
+            _ => bail!("Removed messages option '{}' cannot be deserialized", s),
+        }
+    }
+}
+
+#[derive(Serialize, DeserializeFromStr, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ControlCharacters {
+    /// Zero-width characters and bidirectional overrides are taken out.
+    #[default]
+    Strip,
+    /// They are shown by their abbreviations, such as `<RLO>`.
+    Mark,
+    /// They are kept as they are.
+    Keep,
+}
+
+impl ControlCharacters {
+    pub fn apply(self, text: &str) -> String {
+        match self {
+            Self::Strip => sanitize_display(text),
+            Self::Mark => mark_display(text),
+            Self::Keep => text.to_string(),
+        }
+    }
+}
+
+impl FromStr for ControlCharacters {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "strip" => Ok(Self::Strip),
+            "mark" => Ok(Self::Mark),
+            "keep" => Ok(Self::Keep),
+            _ => bail!("Control characters option '{}' cannot be deserialized", s),
+        }
+    }
+}
+
+#[derive(Serialize, DeserializeFromStr, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum RenderMode {
+    /// Messages are wrapped over as many lines as they need.
+    #[default]
+    Wrapped,
+    /// Each message is one line of `<name> message`, cut off at the edge of chat.
+    Classic,
+}
+
+impl FromStr for RenderMode {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "wrapped" => Ok(Self::Wrapped),
+            "classic" => Ok(Self::Classic),
+            _ => bail!("Render mode option '{}' cannot be deserialized", s),
+        }
+    }
+}
+
+#[derive(Serialize, DeserializeFromStr, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum AutoJoin {
+    /// Join the configured channel.
+    #[default]
+    None,
+    /// Join the first followed channel that is live.
+    Live,
+    /// Join the live followed channel that was joined most recently.
+    Recent,
+}
+
+impl FromStr for AutoJoin {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "none" => Ok(Self::None),
+            "live" => Ok(Self::Live),
+            "recent" => Ok(Self::Recent),
+            _ => bail!("Auto join option '{}' cannot be deserialized", s),
+        }
+    }
+}
+
+#[derive(Serialize, DeserializeFromStr, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum EmoteText {
+    /// Emotes by their names, without emoji.
+    Names,
+    /// Emotes by their names, and emoji as they are.
+    #[default]
+    Unicode,
+    /// Neither emotes nor emoji.
+    Strip,
+}
+
+impl FromStr for EmoteText {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "names" => Ok(Self::Names),
+            "unicode" => Ok(Self::Unicode),
+            "strip" => Ok(Self::Strip),
+            _ => bail!("Emote text option '{}' cannot be deserialized", s),
+        }
+    }
+}
+
+#[derive(Serialize, DeserializeFromStr, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum KeyScheme {
+    #[default]
+    Default,
+    /// Like the default keys, but `/` searches messages and `:` starts a command, as in Vi.
+    Vi,
+}
+
+impl FromStr for KeyScheme {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "default" => Ok(Self::Default),
+            "vi" => Ok(Self::Vi),
+            _ => bail!("Key scheme '{}' cannot be deserialized", s),
+        }
+    }
+}
+
+#[derive(Serialize, DeserializeFromStr, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum DisconnectAlert {
+    #[default]
+    None,
+    Bell,
+    Flash,
+    Both,
+}
+
+impl DisconnectAlert {
+    pub const fn bell(self) -> bool {
+        matches!(self, Self::Bell | Self::Both)
+    }
+
+    pub const fn flash(self) -> bool {
+        matches!(self, Self::Flash | Self::Both)
+    }
+}
+
+impl FromStr for DisconnectAlert {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "none" => Ok(Self::None),
+            "bell" => Ok(Self::Bell),
+            "flash" => Ok(Self::Flash),
+            "both" => Ok(Self::Both),
+            _ => bail!("Disconnect alert '{}' cannot be deserialized", s),
+        }
+    }
+}
+
+/// How tall emote images are at 1x, in pixels, which is about the same for every provider.
+const EMOTE_BASE_HEIGHT: f32 = 28.;
+
+#[derive(Serialize, DeserializeFromStr, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EmoteSize {
+    /// The smallest size that is at least as tall as a row of the terminal.
+    #[default]
+    #[serde(rename = "auto")]
+    Auto,
+    #[serde(rename = "1x")]
+    One,
+    #[serde(rename = "2x")]
+    Two,
+    #[serde(rename = "3x")]
+    Three,
+    #[serde(rename = "4x")]
+    Four,
+}
+
+impl EmoteSize {
+    /// The preferred scale of emote images, such as 2 for 2x, given how tall a row of the terminal is in pixels.
+    pub fn scale(self, cell_height: Option<f32>) -> u8 {
+        match self {
+            Self::Auto => cell_height.map_or(1, |height| {
+                (height / EMOTE_BASE_HEIGHT).ceil().clamp(1., 4.) as u8
+            }),
+            Self::One => 1,
+            Self::Two => 2,
+            Self::Three => 3,
+            Self::Four => 4,
+        }
+    }
+}
+
+impl FromStr for EmoteSize {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "auto" => Ok(Self::Auto),
+            "1x" => Ok(Self::One),
+            "2x" => Ok(Self::Two),
+            "3x" => Ok(Self::Three),
+            "4x" => Ok(Self::Four),
+            _ => bail!("Emote size '{}' cannot be deserialized", s),
+        }
+    }
+}
+
+#[derive(Serialize, DeserializeFromStr, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum MissingScope {
+    /// The action is not sent, and the missing scope is shown.
+    Block,
+    /// The action is sent anyway, and the missing scope is shown.
+    #[default]
+    Warn,
+    /// The action is sent without checking the scopes.
+    Ignore,
+}
+
+impl FromStr for MissingScope {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "block" => Ok(Self::Block),
+            "warn" => Ok(Self::Warn),
+            "ignore" => Ok(Self::Ignore),
+            _ => bail!("Missing scope option '{}' cannot be deserialized", s),
+        }
+    }
+}
+
+#[derive(Serialize, DeserializeFromStr, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum TriggerKind {
+    /// The whole message is the pattern, ignoring case.
+    Exact,
+    /// The message contains the pattern as a whole word, ignoring case.
+    #[default]
+    Keyword,
+    /// The message matches the pattern as a regex.
+    Regex,
+}
+
+impl FromStr for TriggerKind {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "exact" => Ok(Self::Exact),
+            "keyword" => Ok(Self::Keyword),
+            "regex" => Ok(Self::Regex),
+            _ => bail!("Trigger kind '{}' cannot be deserialized", s),
+        }
+    }
+}
+
 #[derive(Serialize, DeserializeFromStr, Debug, Clone)]
 #[serde(rename_all = "lowercase")]
 pub enum Theme {
@@ -332,6 +1191,41 @@ impl ToVec<(String, String)> for TwitchConfig {
             ("Username".to_string(), self.username.to_string()),
             ("Channel".to_string(), self.channel.to_string()),
             ("Server".to_string(), self.server.to_string()),
+            ("Anonymous".to_string(), self.anonymous.to_string()),
+            (
+                "Recent messages".to_string(),
+                self.recent_messages.to_string(),
+            ),
+            ("Auto join".to_string(), format!("{:?}", self.auto_join)),
+            ("EventSub".to_string(), self.eventsub.to_string()),
+            (
+                "Sleep threshold".to_string(),
+                self.sleep_threshold.to_string(),
+            ),
+            ("Ping interval".to_string(), self.ping_interval.to_string()),
+            ("Ping timeout".to_string(), self.ping_timeout.to_string()),
+            ("Join delay".to_string(), self.join_delay.to_string()),
+            ("Queue size".to_string(), self.queue_size.to_string()),
+            ("Queue max age".to_string(), self.queue_max_age.to_string()),
+            (
+                "Proxy".to_string(),
+                Proxy::from_config(self)
+                    .ok()
+                    .flatten()
+                    .map_or_else(|| "None".to_string(), |proxy| proxy.address()),
+            ),
+            (
+                "Root certificates".to_string(),
+                self.root_certificates
+                    .clone()
+                    .filter(|path| !path.is_empty())
+                    .unwrap_or_else(|| "None".to_string()),
+            ),
+            ("Raw commands".to_string(), self.raw_commands.to_string()),
+            ("Cache roles".to_string(), self.cache_roles.to_string()),
+            ("Self identity".to_string(), self.self_identity.to_string()),
+            ("Membership".to_string(), self.membership.to_string()),
+            ("Roles refresh".to_string(), self.roles_refresh.to_string()),
         ]
     }
 }
@@ -348,7 +1242,21 @@ impl ToVec<(String, String)> for TerminalConfig {
                 "Log file".to_string(),
                 self.log_file.clone().map_or("None".to_string(), |f| f),
             ),
+            (
+                "Event file".to_string(),
+                self.event_file
+                    .clone()
+                    .unwrap_or_else(|| "None".to_string()),
+            ),
             ("First state".to_string(), self.first_state.to_string()),
+            (
+                "Redraw on change".to_string(),
+                self.redraw_on_change.to_string(),
+            ),
+            ("Batch window".to_string(), self.batch_window.to_string()),
+            ("Confirm quit".to_string(), self.confirm_quit.to_string()),
+            ("Log emotes".to_string(), format!("{:?}", self.log_emotes)),
+            ("Key scheme".to_string(), format!("{:?}", self.key_scheme)),
         ]
     }
 }
@@ -358,6 +1266,15 @@ impl ToVec<(String, String)> for StorageConfig {
         vec![
             ("Channels enabled".to_string(), self.channels.to_string()),
             ("Mentions enabled".to_string(), self.mentions.to_string()),
+            ("Session enabled".to_string(), self.session.to_string()),
+            (
+                "Ephemeral history".to_string(),
+                self.ephemeral_history.to_string(),
+            ),
+            (
+                "Session summary".to_string(),
+                self.session_summary.to_string(),
+            ),
         ]
     }
 }
@@ -367,6 +1284,8 @@ impl ToVec<(String, String)> for FiltersConfig {
         vec![
             ("Enabled".to_string(), self.enabled.to_string()),
             ("Reversed".to_string(), self.reversed.to_string()),
+            ("Include".to_string(), self.include.join(", ")),
+            ("Languages".to_string(), self.languages.join(", ")),
         ]
     }
 }
@@ -382,20 +1301,43 @@ impl ToVec<(String, String)> for FrontendConfig {
                 "Datetime format".to_string(),
                 self.datetime_format.to_string(),
             ),
+            (
+                "Timestamp position".to_string(),
+                format!("{:?}", self.timestamp_position),
+            ),
             (
                 "Username shown".to_string(),
                 self.username_shown.to_string(),
             ),
             // ("".to_string(), self.palette.to_string()),
+            (
+                "Minimum contrast".to_string(),
+                self.min_contrast.to_string(),
+            ),
             ("Title shown".to_string(), self.title_shown.to_string()),
+            (
+                "Status segments".to_string(),
+                self.status_segments.join(", "),
+            ),
             ("Margin".to_string(), self.margin.to_string()),
+            ("Max width".to_string(), self.max_width.to_string()),
+            (
+                "Column alignment".to_string(),
+                format!("{:?}", self.column_alignment),
+            ),
             ("Badges".to_string(), self.badges.to_string()),
+            ("Sub info".to_string(), self.sub_info.to_string()),
             // ("".to_string(), self.theme.to_string()),
             (
                 "Username highlight".to_string(),
                 self.username_highlight.to_string(),
             ),
             ("State tabs".to_string(), self.state_tabs.to_string()),
+            ("Speakers".to_string(), self.speakers.to_string()),
+            (
+                "Input position".to_string(),
+                format!("{:?}", self.input_position),
+            ),
             // ("".to_string(), self.cursor_shape.to_string()),
             (
                 "Blinking cursor".to_string(),
@@ -409,6 +1351,7 @@ impl ToVec<(String, String)> for FrontendConfig {
                 "Scroll offset shown".to_string(),
                 self.show_scroll_offset.to_string(),
             ),
+            ("Scrollbar".to_string(), self.scrollbar.to_string()),
             ("Twitch emotes".to_string(), self.twitch_emotes.to_string()),
             (
                 "BetterTTV emotes".to_string(),
@@ -422,6 +1365,14 @@ impl ToVec<(String, String)> for FrontendConfig {
                 "FrankerFacez emotes".to_string(),
                 self.frankerfacez_emotes.to_string(),
             ),
+            (
+                "Emote precedence".to_string(),
+                format!("{:?}", self.emote_precedence),
+            ),
+            (
+                "Emote download concurrency".to_string(),
+                self.emote_download_concurrency.to_string(),
+            ),
             // ("".to_string(), self.favorite_channels.to_string()),
             (
                 "Recent channel count".to_string(),
@@ -432,6 +1383,180 @@ impl ToVec<(String, String)> for FrontendConfig {
                 "Right aligned usernames".to_string(),
                 self.right_align_usernames.to_string(),
             ),
+            (
+                "Auto name width".to_string(),
+                self.auto_name_width.to_string(),
+            ),
+            (
+                "Max name width".to_string(),
+                self.max_name_width.to_string(),
+            ),
+            (
+                "Activity style".to_string(),
+                self.activity_style.to_string(),
+            ),
+            (
+                "Activity window".to_string(),
+                self.activity_window.to_string(),
+            ),
+            (
+                "Quiet activity".to_string(),
+                self.quiet_activity.to_string(),
+            ),
+            ("Busy activity".to_string(), self.busy_activity.to_string()),
+            (
+                "New account days".to_string(),
+                self.new_account_days.to_string(),
+            ),
+            (
+                "Group messages window".to_string(),
+                self.group_messages_window.to_string(),
+            ),
+            (
+                "Catch up after".to_string(),
+                self.catch_up_after.to_string(),
+            ),
+            (
+                "Raid digest window".to_string(),
+                self.raid_digest_window.to_string(),
+            ),
+            (
+                "Removed messages".to_string(),
+                format!("{:?}", self.removed_messages),
+            ),
+            (
+                "Control characters".to_string(),
+                format!("{:?}", self.control_characters),
+            ),
+            ("Edit window".to_string(), self.edit_window.to_string()),
+            (
+                "Message max age".to_string(),
+                self.message_max_age.to_string(),
+            ),
+            ("Log aged out".to_string(), self.log_aged_out.to_string()),
+            (
+                "Edit similarity".to_string(),
+                self.edit_similarity.to_string(),
+            ),
+            (
+                "Collapse ASCII art".to_string(),
+                self.collapse_ascii_art.to_string(),
+            ),
+            (
+                "ASCII art ratio".to_string(),
+                self.ascii_art_ratio.to_string(),
+            ),
+            (
+                "Collapse emote runs".to_string(),
+                self.collapse_emote_runs.to_string(),
+            ),
+            ("Max emotes".to_string(), self.max_emotes.to_string()),
+            (
+                "Truncate lines".to_string(),
+                self.truncate_lines.to_string(),
+            ),
+            ("Word lines".to_string(), self.word_lines.to_string()),
+            ("Render mode".to_string(), format!("{:?}", self.render_mode)),
+            (
+                "Emote-only warning".to_string(),
+                self.emote_only_warning.to_string(),
+            ),
+            (
+                "Popup scroll lock".to_string(),
+                self.popup_scroll_lock.to_string(),
+            ),
+            ("Mouse clicks".to_string(), self.mouse_clicks.to_string()),
+            (
+                "Style mentions".to_string(),
+                self.style_mentions.to_string(),
+            ),
+            ("Link marker".to_string(), self.link_marker.to_string()),
+            ("Emote info".to_string(), self.emote_info.to_string()),
+            (
+                "Emote styles".to_string(),
+                [
+                    &self.emote_styles.twitch,
+                    &self.emote_styles.betterttv,
+                    &self.emote_styles.seventv,
+                    &self.emote_styles.frankerfacez,
+                ]
+                .iter()
+                .map(|s| format!("{}{}", s.color, if s.bold { " bold" } else { "" }))
+                .collect::<Vec<String>>()
+                .join(", "),
+            ),
+            (
+                "Emote sizes".to_string(),
+                format!(
+                    "{:?}, {:?}, {:?}, {:?}",
+                    self.emote_sizes.twitch,
+                    self.emote_sizes.betterttv,
+                    self.emote_sizes.seventv,
+                    self.emote_sizes.frankerfacez
+                ),
+            ),
+            (
+                "Thumbnail channels".to_string(),
+                self.thumbnails.channels.join(", "),
+            ),
+            ("Dimmed users".to_string(), self.dimmed.users.join(", ")),
+            (
+                "Dimmed command prefix".to_string(),
+                self.dimmed.command_prefix.clone(),
+            ),
+        ]
+    }
+}
+
+impl ToVec<(String, String)> for AlertsConfig {
+    fn to_vec(&self) -> Vec<(String, String)> {
+        vec![
+            ("Sound".to_string(), self.sound.to_string()),
+            ("Keywords".to_string(), self.keywords.join(", ")),
+            ("Cooldown".to_string(), self.cooldown.to_string()),
+            ("Muted channels".to_string(), self.muted_channels.join(", ")),
+            ("Disconnect".to_string(), format!("{:?}", self.disconnect)),
+            ("Own messages".to_string(), self.own_messages.to_string()),
+        ]
+    }
+}
+
+impl ToVec<(String, String)> for CensorConfig {
+    fn to_vec(&self) -> Vec<(String, String)> {
+        vec![("Terms".to_string(), self.terms.len().to_string())]
+    }
+}
+
+impl ToVec<(String, String)> for ModerationConfig {
+    fn to_vec(&self) -> Vec<(String, String)> {
+        vec![
+            ("Enabled".to_string(), self.enabled.to_string()),
+            (
+                "Timeout duration".to_string(),
+                self.timeout_duration.to_string(),
+            ),
+            ("Delete key".to_string(), self.delete_key.to_string()),
+            ("Timeout key".to_string(), self.timeout_key.to_string()),
+            ("Ban key".to_string(), self.ban_key.to_string()),
+            (
+                "Missing scope".to_string(),
+                format!("{:?}", self.missing_scope),
+            ),
+            ("Feed".to_string(), self.feed.to_string()),
+            ("Auto shoutout".to_string(), self.auto_shoutout.to_string()),
+            (
+                "Shoutout delay".to_string(),
+                self.shoutout_delay.to_string(),
+            ),
+        ]
+    }
+}
+
+impl ToVec<(String, String)> for ResponsesConfig {
+    fn to_vec(&self) -> Vec<(String, String)> {
+        vec![
+            ("Enabled".to_string(), self.enabled.to_string()),
+            ("Triggers".to_string(), self.triggers.len().to_string()),
         ]
     }
 }
@@ -468,14 +1593,18 @@ fn persist_default_config(path: &Path) {
 }
 
 impl CompleteConfig {
-    pub fn new(cli: Cli) -> Result<Self, Error> {
-        let path_str = cache_path("");
-
-        let p = Path::new(&path_str);
-        if !p.exists() {
-            create_dir_all(p).unwrap();
-        }
+    /// The accent color set for a channel, ignoring colors that cannot be parsed.
+    pub fn channel_accent(&self, channel: &str) -> Option<Color> {
+        self.channels
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(channel))
+            .and_then(|(_, settings)| match Color::from_str(&settings.accent) {
+                Ok(Color::Reset) | Err(_) => None,
+                Ok(color) => Some(color),
+            })
+    }
 
+    pub fn new(cli: Cli) -> Result<Self, Error> {
         let path_str = config_path("config.toml");
 
         let p = Path::new(&path_str);
@@ -496,6 +1625,12 @@ impl CompleteConfig {
                 Err(err) => bail!("Config could not be processed. Error: {:?}", err.message()),
             };
 
+            if config.storage.session && !cli.no_restore {
+                if let Some(channel) = Session::load(SESSION_FILE).active_channel() {
+                    config.twitch.channel.clone_from(channel);
+                }
+            }
+
             merge_args_into_config(&mut config, cli);
 
             let token: Option<&'static str> = option_env!("TWT_TOKEN");
@@ -506,19 +1641,34 @@ impl CompleteConfig {
                 }
             }
 
+            // Anonymous connections do not log in, and followed channels cannot be looked up without a login.
+            if config.twitch.anonymous {
+                config.twitch.token = None;
+                config.twitch.auto_join = AutoJoin::None;
+            }
+
             {
                 let t = &config.twitch;
 
                 let check_token = t.token.as_ref().map_or("", |t| t);
 
-                if t.username.is_empty() || t.channel.is_empty() || check_token.is_empty() {
+                // A channel can be picked at startup from the followed channels instead.
+                let missing_channel = t.channel.is_empty() && t.auto_join == AutoJoin::None;
+
+                if t.anonymous {
+                    if t.channel.is_empty() {
+                        bail!("Twitch config section is missing the channel to read anonymously.");
+                    }
+                } else if t.username.is_empty() || missing_channel || check_token.is_empty() {
                     bail!("Twitch config section is missing one or more of the following: username, channel, token.");
                 }
 
-                if emotes_enabled(&config.frontend) && !support_graphics_protocol().unwrap_or(false)
-                {
-                    eprintln!("This terminal does not support the graphics protocol.\nUse a terminal such as kitty, or disable emotes.");
-                    std::process::exit(1);
+                if let Err(err) = Proxy::from_config(t) {
+                    bail!("The proxy cannot be used. Error: {err}");
+                }
+
+                if let Err(err) = RootCertificates::from_config(t) {
+                    bail!("{err}");
                 }
             }
 
@@ -534,3 +1684,35 @@ impl CompleteConfig {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_accent_color() {
+        let mut config = CompleteConfig::default();
+
+        config.channels.insert(
+            "Xithrius".to_string(),
+            ChannelConfig {
+                accent: "#9146FF".to_string(),
+            },
+        );
+        config.channels.insert(
+            "broken".to_string(),
+            ChannelConfig {
+                accent: "not a color".to_string(),
+            },
+        );
+
+        assert_eq!(
+            config.channel_accent("xithrius"),
+            Some(Color::Rgb(0x91, 0x46, 0xFF))
+        );
+
+        // A channel without an accent, or with one that is not a color, has none.
+        assert_eq!(config.channel_accent("other"), None);
+        assert_eq!(config.channel_accent("broken"), None);
+    }
+}
@@ -3,7 +3,9 @@ use fuzzy_matcher::{skim::SkimMatcherV2, FuzzyMatcher};
 use log::{error, warn};
 use memchr::memmem;
 use once_cell::sync::Lazy;
-use std::{borrow::Cow, string::ToString};
+use std::{
+    borrow::Cow, cell::RefCell, collections::HashMap, ops::Range, string::ToString, time::Duration,
+};
 use tui::{
     style::{Color, Color::Rgb, Modifier, Style},
     text::{Line, Span},
@@ -11,49 +13,225 @@ use tui::{
 use unicode_width::UnicodeWidthStr;
 
 use crate::{
-    emotes::{display_emote, load_emote, overlay_emote, EmoteData, SharedEmotes},
-    handlers::config::{FrontendConfig, Palette, Theme},
+    emotes::{
+        display_emote, load_emote, overlay_emote, DownloadedEmotes, EmoteData, EmoteProvider,
+        SharedEmotes, Thumbnail,
+    },
+    handlers::config::{
+        ControlCharacters, DimmedConfig, EmoteText, FrontendConfig, Palette, RenderMode, Theme,
+        TimestampPosition,
+    },
+    twitch::{
+        badges::{Role, Subscription},
+        eventsub::EventSubEvent,
+        identity::{ChannelUserState, SelfIdentity},
+        mod_log::ModLogEntry,
+        raid::Raid,
+        ConnectionState,
+    },
     ui::statics::NAME_MAX_CHARACTERS,
     utils::{
-        colors::{hsl_to_rgb, u32_to_color},
+        colors::{ensure_contrast, hsl_to_rgb, u32_to_color},
         emotes::{
             get_emote_offset, UnicodePlaceholder, PRIVATE_USE_UNICODE, ZERO_WIDTH_SPACE,
             ZERO_WIDTH_SPACE_STR,
         },
+        language::detect_language,
         styles::{
-            DATETIME_DARK, DATETIME_LIGHT, HIGHLIGHT_NAME_DARK, HIGHLIGHT_NAME_LIGHT, SYSTEM_CHAT,
+            ASCII_ART_PLACEHOLDER, DATETIME_DARK, DATETIME_LIGHT, EDITED_MARKER,
+            HIGHLIGHT_NAME_DARK, HIGHLIGHT_NAME_LIGHT, LINK_MARKER, MENTION, REMOVED_MARKER,
+            SHOW_MORE_MARKER, SYSTEM_CHAT,
+        },
+        text::{
+            align_right, display_width, extract_urls, split_cow_in_place, truncate_to_width,
+            wrap_tokens,
         },
-        text::split_cow_in_place,
     },
 };
 
+/// Shown instead of messages that look like ASCII art, until they are expanded.
+const ASCII_ART_COLLAPSED: &str = "<ASCII art, expand>";
+
+/// Ends messages that were cut short, until they are expanded.
+const SHOW_MORE: &str = " … (show more)";
+
+/// Shown before the body of messages that contain links, when they are marked.
+const LINK_GLYPH: &str = "🔗";
+
+/// Shown before the body of messages that were sent again in place of a deleted one.
+const EDITED_LABEL: &str = "(edited)";
+
+/// Ends messages that are cut off at the edge of chat.
+const ELLIPSIS: &str = "…";
+
+/// Goes before how many times an emote was repeated, when runs of it are collapsed.
+const MULTIPLIER: char = '×';
+
+/// If a word is the count that a run of emotes was collapsed into, such as `×3`.
+fn is_multiplier(word: &str) -> bool {
+    word.strip_prefix(MULTIPLIER)
+        .is_some_and(|count| !count.is_empty() && count.bytes().all(|b| b.is_ascii_digit()))
+}
+
+/// Shown in front of the names of users with a recently created account.
+const NEW_ACCOUNT_MARKER: char = '\u{26A0}';
+
+/// Left border of every row of an announcement, in the color of the announcement.
+const ANNOUNCEMENT_BORDER: &str = "\u{258C}";
+
+/// Columns taken up by the parts of a message, depending on where its timestamp goes.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct MessageColumns {
+    /// The timestamp in front of the author, with the space after it.
+    pub left: usize,
+    /// The timestamp column along the right edge, with the space before it.
+    pub right: usize,
+    /// What is left for the author and body to wrap within, on every row.
+    pub body: usize,
+}
+
+impl MessageColumns {
+    pub const fn new(position: TimestampPosition, width: usize, timestamp_width: usize) -> Self {
+        match position {
+            TimestampPosition::Left => Self {
+                left: timestamp_width + 1,
+                right: 0,
+                body: width,
+            },
+            // The column is kept free on every row, so that wrapped lines stay aligned.
+            TimestampPosition::Right => Self {
+                left: 0,
+                right: timestamp_width + 1,
+                body: width.saturating_sub(timestamp_width + 1),
+            },
+            TimestampPosition::Hidden => Self {
+                left: 0,
+                right: 0,
+                body: width,
+            },
+        }
+    }
+}
+
+/// Why a message that is kept in chat was removed by moderators.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Removal {
+    Deleted,
+    /// The author was timed out or banned.
+    TimedOut,
+}
+
+impl Removal {
+    pub const fn marker(self) -> &'static str {
+        match self {
+            Self::Deleted => "<deleted>",
+            Self::TimedOut => "<timed out>",
+        }
+    }
+}
+
 static FUZZY_FINDER: Lazy<SkimMatcherV2> = Lazy::new(SkimMatcherV2::default);
 
 pub enum TwitchToTerminalAction {
     Message(MessageData),
+    /// Messages sent before the channel was joined, from oldest to newest.
+    History(Vec<MessageData>),
     ClearChat(Option<String>),
     DeleteMessage(String),
+    /// The user with this id has a recently created account.
+    NewAccount(String),
+    /// Another channel raiding the current one.
+    Raid(Raid),
+    /// The notice of a user that chats for the first time, which raid digests condense.
+    NewChatter(MessageData),
+    Connection(ConnectionState),
+    EventSub(EventSubEvent),
+    Moderation(ModLogEntry),
+    /// If the channel switched to or from only allowing messages made of emotes.
+    EmoteOnly(bool),
+    /// The tags of a ROOMSTATE, being the room modes that changed.
+    RoomState(HashMap<String, String>),
+    /// Who the user is logged in as, from GLOBALUSERSTATE.
+    SelfIdentity(SelfIdentity),
+    /// What the user is in a channel, from USERSTATE.
+    UserState(ChannelUserState),
 }
 
 enum Word {
-    Emote(Vec<EmoteData>),
-    Text(String),
+    /// The layers of an emote shown as an image, and the names of them.
+    Emote(Vec<EmoteData>, Vec<String>),
+    /// Text of the word, and the ranges within it that are marked as emotes.
+    Text(String, Vec<(Range<usize>, EmoteProvider)>),
+}
+
+/// What the wrapped lines of a message were rendered for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct LinesKey {
+    width: usize,
+    name_width: usize,
+    search_highlight: Option<String>,
+    username_highlight: Option<String>,
+    grouped: bool,
+}
+
+#[derive(Debug, Clone)]
+struct CachedLines {
+    key: LinesKey,
+    lines: Vec<Line<'static>>,
 }
 
 #[derive(Debug, Clone)]
 pub struct MessageData {
     pub time_sent: DateTime<Local>,
     pub author: String,
+    /// The lowercase login of the author, which unlike the shown name never changes.
+    pub login: Option<String>,
     pub user_id: Option<String>,
     pub system: bool,
     pub payload: String,
     pub emotes: Vec<(Color, Color)>,
+    /// The names of the emotes in `emotes`, where their placeholders are in the payload.
+    pub emote_names: Vec<String>,
+    /// Byte ranges of the payload which are emotes rendered as text, and where they come from.
+    pub emote_ranges: Vec<(Range<usize>, EmoteProvider)>,
     pub message_id: Option<String>,
     pub highlight: bool,
+    /// If the message was sent before the channel was joined.
+    pub history: bool,
+    /// If the author has a recently created account.
+    pub new_account: bool,
+    /// The color of the announcement that this message is, if it is one.
+    pub announcement: Option<Color>,
+    /// A preview of the image linked in the message, shown below it.
+    pub thumbnail: Option<Thumbnail>,
+    /// If moderators removed the message, while it is still shown.
+    pub removed: Option<Removal>,
+    /// If the message took the place of one of the author's that was deleted just before.
+    pub edited: bool,
+    /// If the message looks like ASCII art, which can be collapsed.
+    pub ascii_art: bool,
+    /// If only a placeholder is shown instead of the message.
+    pub collapsed: bool,
+    /// If the message is shown in full, even if it is longer than the lines messages are cut to.
+    pub expanded: bool,
+    /// The ISO 639-3 code of the language of the message, if it could be told.
+    pub language: Option<String>,
+    /// The highest role of the author in the channel, from their badges.
+    pub badge_role: Option<Role>,
+    /// The highest role of the author in the channel, from their badges and the roles that the
+    /// channel lists.
+    pub role: Option<Role>,
+    /// The subscription of the author, when it is shown.
+    pub subscription: Option<Subscription>,
+    /// The last wrapped lines of the message, so that they are not rendered every frame.
+    lines_cache: RefCell<Option<Box<CachedLines>>>,
 }
 
 type Highlight<'a> = (&'a [usize], Style);
 
+/// Sorted byte indices of emotes rendered as text, with the style of each.
+type EmoteHighlight<'a> = &'a [(usize, Style)];
+
 impl MessageData {
     pub fn new(
         author: String,
@@ -66,18 +244,374 @@ impl MessageData {
         Self {
             time_sent: Local::now(),
             author,
+            login: None,
             user_id,
             system,
             payload,
             emotes: vec![],
+            emote_names: vec![],
+            emote_ranges: vec![],
             message_id,
             highlight,
+            history: false,
+            new_account: false,
+            announcement: None,
+            thumbnail: None,
+            removed: None,
+            edited: false,
+            ascii_art: false,
+            collapsed: false,
+            expanded: false,
+            language: None,
+            badge_role: None,
+            role: None,
+            subscription: None,
+            lines_cache: RefCell::new(None),
+        }
+    }
+
+    /// The name of the author as it is shown, with what is known about them around it.
+    pub fn shown_author(&self) -> Cow<'_, str> {
+        if !self.new_account && self.subscription.is_none() {
+            return Cow::Borrowed(self.author.as_str());
+        }
+
+        let marker = if self.new_account {
+            NEW_ACCOUNT_MARKER.to_string()
+        } else {
+            String::new()
+        };
+
+        let label = self
+            .subscription
+            .map(|subscription| format!(" [{}]", subscription.label()))
+            .unwrap_or_default();
+
+        Cow::Owned(format!("{marker}{}{label}", self.author))
+    }
+
+    /// Forget the cached lines, which must be done after changing anything that is rendered.
+    pub fn invalidate_lines(&self) {
+        self.lines_cache.replace(None);
+    }
+
+    /// Same as [`MessageData::to_vec`], reusing the lines from the last call with the same arguments.
+    pub fn wrapped_lines(
+        &self,
+        frontend_config: &FrontendConfig,
+        width: usize,
+        name_width: usize,
+        search_highlight: Option<&str>,
+        username_highlight: Option<&str>,
+        grouped: bool,
+    ) -> Vec<Line<'static>> {
+        let key = LinesKey {
+            width,
+            name_width,
+            search_highlight: search_highlight.map(ToString::to_string),
+            username_highlight: username_highlight.map(ToString::to_string),
+            grouped,
+        };
+
+        if let Some(cached) = self.lines_cache.borrow().as_deref() {
+            if cached.key == key {
+                return cached.lines.clone();
+            }
+        }
+
+        let lines = self
+            .to_vec_in_column(
+                frontend_config,
+                width,
+                name_width,
+                search_highlight,
+                username_highlight,
+                grouped,
+            )
+            .into_iter()
+            .map(|line| Line {
+                spans: line
+                    .spans
+                    .into_iter()
+                    .map(|span| Span::styled(span.content.into_owned(), span.style))
+                    .collect(),
+                style: line.style,
+                alignment: line.alignment,
+            })
+            .collect::<Vec<Line<'static>>>();
+
+        self.lines_cache.replace(Some(Box::new(CachedLines {
+            key,
+            lines: lines.clone(),
+        })));
+
+        lines
+    }
+
+    /// If the message only consists of emotes, either rendered as text or as images.
+    pub fn is_emote_only(&self) -> bool {
+        let mut ranges = self.emote_ranges.iter().peekable();
+        let mut has_emotes = false;
+
+        for (i, c) in self.payload.char_indices() {
+            while ranges.next_if(|(r, _)| r.end <= i).is_some() {}
+
+            if c == PRIVATE_USE_UNICODE || ranges.peek().is_some_and(|(r, _)| r.contains(&i)) {
+                has_emotes = true;
+            } else if !c.is_whitespace() && c != ZERO_WIDTH_SPACE {
+                return false;
+            }
+        }
+
+        has_emotes
+    }
+
+    /// The byte ranges of the words of the message, split at spaces, and if each is an emote.
+    ///
+    /// Words are emotes if Twitch marked them as one, or if `is_emote` tells that they are.
+    fn emote_words(&self, is_emote: impl Fn(&str) -> bool) -> Vec<(Range<usize>, bool)> {
+        let mut word_start = 0;
+
+        self.payload
+            .split(' ')
+            .map(|word| {
+                let range = word_start..word_start + word.len();
+                word_start = range.end + 1;
+
+                let emote = !word.is_empty()
+                    && (is_emote(word) || self.emote_ranges.iter().any(|(r, _)| *r == range));
+
+                (range, emote)
+            })
+            .collect()
+    }
+
+    /// Rebuild the message out of some of its words, each followed by its own text if it has any,
+    /// keeping the emotes that Twitch marked within the words.
+    fn keep_words(&mut self, words: &[(Range<usize>, Option<String>)]) {
+        let mut payload = String::with_capacity(self.payload.len());
+        let mut emote_ranges = vec![];
+
+        for (range, after) in words {
+            if !payload.is_empty() {
+                payload.push(' ');
+            }
+
+            let offset = payload.len();
+
+            emote_ranges.extend(
+                self.emote_ranges
+                    .iter()
+                    .filter(|(r, _)| r.start >= range.start && r.end <= range.end)
+                    .map(|(r, p)| {
+                        (
+                            (r.start - range.start + offset)..(r.end - range.start + offset),
+                            *p,
+                        )
+                    }),
+            );
+
+            payload.push_str(&self.payload[range.clone()]);
+
+            if let Some(after) = after {
+                payload.push(' ');
+                payload.push_str(after);
+            }
+        }
+
+        self.payload = payload;
+        self.emote_ranges = emote_ranges;
+    }
+
+    /// Strip or mark the invisible characters that could hide or reorder the text of the message,
+    /// leaving the emotes that Twitch marked in it as they are.
+    pub fn guard_control_characters(&mut self, mode: ControlCharacters) {
+        if mode == ControlCharacters::Keep {
+            return;
+        }
+
+        let mut payload = String::with_capacity(self.payload.len());
+        let mut emote_ranges = Vec::with_capacity(self.emote_ranges.len());
+        let mut last = 0;
+
+        for (range, provider) in &self.emote_ranges {
+            payload.push_str(&mode.apply(&self.payload[last..range.start]));
+
+            let start = payload.len();
+            payload.push_str(&self.payload[range.clone()]);
+            emote_ranges.push((start..payload.len(), *provider));
+
+            last = range.end;
+        }
+
+        payload.push_str(&mode.apply(&self.payload[last..]));
+
+        self.payload = payload;
+        self.emote_ranges = emote_ranges;
+    }
+
+    /// Collapse runs of the same emote into one, followed by how many there were, such as `Kappa ×3`.
+    ///
+    /// Words are emotes if Twitch marked them as one, or if `is_emote` tells that they are.
+    /// This has to happen before [`MessageData::parse_emotes`], which then shows the emote as an image.
+    pub fn collapse_emote_runs(&mut self, is_emote: impl Fn(&str) -> bool) {
+        let words = self.emote_words(is_emote);
+
+        let runs = emote_runs(
+            &words
+                .iter()
+                .map(|(range, emote)| (&self.payload[range.clone()], *emote))
+                .collect::<Vec<_>>(),
+        );
+
+        if runs.len() == words.len() {
+            return;
+        }
+
+        let kept = runs
+            .into_iter()
+            .map(|(first, count)| {
+                (
+                    words[first].0.clone(),
+                    (count > 1).then(|| format!("{MULTIPLIER}{count}")),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        self.keep_words(&kept);
+    }
+
+    /// Show at most `max` emotes of the message, where the ones past it are left out and
+    /// counted at the end, such as `+3 more`. The rest of the text is kept as it is, and a `max` of 0 does not limit them.
+    ///
+    /// Like [`MessageData::collapse_emote_runs`], this has to happen before [`MessageData::parse_emotes`].
+    pub fn cap_emotes(&mut self, max: usize, is_emote: impl Fn(&str) -> bool) {
+        if max == 0 {
+            return;
+        }
+
+        let words = self.emote_words(is_emote);
+
+        let mut emotes = 0;
+        let mut kept = vec![];
+        let mut dropped = false;
+
+        for (range, emote) in words {
+            if emote {
+                emotes += 1;
+                dropped = emotes > max;
+
+                if dropped {
+                    continue;
+                }
+            } else if dropped && is_multiplier(&self.payload[range.clone()]) {
+                // The count of a collapsed run goes along with its emote.
+                continue;
+            } else {
+                dropped = false;
+            }
+
+            kept.push((range, None));
+        }
+
+        let hidden = emotes.saturating_sub(max);
+
+        if hidden == 0 {
+            return;
+        }
+
+        if let Some((_, after)) = kept.last_mut() {
+            *after = Some(format!("+{hidden} more"));
+        }
+
+        self.keep_words(&kept);
+    }
+
+    /// Tell the language of the message, leaving out its emotes. Messages of the client itself,
+    /// and ones made of emotes, have no language.
+    pub fn detect_language(&mut self) {
+        if self.system || self.is_emote_only() {
+            self.language = None;
+
+            return;
+        }
+
+        let mut ranges = self.emote_ranges.iter().peekable();
+
+        let text = self
+            .payload
+            .char_indices()
+            .filter(|&(i, c)| {
+                while ranges.next_if(|(r, _)| r.end <= i).is_some() {}
+
+                c != PRIVATE_USE_UNICODE
+                    && c != ZERO_WIDTH_SPACE
+                    && !ranges.peek().is_some_and(|(r, _)| r.contains(&i))
+            })
+            .map(|(_, c)| c)
+            .collect::<String>();
+
+        self.language = detect_language(&text);
+    }
+
+    /// If the message contains a link.
+    pub fn has_link(&self) -> bool {
+        !self.system && !extract_urls(&self.payload).is_empty()
+    }
+
+    /// If this message is from one of the dimmed users, or starts with the command prefix.
+    pub fn dimmed(&self, config: &DimmedConfig) -> bool {
+        if self.system {
+            return false;
         }
+
+        let from_user = config.users.iter().any(|user| {
+            user.eq_ignore_ascii_case(&self.author)
+                || self
+                    .login
+                    .as_ref()
+                    .is_some_and(|login| user.eq_ignore_ascii_case(login))
+        });
+
+        from_user
+            || (!config.command_prefix.is_empty()
+                && self.payload.starts_with(&config.command_prefix))
+    }
+
+    /// If this message directly follows the previous one from the same author within the window,
+    /// so that its header (time and author) can be left out.
+    pub fn continues_group(&self, previous: &Self, window: Duration) -> bool {
+        !self.system
+            && !previous.system
+            && self.author == previous.author
+            && self
+                .time_sent
+                .signed_duration_since(previous.time_sent)
+                .to_std()
+                .is_ok_and(|gap| gap <= window)
+    }
+
+    /// The color that the name of the author is shown in, readable on the background of the theme.
+    pub fn author_color(&self, frontend_config: &FrontendConfig) -> Color {
+        let background = match frontend_config.theme {
+            Theme::Dark => Color::Rgb(0, 0, 0),
+            _ => Color::Rgb(255, 255, 255),
+        };
+
+        ensure_contrast(
+            self.hash_username(&frontend_config.palette),
+            background,
+            frontend_config.min_contrast,
+        )
     }
 
+    /// The color of the author, which only depends on their login so that it stays the same
+    /// across reconnects and restarts, no matter which badges or display name are shown.
     fn hash_username(&self, palette: &Palette) -> Color {
         let hash = f64::from(
-            self.author
+            self.login
+                .as_deref()
+                .unwrap_or(&self.author)
                 .as_bytes()
                 .iter()
                 .map(|&b| u32::from(b))
@@ -131,6 +665,7 @@ impl MessageData {
         default_style: Style,
         (search_highlight, search_theme): Highlight,
         (username_highlight, username_theme): Highlight,
+        emote_highlight: EmoteHighlight,
     ) -> Vec<Span<'s>> {
         const HAS_NO_HIGHLIGHTS: fn(&[usize], &usize, &usize) -> bool =
             |highlight: &[usize], start: &usize, end: &usize| {
@@ -144,6 +679,12 @@ impl MessageData {
 
         if HAS_NO_HIGHLIGHTS(search_highlight, &offset, start_index)
             && HAS_NO_HIGHLIGHTS(username_highlight, &offset, start_index)
+            && emote_highlight
+                .first()
+                .map_or(true, |(first, _)| *start_index < *first)
+            && emote_highlight
+                .last()
+                .map_or(true, |(last, _)| offset > *last)
         {
             return vec![Span::styled(line, default_style)];
         }
@@ -155,6 +696,8 @@ impl MessageData {
                     Span::styled(c.to_string(), search_theme)
                 } else if username_highlight.binary_search(&i).is_ok() {
                     Span::styled(c.to_string(), username_theme)
+                } else if let Ok(e) = emote_highlight.binary_search_by_key(&i, |(i, _)| *i) {
+                    Span::styled(c.to_string(), default_style.patch(emote_highlight[e].1))
                 } else {
                     Span::styled(c.to_string(), default_style)
                 }
@@ -168,6 +711,7 @@ impl MessageData {
         default_style: Style,
         search_highlight: Highlight,
         username_highlight: Highlight,
+        emote_highlight: EmoteHighlight,
         emotes: &mut &[(Color, Color)],
     ) -> Vec<Span<'s>> {
         static EMOTE_FINDER: Lazy<memmem::Finder> =
@@ -184,6 +728,7 @@ impl MessageData {
                 default_style,
                 search_highlight,
                 username_highlight,
+                emote_highlight,
             )
         } else {
             let mut spans: Vec<Span<'s>> = vec![];
@@ -206,6 +751,7 @@ impl MessageData {
                         default_style,
                         search_highlight,
                         username_highlight,
+                        emote_highlight,
                     ));
                 }
                 *start_index += ZERO_WIDTH_SPACE_STR.len();
@@ -222,24 +768,72 @@ impl MessageData {
         width: usize,
         search_highlight: Option<&str>,
         username_highlight: Option<&str>,
+        grouped: bool,
     ) -> Vec<Line> {
+        self.to_vec_in_column(
+            frontend_config,
+            width,
+            NAME_MAX_CHARACTERS,
+            search_highlight,
+            username_highlight,
+            grouped,
+        )
+    }
+
+    /// Same as [`MessageData::to_vec`], with right aligned names in a column `name_width` wide.
+    pub fn to_vec_in_column(
+        &self,
+        frontend_config: &FrontendConfig,
+        width: usize,
+        name_width: usize,
+        search_highlight: Option<&str>,
+        username_highlight: Option<&str>,
+        grouped: bool,
+    ) -> Vec<Line<'_>> {
+        // Collapsed messages only show a placeholder, with nothing of the message highlighted.
+        let body = if self.collapsed {
+            ASCII_ART_COLLAPSED
+        } else {
+            self.payload.as_str()
+        };
+
         // Theme styles
-        let fg = self.hash_username(&frontend_config.palette);
+        let fg = self.author_color(frontend_config);
 
         let message_theme = if self.highlight {
             Style::default().fg(fg).add_modifier(Modifier::ITALIC)
         } else {
             Style::default()
         };
+        let dimmed = self.dimmed(&frontend_config.dimmed);
+
+        let message_theme = if self.history || self.removed.is_some() || dimmed {
+            message_theme.add_modifier(Modifier::DIM)
+        } else {
+            message_theme
+        };
+        let message_theme = if self.collapsed {
+            message_theme.patch(ASCII_ART_PLACEHOLDER)
+        } else {
+            message_theme
+        };
         let username_theme = match frontend_config.theme {
             Theme::Dark => HIGHLIGHT_NAME_DARK,
             _ => HIGHLIGHT_NAME_LIGHT,
         };
         let author_theme = if self.system {
             SYSTEM_CHAT
+        } else if self.removed.is_some() || dimmed {
+            Style::default().fg(fg).add_modifier(Modifier::DIM)
         } else {
             Style::default().fg(fg)
         };
+        // VIPs, moderators and the broadcaster stand out from the rest of chat.
+        let author_theme = if self.role >= Some(Role::Vip) {
+            author_theme.add_modifier(Modifier::BOLD)
+        } else {
+            author_theme
+        };
         let datetime_theme = match frontend_config.theme {
             Theme::Dark => DATETIME_DARK,
             _ => DATETIME_LIGHT,
@@ -249,8 +843,7 @@ impl MessageData {
         // All indices to highlight like a user
         let username_highlight = username_highlight
             .map(|name| {
-                self.payload
-                    .match_indices(name)
+                body.match_indices(name)
                     .flat_map(|(index, _)| index..(index + name.len()))
                     .collect::<Vec<usize>>()
             })
@@ -259,58 +852,161 @@ impl MessageData {
         // All indices to highlight like a search result
         let search_highlight = search_highlight
             .and_then(|query| {
-                FUZZY_FINDER
-                    .fuzzy_indices(&self.payload, query)
-                    .map(|(_, indices)| {
-                        // `username_highlight` indices are byte indices, whereas `fuzzy_indices` returns char indices.
-                        // Convert those char indices to byte indices, which are easier to work with.
-                        Self::char_to_byte_indices(&self.payload, indices.into_iter())
-                    })
+                FUZZY_FINDER.fuzzy_indices(body, query).map(|(_, indices)| {
+                    // `username_highlight` indices are byte indices, whereas `fuzzy_indices` returns char indices.
+                    // Convert those char indices to byte indices, which are easier to work with.
+                    Self::char_to_byte_indices(body, indices.into_iter())
+                })
             })
             .unwrap_or_default();
 
+        // All indices of emotes rendered as text, and of mentions of users
+        let mentions = if frontend_config.style_mentions && !self.collapsed {
+            mention_ranges(body)
+        } else {
+            vec![]
+        };
+
+        let mut emote_highlight = self
+            .emote_ranges
+            .iter()
+            .filter(|_| !self.collapsed)
+            .flat_map(|(range, provider)| {
+                let style = frontend_config.emote_styles.get(*provider).style();
+
+                range.clone().map(move |i| (i, style))
+            })
+            .chain(
+                mentions
+                    .into_iter()
+                    .flat_map(|range| range.map(|i| (i, MENTION))),
+            )
+            .collect::<Vec<(usize, Style)>>();
+
+        // Looked up by index while highlighting.
+        emote_highlight.sort_by_key(|(i, _)| *i);
+
         let search = (&search_highlight as &[usize], search_theme);
         let username = (&username_highlight as &[usize], username_theme);
+        let emote = &emote_highlight as EmoteHighlight;
 
         // Message prefix
-        let time_sent = if frontend_config.show_datetimes {
+        let timestamp_position = if frontend_config.show_datetimes {
+            frontend_config.timestamp_position
+        } else {
+            TimestampPosition::Hidden
+        };
+
+        let time_sent = if timestamp_position == TimestampPosition::Hidden {
+            None
+        } else {
             Some(
                 self.time_sent
                     .format(&frontend_config.datetime_format)
                     .to_string(),
             )
-        } else {
-            None
         };
 
-        // Add 1 for the space after the timestamp
-        let time_sent_len = time_sent.as_ref().map_or(0, |t| t.len() + 1);
+        let author = self.shown_author();
+
+        // Add 1 for the space after the marker
+        let removed_marker = self.removed.map(Removal::marker);
+        let removed_marker_len = removed_marker.map_or(0, |m| m.width() + 1);
+
+        let edited_marker = self.edited.then_some(EDITED_LABEL);
+
+        let link_marker = (frontend_config.link_marker && !self.collapsed && self.has_link())
+            .then_some(LINK_GLYPH);
+        let markers_len = removed_marker_len
+            + edited_marker.map_or(0, |m| m.width() + 1)
+            + link_marker.map_or(0, |m| m.width() + 1);
+
+        let border = self
+            .announcement
+            .map(|color| Span::styled(ANNOUNCEMENT_BORDER, Style::default().fg(color)));
+
+        // Width of the window - window margin on both sides - announcement border
+        let columns = MessageColumns::new(
+            timestamp_position,
+            {
+                // Add 1 for the border line
+                let window_margin = usize::from(frontend_config.margin) + 1;
+                width - window_margin * 2
+            } - 1
+                - border.as_ref().map_or(0, Span::width),
+            time_sent.as_deref().map_or(0, display_width),
+        );
+
+        // Messages are kept to one line, unless they are to be shown in full.
+        let classic = frontend_config.render_mode == RenderMode::Classic;
+        let one_line = classic && !self.expanded;
 
         let prefix_len = if frontend_config.username_shown {
-            // Add 2 for the ": "
-            time_sent_len + self.author.len() + 2
+            // Add 2 for the ": ", or 3 for the "<" and "> "
+            columns.left + author.width() + if classic { 3 } else { 2 }
         } else {
-            time_sent_len
-        };
+            columns.left
+        } + markers_len;
 
-        // Width of the window - window margin on both sides
-        let wrap_limit = {
-            // Add 1 for the border line
-            let window_margin = usize::from(frontend_config.margin) + 1;
-            width - window_margin * 2
-        } - 1;
+        let wrap_limit = columns.body;
 
         let prefix = " ".repeat(prefix_len);
-        let opts = textwrap::Options::new(wrap_limit).initial_indent(&prefix);
-        let wrapped_message = textwrap::wrap(&self.payload, opts);
+        let mut wrapped_message = if one_line {
+            vec![Cow::Owned(format!("{prefix}{body}"))]
+        } else {
+            wrap_tokens(body, wrap_limit, &prefix, frontend_config.word_lines)
+        };
         if wrapped_message.is_empty() {
             return vec![];
         }
+
+        // Cut off at the edge of chat, leaving room for the ellipsis.
+        let clipped = one_line
+            && truncate_to_width(&wrapped_message[0], wrap_limit).len() < wrapped_message[0].len();
+
+        if clipped {
+            let fits = truncate_to_width(&wrapped_message[0], wrap_limit.saturating_sub(1))
+                .len()
+                .max(prefix_len);
+
+            wrapped_message[0].to_mut().truncate(fits);
+        }
+
+        let max_lines = frontend_config.truncate_lines;
+        let truncated =
+            max_lines > 0 && !self.expanded && !one_line && wrapped_message.len() > max_lines;
+
+        if truncated {
+            wrapped_message.truncate(max_lines);
+
+            // The first line starts with the prefix, which has to be kept whole.
+            let min_len = if max_lines == 1 { prefix_len } else { 0 };
+
+            if let Some(last) = wrapped_message.last_mut() {
+                let fits = truncate_to_width(last, wrap_limit.saturating_sub(SHOW_MORE.width()));
+
+                // Cut at the end of the last word that fits, unless it is the only one.
+                let fits = if last[fits.len()..].starts_with(' ') {
+                    fits
+                } else {
+                    fits.rfind(' ')
+                        .filter(|&i| i > min_len)
+                        .map_or(fits, |i| &fits[..i])
+                };
+
+                let fits = fits.trim_end().len().max(min_len);
+
+                match last {
+                    Cow::Borrowed(s) => *s = &s[..fits],
+                    Cow::Owned(s) => s.truncate(fits),
+                }
+            }
+        }
         let mut lines = wrapped_message.into_iter();
 
         let username_alignment = if frontend_config.username_shown {
             if frontend_config.right_align_usernames {
-                NAME_MAX_CHARACTERS.saturating_sub(self.author.width()) + 1
+                name_width.saturating_sub(author.width()) + 1
             } else {
                 1
             }
@@ -318,20 +1014,40 @@ impl MessageData {
             1
         };
 
-        let mut first_row: Vec<Span<'_>> = vec![];
+        let mut first_row: Vec<Span<'_>> = border.iter().cloned().collect();
+
+        if grouped {
+            // Align the body with the messages above it, without repeating the header.
+            first_row.push(Span::raw(" ".repeat(prefix_len - markers_len)));
+        } else {
+            if let Some(t) = time_sent.clone().filter(|_| columns.left > 0) {
+                first_row.extend(vec![
+                    Span::styled(t, datetime_theme),
+                    Span::raw(" ".repeat(username_alignment)),
+                ]);
+            }
+
+            if frontend_config.username_shown && classic {
+                first_row.extend(vec![
+                    Span::raw("<"),
+                    Span::styled(author, author_theme),
+                    Span::raw("> "),
+                ]);
+            } else if frontend_config.username_shown {
+                first_row.extend(vec![Span::styled(author, author_theme), Span::raw(": ")]);
+            }
+        }
+
+        if let Some(marker) = removed_marker {
+            first_row.push(Span::styled(format!("{marker} "), REMOVED_MARKER));
+        }
 
-        if let Some(t) = time_sent {
-            first_row.extend(vec![
-                Span::styled(t, datetime_theme),
-                Span::raw(" ".repeat(username_alignment)),
-            ]);
+        if let Some(marker) = edited_marker {
+            first_row.push(Span::styled(format!("{marker} "), EDITED_MARKER));
         }
 
-        if frontend_config.username_shown {
-            first_row.extend(vec![
-                Span::styled(&self.author, author_theme),
-                Span::raw(": "),
-            ]);
+        if let Some(marker) = link_marker {
+            first_row.push(Span::styled(format!("{marker} "), LINK_MARKER));
         }
 
         let mut next_index = 0;
@@ -340,7 +1056,11 @@ impl MessageData {
         let mut first_line = lines.next().unwrap();
         let first_line_msg = split_cow_in_place(&mut first_line, prefix_len);
 
-        let mut emotes = &self.emotes[..];
+        let mut emotes = if self.collapsed {
+            &[][..]
+        } else {
+            &self.emotes[..]
+        };
 
         first_row.extend(Self::build_line(
             first_line_msg,
@@ -348,79 +1068,239 @@ impl MessageData {
             message_theme,
             search,
             username,
+            emote,
             &mut emotes,
         ));
 
+        if clipped {
+            first_row.push(Span::styled(ELLIPSIS, SHOW_MORE_MARKER));
+        }
+
+        if let Some(t) = time_sent.filter(|_| columns.right > 0 && !grouped) {
+            let used = first_row.iter().map(Span::width).sum::<usize>();
+            let border_width = border.as_ref().map_or(0, Span::width);
+
+            first_row.push(Span::styled(
+                align_right(
+                    &t,
+                    (border_width + columns.body + columns.right).saturating_sub(used),
+                ),
+                datetime_theme,
+            ));
+        }
+
         let mut rows = vec![Line::from(first_row)];
 
         rows.extend(lines.map(|line| {
-            Line::from(Self::build_line(
+            // Wrapping drops the spaces at which a line was broken, skip over them.
+            next_index += self
+                .payload
+                .get(next_index..)
+                .map_or(0, |rest| rest.bytes().take_while(|&b| b == b' ').count());
+
+            let mut row: Vec<Span<'_>> = border.iter().cloned().collect();
+
+            row.extend(Self::build_line(
                 line,
                 &mut next_index,
                 message_theme,
                 search,
                 username,
+                emote,
                 &mut emotes,
-            ))
+            ));
+
+            Line::from(row)
         }));
 
+        if truncated {
+            if let Some(row) = rows.last_mut() {
+                row.spans.push(Span::styled(SHOW_MORE, SHOW_MORE_MARKER));
+            }
+        }
+
+        if let Some(thumbnail) = self.thumbnail.filter(|_| !one_line) {
+            rows.extend((0..thumbnail.rows).map(|i| {
+                let (placeholder, (id, pid)) = thumbnail.placeholder_row(i);
+
+                let mut row: Vec<Span<'_>> = border.iter().cloned().collect();
+                row.push(Span::styled(
+                    placeholder,
+                    Style::default().fg(id).underline_color(pid),
+                ));
+
+                Line::from(row)
+            }));
+        }
+
         rows
     }
 
-    /// Splits the payload by spaces, then check every word to see if they match an emote.
-    /// If they do, tell the terminal to load the emote, and replace the word by a [`UnicodePlaceholder`].
-    /// The emote will then be displayed by the terminal by encoding its id in its foreground color, and its pid in its underline color.
-    /// Ratatui removes all ansi escape sequences, so the id/pid of the emote is stored and encoded in [`MessageData::to_vec`].
-    pub fn parse_emotes(&mut self, emotes: &SharedEmotes) {
-        if emotes.emotes.borrow().is_empty() {
-            return;
+    /// The columns of the timestamp and of the author on the first row of this message,
+    /// as rendered by [`MessageData::to_vec`], when they are shown.
+    pub fn header_columns(
+        &self,
+        frontend_config: &FrontendConfig,
+        first_row: &Line,
+        grouped: bool,
+    ) -> (Option<Range<usize>>, Option<Range<usize>>) {
+        if grouped {
+            return (None, None);
         }
 
-        let mut words = Vec::new();
+        let mut columns = vec![];
+        let mut used = 0;
 
-        let cell_size = *emotes
-            .cell_size
-            .get()
-            .expect("Terminal cell_size must be defined when emotes are enabled");
+        for span in &first_row.spans {
+            columns.push((used, span.content.as_ref()));
+            used += span.width();
+        }
 
-        self.payload.split(' ').for_each(|word| {
-            let emotes_ref = emotes.emotes.borrow();
-            let Some((filename, zero_width)) = emotes_ref.get(word) else {
-                words.push(Word::Text(word.to_string()));
-                return;
-            };
+        let time = frontend_config
+            .show_datetimes
+            .then(|| {
+                self.time_sent
+                    .format(&frontend_config.datetime_format)
+                    .to_string()
+            })
+            .filter(|time| !time.is_empty());
 
-            let Ok(loaded_emote) = load_emote(
-                word,
-                filename,
-                *zero_width,
-                &mut emotes.info.borrow_mut(),
-                cell_size,
-            )
-            .map_err(|e| warn!("Unable to load emote {word} ({filename}): {e}")) else {
-                drop(emotes_ref);
-                emotes.emotes.borrow_mut().remove(word);
-                words.push(Word::Text(word.to_string()));
-                return;
+        // The timestamp comes first on the left after any border, or last on the right, padded.
+        let candidates = match frontend_config.timestamp_position {
+            TimestampPosition::Left => &columns[..columns.len().min(2)],
+            TimestampPosition::Right => &columns[columns.len().saturating_sub(1)..],
+            TimestampPosition::Hidden => &[],
+        };
+
+        let timestamp = time.and_then(|time| {
+            candidates
+                .iter()
+                .find(|(_, content)| content.trim() == time)
+                .map(|(start, content)| {
+                    let start = start + content.len() - content.trim_start().len();
+
+                    start..start + display_width(&time)
+                })
+        });
+
+        let author = self.shown_author();
+
+        let name = columns
+            .iter()
+            .filter(|_| frontend_config.username_shown)
+            .find(|(_, content)| *content == author)
+            .map(|(start, _)| *start..start + author.width());
+
+        (timestamp, name)
+    }
+
+    /// Mark the words of the payload that are emotes, unless they already are, so that they are
+    /// styled like the emotes that the chat itself tells of.
+    fn mark_emotes(&mut self, emotes: &DownloadedEmotes) {
+        let mut start = 0;
+
+        for word in self.payload.split(' ') {
+            let range = start..start + word.len();
+            start = range.end + 1;
+
+            let Some((_, _, provider)) = emotes.get(word) else {
+                continue;
+            };
+
+            if !self
+                .emote_ranges
+                .iter()
+                .any(|(r, _)| r.start < range.end && range.start < r.end)
+            {
+                self.emote_ranges.push((range, *provider));
+            }
+        }
+
+        self.emote_ranges.sort_by_key(|(r, _)| r.start);
+    }
+
+    /// Splits the payload by spaces, then check every word to see if they match an emote.
+    /// If they do, tell the terminal to load the emote, and replace the word by a [`UnicodePlaceholder`].
+    /// The emote will then be displayed by the terminal by encoding its id in its foreground color, and its pid in its underline color.
+    /// Ratatui removes all ansi escape sequences, so the id/pid of the emote is stored and encoded in [`MessageData::to_vec`].
+    pub fn parse_emotes(&mut self, emotes: &SharedEmotes) {
+        if emotes.emotes.borrow().is_empty() {
+            return;
+        }
+
+        let Some(&cell_size) = emotes.cell_size.get() else {
+            // Without graphics the emotes stay as their names, which are only marked to be styled.
+            self.mark_emotes(&emotes.emotes.borrow());
+            return;
+        };
+
+        let mut words = Vec::new();
+
+        let mut word_start = 0;
+
+        self.payload.split(' ').for_each(|word| {
+            let (start, end) = (word_start, word_start + word.len());
+            word_start = end + 1;
+
+            // Emote ranges within this word, relative to the start of the word.
+            let word_emote_ranges = || {
+                self.emote_ranges
+                    .iter()
+                    .filter(|(r, _)| r.start >= start && r.end <= end)
+                    .map(|(r, p)| ((r.start - start)..(r.end - start), *p))
+                    .collect::<Vec<(Range<usize>, EmoteProvider)>>()
+            };
+
+            let emotes_ref = emotes.emotes.borrow();
+            let Some((filename, zero_width, provider)) = emotes_ref.get(word) else {
+                words.push(Word::Text(word.to_string(), word_emote_ranges()));
+                return;
+            };
+
+            let Ok(loaded_emote) = load_emote(
+                word,
+                filename,
+                *zero_width,
+                &mut emotes.info.borrow_mut(),
+                cell_size,
+            )
+            .map_err(|e| warn!("Unable to load emote {word} ({filename}): {e}")) else {
+                let provider = *provider;
+                drop(emotes_ref);
+                emotes.emotes.borrow_mut().remove(word);
+
+                // The emote still is one, even though its image could not be shown.
+                let mut ranges = word_emote_ranges();
+                if ranges.is_empty() {
+                    ranges.push((0..word.len(), provider));
+                }
+
+                words.push(Word::Text(word.to_string(), ranges));
+                return;
             };
 
             if loaded_emote.overlay {
                 // Check if last word is emote.
-                if let Some(Word::Emote(v)) = words.last_mut() {
+                if let Some(Word::Emote(v, names)) = words.last_mut() {
                     v.push(loaded_emote.into());
+                    names.push(word.to_string());
                     return;
                 }
             }
 
-            words.push(Word::Emote(vec![loaded_emote.into()]));
+            words.push(Word::Emote(
+                vec![loaded_emote.into()],
+                vec![word.to_string()],
+            ));
         });
 
         self.payload.clear();
+        self.emote_ranges.clear();
 
         // Join words by space, or by zero-width spaces if one of them is an emote.
         for w in words {
             match w {
-                Word::Text(s) => {
+                Word::Text(s, ranges) => {
                     if !self.payload.is_empty() {
                         self.payload
                             .push(if self.payload.ends_with(PRIVATE_USE_UNICODE) {
@@ -429,9 +1309,17 @@ impl MessageData {
                                 ' '
                             });
                     }
+
+                    let start = self.payload.len();
+                    self.emote_ranges.extend(
+                        ranges
+                            .into_iter()
+                            .map(|(r, p)| ((r.start + start)..(r.end + start), p)),
+                    );
+
                     self.payload.push_str(&s);
                 }
-                Word::Emote(v) => {
+                Word::Emote(v, names) => {
                     // Unwrapping here is fine as v is never empty.
                     let max_width = v
                         .iter()
@@ -465,6 +1353,7 @@ impl MessageData {
                     });
 
                     self.emotes.push((u32_to_color(id), u32_to_color(pid)));
+                    self.emote_names.push(names.join(" "));
 
                     if !self.payload.is_empty() {
                         self.payload.push(ZERO_WIDTH_SPACE);
@@ -475,6 +1364,154 @@ impl MessageData {
                 }
             }
         }
+
+        self.invalidate_lines();
+    }
+}
+
+/// If a character is an emoji, going by the blocks that emoji are in, along with the
+/// characters that join them together or pick how they look.
+const fn is_emoji(c: char) -> bool {
+    matches!(
+        c,
+        '\u{1F000}'..='\u{1FAFF}' | '\u{2600}'..='\u{27BF}' | '\u{FE0F}' | '\u{200D}' | '\u{20E3}'
+    )
+}
+
+/// The text of a message without anything that only makes sense on screen, such as for logs.
+/// Emotes are written by their names unless they are stripped, and emoji only kept as Unicode.
+pub fn message_to_plain(message: &MessageData, emote_mode: EmoteText) -> String {
+    let mut plain = String::with_capacity(message.payload.len());
+    let mut names = message.emote_names.iter();
+    let mut ranges = message.emote_ranges.iter().peekable();
+    let mut previous = None;
+
+    for (i, c) in message.payload.char_indices() {
+        while ranges.next_if(|(r, _)| r.end <= i).is_some() {}
+
+        let in_emote = ranges.peek().is_some_and(|(r, _)| r.contains(&i));
+
+        if c == PRIVATE_USE_UNICODE {
+            // Emotes shown as images are a run of placeholders.
+            if previous != Some(PRIVATE_USE_UNICODE) {
+                if let Some(name) = names.next().filter(|_| emote_mode != EmoteText::Strip) {
+                    plain.push_str(name);
+                }
+            }
+        } else if c == ZERO_WIDTH_SPACE {
+            plain.push(' ');
+        } else if is_emoji(c) {
+            if emote_mode == EmoteText::Unicode {
+                plain.push(c);
+            }
+        } else if !(in_emote && emote_mode == EmoteText::Strip) {
+            plain.push(c);
+        }
+
+        previous = Some(c);
+    }
+
+    plain.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// A message written out as a quote to share, with who sent it, where and when.
+pub fn message_to_quote(message: &MessageData, channel: &str, datetime_format: &str) -> String {
+    format!(
+        "{} in #{channel}, {}:\n> {}",
+        message.author,
+        message.time_sent.format(datetime_format),
+        message_to_plain(message, EmoteText::Unicode).trim()
+    )
+}
+
+/// Runs of the same emote among the words of a message, as the index of the first word of each
+/// run and how many words it has. Words are given with if they are an emote, and every word that
+/// is not one is a run of its own, breaking any run of emotes.
+pub fn emote_runs(words: &[(&str, bool)]) -> Vec<(usize, usize)> {
+    let mut runs: Vec<(usize, usize)> = vec![];
+
+    for (i, &(word, emote)) in words.iter().enumerate() {
+        match runs.last_mut() {
+            Some((first, count)) if emote && words[*first] == (word, true) => *count += 1,
+            _ => runs.push((i, 1)),
+        }
+    }
+
+    runs
+}
+
+/// Byte ranges of the `@login` mentions in a message, from the `@` to the end of the login.
+/// Only logins that Twitch would allow are mentions: 3 to 25 letters, digits or underscores,
+/// not starting with an underscore.
+pub fn mention_ranges(text: &str) -> Vec<Range<usize>> {
+    let is_login_char = |c: char| c.is_ascii_alphanumeric() || c == '_';
+
+    text.match_indices('@')
+        .filter(|&(start, _)| {
+            // Mentions start a word, unlike the `@` of an email address.
+            text[..start]
+                .chars()
+                .next_back()
+                .map_or(true, char::is_whitespace)
+        })
+        .filter_map(|(start, _)| {
+            let login = &text[start + 1..];
+            let len = login.find(|c| !is_login_char(c)).unwrap_or(login.len());
+
+            // The login has to end the word, apart from punctuation after it.
+            let ends_word = login[len..]
+                .chars()
+                .next()
+                .map_or(true, |c| c.is_whitespace() || c.is_ascii_punctuation());
+
+            ((3..=25).contains(&len) && !login.starts_with('_') && ends_word)
+                .then_some(start..start + 1 + len)
+        })
+        .collect()
+}
+
+/// Byte ranges of the words of a message that are not emotes, which would get it rejected
+/// in an emote-only chat. Empty if the message would go through.
+pub fn emote_only_rejections(text: &str, is_emote: impl Fn(&str) -> bool) -> Vec<Range<usize>> {
+    // Only commands that send a message matter.
+    let (offset, body) = match text.strip_prefix("/me ") {
+        Some(body) => (text.len() - body.len(), body),
+        None if text.starts_with('/') => return vec![],
+        None => (0, text),
+    };
+
+    let words = body
+        .split(' ')
+        .scan(offset, |start, word| {
+            let range = *start..*start + word.len();
+            *start = range.end + 1;
+
+            Some(range)
+        })
+        .filter(|range| !range.is_empty());
+
+    let (emotes, rejected): (Vec<_>, Vec<_>) =
+        words.partition(|range| is_emote(&text[range.clone()]));
+
+    let mut message = MessageData::new(
+        String::new(),
+        None,
+        false,
+        text[offset..].to_string(),
+        None,
+        false,
+    );
+
+    message.emote_ranges = emotes
+        .into_iter()
+        .map(|range| (range.start - offset)..(range.end - offset))
+        .map(|range| (range, EmoteProvider::Twitch))
+        .collect();
+
+    if message.is_emote_only() {
+        vec![]
+    } else {
+        rejected
     }
 }
 
@@ -525,7 +1562,10 @@ impl<'conf> DataBuilder<'conf> {
 
 #[cfg(test)]
 mod tests {
+    use chrono::TimeZone;
+
     use super::*;
+    use crate::handlers::config::EmoteStyle;
 
     #[test]
     fn test_username_hash() {
@@ -543,6 +1583,824 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_username_color_stable_across_reconnect() {
+        let message = |author: &str| {
+            let mut message_data = MessageData::new(
+                author.to_string(),
+                Some("1".to_string()),
+                false,
+                "beep boop".to_string(),
+                None,
+                false,
+            );
+            message_data.login = Some("xithrius".to_string());
+            message_data
+        };
+
+        // The same user before and after reconnecting, having gained a badge in between.
+        let before = message("Xithrius");
+        let after = message("\u{1F48E}Xithrius");
+
+        for palette in [
+            Palette::Pastel,
+            Palette::Vibrant,
+            Palette::Warm,
+            Palette::Cool,
+        ] {
+            assert_eq!(
+                before.hash_username(&palette),
+                after.hash_username(&palette)
+            );
+        }
+    }
+
+    #[test]
+    fn test_is_emote_only() {
+        let mut message = MessageData::new(
+            "human".to_string(),
+            None,
+            false,
+            "Kappa Kappa PogChamp".to_string(),
+            None,
+            false,
+        );
+
+        assert!(!message.is_emote_only());
+
+        message.emote_ranges = vec![
+            (0..5, EmoteProvider::Twitch),
+            (6..11, EmoteProvider::Twitch),
+        ];
+        assert!(!message.is_emote_only());
+
+        message.emote_ranges.push((12..20, EmoteProvider::SevenTV));
+        assert!(message.is_emote_only());
+    }
+
+    #[test]
+    fn test_message_to_plain() {
+        let mut message = MessageData::new(
+            "bob".to_string(),
+            None,
+            false,
+            format!(
+                "Kappa hi 😀 {PRIVATE_USE_UNICODE}{PRIVATE_USE_UNICODE}{ZERO_WIDTH_SPACE}there"
+            ),
+            None,
+            false,
+        );
+        message.emote_ranges = vec![(0..5, EmoteProvider::Twitch)];
+        message.emote_names = vec!["LUL".to_string()];
+
+        assert_eq!(
+            message_to_plain(&message, EmoteText::Names),
+            "Kappa hi LUL there"
+        );
+        assert_eq!(
+            message_to_plain(&message, EmoteText::Unicode),
+            "Kappa hi 😀 LUL there"
+        );
+        assert_eq!(message_to_plain(&message, EmoteText::Strip), "hi there");
+    }
+
+    #[test]
+    fn test_message_to_quote() {
+        let mut message = MessageData::new(
+            "Xithrius".to_string(),
+            None,
+            false,
+            format!("good game {PRIVATE_USE_UNICODE}{PRIVATE_USE_UNICODE} 😀"),
+            None,
+            false,
+        );
+        message.emote_names = vec!["GG".to_string()];
+        message.time_sent = Local.with_ymd_and_hms(2024, 3, 1, 20, 15, 0).unwrap();
+
+        assert_eq!(
+            message_to_quote(&message, "twitchdev", "%Y-%m-%d %H:%M"),
+            "Xithrius in #twitchdev, 2024-03-01 20:15:\n> good game GG 😀"
+        );
+    }
+
+    #[test]
+    fn test_emote_runs() {
+        let words = [
+            ("Kappa", true),
+            ("Kappa", true),
+            ("Kappa", true),
+            ("hi", false),
+            ("hi", false),
+            ("Kappa", true),
+            ("Kappa", true),
+            ("LUL", true),
+            ("Kappa", true),
+        ];
+
+        assert_eq!(
+            emote_runs(&words),
+            vec![(0, 3), (3, 1), (4, 1), (5, 2), (7, 1), (8, 1)]
+        );
+        assert!(emote_runs(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_collapse_emote_runs() {
+        let mut message = MessageData::new(
+            "bob".to_string(),
+            None,
+            false,
+            "Kappa Kappa Kappa gg Kappa LUL LUL".to_string(),
+            None,
+            false,
+        );
+        message.emote_ranges = vec![
+            (0..5, EmoteProvider::Twitch),
+            (6..11, EmoteProvider::Twitch),
+            (12..17, EmoteProvider::Twitch),
+            (21..26, EmoteProvider::Twitch),
+        ];
+
+        // LUL is only known as an emote from the downloaded ones.
+        message.collapse_emote_runs(|word| word == "LUL");
+
+        assert_eq!(message.payload, "Kappa ×3 gg Kappa LUL ×2");
+        assert_eq!(
+            message.emote_ranges,
+            vec![
+                (0..5, EmoteProvider::Twitch),
+                (13..18, EmoteProvider::Twitch)
+            ]
+        );
+
+        // Without runs, the message is left as it is.
+        let mut single = MessageData::new(
+            "bob".to_string(),
+            None,
+            false,
+            "gg gg Kappa".to_string(),
+            None,
+            false,
+        );
+        single.collapse_emote_runs(|word| word == "Kappa");
+        assert_eq!(single.payload, "gg gg Kappa");
+    }
+
+    #[test]
+    fn test_guard_control_characters() {
+        let mut message = MessageData::new(
+            "bob".to_string(),
+            None,
+            false,
+            "\u{202E}gg\u{202C} Kappa hi\u{200B}there Kappa".to_string(),
+            None,
+            false,
+        );
+        message.emote_ranges = vec![
+            (9..14, EmoteProvider::Twitch),
+            (26..31, EmoteProvider::Twitch),
+        ];
+
+        let mut marked = message.clone();
+
+        message.guard_control_characters(ControlCharacters::Strip);
+
+        assert_eq!(message.payload, "gg Kappa hithere Kappa");
+        assert_eq!(
+            message.emote_ranges,
+            vec![
+                (3..8, EmoteProvider::Twitch),
+                (17..22, EmoteProvider::Twitch)
+            ]
+        );
+
+        marked.guard_control_characters(ControlCharacters::Mark);
+
+        assert_eq!(marked.payload, "<RLO>gg<PDF> Kappa hi<ZWSP>there Kappa");
+        for (range, _) in &marked.emote_ranges {
+            assert_eq!(&marked.payload[range.clone()], "Kappa");
+        }
+    }
+
+    #[test]
+    fn test_cap_emotes() {
+        let mut message = MessageData::new(
+            "bob".to_string(),
+            None,
+            false,
+            "Kappa hi LUL PogChamp there Kappa ×3 omg LUL".to_string(),
+            None,
+            false,
+        );
+        message.emote_ranges = vec![
+            (0..5, EmoteProvider::Twitch),
+            (13..21, EmoteProvider::Twitch),
+            (28..33, EmoteProvider::Twitch),
+        ];
+
+        message.cap_emotes(2, |word| word == "LUL");
+
+        assert_eq!(message.payload, "Kappa hi LUL there omg +3 more");
+        assert_eq!(message.emote_ranges, vec![(0..5, EmoteProvider::Twitch)]);
+
+        // Messages within the cap, or without a cap, are left as they are.
+        let mut few = MessageData::new(
+            "bob".to_string(),
+            None,
+            false,
+            "gg Kappa".to_string(),
+            None,
+            false,
+        );
+        few.cap_emotes(1, |word| word == "Kappa");
+        assert_eq!(few.payload, "gg Kappa");
+
+        few.cap_emotes(0, |_| true);
+        assert_eq!(few.payload, "gg Kappa");
+    }
+
+    #[test]
+    fn test_mention_ranges() {
+        let text = "@Xithrius hi, @bob_42: look @ me@mail.com @_nope @ab @valid.";
+
+        let mentions = mention_ranges(text)
+            .into_iter()
+            .map(|range| &text[range])
+            .collect::<Vec<_>>();
+
+        assert_eq!(mentions, vec!["@Xithrius", "@bob_42", "@valid"]);
+        assert!(mention_ranges("@this_login_is_far_too_long_for_twitch").is_empty());
+        assert!(mention_ranges("@name😀").is_empty());
+    }
+
+    #[test]
+    fn test_mentions_styled() {
+        let message = MessageData::new(
+            "bob".to_string(),
+            None,
+            false,
+            "hi @alice".to_string(),
+            None,
+            false,
+        );
+
+        let lines = message.to_vec(&FrontendConfig::default(), 80, None, None, false);
+
+        let underlined = lines[0]
+            .spans
+            .iter()
+            .filter(|span| span.style.add_modifier.contains(Modifier::UNDERLINED))
+            .map(|span| span.content.as_ref())
+            .collect::<String>();
+
+        assert_eq!(underlined, "@alice");
+    }
+
+    #[test]
+    fn test_emote_only_rejections() {
+        let is_emote = |word: &str| ["Kappa", "PogChamp"].contains(&word);
+
+        assert!(emote_only_rejections("Kappa PogChamp  Kappa", is_emote).is_empty());
+        assert!(emote_only_rejections("/me Kappa", is_emote).is_empty());
+        assert!(emote_only_rejections("/ban troll", is_emote).is_empty());
+
+        assert_eq!(
+            emote_only_rejections("Kappa hello PogChamp world", is_emote),
+            vec![6..11, 21..26]
+        );
+        assert_eq!(emote_only_rejections("/me waves", is_emote), vec![4..9]);
+        assert_eq!(emote_only_rejections("hi", is_emote), vec![0..2]);
+    }
+
+    fn message_at(author: &str, seconds: i64) -> MessageData {
+        let mut message = MessageData::new(
+            author.to_string(),
+            None,
+            false,
+            "beep boop".to_string(),
+            None,
+            false,
+        );
+        message.time_sent = DateTime::from_timestamp(1_700_000_000 + seconds, 0)
+            .unwrap()
+            .with_timezone(&Local);
+
+        message
+    }
+
+    #[test]
+    fn test_continues_group() {
+        let window = Duration::from_secs(60);
+
+        let first = message_at("human", 0);
+        let same_author = message_at("human", 10);
+        let other_author = message_at("robot", 20);
+        let after_other_author = message_at("human", 30);
+        let after_gap = message_at("human", 200);
+
+        assert!(same_author.continues_group(&first, window));
+        assert!(!other_author.continues_group(&same_author, window));
+        assert!(!after_other_author.continues_group(&other_author, window));
+        assert!(!after_gap.continues_group(&after_other_author, window));
+    }
+
+    #[test]
+    fn test_header_columns() {
+        let config = FrontendConfig::default();
+        let message = message_at("human", 0);
+
+        let lines = message.to_vec(&config, 80, None, None, false);
+        let time = message
+            .time_sent
+            .format(&config.datetime_format)
+            .to_string();
+
+        let (timestamp, name) = message.header_columns(&config, &lines[0], false);
+
+        assert_eq!(timestamp, Some(0..time.width()));
+        assert_eq!(name, Some(time.width() + 1..time.width() + 6));
+        assert_eq!(
+            message.header_columns(&config, &lines[0], true),
+            (None, None)
+        );
+
+        let right = FrontendConfig {
+            timestamp_position: TimestampPosition::Right,
+            ..FrontendConfig::default()
+        };
+        let lines = message.to_vec(&right, 80, None, None, false);
+        let row = lines_text(&lines[..1]);
+        let start = row.find(&time).unwrap();
+
+        let (timestamp, name) = message.header_columns(&right, &lines[0], false);
+
+        assert_eq!(timestamp, Some(start..start + time.width()));
+        assert_eq!(name, Some(0..5));
+    }
+
+    #[test]
+    fn test_dimmed_messages() {
+        let config = DimmedConfig {
+            users: vec!["nightbot".to_string()],
+            command_prefix: "!".to_string(),
+        };
+
+        let mut bot = message_at("Nightbot", 0);
+        let mut command = message_at("human", 0);
+        command.payload = "!uptime".to_string();
+        let mut login = message_at("Botty", 0);
+        login.login = Some("NIGHTBOT".to_string());
+
+        assert!(bot.dimmed(&config));
+        assert!(command.dimmed(&config));
+        assert!(login.dimmed(&config));
+        assert!(!message_at("human", 0).dimmed(&config));
+
+        bot.system = true;
+        assert!(!bot.dimmed(&config));
+
+        let no_prefix = DimmedConfig {
+            command_prefix: String::new(),
+            ..config
+        };
+        assert!(!command.dimmed(&no_prefix));
+        assert!(!command.dimmed(&DimmedConfig::default()));
+    }
+
+    #[test]
+    fn test_grouped_message_has_no_header() {
+        let config = FrontendConfig::default();
+        let message = message_at("human", 0);
+
+        let header = message.to_vec(&config, 80, None, None, false);
+        let grouped = message.to_vec(&config, 80, None, None, true);
+
+        let text = |lines: &[Line]| {
+            lines[0]
+                .spans
+                .iter()
+                .map(|s| s.content.to_string())
+                .collect::<String>()
+        };
+
+        assert!(text(&header).contains("human: "));
+        assert!(!text(&grouped).contains("human"));
+        assert_eq!(text(&header).width(), text(&grouped).width());
+        assert!(text(&grouped).ends_with("beep boop"));
+    }
+
+    #[test]
+    fn test_emote_styles_per_provider() {
+        let mut config = FrontendConfig {
+            show_datetimes: false,
+            username_shown: false,
+            ..FrontendConfig::default()
+        };
+        config.emote_styles.twitch.color = "yellow".to_string();
+        config.emote_styles.betterttv = EmoteStyle {
+            color: "#ff0000".to_string(),
+            bold: false,
+        };
+
+        let mut message = MessageData::new(
+            "human".to_string(),
+            None,
+            false,
+            "hi Kappa and LUL".to_string(),
+            None,
+            false,
+        );
+        message.emote_ranges = vec![
+            (3..8, EmoteProvider::Twitch),
+            (13..16, EmoteProvider::BetterTTV),
+        ];
+
+        let lines = message.to_vec(&config, 80, None, None, false);
+
+        // Every character gets its own span when there are emotes on the line.
+        let styles = lines[0]
+            .spans
+            .iter()
+            .map(|s| s.style)
+            .collect::<Vec<Style>>();
+        assert_eq!(styles.len(), message.payload.len());
+
+        let twitch = Style::default()
+            .fg(Color::Yellow)
+            .add_modifier(Modifier::BOLD);
+        let betterttv = Style::default().fg(Color::Rgb(255, 0, 0));
+
+        assert_eq!(styles[0..3], [Style::default(); 3]);
+        assert_eq!(styles[3..8], [twitch; 5]);
+        assert_eq!(styles[8..13], [Style::default(); 5]);
+        assert_eq!(styles[13..16], [betterttv; 3]);
+    }
+
+    #[test]
+    fn test_emote_names_without_graphics() {
+        let emotes = SharedEmotes::default();
+        emotes.emotes.borrow_mut().extend([
+            (
+                "Kappa".to_string(),
+                ("25".to_string(), false, EmoteProvider::Twitch),
+            ),
+            (
+                "LUL".to_string(),
+                ("bttv_LUL.png".to_string(), false, EmoteProvider::BetterTTV),
+            ),
+        ]);
+
+        let mut message = MessageData::new(
+            "human".to_string(),
+            None,
+            false,
+            "hi Kappa and LUL LULW".to_string(),
+            None,
+            false,
+        );
+        message.emote_ranges = vec![(3..8, EmoteProvider::Twitch)];
+
+        message.parse_emotes(&emotes);
+        // Parsing again once more emotes are known leaves the marked ones as they are.
+        message.parse_emotes(&emotes);
+
+        assert_eq!(message.payload, "hi Kappa and LUL LULW");
+        assert_eq!(
+            message.emote_ranges,
+            vec![
+                (3..8, EmoteProvider::Twitch),
+                (13..16, EmoteProvider::BetterTTV)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_role_author_style() {
+        let config = FrontendConfig {
+            show_datetimes: false,
+            ..FrontendConfig::default()
+        };
+
+        let mut message = MessageData::new(
+            "human".to_string(),
+            None,
+            false,
+            "hello".to_string(),
+            None,
+            false,
+        );
+
+        let author_style = |message: &MessageData| {
+            message.to_vec(&config, 80, None, None, false)[0]
+                .spans
+                .iter()
+                .find(|span| span.content.contains("human"))
+                .map(|span| span.style)
+                .unwrap()
+        };
+
+        assert!(!author_style(&message).add_modifier.contains(Modifier::BOLD));
+
+        message.role = Some(Role::Subscriber);
+        assert!(!author_style(&message).add_modifier.contains(Modifier::BOLD));
+
+        message.role = Some(Role::Moderator);
+        assert!(author_style(&message).add_modifier.contains(Modifier::BOLD));
+    }
+
+    fn lines_text(lines: &[Line]) -> String {
+        lines
+            .iter()
+            .flat_map(|l| l.spans.iter().map(|s| s.content.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_collapsed_ascii_art() {
+        let config = FrontendConfig::default();
+        let mut message = message_at("artist", 0);
+        message.payload = "⣿".repeat(300);
+        message.ascii_art = true;
+        message.collapsed = true;
+
+        let lines = message.to_vec(&config, 80, None, None, false);
+
+        assert_eq!(lines.len(), 1);
+        assert!(lines_text(&lines).ends_with("artist: <ASCII art, expand>"));
+
+        message.collapsed = false;
+
+        assert!(message.to_vec(&config, 80, None, None, false).len() > 1);
+    }
+
+    #[test]
+    fn test_truncated_message() {
+        let config = FrontendConfig {
+            truncate_lines: 2,
+            ..FrontendConfig::default()
+        };
+        let mut message = message_at("talker", 0);
+        message.payload = "word ".repeat(100).trim_end().to_string();
+
+        let lines = message.to_vec(&config, 60, None, None, false);
+
+        assert_eq!(lines.len(), 2);
+        assert!(lines[1].width() <= lines[0].width());
+        assert!(lines_text(&lines).ends_with("word word … (show more)"));
+
+        message.expanded = true;
+
+        let lines = message.to_vec(&config, 60, None, None, false);
+
+        assert!(lines.len() > 2);
+        assert!(!lines_text(&lines).contains("show more"));
+        assert_eq!(lines_text(&lines).matches("word").count(), 100);
+    }
+
+    #[test]
+    fn test_link_marker() {
+        let config = FrontendConfig {
+            link_marker: true,
+            ..FrontendConfig::default()
+        };
+
+        let mut linked = message_at("talker", 0);
+        linked.payload = "have a look at https://example.com/page".to_string();
+
+        let mut plain = message_at("talker", 0);
+        plain.payload = "have a look at example dot com".to_string();
+
+        assert!(linked.has_link());
+        assert!(!plain.has_link());
+
+        assert!(lines_text(&linked.to_vec(&config, 80, None, None, false)).contains(LINK_GLYPH));
+        assert!(!lines_text(&plain.to_vec(&config, 80, None, None, false)).contains(LINK_GLYPH));
+        assert!(
+            !lines_text(&linked.to_vec(&FrontendConfig::default(), 80, None, None, false))
+                .contains(LINK_GLYPH)
+        );
+    }
+
+    #[test]
+    fn test_classic_render_mode() {
+        let config = FrontendConfig {
+            render_mode: RenderMode::Classic,
+            show_datetimes: false,
+            ..FrontendConfig::default()
+        };
+        let mut message = message_at("talker", 0);
+        message.payload = "word ".repeat(100).trim_end().to_string();
+
+        let lines = message.to_vec(&config, 60, None, None, false);
+
+        assert_eq!(lines.len(), 1);
+        assert!(lines_text(&lines).starts_with("<talker> word word"));
+        assert!(lines_text(&lines).ends_with('…'));
+
+        // Filling the width that is left within the borders of chat.
+        assert_eq!(lines[0].width(), 60 - 2 - 1);
+
+        message.expanded = true;
+
+        assert!(message.to_vec(&config, 60, None, None, false).len() > 1);
+    }
+
+    #[test]
+    fn test_short_message_not_truncated() {
+        let config = FrontendConfig {
+            truncate_lines: 1,
+            ..FrontendConfig::default()
+        };
+
+        let message = message_at("talker", 0);
+        let lines = message.to_vec(&config, 80, None, None, false);
+
+        assert_eq!(lines.len(), 1);
+        assert!(!lines_text(&lines).contains("show more"));
+    }
+
+    #[test]
+    fn test_wrapped_lines_cache_hit_on_same_width() {
+        let config = FrontendConfig::default();
+        let mut message = message_at("human", 0);
+
+        let first = message.wrapped_lines(&config, 80, NAME_MAX_CHARACTERS, None, None, false);
+
+        // Not invalidating on purpose, to see that the cached lines are reused.
+        message.payload = "changed".to_string();
+
+        let second = message.wrapped_lines(&config, 80, NAME_MAX_CHARACTERS, None, None, false);
+
+        assert_eq!(first, second);
+        assert!(lines_text(&second).contains("beep boop"));
+    }
+
+    #[test]
+    fn test_wrapped_lines_cache_invalidation() {
+        let config = FrontendConfig::default();
+        let mut message = message_at("human", 0);
+
+        message.wrapped_lines(&config, 80, NAME_MAX_CHARACTERS, None, None, false);
+        message.payload = "changed".to_string();
+
+        let resized = message.wrapped_lines(&config, 60, NAME_MAX_CHARACTERS, None, None, false);
+        assert!(lines_text(&resized).contains("changed"));
+
+        message.payload = "changed again".to_string();
+        message.invalidate_lines();
+
+        let edited = message.wrapped_lines(&config, 60, NAME_MAX_CHARACTERS, None, None, false);
+        assert!(lines_text(&edited).contains("changed again"));
+        assert_eq!(
+            edited,
+            message.to_vec(&config, 60, None, None, false),
+            "cached lines should be the same as freshly rendered ones"
+        );
+    }
+
+    #[test]
+    fn test_announcement_border_on_every_row() {
+        let config = FrontendConfig::default();
+        let mut message = MessageData::new(
+            "Mod".to_string(),
+            None,
+            false,
+            "this announcement is long enough to be wrapped onto multiple rows".to_string(),
+            None,
+            false,
+        );
+        message.announcement = Some(Color::Rgb(0, 219, 219));
+
+        let lines = message.to_vec(&config, 40, None, None, false);
+
+        assert!(lines.len() > 1);
+
+        for line in &lines {
+            assert_eq!(
+                line.spans[0],
+                Span::styled(
+                    ANNOUNCEMENT_BORDER,
+                    Style::default().fg(Color::Rgb(0, 219, 219))
+                )
+            );
+            assert!(line.width() <= 40);
+        }
+
+        assert_eq!(
+            lines_text(&lines).replace(ANNOUNCEMENT_BORDER, ""),
+            lines_text(
+                &MessageData::new(
+                    "Mod".to_string(),
+                    None,
+                    false,
+                    message.payload.clone(),
+                    None,
+                    false,
+                )
+                .to_vec(&config, 39, None, None, false)
+            )
+        );
+    }
+
+    #[test]
+    fn test_message_columns_left() {
+        assert_eq!(
+            MessageColumns::new(TimestampPosition::Left, 60, 8),
+            MessageColumns {
+                left: 9,
+                right: 0,
+                body: 60
+            }
+        );
+    }
+
+    #[test]
+    fn test_message_columns_right() {
+        assert_eq!(
+            MessageColumns::new(TimestampPosition::Right, 60, 8),
+            MessageColumns {
+                left: 0,
+                right: 9,
+                body: 51
+            }
+        );
+        assert_eq!(MessageColumns::new(TimestampPosition::Right, 5, 8).body, 0);
+    }
+
+    #[test]
+    fn test_message_columns_hidden() {
+        assert_eq!(
+            MessageColumns::new(TimestampPosition::Hidden, 60, 8),
+            MessageColumns {
+                left: 0,
+                right: 0,
+                body: 60
+            }
+        );
+    }
+
+    #[test]
+    fn test_timestamp_on_the_right() {
+        let config = FrontendConfig {
+            datetime_format: "%H:%M".to_string(),
+            timestamp_position: TimestampPosition::Right,
+            ..FrontendConfig::default()
+        };
+        let message = MessageData::new(
+            "human".to_string(),
+            None,
+            false,
+            "a message that is long enough to be wrapped onto a second row".to_string(),
+            None,
+            false,
+        );
+
+        let width = 40;
+        let available = width - (usize::from(config.margin) + 1) * 2 - 1;
+        let time = message.time_sent.format("%H:%M").to_string();
+
+        let lines = message.to_vec(&config, width, None, None, false);
+
+        assert!(lines.len() > 1);
+        assert_eq!(lines[0].width(), available);
+        assert!(lines[0].spans.last().unwrap().content.ends_with(&time));
+        assert!(lines_text(&lines[..1]).starts_with("human: a message"));
+
+        for line in &lines[1..] {
+            assert!(line.width() < available - time.len());
+        }
+    }
+
+    #[test]
+    fn test_thumbnail_rows_below_message() {
+        let config = FrontendConfig::default();
+        let mut message = MessageData::new(
+            "human".to_string(),
+            None,
+            false,
+            "look https://example.com/cat.png".to_string(),
+            None,
+            false,
+        );
+
+        let text_rows = message.to_vec(&config, 80, None, None, false).len();
+
+        message.thumbnail = Some(Thumbnail {
+            id: 1,
+            rows: 3,
+            cols: 4,
+        });
+
+        let lines = message.to_vec(&config, 80, None, None, false);
+
+        assert_eq!(lines.len(), text_rows + 3);
+
+        for line in &lines[text_rows..] {
+            assert_eq!(line.width(), 4);
+            assert_eq!(line.spans[0].style.fg, Some(u32_to_color(1)));
+        }
+    }
+
     const EMOTES_ID_PID: [(Color, Color); 3] = [
         (Color::Red, Color::Green),
         (Color::Black, Color::Cyan),
@@ -562,6 +2420,7 @@ mod tests {
     ];
 
     const NO_HIGHLIGHTS: (&[usize], Style) = ([].as_slice(), Style::new());
+    const NO_EMOTE_HIGHLIGHTS: EmoteHighlight = &[];
 
     #[test]
     fn emote_span() {
@@ -596,6 +2455,7 @@ mod tests {
             Style::default(),
             (search_highlight, STYLES[0]),
             (username_highlight, STYLES[1]),
+            NO_EMOTE_HIGHLIGHTS,
         );
 
         assert_eq!(start_index, line_len + 1);
@@ -635,6 +2495,7 @@ mod tests {
                 Style::default(),
                 search_highlight,
                 username_highlight,
+                NO_EMOTE_HIGHLIGHTS,
                 &mut emotes,
             );
 
@@ -651,6 +2512,7 @@ mod tests {
                 Style::default(),
                 search_highlight,
                 username_highlight,
+                NO_EMOTE_HIGHLIGHTS,
                 &mut emotes,
             );
 
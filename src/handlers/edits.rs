@@ -0,0 +1,249 @@
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+use crate::handlers::{config::FrontendConfig, data::MessageData};
+
+/// A message that was deleted, which its author could send again as an edit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeletedMessage {
+    pub message_id: String,
+    user_id: String,
+    payload: String,
+    deleted_at: Instant,
+}
+
+impl DeletedMessage {
+    /// The deletion of a message, where only messages from users can be edited.
+    pub fn new(message: &MessageData, deleted_at: Instant) -> Option<Self> {
+        if message.system {
+            return None;
+        }
+
+        Some(Self {
+            message_id: message.message_id.clone()?,
+            user_id: message.user_id.clone()?,
+            payload: message.payload.clone(),
+            deleted_at,
+        })
+    }
+}
+
+/// How alike two messages are from 0 to 1, by the characters that have to be changed to turn
+/// one into the other. Case and runs of whitespace are not told apart.
+pub fn similarity(a: &str, b: &str) -> f32 {
+    let normalize = |text: &str| {
+        text.split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+            .to_lowercase()
+            .chars()
+            .collect::<Vec<_>>()
+    };
+
+    let (a, b) = (normalize(a), normalize(b));
+
+    let longest = a.len().max(b.len());
+
+    if longest == 0 {
+        return 1.0;
+    }
+
+    // The Levenshtein distance, keeping a single row of the table.
+    let mut row = (0..=b.len()).collect::<Vec<_>>();
+
+    for (i, a_char) in a.iter().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(a_char != b_char);
+
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+
+    1.0 - row[b.len()] as f32 / longest as f32
+}
+
+/// If a message is its author sending a deleted message again, soon enough after it was deleted
+/// and with close enough to the same text.
+pub fn is_edit(
+    deleted: &DeletedMessage,
+    message: &MessageData,
+    now: Instant,
+    window: Duration,
+    min_similarity: f32,
+) -> bool {
+    !message.system
+        && message.user_id.as_ref() == Some(&deleted.user_id)
+        && now.saturating_duration_since(deleted.deleted_at) <= window
+        && similarity(&deleted.payload, &message.payload) >= min_similarity
+}
+
+/// Remembers recently deleted messages, to tell when a message is an edit of one of them.
+#[derive(Debug, Clone)]
+pub struct EditDetector {
+    window: Duration,
+    min_similarity: f32,
+    /// Oldest first.
+    deleted: VecDeque<DeletedMessage>,
+}
+
+impl EditDetector {
+    pub const fn new(frontend_config: &FrontendConfig) -> Self {
+        Self {
+            window: Duration::from_secs(frontend_config.edit_window),
+            min_similarity: frontend_config.edit_similarity,
+            deleted: VecDeque::new(),
+        }
+    }
+
+    pub const fn enabled(&self) -> bool {
+        !self.window.is_zero()
+    }
+
+    fn forget_expired(&mut self, now: Instant) {
+        while self
+            .deleted
+            .front()
+            .is_some_and(|d| now.saturating_duration_since(d.deleted_at) > self.window)
+        {
+            self.deleted.pop_front();
+        }
+    }
+
+    pub fn deleted(&mut self, message: &MessageData, now: Instant) {
+        if !self.enabled() {
+            return;
+        }
+
+        self.forget_expired(now);
+
+        if let Some(deleted) = DeletedMessage::new(message, now) {
+            self.deleted.push_back(deleted);
+        }
+    }
+
+    /// The ID of the deleted message that a message is an edit of, preferring the latest one.
+    /// Each deleted message is only ever edited once.
+    pub fn take_edit(&mut self, message: &MessageData, now: Instant) -> Option<String> {
+        if !self.enabled() {
+            return None;
+        }
+
+        self.forget_expired(now);
+
+        let index = self
+            .deleted
+            .iter()
+            .rposition(|d| is_edit(d, message, now, self.window, self.min_similarity))?;
+
+        self.deleted.remove(index).map(|d| d.message_id)
+    }
+
+    pub fn clear(&mut self) {
+        self.deleted.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(user_id: &str, payload: &str, message_id: &str) -> MessageData {
+        MessageData::new(
+            user_id.to_string(),
+            Some(user_id.to_string()),
+            false,
+            payload.to_string(),
+            Some(message_id.to_string()),
+            false,
+        )
+    }
+
+    #[test]
+    fn test_similarity() {
+        assert!((similarity("hello there", "Hello   there") - 1.0).abs() < f32::EPSILON);
+        assert!((similarity("", "") - 1.0).abs() < f32::EPSILON);
+        assert!(similarity("", "hello").abs() < f32::EPSILON);
+        // One character changed out of ten.
+        assert!((similarity("kitten sat", "sitten sat") - 0.9).abs() < 0.001);
+        assert!(similarity("the stream starts at 5", "the stream starts at 6") > 0.9);
+        assert!(similarity("hello", "goodbye") < 0.5);
+    }
+
+    #[test]
+    fn test_is_edit() {
+        let now = Instant::now();
+        let window = Duration::from_secs(30);
+        let deleted =
+            DeletedMessage::new(&message("1", "giveaway at 5pm tonight", "a"), now).unwrap();
+
+        let fixed = message("1", "giveaway at 6pm tonight", "b");
+
+        assert!(is_edit(
+            &deleted,
+            &fixed,
+            now + Duration::from_secs(10),
+            window,
+            0.8
+        ));
+        // Too late.
+        assert!(!is_edit(
+            &deleted,
+            &fixed,
+            now + Duration::from_secs(31),
+            window,
+            0.8
+        ));
+        // Someone else.
+        assert!(!is_edit(
+            &deleted,
+            &message("2", "giveaway at 6pm tonight", "c"),
+            now,
+            window,
+            0.8
+        ));
+        // Something else.
+        assert!(!is_edit(
+            &deleted,
+            &message("1", "anyway, back to the game", "d"),
+            now,
+            window,
+            0.8
+        ));
+    }
+
+    #[test]
+    fn test_edit_detector() {
+        let mut detector = EditDetector::new(&FrontendConfig {
+            edit_window: 30,
+            ..FrontendConfig::default()
+        });
+        let now = Instant::now();
+
+        detector.deleted(&message("1", "first tryy", "a"), now);
+        detector.deleted(&message("1", "unrelated", "b"), now);
+
+        let edit = message("1", "first try", "c");
+
+        assert_eq!(detector.take_edit(&edit, now), Some("a".to_string()));
+        assert_eq!(detector.take_edit(&edit, now), None);
+
+        detector.deleted(&message("1", "first tryy", "d"), now);
+
+        assert_eq!(
+            detector.take_edit(&edit, now + Duration::from_secs(60)),
+            None
+        );
+
+        let mut disabled = EditDetector::new(&FrontendConfig::default());
+
+        disabled.deleted(&message("1", "first tryy", "a"), now);
+
+        assert_eq!(disabled.take_edit(&edit, now), None);
+    }
+}
@@ -1,5 +1,6 @@
-use std::{cell::RefCell, fs::read_to_string, rc::Rc};
+use std::{cell::RefCell, collections::HashSet, fs::read_to_string, path::Path, rc::Rc};
 
+use log::warn;
 use regex::Regex;
 
 use crate::{handlers::config::FiltersConfig, utils::pathing::config_path};
@@ -17,16 +18,36 @@ impl Filters {
     pub fn new(file: &str, config: &FiltersConfig) -> Self {
         let file_path = config_path(file);
 
+        let mut patterns = read_to_string(file_path).map_or_else(
+            |_| vec![],
+            |f| {
+                f.split('\n')
+                    .filter(|s| !s.is_empty())
+                    .map(ToString::to_string)
+                    .collect::<Vec<String>>()
+            },
+        );
+
+        for include in &config.include {
+            let include_path = if Path::new(include).is_absolute() {
+                include.clone()
+            } else {
+                config_path(include)
+            };
+
+            match read_to_string(&include_path) {
+                Ok(contents) => patterns.extend(parse_include(&contents)),
+                Err(err) => {
+                    warn!("Filter list {include_path} could not be read, skipping it: {err}");
+                }
+            }
+        }
+
         Self {
-            captures: read_to_string(file_path).map_or_else(
-                |_| vec![],
-                |f| {
-                    f.split('\n')
-                        .filter(|s| !s.is_empty())
-                        .flat_map(Regex::new)
-                        .collect::<Vec<Regex>>()
-                },
-            ),
+            captures: dedup_patterns(patterns)
+                .iter()
+                .flat_map(|pattern| Regex::new(pattern))
+                .collect::<Vec<Regex>>(),
             enabled: config.enabled,
             reversed: config.reversed,
         }
@@ -61,6 +82,26 @@ impl Filters {
     }
 }
 
+/// The patterns of an included filter list, one per line, leaving out blank lines and `#` comments.
+pub fn parse_include(contents: &str) -> Vec<String> {
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(ToString::to_string)
+        .collect()
+}
+
+/// The patterns without the ones that were already seen, in the order that they came in.
+pub fn dedup_patterns(patterns: Vec<String>) -> Vec<String> {
+    let mut seen = HashSet::new();
+
+    patterns
+        .into_iter()
+        .filter(|pattern| seen.insert(pattern.clone()))
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -104,4 +145,22 @@ mod tests {
 
         assert!(filters.contaminated("not a bad word"));
     }
+
+    #[test]
+    fn test_parse_and_merge_includes() {
+        let shared = "# Shared spam list\n\n^buy followers\n  spam\\.com  \n# spam\\.net\n\n";
+        let local = "spam\\.com\n^bad.*$\n";
+
+        assert_eq!(parse_include(shared), vec!["^buy followers", "spam\\.com"]);
+
+        let patterns = dedup_patterns(
+            vec!["^bad.*$".to_string()]
+                .into_iter()
+                .chain(parse_include(shared))
+                .chain(parse_include(local))
+                .collect(),
+        );
+
+        assert_eq!(patterns, vec!["^bad.*$", "^buy followers", "spam\\.com"]);
+    }
 }
@@ -0,0 +1,270 @@
+use std::{
+    fs::{read_to_string, remove_file, File},
+    io::{ErrorKind, Write},
+};
+
+use log::warn;
+
+use crate::utils::pathing::capabilities;
+
+pub const HISTORY_FILE: &str = "history.json";
+
+/// How many sent messages are remembered.
+const HISTORY_CAPACITY: usize = 100;
+
+/// Messages and commands that were sent from the chat input, from oldest to newest.
+#[derive(Debug, Clone, Default)]
+pub struct InputHistory {
+    entries: Vec<String>,
+    /// If the history is only kept in memory, so that it is gone after quitting.
+    ephemeral: bool,
+}
+
+impl InputHistory {
+    /// Load the history saved at `path`, where an ephemeral history starts out empty.
+    pub fn load(path: &str, ephemeral: bool) -> Self {
+        let entries = if ephemeral {
+            vec![]
+        } else {
+            read_to_string(path)
+                .ok()
+                .and_then(|content| serde_json::from_str(&content).ok())
+                .unwrap_or_default()
+        };
+
+        Self { entries, ephemeral }
+    }
+
+    /// Save the history to `path`, unless it is ephemeral or can only be kept in memory.
+    pub fn save(&self, path: &str) {
+        if self.ephemeral || !capabilities().data {
+            return;
+        }
+
+        let Ok(history_str) = serde_json::to_string(&self.entries) else {
+            return;
+        };
+
+        if let Err(err) = File::create(path).and_then(|mut f| f.write_all(history_str.as_bytes())) {
+            warn!("Unable to save input history to {path}: {err}");
+        }
+    }
+
+    pub const fn is_ephemeral(&self) -> bool {
+        self.ephemeral
+    }
+
+    /// Keep the history in memory only from now on, or save it again on quitting.
+    ///
+    /// The history that was saved at `path` is removed when becoming ephemeral.
+    pub fn set_ephemeral(&mut self, ephemeral: bool, path: &str) {
+        self.ephemeral = ephemeral;
+
+        if ephemeral {
+            remove_history_file(path);
+        }
+    }
+
+    /// Forget every entry right away, along with the ones that were saved at `path`.
+    pub fn wipe(&mut self, path: &str) {
+        self.entries.clear();
+
+        remove_history_file(path);
+    }
+
+    /// Remember an input as the newest one, moving it there if it was sent before.
+    pub fn push(&mut self, input: &str) {
+        if input.trim().is_empty() {
+            return;
+        }
+
+        self.entries.retain(|e| e != input);
+        self.entries.push(input.to_string());
+
+        if self.entries.len() > HISTORY_CAPACITY {
+            self.entries.remove(0);
+        }
+    }
+
+    /// Entries containing the query, ignoring case, from newest to oldest.
+    pub fn matches(&self, query: &str) -> Vec<&str> {
+        let query = query.to_lowercase();
+
+        self.entries
+            .iter()
+            .rev()
+            .filter(|e| e.to_lowercase().contains(&query))
+            .map(String::as_str)
+            .collect()
+    }
+}
+
+fn remove_history_file(path: &str) {
+    match remove_file(path) {
+        Err(err) if err.kind() != ErrorKind::NotFound => {
+            warn!("Unable to remove input history at {path}: {err}");
+        }
+        _ => {}
+    }
+}
+
+/// An incremental search through the input history, like a shell's reverse-i-search.
+#[derive(Debug, Clone, Default)]
+pub struct HistorySearch {
+    pub query: String,
+    /// How many matches have been skipped over, to get to older ones.
+    skipped: usize,
+}
+
+impl HistorySearch {
+    /// The currently selected match within the history, if anything matches.
+    ///
+    /// After the oldest match, this cycles back to the newest one.
+    pub fn selected<'a>(&self, history: &'a InputHistory) -> Option<&'a str> {
+        let matches = history.matches(&self.query);
+
+        (!matches.is_empty()).then(|| matches[self.skipped % matches.len()])
+    }
+
+    pub fn push(&mut self, c: char) {
+        self.query.push(c);
+        self.skipped = 0;
+    }
+
+    pub fn pop(&mut self) {
+        self.query.pop();
+        self.skipped = 0;
+    }
+
+    /// Select the next older match.
+    pub fn next(&mut self) {
+        self.skipped += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+
+    fn history(entries: &[&str]) -> InputHistory {
+        let mut history = InputHistory::default();
+
+        for entry in entries {
+            history.push(entry);
+        }
+
+        history
+    }
+
+    #[test]
+    fn test_incremental_search_narrows_to_newest_match() {
+        let history = history(&["hello chat", "/ban spammer", "hi there", "/color red"]);
+        let mut search = HistorySearch::default();
+
+        assert_eq!(search.selected(&history), Some("/color red"));
+
+        search.push('h');
+        assert_eq!(search.selected(&history), Some("hi there"));
+
+        search.push('e');
+        assert_eq!(search.selected(&history), Some("hi there"));
+
+        search.push('l');
+        assert_eq!(search.selected(&history), Some("hello chat"));
+
+        search.pop();
+        search.pop();
+        search.push('I');
+        assert_eq!(search.selected(&history), Some("hi there"));
+    }
+
+    #[test]
+    fn test_repeated_search_cycles_through_matches() {
+        let history = history(&["/ban foo", "hello", "/ban bar", "/ban baz"]);
+        let mut search = HistorySearch::default();
+
+        for c in "/ban".chars() {
+            search.push(c);
+        }
+
+        assert_eq!(search.selected(&history), Some("/ban baz"));
+
+        search.next();
+        assert_eq!(search.selected(&history), Some("/ban bar"));
+
+        search.next();
+        assert_eq!(search.selected(&history), Some("/ban foo"));
+
+        search.next();
+        assert_eq!(search.selected(&history), Some("/ban baz"));
+    }
+
+    #[test]
+    fn test_no_match() {
+        let history = history(&["hello"]);
+        let mut search = HistorySearch::default();
+
+        search.push('x');
+
+        assert_eq!(search.selected(&history), None);
+        assert_eq!(search.selected(&InputHistory::default()), None);
+    }
+
+    #[test]
+    fn test_history_persistence() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(HISTORY_FILE);
+        let path = path.to_str().unwrap();
+
+        let mut saved = InputHistory::load(path, false);
+        saved.push("hello chat");
+        saved.save(path);
+
+        assert_eq!(
+            InputHistory::load(path, false).matches(""),
+            vec!["hello chat"]
+        );
+        // An ephemeral history does not pick up what was saved.
+        assert!(InputHistory::load(path, true).matches("").is_empty());
+
+        saved.wipe(path);
+        assert!(saved.matches("").is_empty());
+        assert!(!Path::new(path).exists());
+    }
+
+    #[test]
+    fn test_ephemeral_history_is_not_saved() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(HISTORY_FILE);
+        let path = path.to_str().unwrap();
+
+        let mut ephemeral = InputHistory::load(path, true);
+        ephemeral.push("secret");
+        ephemeral.save(path);
+
+        assert!(ephemeral.is_ephemeral());
+        assert!(!Path::new(path).exists());
+        // It is still there to recall until quitting.
+        assert_eq!(ephemeral.matches(""), vec!["secret"]);
+
+        // Becoming ephemeral removes what was saved before.
+        let mut history = InputHistory::load(path, false);
+        history.push("hello");
+        history.save(path);
+        assert!(Path::new(path).exists());
+
+        history.set_ephemeral(true, path);
+        history.push("secret");
+        history.save(path);
+        assert!(!Path::new(path).exists());
+    }
+
+    #[test]
+    fn test_resent_input_moves_to_newest() {
+        let history = history(&["first", "second", "first"]);
+
+        assert_eq!(history.matches(""), vec!["first", "second"]);
+    }
+}
@@ -44,6 +44,7 @@ pub(super) fn interactive_config() -> Option<CompleteConfig> {
             channel,
             server,
             token: Some(token),
+            ..Default::default()
         },
         ..Default::default()
     })
@@ -0,0 +1,314 @@
+use crate::handlers::{config::KeyScheme, state::InputMode, user_input::events::Key};
+
+/// What a key does in normal mode, apart from the moderator actions bound in the config.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NormalAction {
+    Insert,
+    InsertMention,
+    InsertCommand,
+    SwitchChannel,
+    SearchMessages,
+    Following,
+    ToggleFilter,
+    ReverseFilter,
+    PreviousChannel,
+    Dashboard,
+    Help,
+    Quit,
+    OpenPlayer,
+    OpenLatestMedia,
+    ToggleAsciiArt,
+    DismissPoll,
+    TogglePin,
+    CopyQuote,
+    SelectOlder,
+    SelectNewer,
+    ToggleExpanded,
+    ToggleFocus,
+    Resume,
+    TogglePause,
+    Top,
+    Back,
+    CommandPalette,
+    Panic,
+}
+
+impl NormalAction {
+    /// Every action, in the order that the command palette lists them in.
+    pub const ALL: [Self; 28] = [
+        Self::Insert,
+        Self::InsertMention,
+        Self::InsertCommand,
+        Self::SwitchChannel,
+        Self::SearchMessages,
+        Self::Following,
+        Self::ToggleFilter,
+        Self::ReverseFilter,
+        Self::PreviousChannel,
+        Self::Dashboard,
+        Self::Help,
+        Self::Quit,
+        Self::OpenPlayer,
+        Self::OpenLatestMedia,
+        Self::ToggleAsciiArt,
+        Self::DismissPoll,
+        Self::TogglePin,
+        Self::CopyQuote,
+        Self::SelectOlder,
+        Self::SelectNewer,
+        Self::ToggleExpanded,
+        Self::ToggleFocus,
+        Self::Resume,
+        Self::TogglePause,
+        Self::Top,
+        Self::Back,
+        Self::CommandPalette,
+        Self::Panic,
+    ];
+
+    /// What the action is called in the command palette.
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Insert => "Send a message",
+            Self::InsertMention => "Mention a user",
+            Self::InsertCommand => "Type a command",
+            Self::SwitchChannel => "Switch channel",
+            Self::SearchMessages => "Search messages",
+            Self::Following => "Search followed channels",
+            Self::ToggleFilter => "Toggle the message filter",
+            Self::ReverseFilter => "Reverse the message filter",
+            Self::PreviousChannel => "Go to the previous channel",
+            Self::Dashboard => "Go to the dashboard",
+            Self::Help => "Show the keybinds",
+            Self::Quit => "Quit",
+            Self::OpenPlayer => "Open the stream in the browser",
+            Self::OpenLatestMedia => "Open the latest link",
+            Self::ToggleAsciiArt => "Expand or collapse ASCII art",
+            Self::DismissPoll => "Dismiss the poll",
+            Self::TogglePin => "Pin or unpin a message",
+            Self::CopyQuote => "Copy a message as a quote",
+            Self::SelectOlder => "Select an older message",
+            Self::SelectNewer => "Select a newer message",
+            Self::ToggleExpanded => "Expand or shorten the selected message",
+            Self::ToggleFocus => "Toggle focus mode",
+            Self::Resume => "Resume following chat",
+            Self::TogglePause => "Pause or resume chat",
+            Self::Top => "Jump to the oldest message",
+            Self::Back => "Go back",
+            Self::CommandPalette => "Open the command palette",
+            Self::Panic => "Crash the application",
+        }
+    }
+
+    /// The mode that the action leaves normal mode for, if it does.
+    pub const fn enters(self) -> Option<InputMode> {
+        match self {
+            Self::Insert | Self::InsertMention | Self::InsertCommand => Some(InputMode::Insert),
+            Self::SwitchChannel | Self::SearchMessages | Self::Following => Some(InputMode::Search),
+            _ => None,
+        }
+    }
+}
+
+const DEFAULT_BINDINGS: &[(Key, NormalAction)] = &[
+    (Key::Char('i'), NormalAction::Insert),
+    (Key::Char('c'), NormalAction::Insert),
+    (Key::Char('@'), NormalAction::InsertMention),
+    (Key::Char('/'), NormalAction::InsertCommand),
+    (Key::Char('s'), NormalAction::SwitchChannel),
+    (Key::Ctrl('f'), NormalAction::SearchMessages),
+    (Key::Char('f'), NormalAction::Following),
+    (Key::Ctrl('t'), NormalAction::ToggleFilter),
+    (Key::Ctrl('r'), NormalAction::ReverseFilter),
+    (Key::Tab, NormalAction::PreviousChannel),
+    (Key::Char('S'), NormalAction::Dashboard),
+    (Key::Char('?'), NormalAction::Help),
+    (Key::Char('h'), NormalAction::Help),
+    (Key::Char('q'), NormalAction::Quit),
+    (Key::Char('o'), NormalAction::OpenPlayer),
+    (Key::Char('l'), NormalAction::OpenLatestMedia),
+    (Key::Char('e'), NormalAction::ToggleAsciiArt),
+    (Key::Char('x'), NormalAction::DismissPoll),
+    (Key::Char('P'), NormalAction::TogglePin),
+    (Key::Char('y'), NormalAction::CopyQuote),
+    (Key::Char('k'), NormalAction::SelectOlder),
+    (Key::Up, NormalAction::SelectOlder),
+    (Key::Char('j'), NormalAction::SelectNewer),
+    (Key::Down, NormalAction::SelectNewer),
+    (Key::Enter, NormalAction::ToggleExpanded),
+    (Key::Char('z'), NormalAction::ToggleFocus),
+    (Key::End, NormalAction::Resume),
+    (Key::Char('G'), NormalAction::Resume),
+    (Key::Char('p'), NormalAction::TogglePause),
+    (Key::Char('g'), NormalAction::Top),
+    (Key::Esc, NormalAction::Back),
+    (Key::Ctrl('k'), NormalAction::CommandPalette),
+    (Key::Ctrl('p'), NormalAction::Panic),
+];
+
+/// Where the Vi scheme differs from the default one, which it otherwise keeps.
+const VI_BINDINGS: &[(Key, NormalAction)] = &[
+    (Key::Char('/'), NormalAction::SearchMessages),
+    (Key::Char(':'), NormalAction::InsertCommand),
+    (Key::Char('a'), NormalAction::Insert),
+];
+
+/// The keys of normal mode, looked up from tables so that other schemes can be layered on top.
+#[derive(Debug, Clone)]
+pub struct Keymap {
+    /// Looked up in order, so that the first binding of a key wins.
+    bindings: Vec<(Key, NormalAction)>,
+}
+
+impl Keymap {
+    pub fn new(scheme: KeyScheme) -> Self {
+        let overrides = match scheme {
+            KeyScheme::Default => &[][..],
+            KeyScheme::Vi => VI_BINDINGS,
+        };
+
+        Self {
+            bindings: overrides.iter().chain(DEFAULT_BINDINGS).copied().collect(),
+        }
+    }
+
+    pub fn action(&self, key: Key) -> Option<NormalAction> {
+        self.bindings
+            .iter()
+            .find(|(bound, _)| *bound == key)
+            .map(|(_, action)| *action)
+    }
+
+    /// The keys that do an action, leaving out the ones that an earlier binding took.
+    pub fn keys(&self, action: NormalAction) -> Vec<Key> {
+        let mut keys = vec![];
+
+        for (key, bound) in &self.bindings {
+            if *bound == action && !keys.contains(key) && self.action(*key) == Some(action) {
+                keys.push(*key);
+            }
+        }
+
+        keys
+    }
+
+    /// The mode that chat is in after a key is pressed in a mode, where typing in insert
+    /// and search mode only stops with Esc.
+    pub fn next_mode(&self, mode: InputMode, key: Key) -> InputMode {
+        match mode {
+            InputMode::Normal => self
+                .action(key)
+                .and_then(NormalAction::enters)
+                .unwrap_or(mode),
+            InputMode::Insert | InputMode::Search => {
+                if key == Key::Esc {
+                    InputMode::Normal
+                } else {
+                    mode
+                }
+            }
+        }
+    }
+}
+
+/// How a key is written in the help and the command palette, such as `Ctrl + f`.
+pub fn key_label(key: Key) -> String {
+    match key {
+        Key::Char(c) => c.to_string(),
+        Key::Ctrl(c) => format!("Ctrl + {c}"),
+        Key::Alt(c) => format!("Alt + {c}"),
+        Key::Click(..) => "Click".to_string(),
+        other => format!("{other:?}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn modes(keymap: &Keymap, keys: &[Key]) -> Vec<InputMode> {
+        keys.iter()
+            .scan(InputMode::Normal, |mode, key| {
+                *mode = keymap.next_mode(*mode, *key);
+
+                Some(*mode)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_vi_mode_transitions() {
+        let keymap = Keymap::new(KeyScheme::Vi);
+
+        assert_eq!(
+            modes(
+                &keymap,
+                &[
+                    Key::Char('j'),
+                    Key::Char('i'),
+                    Key::Char('j'),
+                    Key::Esc,
+                    Key::Char('/'),
+                    Key::Char('g'),
+                    Key::Esc,
+                    Key::Char('G'),
+                    Key::Char(':'),
+                    Key::Esc,
+                ]
+            ),
+            vec![
+                InputMode::Normal,
+                InputMode::Insert,
+                InputMode::Insert,
+                InputMode::Normal,
+                InputMode::Search,
+                InputMode::Search,
+                InputMode::Normal,
+                InputMode::Normal,
+                InputMode::Insert,
+                InputMode::Normal,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_schemes_share_bindings() {
+        let default = Keymap::new(KeyScheme::Default);
+        let vi = Keymap::new(KeyScheme::Vi);
+
+        assert_eq!(
+            default.action(Key::Char('/')),
+            Some(NormalAction::InsertCommand)
+        );
+        assert_eq!(
+            vi.action(Key::Char('/')),
+            Some(NormalAction::SearchMessages)
+        );
+        assert_eq!(default.action(Key::Char(':')), None);
+
+        for key in [Key::Char('g'), Key::Char('G'), Key::Char('k'), Key::Esc] {
+            assert_eq!(default.action(key), vi.action(key));
+        }
+
+        assert_eq!(
+            modes(&default, &[Key::Char('/'), Key::Char('j')]),
+            vec![InputMode::Insert, InputMode::Insert]
+        );
+    }
+
+    #[test]
+    fn test_keys_of_actions() {
+        let vi = Keymap::new(KeyScheme::Vi);
+
+        assert_eq!(
+            vi.keys(NormalAction::Insert),
+            vec![Key::Char('a'), Key::Char('i'), Key::Char('c')]
+        );
+        // The default `/` is taken by searching in the Vi scheme.
+        assert_eq!(vi.keys(NormalAction::InsertCommand), vec![Key::Char(':')]);
+
+        assert_eq!(key_label(Key::Ctrl('f')), "Ctrl + f");
+        assert_eq!(key_label(Key::Char('G')), "G");
+        assert_eq!(key_label(Key::Esc), "Esc");
+    }
+}
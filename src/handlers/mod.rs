@@ -1,9 +1,23 @@
+pub mod activity;
+pub mod alerts;
 pub mod app;
 pub mod args;
+pub mod away;
+pub mod batch;
+pub mod channel_history;
+pub mod chat_stats;
 pub mod config;
 pub mod data;
+pub mod edits;
 pub mod filters;
+pub mod input_history;
 mod interactive;
+pub mod keymap;
+pub mod name_width;
+pub mod redraw;
+pub mod responses;
+pub mod speakers;
 pub mod state;
 pub mod storage;
+pub mod summary;
 pub mod user_input;
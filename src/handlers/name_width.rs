@@ -0,0 +1,133 @@
+use std::time::{Duration, Instant};
+
+use unicode_width::UnicodeWidthStr;
+
+use crate::handlers::data::MessageData;
+
+/// How many of the latest messages the width of the name column is sampled from.
+const NAME_SAMPLE: usize = 100;
+
+/// How often the name column can narrow again, so that it does not change with every message.
+const SHRINK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// How much narrower the names have to get before the column follows, so that it does not
+/// go back and forth between names of about the same width.
+const SHRINK_HYSTERESIS: usize = 3;
+
+/// The widest name of the latest messages, from newest to oldest, capped at `max`.
+pub fn sampled_name_width<'a>(
+    messages: impl IntoIterator<Item = &'a MessageData>,
+    max: usize,
+) -> usize {
+    messages
+        .into_iter()
+        .filter(|message| !message.system)
+        .take(NAME_SAMPLE)
+        .map(|message| message.shown_author().width())
+        .max()
+        .unwrap_or_default()
+        .clamp(1, max.max(1))
+}
+
+/// The width of the name column after a new sample of the names. It widens right away so
+/// that names stay aligned, but only narrows when names got narrower by enough.
+pub fn next_name_width(current: usize, sample: usize, max: usize) -> usize {
+    let sample = sample.min(max);
+
+    if sample > current || current - sample >= SHRINK_HYSTERESIS {
+        sample
+    } else {
+        current
+    }
+}
+
+/// Sizes the name column to the names in chat, when right aligned names are sized automatically.
+#[derive(Debug, Clone)]
+pub struct NameWidth {
+    width: usize,
+    next_shrink: Instant,
+}
+
+impl NameWidth {
+    pub const fn new(max: usize, now: Instant) -> Self {
+        Self {
+            width: max,
+            next_shrink: now,
+        }
+    }
+
+    pub const fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Take in the widest of the latest names, where the column only narrows every so often.
+    pub fn update(&mut self, sample: usize, max: usize, now: Instant) -> usize {
+        let next = next_name_width(self.width, sample, max);
+
+        if next > self.width || (next < self.width && now >= self.next_shrink) {
+            self.width = next;
+            self.next_shrink = now + SHRINK_INTERVAL;
+        }
+
+        self.width
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(author: &str) -> MessageData {
+        MessageData::new(
+            author.to_string(),
+            None,
+            false,
+            "hi".to_string(),
+            None,
+            false,
+        )
+    }
+
+    #[test]
+    fn test_sampled_name_width() {
+        let messages = [message("ab"), message("abcdef"), message("abcd")];
+
+        assert_eq!(sampled_name_width(&messages, 25), 6);
+        assert_eq!(sampled_name_width(&messages, 4), 4);
+        assert_eq!(sampled_name_width(&[], 25), 1);
+
+        let mut system = message("a very long system name");
+        system.system = true;
+        assert_eq!(sampled_name_width(&[system, message("ab")], 25), 2);
+    }
+
+    #[test]
+    fn test_next_name_width() {
+        assert_eq!(next_name_width(10, 14, 25), 14);
+        assert_eq!(next_name_width(10, 40, 25), 25);
+        // Slightly narrower names keep the column as it is.
+        assert_eq!(next_name_width(10, 8, 25), 10);
+        assert_eq!(next_name_width(10, 7, 25), 7);
+    }
+
+    #[test]
+    fn test_name_width_does_not_oscillate() {
+        let start = Instant::now();
+        let mut width = NameWidth::new(25, start);
+
+        assert_eq!(width.update(12, 25, start), 12);
+
+        // Names around the same width, one message after another.
+        for (i, sample) in [11, 12, 10, 12, 11, 10, 12].into_iter().enumerate() {
+            let now = start + SHRINK_INTERVAL * 2 + Duration::from_secs(i as u64);
+
+            assert_eq!(width.update(sample, 25, now), 12);
+        }
+
+        // A wider name widens it right away, but it only narrows after a while.
+        let later = start + SHRINK_INTERVAL * 3;
+        assert_eq!(width.update(20, 25, later), 20);
+        assert_eq!(width.update(12, 25, later + Duration::from_secs(1)), 20);
+        assert_eq!(width.update(12, 25, later + SHRINK_INTERVAL), 12);
+    }
+}
@@ -0,0 +1,107 @@
+use std::time::{Duration, Instant};
+
+use crate::handlers::user_input::events::{Event, Key};
+
+/// How often the screen is redrawn when nothing changed, so that the clock in the title
+/// and anything else that depends on the time stays up to date.
+const CLOCK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// If an event changes what should be on screen.
+///
+/// Ticks only exist to wake up the UI loop, and keys that are not bound to anything
+/// (such as mouse movement) do not change anything either.
+pub const fn needs_redraw(event: &Event) -> bool {
+    match event {
+        Event::Input(Key::Null) | Event::Tick => false,
+        Event::Input(_) | Event::Resize => true,
+    }
+}
+
+/// Keeps track of whether the screen is out of date, to only draw it when needed.
+#[derive(Debug, Clone)]
+pub struct Redraw {
+    /// If disabled, the screen is drawn on every tick.
+    enabled: bool,
+    dirty: bool,
+    last_draw: Option<Instant>,
+}
+
+impl Redraw {
+    pub const fn new(enabled: bool) -> Self {
+        Self {
+            enabled,
+            dirty: true,
+            last_draw: None,
+        }
+    }
+
+    /// Something changed, so the screen needs to be drawn again.
+    pub fn mark(&mut self) {
+        self.dirty = true;
+    }
+
+    pub fn mark_event(&mut self, event: &Event) {
+        if needs_redraw(event) {
+            self.mark();
+        }
+    }
+
+    pub fn should_draw(&self, now: Instant) -> bool {
+        !self.enabled
+            || self.dirty
+            || self.last_draw.map_or(true, |last| {
+                now.saturating_duration_since(last) >= CLOCK_INTERVAL
+            })
+    }
+
+    pub fn drawn(&mut self, now: Instant) {
+        self.dirty = false;
+        self.last_draw = Some(now);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_events_needing_redraw() {
+        assert!(needs_redraw(&Event::Input(Key::Char('a'))));
+        assert!(needs_redraw(&Event::Input(Key::Ctrl('f'))));
+        assert!(needs_redraw(&Event::Input(Key::Backspace)));
+        assert!(needs_redraw(&Event::Input(Key::ScrollUp)));
+        assert!(needs_redraw(&Event::Resize));
+
+        assert!(!needs_redraw(&Event::Tick));
+        assert!(!needs_redraw(&Event::Input(Key::Null)));
+    }
+
+    #[test]
+    fn test_redraw_only_when_dirty_or_clock() {
+        let start = Instant::now();
+        let mut redraw = Redraw::new(true);
+
+        assert!(redraw.should_draw(start));
+        redraw.drawn(start);
+
+        redraw.mark_event(&Event::Tick);
+        assert!(!redraw.should_draw(start + Duration::from_millis(30)));
+
+        redraw.mark_event(&Event::Input(Key::Char('a')));
+        assert!(redraw.should_draw(start + Duration::from_millis(60)));
+        redraw.drawn(start + Duration::from_millis(60));
+
+        assert!(!redraw.should_draw(start + Duration::from_millis(500)));
+        assert!(redraw.should_draw(start + Duration::from_millis(1060)));
+    }
+
+    #[test]
+    fn test_redraw_disabled_draws_every_time() {
+        let start = Instant::now();
+        let mut redraw = Redraw::new(false);
+
+        redraw.drawn(start);
+
+        assert!(redraw.should_draw(start));
+    }
+}
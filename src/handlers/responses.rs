@@ -0,0 +1,311 @@
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+use log::warn;
+use regex::Regex;
+
+use crate::handlers::{
+    alerts::is_own_message,
+    config::{ResponseTrigger, ResponsesConfig, TriggerKind},
+    data::MessageData,
+};
+
+/// How many replies can be sent within [`RESPONSE_PERIOD`], across all triggers.
+pub const RESPONSE_LIMIT: usize = 5;
+
+/// The period over which [`RESPONSE_LIMIT`] counts the replies.
+pub const RESPONSE_PERIOD: Duration = Duration::from_secs(60);
+
+/// How a trigger tells if a message is one to reply to.
+#[derive(Debug, Clone)]
+enum Matcher {
+    Exact(String),
+    Pattern(Regex),
+}
+
+impl Matcher {
+    fn new(trigger: &ResponseTrigger) -> Option<Self> {
+        let pattern = trigger.pattern.trim();
+
+        if pattern.is_empty() {
+            return None;
+        }
+
+        let regex = match trigger.kind {
+            TriggerKind::Exact => return Some(Self::Exact(pattern.to_lowercase())),
+            TriggerKind::Keyword => Regex::new(&format!(r"(?i)\b{}\b", regex::escape(pattern))),
+            TriggerKind::Regex => Regex::new(pattern),
+        };
+
+        regex
+            .map_err(|err| warn!("Skipping response trigger '{pattern}': {err}"))
+            .ok()
+            .map(Self::Pattern)
+    }
+
+    fn matches(&self, payload: &str) -> bool {
+        match self {
+            Self::Exact(pattern) => payload.trim().to_lowercase() == *pattern,
+            Self::Pattern(regex) => regex.is_match(payload),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Response {
+    matcher: Matcher,
+    reply: String,
+    cooldown: Duration,
+    last: Option<Instant>,
+}
+
+/// Replies to messages that match one of the configured triggers.
+///
+/// Replying to a message that another bot sent in reply to this one could go back and forth
+/// forever, so the user's own messages and ones that contain any of the replies are left alone,
+/// each trigger has a cooldown, and there are never more than [`RESPONSE_LIMIT`] replies
+/// within [`RESPONSE_PERIOD`].
+#[derive(Debug, Clone)]
+pub struct AutoResponder {
+    enabled: bool,
+    responses: Vec<Response>,
+    /// When the latest replies were sent, oldest first.
+    sent: VecDeque<Instant>,
+}
+
+impl AutoResponder {
+    pub fn new(config: &ResponsesConfig) -> Self {
+        let responses = config
+            .triggers
+            .iter()
+            .filter(|trigger| !trigger.reply.trim().is_empty())
+            .filter_map(|trigger| {
+                Some(Response {
+                    matcher: Matcher::new(trigger)?,
+                    reply: trigger.reply.trim().to_string(),
+                    cooldown: Duration::from_secs(trigger.cooldown),
+                    last: None,
+                })
+            })
+            .collect();
+
+        Self {
+            enabled: config.enabled,
+            responses,
+            sent: VecDeque::new(),
+        }
+    }
+
+    /// If a message could be a reply, either by this or another instance of it.
+    fn is_reply(&self, payload: &str) -> bool {
+        let payload = payload.to_lowercase();
+
+        self.responses
+            .iter()
+            .any(|response| payload.contains(&response.reply.to_lowercase()))
+    }
+
+    /// The reply to a message at `now`, if the first trigger it matches is not cooling down
+    /// and replies are not being sent too often.
+    pub fn respond(&mut self, message: &MessageData, login: &str, now: Instant) -> Option<String> {
+        if !self.enabled
+            || message.system
+            || message.history
+            || is_own_message(message, login)
+            || self.is_reply(&message.payload)
+        {
+            return None;
+        }
+
+        while self
+            .sent
+            .front()
+            .is_some_and(|&sent| now.saturating_duration_since(sent) >= RESPONSE_PERIOD)
+        {
+            self.sent.pop_front();
+        }
+
+        if self.sent.len() >= RESPONSE_LIMIT {
+            return None;
+        }
+
+        let response = self
+            .responses
+            .iter_mut()
+            .find(|response| response.matcher.matches(&message.payload))?;
+
+        if response
+            .last
+            .is_some_and(|last| now.saturating_duration_since(last) < response.cooldown)
+        {
+            return None;
+        }
+
+        response.last = Some(now);
+        self.sent.push_back(now);
+
+        Some(response.reply.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trigger(kind: TriggerKind, pattern: &str, reply: &str) -> ResponseTrigger {
+        ResponseTrigger {
+            kind,
+            pattern: pattern.to_string(),
+            reply: reply.to_string(),
+            cooldown: 30,
+        }
+    }
+
+    fn responder(triggers: Vec<ResponseTrigger>) -> AutoResponder {
+        AutoResponder::new(&ResponsesConfig {
+            enabled: true,
+            triggers,
+        })
+    }
+
+    fn message(author: &str, payload: &str) -> MessageData {
+        MessageData::new(
+            author.to_string(),
+            None,
+            false,
+            payload.to_string(),
+            None,
+            false,
+        )
+    }
+
+    #[test]
+    fn test_trigger_kinds() {
+        let mut responder = responder(vec![
+            trigger(TriggerKind::Exact, "!socials", "Socials are in the panels"),
+            trigger(
+                TriggerKind::Keyword,
+                "discord",
+                "The discord is in the panels",
+            ),
+            trigger(
+                TriggerKind::Regex,
+                r"^!(?:uptime|live)\b",
+                "Live since noon",
+            ),
+            trigger(TriggerKind::Regex, "(unclosed", "Never sent"),
+        ]);
+        let now = Instant::now();
+
+        assert_eq!(
+            responder.respond(&message("viewer", " !SOCIALS "), "nanashi", now),
+            Some("Socials are in the panels".to_string())
+        );
+        assert_eq!(
+            responder.respond(&message("viewer", "!socials please"), "nanashi", now),
+            None
+        );
+        assert_eq!(
+            responder.respond(&message("viewer", "is there a Discord?"), "nanashi", now),
+            Some("The discord is in the panels".to_string())
+        );
+        assert_eq!(
+            responder.respond(&message("viewer", "!uptime"), "nanashi", now),
+            Some("Live since noon".to_string())
+        );
+        assert_eq!(
+            responder.respond(&message("viewer", "(unclosed"), "nanashi", now),
+            None
+        );
+    }
+
+    #[test]
+    fn test_keyword_is_whole_word() {
+        let mut responder = responder(vec![trigger(TriggerKind::Keyword, "gg", "GG!")]);
+
+        assert_eq!(
+            responder.respond(&message("viewer", "eggs"), "nanashi", Instant::now()),
+            None
+        );
+    }
+
+    #[test]
+    fn test_cooldown_and_limit() {
+        let mut responder = responder(vec![
+            trigger(TriggerKind::Keyword, "hello", "Welcome in"),
+            trigger(TriggerKind::Keyword, "bye", "See you"),
+        ]);
+        let start = Instant::now();
+        let hello = message("viewer", "hello");
+
+        assert!(responder.respond(&hello, "nanashi", start).is_some());
+        assert!(responder
+            .respond(&hello, "nanashi", start + Duration::from_secs(29))
+            .is_none());
+        assert!(responder
+            .respond(&hello, "nanashi", start + Duration::from_secs(30))
+            .is_some());
+
+        // Other triggers can keep replying until the limit over all of them is reached.
+        responder.responses[1].cooldown = Duration::ZERO;
+
+        let bye = message("viewer", "bye");
+
+        for second in 31..34 {
+            assert!(responder
+                .respond(&bye, "nanashi", start + Duration::from_secs(second))
+                .is_some());
+        }
+
+        assert!(responder
+            .respond(&bye, "nanashi", start + Duration::from_secs(34))
+            .is_none());
+        assert!(responder
+            .respond(&bye, "nanashi", start + RESPONSE_PERIOD)
+            .is_some());
+    }
+
+    #[test]
+    fn test_no_feedback_loop() {
+        let mut responder = responder(vec![trigger(
+            TriggerKind::Keyword,
+            "discord",
+            "The discord is in the panels",
+        )]);
+        let now = Instant::now();
+
+        // Own messages, even from another client.
+        let mut own = message("Nanashi", "discord?");
+        own.login = Some("nanashi".to_string());
+        assert_eq!(responder.respond(&own, "nanashi", now), None);
+
+        // The reply, as another bot or instance would send it back.
+        assert_eq!(
+            responder.respond(
+                &message("otherbot", "@nanashi THE DISCORD IS IN THE PANELS"),
+                "nanashi",
+                now
+            ),
+            None
+        );
+
+        let mut old = message("viewer", "discord?");
+        old.history = true;
+        assert_eq!(responder.respond(&old, "nanashi", now), None);
+    }
+
+    #[test]
+    fn test_disabled() {
+        let mut responder = AutoResponder::new(&ResponsesConfig {
+            enabled: false,
+            triggers: vec![trigger(TriggerKind::Keyword, "hello", "Welcome in")],
+        });
+
+        assert_eq!(
+            responder.respond(&message("viewer", "hello"), "nanashi", Instant::now()),
+            None
+        );
+    }
+}
@@ -0,0 +1,133 @@
+use std::collections::VecDeque;
+
+use tui::style::Color;
+
+use crate::{
+    handlers::{config::FrontendConfig, data::MessageData},
+    twitch::badges::{strip_badges, Role},
+};
+
+/// How many of the latest people to talk are kept.
+pub const SPEAKERS_KEPT: usize = 50;
+
+/// Someone who talked in chat, as shown in the list of recent speakers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Speaker {
+    /// Tells people apart, since the shown name can change.
+    pub login: String,
+    pub name: String,
+    /// The role that their latest message showed them to have.
+    pub role: Option<Role>,
+    pub color: Color,
+}
+
+impl Speaker {
+    /// The speaker of a message, where system messages have none.
+    pub fn from_message(message: &MessageData, frontend_config: &FrontendConfig) -> Option<Self> {
+        if message.system {
+            return None;
+        }
+
+        Some(Self {
+            login: message
+                .login
+                .clone()
+                .unwrap_or_else(|| message.author.to_lowercase()),
+            name: strip_badges(&message.author).to_string(),
+            role: message.role,
+            color: message.author_color(frontend_config),
+        })
+    }
+
+    /// The name with the glyph of the role in front of it, if there is one.
+    pub fn label(&self) -> String {
+        self.role.map_or_else(
+            || self.name.clone(),
+            |role| format!("{}{}", role.glyph(), self.name),
+        )
+    }
+}
+
+/// The latest people to talk in the channel, most recent first.
+#[derive(Debug, Clone, Default)]
+pub struct RecentSpeakers {
+    speakers: VecDeque<Speaker>,
+}
+
+impl RecentSpeakers {
+    /// Move the speaker to the front, taking on their latest name and role.
+    pub fn push(&mut self, speaker: Speaker) {
+        self.speakers.retain(|s| s.login != speaker.login);
+        self.speakers.push_front(speaker);
+        self.speakers.truncate(SPEAKERS_KEPT);
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = &Speaker> {
+        self.speakers.iter()
+    }
+
+    pub fn clear(&mut self) {
+        self.speakers.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(author: &str, role: Option<Role>) -> MessageData {
+        let mut message = MessageData::new(
+            author.to_string(),
+            None,
+            false,
+            "hello".to_string(),
+            None,
+            false,
+        );
+        message.login = Some(strip_badges(author).to_lowercase());
+        message.role = role;
+
+        message
+    }
+
+    #[test]
+    fn test_speaker_from_message() {
+        let frontend_config = FrontendConfig::default();
+        let message = message("\u{1F528}Nanashi", Some(Role::Moderator));
+
+        let speaker = Speaker::from_message(&message, &frontend_config).unwrap();
+
+        assert_eq!(speaker.name, "Nanashi");
+        assert_eq!(speaker.label(), "\u{1F528}Nanashi");
+        assert_eq!(speaker.color, message.author_color(&frontend_config));
+
+        let system = MessageData::new(
+            "Twitch".to_string(),
+            None,
+            true,
+            "Connected".to_string(),
+            None,
+            false,
+        );
+
+        assert_eq!(Speaker::from_message(&system, &frontend_config), None);
+    }
+
+    #[test]
+    fn test_role_changes_mid_session() {
+        let frontend_config = FrontendConfig::default();
+        let mut speakers = RecentSpeakers::default();
+
+        for message in [
+            message("Nanashi", None),
+            message("Other", Some(Role::Subscriber)),
+            message("Nanashi", Some(Role::Vip)),
+        ] {
+            speakers.push(Speaker::from_message(&message, &frontend_config).unwrap());
+        }
+
+        let labels = speakers.entries().map(Speaker::label).collect::<Vec<_>>();
+
+        assert_eq!(labels, vec!["\u{1F48E}Nanashi", "\u{2B50}Other"]);
+    }
+}
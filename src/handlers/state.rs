@@ -6,31 +6,35 @@ use std::{
 use color_eyre::eyre::{bail, Error, Result};
 use serde::Serialize;
 use serde_with::DeserializeFromStr;
+use tui::style::Color;
 
-#[derive(Debug, PartialEq, Eq, Clone, Serialize, DeserializeFromStr)]
-pub enum NormalMode {
+/// Where keys go in chat, which decides what they do.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum InputMode {
+    /// Keys are bindings, such as for scrolling or opening popups.
+    Normal,
+    /// Keys are typed into the message to send.
     Insert,
+    /// Keys are typed into a search.
     Search,
 }
 
-impl ToString for NormalMode {
-    fn to_string(&self) -> String {
+impl InputMode {
+    /// The label of the mode indicator.
+    pub const fn label(self) -> &'static str {
         match self {
-            Self::Insert => "insert",
-            Self::Search => "search",
+            Self::Normal => "NORMAL",
+            Self::Insert => "INSERT",
+            Self::Search => "SEARCH",
         }
-        .to_string()
     }
-}
-
-impl FromStr for NormalMode {
-    type Err = Error;
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s.to_lowercase().as_str() {
-            "insert" | "input" => Ok(Self::Insert),
-            "search" => Ok(Self::Search),
-            _ => bail!("Normal mode '{}' cannot be deserialized", s),
+    /// The color of the mode indicator.
+    pub const fn color(self) -> Color {
+        match self {
+            Self::Normal => Color::Blue,
+            Self::Insert => Color::Green,
+            Self::Search => Color::Yellow,
         }
     }
 }
@@ -71,3 +75,18 @@ impl FromStr for State {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_input_mode_indicator() {
+        assert_eq!(InputMode::Normal.label(), "NORMAL");
+        assert_eq!(InputMode::Normal.color(), Color::Blue);
+        assert_eq!(InputMode::Insert.label(), "INSERT");
+        assert_eq!(InputMode::Insert.color(), Color::Green);
+        assert_eq!(InputMode::Search.label(), "SEARCH");
+        assert_eq!(InputMode::Search.color(), Color::Yellow);
+    }
+}
@@ -5,12 +5,27 @@ use std::{
     io::Write,
     path::Path,
     rc::Rc,
+    sync::{Mutex, MutexGuard, PoisonError},
+    time::{Duration, Instant},
 };
 
+use log::warn;
 use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
 
-use crate::{handlers::config::StorageConfig, utils::pathing::config_path};
+use crate::{
+    handlers::config::StorageConfig,
+    utils::pathing::{capabilities, config_path},
+};
+
+pub const SESSION_FILE: &str = "session.json";
+
+/// How often the session is written at most while the application runs.
+const SESSION_SAVE_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Where the session goes and what it is, if it changed since it was last written,
+/// so that it can still be written if the application panics.
+static PENDING_SESSION: Mutex<Option<(String, Session)>> = Mutex::new(None);
 
 static ITEM_KEYS: Lazy<Vec<&str>> = Lazy::new(|| vec!["channels", "mentions"]);
 
@@ -52,13 +67,10 @@ impl Storage {
                 );
             }
 
-            let storage_str = serde_json::to_string(&items).unwrap();
-
-            let mut file = File::create(&file_path).unwrap();
-
-            file.write_all(storage_str.as_bytes()).unwrap();
+            let storage = Self { items, file_path };
+            storage.dump_data();
 
-            return Self { items, file_path };
+            return storage;
         }
 
         let file_content = read_to_string(&file_path).unwrap();
@@ -68,7 +80,12 @@ impl Storage {
         Self { items, file_path }
     }
 
+    /// Save the storage to disk, unless it can only be kept in memory.
     pub fn dump_data(&self) {
+        if !capabilities().data {
+            return;
+        }
+
         let storage_str = serde_json::to_string(&self.items).unwrap();
 
         let mut file = File::create(&self.file_path).unwrap();
@@ -135,3 +152,201 @@ impl Storage {
         }
     }
 }
+
+/// What was open when the application last exited, so it can be continued on the next launch.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Session {
+    /// Channels that were open, the last one being the active channel.
+    pub channels: Vec<String>,
+    /// A message that was typed in the chat input, but not sent.
+    pub input_draft: String,
+}
+
+impl Session {
+    /// Load the previous session, where a missing or corrupt file is an empty session.
+    pub fn load(file: &str) -> Self {
+        let file_path = config_path(file);
+
+        let Ok(file_content) = read_to_string(&file_path) else {
+            return Self::default();
+        };
+
+        Self::from_json(&file_content).unwrap_or_else(|| {
+            warn!("Session file {file_path} could not be read, ignoring it.");
+
+            Self::default()
+        })
+    }
+
+    fn from_json(s: &str) -> Option<Self> {
+        serde_json::from_str(s).ok()
+    }
+
+    fn write_to(&self, file_path: &str) {
+        if !capabilities().data {
+            return;
+        }
+
+        let Ok(session_str) = serde_json::to_string(self) else {
+            return;
+        };
+
+        if let Err(err) =
+            File::create(file_path).and_then(|mut f| f.write_all(session_str.as_bytes()))
+        {
+            warn!("Unable to save session to {file_path}: {err}");
+        }
+    }
+
+    /// The channel that was active, if any.
+    pub fn active_channel(&self) -> Option<&String> {
+        self.channels.last().filter(|c| !c.is_empty())
+    }
+}
+
+/// Writes the session as it changes while the application runs, so that it survives a crash.
+#[derive(Debug)]
+pub struct SessionWriter {
+    file_path: String,
+    /// The session as it was last written, or loaded.
+    saved: Session,
+    last_write: Option<Instant>,
+}
+
+impl SessionWriter {
+    pub fn new(file: &str, saved: Session) -> Self {
+        Self {
+            file_path: config_path(file),
+            saved,
+            last_write: None,
+        }
+    }
+
+    /// Write the session if it changed and was not written in the last few seconds,
+    /// returning if it was written. A change that has to wait is kept for [`save_pending_session`].
+    pub fn update(&mut self, session: Session, now: Instant) -> bool {
+        if session == self.saved {
+            *pending_session() = None;
+
+            return false;
+        }
+
+        if self
+            .last_write
+            .is_some_and(|last_write| now.duration_since(last_write) < SESSION_SAVE_INTERVAL)
+        {
+            *pending_session() = Some((self.file_path.clone(), session));
+
+            return false;
+        }
+
+        self.write(session, now);
+
+        true
+    }
+
+    /// Write the session if it changed, no matter when it was last written.
+    pub fn flush(&mut self, session: Session) {
+        if session != self.saved {
+            self.write(session, Instant::now());
+        }
+    }
+
+    fn write(&mut self, session: Session, now: Instant) {
+        session.write_to(&self.file_path);
+
+        *pending_session() = None;
+        self.saved = session;
+        self.last_write = Some(now);
+    }
+}
+
+/// The session waiting to be written, where a panic while it was held does not stop it from being.
+fn pending_session() -> MutexGuard<'static, Option<(String, Session)>> {
+    PENDING_SESSION
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Write the session that changed since it was last written, such as when the application panics.
+pub fn save_pending_session() {
+    let pending = pending_session().take();
+
+    if let Some((file_path, session)) = pending {
+        session.write_to(&file_path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_session_round_trip_multiple_channels() {
+        let session = Session {
+            channels: vec![
+                "xithrius".to_string(),
+                "rocketleague".to_string(),
+                "antvenom".to_string(),
+            ],
+            input_draft: "half typed mess".to_string(),
+        };
+
+        let json = serde_json::to_string(&session).unwrap();
+        let restored = Session::from_json(&json).unwrap();
+
+        assert_eq!(restored, session);
+        assert_eq!(restored.active_channel(), Some(&"antvenom".to_string()));
+    }
+
+    #[test]
+    fn test_session_missing_fields() {
+        let restored = Session::from_json(r#"{"channels": ["xithrius"]}"#).unwrap();
+
+        assert_eq!(restored.channels, vec!["xithrius".to_string()]);
+        assert!(restored.input_draft.is_empty());
+    }
+
+    #[test]
+    fn test_session_corrupt() {
+        assert!(Session::from_json("{\"channels\": [\"xith").is_none());
+        assert!(Session::from_json("").is_none());
+    }
+
+    #[test]
+    fn test_session_written_on_change() {
+        let dir = tempfile::tempdir().unwrap();
+        let file_path = dir.path().join(SESSION_FILE).to_string_lossy().to_string();
+        let written = || Session::from_json(&read_to_string(&file_path).unwrap()).unwrap();
+
+        let mut writer = SessionWriter {
+            file_path: file_path.clone(),
+            saved: Session::default(),
+            last_write: None,
+        };
+
+        let now = Instant::now();
+        let draft = |input_draft: &str| Session {
+            channels: vec!["xithrius".to_string()],
+            input_draft: input_draft.to_string(),
+        };
+
+        // Nothing is written while the session is as it was.
+        assert!(!writer.update(Session::default(), now));
+        assert!(read_to_string(&file_path).is_err());
+
+        assert!(writer.update(draft("half"), now));
+        assert_eq!(written(), draft("half"));
+
+        // Changes right after are only written once some time has passed, or on a panic.
+        assert!(!writer.update(draft("half typed"), now + Duration::from_secs(1)));
+        assert_eq!(written(), draft("half"));
+
+        save_pending_session();
+        assert_eq!(written(), draft("half typed"));
+
+        assert!(writer.update(draft("half typed mess"), now + SESSION_SAVE_INTERVAL));
+        assert_eq!(written(), draft("half typed mess"));
+    }
+}
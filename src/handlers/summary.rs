@@ -0,0 +1,330 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    fs::File,
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, Local};
+use log::{info, warn};
+use serde::Serialize;
+
+use crate::{
+    handlers::{
+        alerts::is_alert,
+        chat_stats::TOP_TALKERS,
+        config::{AlertsConfig, EmoteText},
+        data::{message_to_plain, MessageData},
+    },
+    utils::pathing::{capabilities, config_path},
+};
+
+/// How many of the messages before a mention are kept along with it.
+const MENTION_CONTEXT: usize = 2;
+
+/// A chatter and how many messages they sent.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Talker {
+    pub name: String,
+    pub messages: usize,
+}
+
+/// A message that mentioned the user, with what was said right before it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Mention {
+    pub time: String,
+    pub author: String,
+    pub message: String,
+    /// The messages before the mention as `author: message`, oldest first.
+    pub context: Vec<String>,
+}
+
+/// What happened in chat over a session, to look back on after the stream.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SessionSummary {
+    pub channel: String,
+    pub started: String,
+    pub ended: String,
+    pub messages: usize,
+    pub chatters: usize,
+    pub top_talkers: Vec<Talker>,
+    pub mentions: Vec<Mention>,
+}
+
+fn plain(message: &MessageData) -> String {
+    message_to_plain(message, EmoteText::Names)
+}
+
+/// What chat said over the session, counted as messages come in, since chat itself only
+/// keeps its latest messages.
+#[derive(Debug, Clone, Default)]
+pub struct SessionLog {
+    messages: usize,
+    /// By login, the name that was shown and how many messages they sent.
+    talkers: HashMap<String, (String, usize)>,
+    mentions: Vec<Mention>,
+    /// The latest messages as `author: message`, oldest first, to go along with a mention.
+    recent: VecDeque<String>,
+}
+
+impl SessionLog {
+    /// Count a message of chat, where it is a mention if it would alert the user.
+    pub fn push(&mut self, message: &MessageData, login: &str, alerts: &AlertsConfig) {
+        if message.system {
+            return;
+        }
+
+        self.messages += 1;
+
+        // Chatters are told apart by their login, the display name is kept to be shown.
+        let chatter = message
+            .login
+            .as_deref()
+            .unwrap_or(&message.author)
+            .to_lowercase();
+
+        self.talkers
+            .entry(chatter)
+            .or_insert_with(|| (message.author.clone(), 0))
+            .1 += 1;
+
+        let text = plain(message);
+
+        if is_alert(message, login, alerts) {
+            self.mentions.push(Mention {
+                time: message.time_sent.to_rfc3339(),
+                author: message.author.clone(),
+                message: text.clone(),
+                context: self.recent.iter().cloned().collect(),
+            });
+        }
+
+        if self.recent.len() == MENTION_CONTEXT {
+            self.recent.pop_front();
+        }
+
+        self.recent.push_back(format!("{}: {text}", message.author));
+    }
+
+    /// The summary of everything counted so far.
+    pub fn summary(
+        &self,
+        channel: &str,
+        started: DateTime<Local>,
+        ended: DateTime<Local>,
+    ) -> SessionSummary {
+        let mut top_talkers = self
+            .talkers
+            .values()
+            .map(|(name, messages)| Talker {
+                name: name.clone(),
+                messages: *messages,
+            })
+            .collect::<Vec<_>>();
+
+        top_talkers.sort_by(|a, b| b.messages.cmp(&a.messages).then(a.name.cmp(&b.name)));
+        top_talkers.truncate(TOP_TALKERS);
+
+        SessionSummary {
+            channel: channel.to_string(),
+            started: started.to_rfc3339(),
+            ended: ended.to_rfc3339(),
+            messages: self.messages,
+            chatters: self.talkers.len(),
+            top_talkers,
+            mentions: self.mentions.clone(),
+        }
+    }
+}
+
+impl SessionSummary {
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(self)
+    }
+
+    /// The name of the file that a summary of a session ending at `ended` is written to.
+    pub fn file_name(ended: DateTime<Local>) -> String {
+        format!("summary-{}.json", ended.format("%Y-%m-%d_%H-%M-%S"))
+    }
+
+    /// Write the summary to a file in `dir`, returning its path.
+    fn write_to(&self, dir: &Path, ended: DateTime<Local>) -> io::Result<PathBuf> {
+        let file_path = dir.join(Self::file_name(ended));
+        let summary = self.to_json()?;
+
+        File::create(&file_path)?.write_all(summary.as_bytes())?;
+
+        Ok(file_path)
+    }
+
+    /// Write the summary next to the config, returning where it went. Nothing is written
+    /// when the directory cannot be written to, nor does that stop the client from quitting.
+    pub fn dump_data(&self, ended: DateTime<Local>) -> Option<PathBuf> {
+        if !capabilities().data {
+            warn!("Not writing the session summary, as its directory cannot be written to.");
+
+            return None;
+        }
+
+        let dir = PathBuf::from(config_path(""));
+
+        match self.write_to(&dir, ended) {
+            Ok(file_path) => {
+                info!("Wrote the session summary to {}", file_path.display());
+
+                Some(file_path)
+            }
+            Err(err) => {
+                warn!(
+                    "Unable to write the session summary to {}: {err}",
+                    dir.display()
+                );
+
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, TimeZone};
+
+    use super::*;
+
+    fn message(author: &str, text: &str, at: DateTime<Local>) -> MessageData {
+        let mut message = MessageData::new(
+            author.to_string(),
+            None,
+            false,
+            text.to_string(),
+            None,
+            false,
+        );
+        message.login = Some(author.to_lowercase());
+        message.time_sent = at;
+
+        message
+    }
+
+    #[test]
+    fn test_session_summary() {
+        let started = Local.with_ymd_and_hms(2024, 3, 1, 20, 0, 0).unwrap();
+        let ended = started + Duration::hours(2);
+        let at = |minutes| started + Duration::minutes(minutes);
+
+        let mut system = message("twitch-tui", "Joined xithrius", at(0));
+        system.system = true;
+
+        let messages = vec![
+            system,
+            message("Xithrius", "thanks xithrius for watching", at(4)),
+            message("Alice", "hi", at(5)),
+            message("Carol", "that was close", at(6)),
+            message("bob", "gg", at(7)),
+            message("Alice", "@xithrius what game is next?", at(8)),
+            message("bob", "see you", at(9)),
+        ];
+
+        let mut log = SessionLog::default();
+
+        for message in &messages {
+            log.push(message, "xithrius", &AlertsConfig::default());
+        }
+
+        let summary = log.summary("xithrius", started, ended);
+
+        assert_eq!(summary.messages, 6);
+        assert_eq!(summary.chatters, 4);
+        assert_eq!(
+            summary.top_talkers[..2],
+            [
+                Talker {
+                    name: "Alice".to_string(),
+                    messages: 2
+                },
+                Talker {
+                    name: "bob".to_string(),
+                    messages: 2
+                }
+            ]
+        );
+
+        // The user's own messages are not mentions of them.
+        assert_eq!(
+            summary.mentions,
+            vec![Mention {
+                time: at(8).to_rfc3339(),
+                author: "Alice".to_string(),
+                message: "@xithrius what game is next?".to_string(),
+                context: vec!["Carol: that was close".to_string(), "bob: gg".to_string()],
+            }]
+        );
+
+        let json: serde_json::Value = serde_json::from_str(&summary.to_json().unwrap()).unwrap();
+
+        assert_eq!(json["channel"], "xithrius");
+        assert_eq!(json["started"], started.to_rfc3339());
+        assert_eq!(json["messages"], 6);
+        assert_eq!(json["top_talkers"][0]["name"], "Alice");
+        assert_eq!(json["mentions"][0]["context"][1], "bob: gg");
+    }
+
+    #[test]
+    fn test_session_log_outlives_chat() {
+        let now = Local.with_ymd_and_hms(2024, 3, 1, 20, 0, 0).unwrap();
+        let alerts = AlertsConfig::default();
+
+        let mut log = SessionLog::default();
+
+        log.push(&message("Alice", "hey xithrius", now), "xithrius", &alerts);
+
+        // Far more messages than chat keeps, which would have pushed the mention out of it.
+        for i in 0..1000 {
+            log.push(&message("bob", &i.to_string(), now), "xithrius", &alerts);
+        }
+
+        let summary = log.summary("xithrius", now, now);
+
+        assert_eq!(summary.messages, 1001);
+        assert_eq!(summary.chatters, 2);
+        assert_eq!(summary.top_talkers[0].messages, 1000);
+        assert_eq!(summary.mentions.len(), 1);
+        assert!(summary.mentions[0].context.is_empty());
+    }
+
+    #[test]
+    fn test_empty_session_summary() {
+        let now = Local.with_ymd_and_hms(2024, 3, 1, 20, 15, 0).unwrap();
+
+        let summary = SessionLog::default().summary("xithrius", now, now);
+
+        assert_eq!(summary.messages, 0);
+        assert!(summary.top_talkers.is_empty());
+        assert!(summary.mentions.is_empty());
+        assert!(summary.to_json().is_ok());
+
+        assert_eq!(
+            SessionSummary::file_name(now),
+            "summary-2024-03-01_20-15-00.json"
+        );
+    }
+
+    #[test]
+    fn test_write_summary() {
+        let now = Local.with_ymd_and_hms(2024, 3, 1, 20, 15, 0).unwrap();
+        let summary = SessionLog::default().summary("xithrius", now, now);
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = summary.write_to(dir.path(), now).unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(path).unwrap(),
+            summary.to_json().unwrap()
+        );
+
+        // A directory that cannot be written to is an error instead of a panic.
+        let file = tempfile::NamedTempFile::new().unwrap();
+        assert!(summary.write_to(&file.path().join("data"), now).is_err());
+    }
+}
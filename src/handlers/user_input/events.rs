@@ -1,11 +1,12 @@
 use std::time::Duration;
 
 use crossterm::event::{
-    self, Event as CEvent, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, MouseEventKind,
+    self, Event as CEvent, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, MouseButton,
+    MouseEventKind,
 };
 use tokio::{sync::mpsc, time::Instant};
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Key {
     // Keyboard controls
     Backspace,
@@ -27,6 +28,8 @@ pub enum Key {
 
     // Mouse controls
     ScrollUp,
+    /// A left click, at a column and row.
+    Click(u16, u16),
     ScrollDown,
 }
 
@@ -42,6 +45,8 @@ impl ToString for Key {
 
 pub enum Event {
     Input(Key),
+    /// The terminal changed size.
+    Resize,
     Tick,
 }
 
@@ -106,6 +111,9 @@ impl Events {
                             let key = match key.kind {
                                 MouseEventKind::ScrollDown => Key::ScrollDown,
                                 MouseEventKind::ScrollUp => Key::ScrollUp,
+                                MouseEventKind::Down(MouseButton::Left) => {
+                                    Key::Click(key.column, key.row)
+                                }
                                 _ => Key::Null,
                             };
 
@@ -114,6 +122,12 @@ impl Events {
                                 return;
                             }
                         }
+                        Ok(CEvent::Resize(_, _)) => {
+                            if let Err(err) = tx.send(Event::Resize).await {
+                                eprintln!("Resize event error: {err}");
+                                return;
+                            }
+                        }
                         _ => (),
                     }
                 }
@@ -1,8 +1,40 @@
+use std::collections::VecDeque;
+
+use crate::handlers::data::MessageData;
+
+/// How the chat window follows new messages.
+///
+/// - `Following` becomes `ScrolledUp` when scrolling away from the newest message,
+///   and goes back to `Following` once scrolled all the way down again.
+/// - Any state becomes `Paused` when pausing, which scrolling does not change.
+/// - Any state becomes `Following` at the newest message when resuming.
+/// - `Following` becomes `ScrolledUp` at the newest message when selecting one with the keyboard.
+///
+/// While a popup is open over chat, new messages are held back as if paused,
+/// without changing the state.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum ScrollState {
+    /// The newest message is shown, and new messages appear as they arrive.
+    #[default]
+    Following,
+    /// Older messages are shown, which stay in place as new messages arrive.
+    ScrolledUp,
+    /// New messages are held back until chat is resumed.
+    Paused,
+}
+
 pub struct Scrolling {
     /// Offset of scroll
     offset: usize,
     /// If the scrolling is currently inverted
     inverted: bool,
+    state: ScrollState,
+    /// Messages that arrived while paused or behind a popup, from newest to oldest.
+    held: VecDeque<MessageData>,
+    /// If a popup is open, which chat should not move behind.
+    modal: bool,
+    /// If a message was selected with the keyboard, which stays selected until chat is resumed.
+    selected: bool,
 }
 
 impl Scrolling {
@@ -10,6 +42,10 @@ impl Scrolling {
         Self {
             offset: 0,
             inverted,
+            state: ScrollState::Following,
+            held: VecDeque::new(),
+            modal: false,
+            selected: false,
         }
     }
 
@@ -17,27 +53,168 @@ impl Scrolling {
         self.inverted
     }
 
+    pub const fn state(&self) -> ScrollState {
+        self.state
+    }
+
+    /// How many messages are waiting for chat to be resumed.
+    pub fn held(&self) -> usize {
+        self.held.len()
+    }
+
     pub fn up(&mut self) {
         self.offset = self.offset.saturating_add(1);
+
+        if self.state == ScrollState::Following {
+            self.state = ScrollState::ScrolledUp;
+        }
     }
 
     pub fn down(&mut self) {
         self.offset = self.offset.saturating_sub(1);
+
+        if self.state == ScrollState::ScrolledUp && self.offset == 0 {
+            self.state = ScrollState::Following;
+            self.selected = false;
+        }
+    }
+
+    /// If a message was selected with [`Self::select`].
+    pub const fn is_selected(&self) -> bool {
+        self.selected
+    }
+
+    /// Move the selection by `step` messages, to older ones when positive, among the `count`
+    /// messages shown in chat. The first move selects the newest message.
+    ///
+    /// The selected message is the lowest one shown, so that it always stays in view.
+    pub fn select(&mut self, step: isize, count: usize) {
+        if count == 0 {
+            return;
+        }
+
+        self.offset = if self.state == ScrollState::Following {
+            0
+        } else {
+            clamp_offset(self.offset, step, count)
+        };
+
+        self.selected = true;
+
+        if self.state == ScrollState::Following {
+            self.state = ScrollState::ScrolledUp;
+        }
+    }
+
+    /// Select a message by its index among the messages shown in chat, newest first.
+    pub fn select_at(&mut self, index: usize) {
+        self.offset = index;
+        self.selected = true;
+
+        if self.state == ScrollState::Following {
+            self.state = ScrollState::ScrolledUp;
+        }
     }
 
     pub fn jump_to(&mut self, index: usize) {
         self.offset = index;
+
+        if self.state != ScrollState::Paused {
+            self.state = if index == 0 {
+                self.selected = false;
+
+                ScrollState::Following
+            } else {
+                ScrollState::ScrolledUp
+            };
+        }
+    }
+
+    /// Keep the offset on a message once messages were taken out, leaving `count` of them.
+    pub fn clamp_to(&mut self, count: usize) {
+        if self.offset > 0 && self.offset >= count {
+            self.jump_to(count.saturating_sub(1));
+        }
     }
 
     pub const fn get_offset(&self) -> usize {
         self.offset
     }
+
+    /// Hold back new messages until chat is resumed.
+    pub fn pause(&mut self) {
+        self.state = ScrollState::Paused;
+    }
+
+    /// Hold back new messages while a popup is open, and show them once it is closed,
+    /// unless chat was paused.
+    pub fn set_modal(&mut self, open: bool, messages: &mut VecDeque<MessageData>) {
+        if self.modal == open {
+            return;
+        }
+
+        self.modal = open;
+
+        if !open && self.state != ScrollState::Paused {
+            while let Some(message) = self.held.pop_back() {
+                self.push(messages, message);
+            }
+        }
+    }
+
+    /// Add a new message to the front of chat, or hold it back if chat is paused.
+    pub fn push(&mut self, messages: &mut VecDeque<MessageData>, message: MessageData) {
+        match self.state {
+            ScrollState::Following if self.modal => self.held.push_front(message),
+            ScrollState::Following => messages.push_front(message),
+            ScrollState::ScrolledUp => {
+                messages.push_front(message);
+
+                // Keep the same messages in view.
+                self.offset = self.offset.saturating_add(1);
+            }
+            ScrollState::Paused => self.held.push_front(message),
+        }
+    }
+
+    /// Show the messages that were held back, and follow chat from the newest message.
+    pub fn resume(&mut self, messages: &mut VecDeque<MessageData>) {
+        while let Some(message) = self.held.pop_back() {
+            messages.push_front(message);
+        }
+
+        self.offset = 0;
+        self.state = ScrollState::Following;
+        self.selected = false;
+    }
+}
+
+/// The offset `step` messages away from `offset`, without going past the newest or oldest of `count` messages.
+pub fn clamp_offset(offset: usize, step: isize, count: usize) -> usize {
+    offset
+        .saturating_add_signed(step)
+        .min(count.saturating_sub(1))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn message(payload: &str) -> MessageData {
+        MessageData::new(
+            "Foo".to_string(),
+            None,
+            false,
+            payload.to_string(),
+            None,
+            false,
+        )
+    }
+
+    fn payloads(messages: &VecDeque<MessageData>) -> Vec<&str> {
+        messages.iter().map(|m| m.payload.as_str()).collect()
+    }
+
     #[test]
     fn test_no_scroll_overflow_not_inverted() {
         let mut scroll = Scrolling::new(false);
@@ -46,4 +223,168 @@ mod tests {
         scroll.down();
         assert_eq!(scroll.get_offset(), 0);
     }
+
+    #[test]
+    fn test_scrolling_back_down_follows() {
+        let mut scroll = Scrolling::new(false);
+
+        scroll.up();
+        scroll.up();
+        assert_eq!(scroll.state(), ScrollState::ScrolledUp);
+
+        scroll.down();
+        assert_eq!(scroll.state(), ScrollState::ScrolledUp);
+
+        scroll.down();
+        assert_eq!(scroll.state(), ScrollState::Following);
+    }
+
+    #[test]
+    fn test_clamp_to_remaining_messages() {
+        let mut scroll = Scrolling::new(false);
+
+        scroll.select_at(8);
+        scroll.clamp_to(10);
+        assert_eq!(scroll.get_offset(), 8);
+
+        scroll.clamp_to(5);
+        assert_eq!(scroll.get_offset(), 4);
+        assert_eq!(scroll.state(), ScrollState::ScrolledUp);
+
+        scroll.clamp_to(0);
+        assert_eq!(scroll.get_offset(), 0);
+        assert_eq!(scroll.state(), ScrollState::Following);
+    }
+
+    #[test]
+    fn test_resume_while_following() {
+        let mut scroll = Scrolling::new(false);
+        let mut messages = VecDeque::new();
+
+        scroll.push(&mut messages, message("first"));
+        scroll.resume(&mut messages);
+
+        assert_eq!(scroll.state(), ScrollState::Following);
+        assert_eq!(scroll.get_offset(), 0);
+        assert_eq!(payloads(&messages), vec!["first"]);
+    }
+
+    #[test]
+    fn test_resume_while_scrolled_up() {
+        let mut scroll = Scrolling::new(false);
+        let mut messages = VecDeque::from([message("first")]);
+
+        scroll.up();
+        scroll.push(&mut messages, message("second"));
+        assert_eq!(scroll.get_offset(), 2);
+
+        scroll.resume(&mut messages);
+
+        assert_eq!(scroll.state(), ScrollState::Following);
+        assert_eq!(scroll.get_offset(), 0);
+        assert_eq!(payloads(&messages), vec!["second", "first"]);
+    }
+
+    #[test]
+    fn test_resume_while_paused() {
+        let mut scroll = Scrolling::new(false);
+        let mut messages = VecDeque::from([message("first")]);
+
+        scroll.pause();
+        scroll.up();
+        scroll.push(&mut messages, message("second"));
+        scroll.push(&mut messages, message("third"));
+
+        assert_eq!(scroll.state(), ScrollState::Paused);
+        assert_eq!(scroll.held(), 2);
+        assert_eq!(payloads(&messages), vec!["first"]);
+
+        scroll.resume(&mut messages);
+
+        assert_eq!(scroll.state(), ScrollState::Following);
+        assert_eq!(scroll.get_offset(), 0);
+        assert_eq!(scroll.held(), 0);
+        assert_eq!(payloads(&messages), vec!["third", "second", "first"]);
+    }
+
+    #[test]
+    fn test_clamp_offset() {
+        assert_eq!(clamp_offset(0, 1, 3), 1);
+        assert_eq!(clamp_offset(2, 1, 3), 2);
+        assert_eq!(clamp_offset(0, -1, 3), 0);
+        assert_eq!(clamp_offset(5, -1, 3), 2);
+        assert_eq!(clamp_offset(0, 1, 0), 0);
+    }
+
+    #[test]
+    fn test_select_moves_per_message() {
+        let mut scroll = Scrolling::new(false);
+        let mut messages = VecDeque::new();
+
+        scroll.select(1, 0);
+        assert_eq!(scroll.state(), ScrollState::Following);
+        assert!(!scroll.is_selected());
+
+        scroll.select(1, 3);
+        assert_eq!(scroll.state(), ScrollState::ScrolledUp);
+        assert_eq!(scroll.get_offset(), 0);
+
+        for _ in 0..5 {
+            scroll.select(1, 3);
+        }
+        assert_eq!(scroll.get_offset(), 2);
+
+        scroll.select(-1, 3);
+        assert_eq!(scroll.get_offset(), 1);
+
+        // New messages keep the same one selected.
+        scroll.push(&mut messages, message("new"));
+        assert_eq!(scroll.get_offset(), 2);
+
+        scroll.select(-5, 4);
+        assert_eq!(scroll.get_offset(), 0);
+        assert!(scroll.is_selected());
+
+        scroll.resume(&mut messages);
+        assert_eq!(scroll.state(), ScrollState::Following);
+        assert!(!scroll.is_selected());
+    }
+
+    #[test]
+    fn test_modal_holds_messages() {
+        let mut scroll = Scrolling::new(false);
+        let mut messages = VecDeque::from([message("first")]);
+
+        scroll.set_modal(true, &mut messages);
+        scroll.push(&mut messages, message("second"));
+
+        assert_eq!(scroll.state(), ScrollState::Following);
+        assert_eq!(scroll.held(), 1);
+        assert_eq!(payloads(&messages), vec!["first"]);
+
+        scroll.set_modal(false, &mut messages);
+        scroll.push(&mut messages, message("third"));
+
+        assert_eq!(scroll.held(), 0);
+        assert_eq!(payloads(&messages), vec!["third", "second", "first"]);
+    }
+
+    #[test]
+    fn test_modal_closed_while_paused() {
+        let mut scroll = Scrolling::new(false);
+        let mut messages = VecDeque::new();
+
+        scroll.set_modal(true, &mut messages);
+        scroll.pause();
+        scroll.push(&mut messages, message("first"));
+        scroll.set_modal(false, &mut messages);
+
+        assert_eq!(scroll.state(), ScrollState::Paused);
+        assert_eq!(scroll.held(), 1);
+        assert!(messages.is_empty());
+
+        scroll.resume(&mut messages);
+
+        assert_eq!(payloads(&messages), vec!["first"]);
+    }
 }
@@ -23,8 +23,23 @@ use std::thread;
 use tokio::sync::{broadcast, mpsc};
 
 use crate::{
-    handlers::{app::App, args::Cli, config::CompleteConfig},
-    utils::emotes::emotes_enabled,
+    handlers::{
+        app::App,
+        args::Cli,
+        config::{AutoJoin, CompleteConfig},
+        state::State,
+        storage::Storage,
+    },
+    twitch::{
+        channels::{get_followed_live, pick_startup_channel, StartupChannel},
+        events::{emit_json_lines, EVENTS},
+    },
+    utils::{
+        certificates::{init_root_certificates, RootCertificates},
+        emotes::emotes_enabled,
+        pathing::{init_capabilities, Capabilities},
+        proxy::{init_proxy, Proxy},
+    },
 };
 
 mod commands;
@@ -35,7 +50,7 @@ pub mod twitch;
 mod ui;
 mod utils;
 
-fn initialize_logging(config: &CompleteConfig) {
+fn initialize_logging(config: &CompleteConfig, capabilities: Capabilities) {
     let logger = fern::Dispatch::new()
         .format(|out, message, record| {
             out.finish(format_args!(
@@ -54,10 +69,13 @@ fn initialize_logging(config: &CompleteConfig) {
 
     if let Some(log_file_path) = config.terminal.log_file.clone() {
         if !log_file_path.is_empty() {
-            logger
-                .chain(fern::log_file(log_file_path).unwrap())
-                .apply()
-                .unwrap();
+            match fern::log_file(log_file_path)
+                .ok()
+                .filter(|_| capabilities.log_file)
+            {
+                Some(log_file) => logger.chain(log_file).apply().unwrap(),
+                None => logger.apply().unwrap(),
+            }
         }
     } else {
         logger.apply().unwrap();
@@ -70,22 +88,86 @@ async fn main() -> Result<()> {
 
     color_eyre::install().unwrap();
 
-    let mut config = CompleteConfig::new(Cli::parse())
+    let cli = Cli::parse();
+    let channel_given = cli.channel.is_some();
+    let irc_capture = cli.irc_capture.clone();
+
+    let mut config = CompleteConfig::new(cli)
         .wrap_err("Configuration error.")
         .unwrap();
 
-    initialize_logging(&config);
+    init_proxy(
+        Proxy::from_config(&config.twitch)
+            .wrap_err("Proxy error.")
+            .unwrap(),
+    );
+
+    let capabilities = init_capabilities(config.terminal.log_file.as_deref());
+
+    initialize_logging(&config, capabilities);
 
     info!("Logging system initialised");
 
+    let capability_warning = capabilities.warning();
+
+    if let Some(warning) = &capability_warning {
+        warn!("{warning}");
+    }
+
+    // Only saved for chat once the cache directory is known to be writable.
+    init_root_certificates(RootCertificates::from_config(&config.twitch).unwrap_or_default());
+
+    if !capabilities.cache {
+        config.frontend.twitch_emotes = false;
+        config.frontend.betterttv_emotes = false;
+        config.frontend.seventv_emotes = false;
+        config.frontend.frankerfacez_emotes = false;
+        config.frontend.thumbnails.channels.clear();
+    }
+
+    if config.twitch.auto_join != AutoJoin::None && !channel_given {
+        let live = get_followed_live(&config.twitch)
+            .await
+            .map_err(|e| warn!("Unable to get live followed channels: {e}"))
+            .unwrap_or_default();
+
+        let recent_channels = Storage::new("storage.json", &config.storage).get("channels");
+
+        match pick_startup_channel(&live, &recent_channels, &config.twitch) {
+            StartupChannel::Join(channel) => config.twitch.channel = channel.to_lowercase(),
+            StartupChannel::OpenSwitcher => config.terminal.first_state = State::Dashboard,
+        }
+    }
+
+    if let Some(event_file) = config
+        .terminal
+        .event_file
+        .as_deref()
+        .filter(|f| !f.is_empty())
+    {
+        if let Err(err) = emit_json_lines(&EVENTS, event_file) {
+            warn!("Unable to write chat events to {event_file}: {err}");
+        }
+    }
+
     let (twitch_tx, terminal_rx) = mpsc::channel(100);
     let (terminal_tx, twitch_rx) = broadcast::channel(100);
 
-    let app = App::new(config.clone(), startup_time);
+    let mut app = App::new(config.clone(), startup_time);
+
+    if let Some(warning) = capability_warning {
+        app.components.chat.show_banner(warning);
+    }
 
     info!("Started tokio communication channels.");
 
-    let decoded_rx = if emotes_enabled(&config.frontend) {
+    let decoded_rx = if emotes_enabled(&config.frontend)
+        && !emotes::support_graphics_protocol().unwrap_or(false)
+    {
+        // The emotes are still known by their names, to be styled as text.
+        warn!("This terminal does not support the graphics protocol, emotes are shown as their names.");
+        None
+    } else if emotes_enabled(&config.frontend) {
         // We need to probe the terminal for it's size before starting the tui,
         // as writing on stdout on a different thread can interfere.
         match crossterm::terminal::window_size() {
@@ -124,7 +206,11 @@ async fn main() -> Result<()> {
     let cloned_config = config.clone();
 
     tokio::task::spawn(async move {
-        twitch::twitch_irc(config, twitch_tx, twitch_rx).await;
+        if let Some(path) = irc_capture {
+            twitch::capture::replay_capture(path, config, twitch_tx, twitch_rx).await;
+        } else {
+            twitch::twitch_irc(config, twitch_tx, twitch_rx).await;
+        }
     });
 
     terminal::ui_driver(cloned_config, app, terminal_tx, terminal_rx, decoded_rx).await;
@@ -1,28 +1,107 @@
+use chrono::Local;
 use log::{debug, info, warn};
-use std::time::Duration;
-use tokio::sync::{broadcast::Sender, mpsc::Receiver};
+use std::time::{Duration, Instant};
+use tokio::sync::{broadcast::Sender, mpsc::Receiver, oneshot};
+use tui::style::{Modifier, Style};
 
 use crate::{
     commands::{init_terminal, quit_terminal, reset_terminal},
-    emotes::{display_emote, query_emotes, ApplyCommand, DecodedEmote},
+    emotes::{display_image, query_emotes, ApplyCommand, DecodedEmote, Thumbnails},
     handlers::{
+        alerts::{is_alert, ConnectionAlert, SoundAlert},
         app::App,
+        away::AwayTracker,
+        batch::Batch,
         config::CompleteConfig,
-        data::{MessageData, TwitchToTerminalAction},
+        data::{message_to_plain, MessageData, TwitchToTerminalAction},
+        edits::EditDetector,
+        redraw::Redraw,
+        responses::AutoResponder,
         state::State,
-        user_input::events::{Config, Events, Key},
+        storage::save_pending_session,
+        summary::SessionLog,
+        user_input::events::{Config, Event, Events, Key},
+    },
+    twitch::{
+        chat_settings::RoomModes,
+        eventsub::EventSubEvent,
+        identity::SelfIdentity,
+        oauth::query_token_info,
+        raid::{format_cooldown, RaidDigest, Shoutouts},
+        roles::{query_channel_roles, ChannelRoles},
+        whisper::Whisper,
+        TwitchAction,
     },
-    twitch::TwitchAction,
 };
 
+/// How often chat is checked for messages that are too old to keep.
+const AGE_OUT_INTERVAL: Duration = Duration::from_secs(1);
+
 pub enum TerminalAction {
     Quit,
     BackOneLayer,
     SwitchState(State),
     ClearMessages,
+    /// Show the statistics of chat, which the chat widget does itself.
+    ShowStats,
     Enter(TwitchAction),
 }
 
+/// A message that the user sends as it is shown in chat, where there is none while reading
+/// chat anonymously, as nothing is sent then.
+fn echoed_message(
+    config: &CompleteConfig,
+    identity: Option<(&SelfIdentity, &str)>,
+    message: String,
+    highlight: bool,
+) -> Option<MessageData> {
+    if config.twitch.anonymous {
+        return None;
+    }
+
+    let mut message_data = MessageData::new(
+        config.twitch.username.to_string(),
+        None,
+        false,
+        message,
+        None,
+        highlight,
+    );
+
+    if let Some((identity, login)) = identity {
+        identity.apply(&mut message_data, login, &config.frontend.username_display);
+    }
+
+    Some(message_data)
+}
+
+/// Show a message that the user sends, since chat does not send it back.
+fn echo_message(
+    app: &App,
+    config: &CompleteConfig,
+    identity: Option<(&SelfIdentity, &str)>,
+    message: String,
+    highlight: bool,
+    session_log: Option<&mut SessionLog>,
+) {
+    let Some(mut message_data) = echoed_message(config, identity, message, highlight) else {
+        return;
+    };
+
+    message_data.parse_emotes(&app.emotes);
+
+    if let Some(session_log) = session_log {
+        let login = identity.map_or_else(
+            || config.twitch.username.to_lowercase(),
+            |(_, login)| login.to_string(),
+        );
+
+        session_log.push(&message_data, &login, &config.alerts);
+    }
+
+    app.messages.borrow_mut().push_front(message_data);
+}
+
 pub async fn ui_driver(
     config: CompleteConfig,
     mut app: App,
@@ -37,6 +116,8 @@ pub async fn ui_driver(
     std::panic::set_hook(Box::new(move |panic| {
         debug!("Panic hook hit.");
 
+        // What was typed since the session was last saved would otherwise be lost.
+        save_pending_session();
         reset_terminal();
         original_hook(panic);
     }));
@@ -46,13 +127,65 @@ pub async fn ui_driver(
         tick_rate: Duration::from_millis(config.terminal.delay),
     });
 
-    let mut erx = query_emotes(&config, config.twitch.channel.clone());
+    // Emotes are downloaded in a size that suits the rows of the terminal.
+    let cell_height = app.emotes.cell_size.get().map(|&(_, height)| height);
+
+    let mut erx = query_emotes(&config, config.twitch.channel.clone(), cell_height);
+
+    let mut sound_alert = SoundAlert::new(&config.alerts);
+
+    let session_started = Local::now();
+    // Chat only keeps its latest messages, so the summary is counted as they come in.
+    let mut session_log = config.storage.session_summary.then(SessionLog::default);
+
+    // Messages of the user are told apart by their login, which the configured username is until the token tells it.
+    let mut own_login = config.twitch.username.to_lowercase();
+    let mut token_rx = query_token_info(config.twitch.token.clone());
+    let mut identity: Option<SelfIdentity> = None;
+
+    let mut connection_alert = ConnectionAlert::new(config.alerts.disconnect);
+
+    let mut responder = AutoResponder::new(&config.responses);
+    let mut edits = EditDetector::new(&config.frontend);
+    let mut next_age_out = Instant::now();
+
+    // Thumbnails are shown the same way as emotes, so they need the same graphics support.
+    let mut thumbnails = app.emotes.cell_size.get().map(|_| {
+        Thumbnails::new(
+            &config.frontend.thumbnails,
+            config.frontend.emote_download_concurrency,
+        )
+    });
+
+    let mut shoutouts = Shoutouts::default();
+
+    let mut roles = ChannelRoles::default();
+    let mut roles_rx = None;
+    let mut next_roles_query = Some(Instant::now()).filter(|_| config.twitch.cache_roles);
+    let mut raid_digest = RaidDigest::new(Duration::from_secs(config.frontend.raid_digest_window));
+
+    let mut redraw = Redraw::new(config.terminal.redraw_on_change);
+
+    let mut away = AwayTracker::new(Duration::from_secs(config.frontend.catch_up_after));
+
+    // Messages are applied together, so that bursts of them do not each cause a redraw.
+    let mut batch = Batch::new(Duration::from_millis(config.terminal.batch_window));
 
     let mut terminal = init_terminal(&config.frontend);
 
     terminal.clear().unwrap();
 
+    // No channel could be picked at startup, so one of the followed channels has to be.
+    if config.terminal.first_state == State::Dashboard && config.twitch.channel.is_empty() {
+        app.components.dashboard.open_following().await;
+    }
+
     loop {
+        if let Ok(info) = token_rx.try_recv() {
+            own_login = info.login;
+            app.components.chat.scopes = Some(info.scopes);
+        }
+
         // Check if we have received any emotes
         if let Ok(e) = erx.try_recv() {
             *app.emotes.emotes.borrow_mut() = e;
@@ -60,16 +193,20 @@ pub async fn ui_driver(
             for message in &mut *app.messages.borrow_mut() {
                 message.parse_emotes(&app.emotes);
             }
+
+            redraw.mark();
         };
 
         // Check if we need to load a decoded emote
         if let Some(rx) = &mut drx {
             if let Ok(r) = rx.try_recv() {
+                redraw.mark();
+
                 match r {
                     Ok(d) => {
                         if let Err(e) = d.apply() {
                             warn!("Unable to send command to load emote. {e}");
-                        } else if let Err(e) = display_emote(d.id(), 1, d.cols()) {
+                        } else if let Err(e) = display_image(d.id(), 1, d.cols(), d.rows()) {
                             warn!("Unable to send command to display emote. {e}");
                         }
                     }
@@ -82,36 +219,239 @@ pub async fn ui_driver(
             }
         }
 
-        if let Ok(msg) = rx.try_recv() {
-            match msg {
-                TwitchToTerminalAction::Message(mut m) => {
-                    m.parse_emotes(&app.emotes);
-                    app.messages.borrow_mut().push_front(m);
+        if let (Some(thumbnails), Some(&cell_size)) = (&mut thumbnails, app.emotes.cell_size.get())
+        {
+            while let Some((url, thumbnail)) = thumbnails.next_loaded(cell_size) {
+                for message in app.messages.borrow_mut().iter_mut().filter(|m| {
+                    m.thumbnail.is_none() && m.payload.split_whitespace().any(|w| w == url)
+                }) {
+                    message.thumbnail = Some(thumbnail);
+                    message.invalidate_lines();
+                }
 
-                    // If scrolling is enabled, pad for more messages.
-                    if app.components.chat.scroll_offset.get_offset() > 0 {
-                        app.components.chat.scroll_offset.up();
+                redraw.mark();
+            }
+        }
+
+        while let Ok(msg) = rx.try_recv() {
+            batch.push(msg, Instant::now());
+        }
+
+        if let Some(actions) = batch.take_ready(Instant::now()) {
+            redraw.mark();
+
+            for msg in actions {
+                // New chatters are shown as any other message, unless a raid digest counts them.
+                if matches!(msg, TwitchToTerminalAction::NewChatter(_)) {
+                    if let Some(digest) = raid_digest.new_chatter(Instant::now()) {
+                        app.components.chat.show_raid_digest(digest);
+
+                        continue;
                     }
                 }
-                TwitchToTerminalAction::ClearChat(user_id) => {
-                    if let Some(user) = user_id {
-                        app.purge_user_messages(user.as_str());
-                    } else {
-                        app.clear_messages();
+
+                match msg {
+                    TwitchToTerminalAction::Message(mut m)
+                    | TwitchToTerminalAction::NewChatter(mut m) => {
+                        if is_alert(&m, &own_login, &config.alerts) {
+                            sound_alert.play(&app.config.borrow().twitch.channel);
+                        }
+
+                        // Nothing can be sent while anonymous, so there is no replying either.
+                        let reply = if config.twitch.anonymous {
+                            None
+                        } else {
+                            responder.respond(&m, &own_login, Instant::now())
+                        };
+
+                        if let Some(reply) = reply {
+                            echo_message(
+                                &app,
+                                &config,
+                                identity.as_ref().map(|i| (i, own_login.as_str())),
+                                reply.clone(),
+                                false,
+                                session_log.as_mut(),
+                            );
+
+                            tx.send(TwitchAction::Privmsg(reply)).unwrap();
+                        }
+
+                        if let Some(prior) = edits.take_edit(&m, Instant::now()) {
+                            app.forget_message_with(&prior);
+                            m.edited = true;
+                        }
+
+                        m.new_account = m.user_id.as_ref().is_some_and(|id| app.is_new_account(id));
+                        roles.apply(&mut m);
+                        app.apply_pending_highlight(&mut m);
+
+                        if let Some(thumbnails) = thumbnails.as_mut().filter(|_| {
+                            config
+                                .frontend
+                                .thumbnails
+                                .enabled(&app.config.borrow().twitch.channel)
+                        }) {
+                            m.thumbnail = thumbnails.request(&m.payload);
+                        }
+
+                        if config.frontend.collapse_emote_runs {
+                            m.collapse_emote_runs(|word| {
+                                app.emotes.emotes.borrow().contains_key(word)
+                            });
+                        }
+
+                        m.cap_emotes(config.frontend.max_emotes, |word| {
+                            app.emotes.emotes.borrow().contains_key(word)
+                        });
+
+                        m.parse_emotes(&app.emotes);
+                        m.detect_language();
+
+                        if let Some(session_log) = &mut session_log {
+                            session_log.push(&m, &own_login, &config.alerts);
+                        }
+
+                        app.components.chat.push_message(m);
+                    }
+                    TwitchToTerminalAction::History(history) => {
+                        let mut messages = app.messages.borrow_mut();
+
+                        // Messages are stored from newest to oldest, so the history goes behind them.
+                        for mut m in history.into_iter().rev() {
+                            // Live messages may have arrived while the history was being fetched.
+                            if m.message_id.as_ref().is_some_and(|id| {
+                                messages.iter().any(|l| l.message_id.as_ref() == Some(id))
+                            }) {
+                                continue;
+                            }
+
+                            m.collapsed = m.ascii_art && !app.components.chat.ascii_art_expanded();
+                            roles.apply(&mut m);
+
+                            if config.frontend.collapse_emote_runs {
+                                m.collapse_emote_runs(|word| {
+                                    app.emotes.emotes.borrow().contains_key(word)
+                                });
+                            }
+
+                            m.cap_emotes(config.frontend.max_emotes, |word| {
+                                app.emotes.emotes.borrow().contains_key(word)
+                            });
+
+                            m.parse_emotes(&app.emotes);
+                            m.detect_language();
+                            messages.push_back(m);
+                        }
+                    }
+                    TwitchToTerminalAction::ClearChat(user_id) => {
+                        if let Some(user) = user_id {
+                            app.purge_user_messages(user.as_str());
+                        } else {
+                            app.clear_messages();
+                        }
+                    }
+                    TwitchToTerminalAction::DeleteMessage(message_id) => {
+                        if let Some(message) = app
+                            .messages
+                            .borrow()
+                            .iter()
+                            .find(|m| m.message_id.as_ref() == Some(&message_id))
+                        {
+                            edits.deleted(message, Instant::now());
+                        }
+
+                        app.remove_message_with(message_id.as_str());
+                    }
+                    TwitchToTerminalAction::NewAccount(user_id) => {
+                        app.mark_new_account(user_id);
+                    }
+                    TwitchToTerminalAction::Raid(raid) => {
+                        app.components.chat.show_banner(raid.banner());
+
+                        if raid_digest.enabled() {
+                            let digest = raid_digest.raided(&raid, Instant::now());
+
+                            app.components.chat.show_raid_digest(digest);
+                        }
+
+                        if config.moderation.auto_shoutout {
+                            shoutouts.raided(
+                                &raid,
+                                Duration::from_secs(config.moderation.shoutout_delay),
+                                Instant::now(),
+                            );
+                        }
+                    }
+                    TwitchToTerminalAction::SelfIdentity(self_identity) => {
+                        identity = Some(self_identity);
+                    }
+                    TwitchToTerminalAction::UserState(state) => {
+                        app.components.chat.user_states.update(state);
+                    }
+                    TwitchToTerminalAction::Connection(state) => {
+                        connection_alert.update(state, Instant::now());
+                        app.components.chat.set_connection_state(state);
+                    }
+                    TwitchToTerminalAction::EventSub(EventSubEvent::Redemption(redemption)) => {
+                        app.highlight_redeemed_message(&redemption);
+
+                        app.components.chat.push_message(MessageData::new(
+                            "Twitch".to_string(),
+                            None,
+                            true,
+                            redemption.summary(),
+                            None,
+                            false,
+                        ));
+                    }
+                    TwitchToTerminalAction::EmoteOnly(emote_only) => {
+                        app.components.chat.set_emote_only(emote_only);
+                    }
+                    TwitchToTerminalAction::RoomState(room_state) => {
+                        app.components.chat.room_modes.apply(&room_state);
+                    }
+                    TwitchToTerminalAction::Moderation(entry) => {
+                        app.components.chat.mod_log.push(entry);
+                    }
+                    TwitchToTerminalAction::EventSub(event) => {
+                        app.components.chat.apply_eventsub(event);
                     }
-                }
-                TwitchToTerminalAction::DeleteMessage(message_id) => {
-                    app.remove_message_with(message_id.as_str());
                 }
             }
         }
 
         if let Some(event) = events.next().await {
+            redraw.mark_event(&event);
+
+            if matches!(event, Event::Input(_)) {
+                if let Some(since) = away.input(Instant::now(), Local::now()) {
+                    app.components
+                        .chat
+                        .show_catch_up(since, &own_login, &config.alerts);
+                }
+            }
+
             if let Some(action) = app.event(&event).await {
                 match action {
                     TerminalAction::Quit => {
+                        if let Some(session_log) = &session_log {
+                            let ended = Local::now();
+
+                            session_log
+                                .summary(
+                                    &app.config.borrow().twitch.channel,
+                                    session_started,
+                                    ended,
+                                )
+                                .dump_data(ended);
+                        }
+
                         // Emotes need to be unloaded before we exit the alternate screen
                         app.emotes.unload();
+                        if let Some(thumbnails) = &mut thumbnails {
+                            thumbnails.unload();
+                        }
                         quit_terminal(terminal);
 
                         break;
@@ -135,49 +475,188 @@ pub async fn ui_driver(
 
                         tx.send(TwitchAction::ClearMessages).unwrap();
                     }
+                    TerminalAction::ShowStats => {}
                     TerminalAction::Enter(action) => match action {
                         TwitchAction::Privmsg(message) => {
                             const ME_COMMAND: &str = "/me ";
 
-                            let (msg, highlight) = message.strip_prefix(ME_COMMAND).map_or_else(
-                                || (message.clone(), false),
-                                |msg| (msg.to_string(), true),
-                            );
+                            let (msg, highlight) =
+                                if let Some(Ok(whisper)) = Whisper::parse(&message) {
+                                    (whisper.echo(), false)
+                                } else {
+                                    message.strip_prefix(ME_COMMAND).map_or_else(
+                                        || (message.clone(), false),
+                                        |msg| (msg.to_string(), true),
+                                    )
+                                };
 
-                            let mut message_data = MessageData::new(
-                                config.twitch.username.to_string(),
-                                None,
-                                false,
-                                msg.to_string(),
-                                None,
+                            echo_message(
+                                &app,
+                                &config,
+                                identity.as_ref().map(|i| (i, own_login.as_str())),
+                                msg,
                                 highlight,
+                                session_log.as_mut(),
                             );
 
-                            message_data.parse_emotes(&app.emotes);
-
-                            app.messages.borrow_mut().push_front(message_data);
-
                             tx.send(TwitchAction::Privmsg(message)).unwrap();
                         }
                         TwitchAction::Join(channel) => {
+                            app.channel_history.borrow_mut().switch_to(&channel);
                             app.clear_messages();
+                            app.components.chat.mod_log.clear();
+                            app.components.chat.speakers.clear();
+                            edits.clear();
+                            app.components.chat.set_emote_only(false);
+                            app.components.chat.room_modes = RoomModes::default();
+                            shoutouts = Shoutouts::default();
+                            roles = ChannelRoles::default();
+                            roles_rx = None;
+                            next_roles_query =
+                                Some(Instant::now()).filter(|_| config.twitch.cache_roles);
+                            raid_digest = RaidDigest::new(Duration::from_secs(
+                                config.frontend.raid_digest_window,
+                            ));
+                            app.components.chat.pinned.unpin();
                             app.emotes.unload();
 
                             tx.send(TwitchAction::Join(channel.clone())).unwrap();
-                            erx = query_emotes(&config, channel);
+                            erx = query_emotes(&config, channel, cell_height);
 
                             app.set_state(State::Normal);
                         }
                         TwitchAction::ClearMessages => {}
+                        TwitchAction::ChatSettings(setting) => {
+                            tx.send(TwitchAction::ChatSettings(setting)).unwrap();
+                        }
+                        TwitchAction::Raw(raw) => {
+                            tx.send(TwitchAction::Raw(raw)).unwrap();
+                        }
+                        TwitchAction::Shoutout(shoutout) => {
+                            match shoutouts.shout_out(&shoutout, Instant::now()) {
+                                Ok(()) => {
+                                    tx.send(TwitchAction::Shoutout(shoutout)).unwrap();
+                                }
+                                Err(wait) => app.components.chat.show_banner(format!(
+                                    "Unable to shout out {} for another {}, Twitch limits how often shoutouts can be sent",
+                                    shoutout.to,
+                                    format_cooldown(wait)
+                                )),
+                            }
+                        }
+                        TwitchAction::Reconnect => {
+                            let chat = &mut app.components.chat;
+
+                            if let Some(state) = chat.connection_state().manual_reconnect() {
+                                chat.set_connection_state(state);
+
+                                tx.send(TwitchAction::Reconnect).unwrap();
+                            } else {
+                                chat.show_banner("Already reconnecting".to_string());
+                            }
+                        }
                     },
                 }
             }
         }
 
-        terminal.draw(|f| app.draw(f)).unwrap();
+        app.update_scroll_lock();
+
+        let now = Instant::now();
+
+        app.save_session(now);
+
+        if config.frontend.message_max_age > 0 && now >= next_age_out {
+            let cutoff = Local::now()
+                - chrono::Duration::seconds(
+                    i64::try_from(config.frontend.message_max_age.min(u64::from(u32::MAX)))
+                        .unwrap_or_default(),
+                );
+
+            for message in app.age_out_messages(cutoff) {
+                redraw.mark();
+
+                if config.frontend.log_aged_out {
+                    info!(
+                        "Aged out message from {}: {}",
+                        message.author,
+                        message_to_plain(&message, config.terminal.log_emotes)
+                    );
+                }
+            }
+
+            next_age_out = now + AGE_OUT_INTERVAL;
+        }
+
+        if next_roles_query.is_some_and(|next| now >= next) {
+            let mut twitch_config = config.twitch.clone();
+            twitch_config
+                .channel
+                .clone_from(&app.config.borrow().twitch.channel);
+
+            roles_rx = Some(query_channel_roles(twitch_config));
+            next_roles_query = (config.twitch.roles_refresh > 0)
+                .then(|| now + Duration::from_secs(config.twitch.roles_refresh * 60));
+        }
+
+        if let Some(Ok(listed)) = roles_rx.as_mut().map(oneshot::Receiver::try_recv) {
+            roles = listed;
+            roles_rx = None;
+
+            for message in app.messages.borrow_mut().iter_mut() {
+                roles.apply(message);
+            }
+
+            redraw.mark();
+        }
+
+        if let Some(shoutout) = shoutouts.take_due(now) {
+            tx.send(TwitchAction::Shoutout(shoutout)).unwrap();
+        }
+
+        if connection_alert.flash_ended(now) {
+            redraw.mark();
+        }
+
+        if redraw.should_draw(now) {
+            let flashing = connection_alert.flashing(now);
+
+            terminal
+                .draw(|f| {
+                    app.draw(f);
+
+                    if flashing {
+                        let area = f.size();
+                        f.buffer_mut()
+                            .set_style(area, Style::default().add_modifier(Modifier::REVERSED));
+                    }
+                })
+                .unwrap();
+
+            redraw.drawn(now);
+        }
     }
 
     app.cleanup();
 
     reset_terminal();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nothing_echoed_while_anonymous() {
+        let mut config = CompleteConfig::default();
+        config.twitch.username = "Xithrius".to_string();
+
+        let echoed = echoed_message(&config, None, "hello".to_string(), false).unwrap();
+        assert_eq!(echoed.author, "Xithrius");
+        assert_eq!(echoed.payload, "hello");
+
+        config.twitch.anonymous = true;
+        assert!(echoed_message(&config, None, "hello".to_string(), false).is_none());
+        assert!(echoed_message(&config, None, "/me waves".to_string(), true).is_none());
+    }
+}
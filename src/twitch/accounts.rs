@@ -0,0 +1,209 @@
+use std::{collections::HashSet, time::Duration};
+
+use chrono::{DateTime, Utc};
+use color_eyre::Result;
+use log::{debug, warn};
+use reqwest::{Client, StatusCode};
+use serde::Deserialize;
+use tokio::{
+    sync::mpsc::{self, Sender, UnboundedReceiver, UnboundedSender},
+    time::sleep,
+};
+
+use crate::{handlers::data::TwitchToTerminalAction, twitch::oauth::get_twitch_client};
+
+/// The most users that can be looked up with a single request.
+const USERS_PER_REQUEST: usize = 100;
+
+/// How long to wait for more users to look up before sending a request.
+const BATCH_DELAY: Duration = Duration::from_secs(2);
+
+#[derive(Deserialize, Debug, Clone)]
+struct User {
+    id: String,
+    created_at: String,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+struct UserList {
+    data: Vec<User>,
+}
+
+/// If an account was created less than the threshold amount of days ago.
+pub fn is_suspiciously_new(
+    created_at: DateTime<Utc>,
+    now: DateTime<Utc>,
+    threshold_days: u64,
+) -> bool {
+    // Durations of more than a few thousand years cannot be represented.
+    let threshold =
+        chrono::Duration::days(i64::try_from(threshold_days.min(36_500)).unwrap_or_default());
+
+    now.signed_duration_since(created_at) < threshold
+}
+
+/// Looks up the creation date of users the first time they send a message.
+pub struct AccountAges {
+    seen: HashSet<String>,
+    tx: Option<UnboundedSender<String>>,
+}
+
+impl AccountAges {
+    /// Spawn the task that looks up users, if accounts should be checked at all.
+    pub fn new(
+        token: Option<String>,
+        threshold_days: u64,
+        terminal_tx: Sender<TwitchToTerminalAction>,
+    ) -> Self {
+        let tx = (threshold_days > 0).then(|| {
+            let (tx, rx) = mpsc::unbounded_channel();
+
+            tokio::spawn(check_accounts(token, threshold_days, rx, terminal_tx));
+
+            tx
+        });
+
+        Self {
+            seen: HashSet::new(),
+            tx,
+        }
+    }
+
+    /// Queue a user to be looked up, unless they have been already.
+    pub fn check(&mut self, user_id: &str) {
+        let Some(tx) = &self.tx else {
+            return;
+        };
+
+        if self.seen.insert(user_id.to_string()) && tx.send(user_id.to_string()).is_err() {
+            self.tx = None;
+        }
+    }
+}
+
+async fn get_users(client: &Client, user_ids: &[String]) -> Result<Result<UserList, Duration>> {
+    let query = user_ids
+        .iter()
+        .map(|id| format!("id={id}"))
+        .collect::<Vec<String>>()
+        .join("&");
+
+    let response = client
+        .get(format!("https://api.twitch.tv/helix/users?{query}"))
+        .send()
+        .await?;
+
+    // https://dev.twitch.tv/docs/api/guide/#twitch-rate-limits
+    if response.status() == StatusCode::TOO_MANY_REQUESTS {
+        let reset = response
+            .headers()
+            .get("Ratelimit-Reset")
+            .and_then(|r| r.to_str().ok())
+            .and_then(|r| r.parse::<i64>().ok())
+            .and_then(|r| DateTime::from_timestamp(r, 0))
+            .and_then(|r| r.signed_duration_since(Utc::now()).to_std().ok())
+            .unwrap_or(Duration::from_secs(60));
+
+        return Ok(Err(reset));
+    }
+
+    Ok(Ok(response.error_for_status()?.json::<UserList>().await?))
+}
+
+async fn check_accounts(
+    token: Option<String>,
+    threshold_days: u64,
+    mut rx: UnboundedReceiver<String>,
+    terminal_tx: Sender<TwitchToTerminalAction>,
+) {
+    let client = match get_twitch_client(token).await {
+        Ok(client) => client,
+        Err(err) => {
+            warn!("Unable to create client to look up account ages: {err}");
+            return;
+        }
+    };
+
+    let mut queue = vec![];
+
+    while let Some(user_id) = rx.recv().await {
+        queue.push(user_id);
+
+        // Wait for more users, so that they can be looked up together.
+        sleep(BATCH_DELAY).await;
+
+        while let Ok(user_id) = rx.try_recv() {
+            queue.push(user_id);
+        }
+
+        while !queue.is_empty() {
+            let batch = queue
+                .drain(..queue.len().min(USERS_PER_REQUEST))
+                .collect::<Vec<String>>();
+
+            let users = match get_users(&client, &batch).await {
+                Ok(Ok(users)) => users,
+                Ok(Err(reset)) => {
+                    debug!("Rate limited when looking up account ages, waiting {reset:?}");
+
+                    queue.extend(batch);
+                    sleep(reset).await;
+
+                    continue;
+                }
+                Err(err) => {
+                    warn!("Unable to look up account ages: {err}");
+                    continue;
+                }
+            };
+
+            let now = Utc::now();
+
+            for user in users.data {
+                let Ok(created_at) = DateTime::parse_from_rfc3339(&user.created_at) else {
+                    continue;
+                };
+
+                if is_suspiciously_new(created_at.with_timezone(&Utc), now, threshold_days)
+                    && terminal_tx
+                        .send(TwitchToTerminalAction::NewAccount(user.id))
+                        .await
+                        .is_err()
+                {
+                    return;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(y, m, d, 12, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn test_new_account_within_threshold() {
+        assert!(is_suspiciously_new(date(2024, 3, 10), date(2024, 3, 12), 7));
+    }
+
+    #[test]
+    fn test_old_account_outside_threshold() {
+        assert!(!is_suspiciously_new(date(2020, 1, 1), date(2024, 3, 12), 7));
+    }
+
+    #[test]
+    fn test_account_exactly_at_threshold() {
+        assert!(!is_suspiciously_new(date(2024, 3, 5), date(2024, 3, 12), 7));
+    }
+
+    #[test]
+    fn test_account_created_in_the_future() {
+        assert!(is_suspiciously_new(date(2024, 3, 13), date(2024, 3, 12), 7));
+    }
+}
@@ -0,0 +1,119 @@
+use std::collections::HashMap;
+
+use irc::proto::{Command, Message};
+use regex::Regex;
+use tui::style::Color;
+
+use crate::{
+    handlers::{config::FrontendConfig, data::MessageData},
+    twitch::privmsg_data,
+};
+
+/// The highlight color a moderator picked for an announcement, with `/announce<color>`.
+///
+/// <https://dev.twitch.tv/docs/irc/tags/#usernotice-tags>
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AnnouncementColor {
+    /// The color of the channel, which is also used for colors that are not known.
+    #[default]
+    Primary,
+    Blue,
+    Green,
+    Orange,
+    Purple,
+}
+
+impl AnnouncementColor {
+    pub fn parse(value: &str) -> Self {
+        match value.to_uppercase().as_str() {
+            "BLUE" => Self::Blue,
+            "GREEN" => Self::Green,
+            "ORANGE" => Self::Orange,
+            "PURPLE" => Self::Purple,
+            _ => Self::Primary,
+        }
+    }
+
+    pub const fn color(self) -> Color {
+        match self {
+            Self::Primary => Color::Rgb(145, 71, 255),
+            Self::Blue => Color::Rgb(0, 219, 219),
+            Self::Green => Color::Rgb(0, 219, 132),
+            Self::Orange => Color::Rgb(255, 179, 38),
+            Self::Purple => Color::Rgb(155, 138, 255),
+        }
+    }
+}
+
+/// Turn an announcement `USERNOTICE` into a chat message, if it is one.
+pub fn announcement_data(
+    message: &Message,
+    tags: &HashMap<&str, &str>,
+    frontend_config: &FrontendConfig,
+    censor_terms: &[Regex],
+) -> Option<MessageData> {
+    if tags.get("msg-id") != Some(&"announcement") {
+        return None;
+    }
+
+    let Command::Raw(_, ref items) = message.command else {
+        return None;
+    };
+
+    let text = items.get(1)?;
+
+    let color = tags
+        .get("msg-param-color")
+        .map_or_else(AnnouncementColor::default, |c| AnnouncementColor::parse(c));
+
+    let mut message_data = privmsg_data(message, text, tags, frontend_config, censor_terms);
+    message_data.announcement = Some(color.color());
+
+    Some(message_data)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+    use crate::twitch::message_tags;
+
+    fn parse(raw: &str) -> Option<MessageData> {
+        let message = Message::from_str(raw).unwrap();
+        let tags = message_tags(&message);
+
+        announcement_data(&message, &tags, &FrontendConfig::default(), &[])
+    }
+
+    #[test]
+    fn test_parse_announcement() {
+        let data = parse(
+            "@badges=moderator/1;display-name=Mod;emotes=;id=abc;login=mod;msg-id=announcement;msg-param-color=BLUE;user-id=7 :tmi.twitch.tv USERNOTICE #xithrius :Stream starts soon!",
+        )
+        .unwrap();
+
+        assert!(data.author.ends_with("Mod"));
+        assert_eq!(data.payload, "Stream starts soon!");
+        assert_eq!(data.user_id.as_deref(), Some("7"));
+        assert_eq!(data.announcement, Some(AnnouncementColor::Blue.color()));
+    }
+
+    #[test]
+    fn test_unknown_announcement_color() {
+        let data = parse(
+            "@display-name=Mod;login=mod;msg-id=announcement;msg-param-color=RAINBOW :tmi.twitch.tv USERNOTICE #xithrius :hi",
+        )
+        .unwrap();
+
+        assert_eq!(data.announcement, Some(AnnouncementColor::Primary.color()));
+    }
+
+    #[test]
+    fn test_not_an_announcement() {
+        assert!(parse(
+            "@display-name=Sub;login=sub;msg-id=resub :tmi.twitch.tv USERNOTICE #xithrius :hi"
+        )
+        .is_none());
+    }
+}
@@ -1,9 +1,123 @@
+use std::collections::HashMap;
+
 use irc::proto::Message;
 
 const VIP_BADGE: char = '\u{1F48E}';
 const MODERATOR_BADGE: char = '\u{1F528}';
 const SUBSCRIBER_BADGE: char = '\u{2B50}';
 const PRIME_GAMING_BADGE: char = '\u{1F451}';
+const BROADCASTER_BADGE: char = '\u{1F3A5}';
+
+/// The most important role that a user has in a channel, as told by their badges.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Role {
+    Subscriber,
+    Vip,
+    Moderator,
+    Broadcaster,
+}
+
+impl Role {
+    /// The highest role in a `badges` tag, such as `broadcaster/1,subscriber/12`.
+    pub fn from_badges(badges: &str) -> Option<Self> {
+        badges
+            .split(',')
+            .filter_map(|badge| match badge.split('/').next()? {
+                "broadcaster" => Some(Self::Broadcaster),
+                "moderator" => Some(Self::Moderator),
+                "vip" => Some(Self::Vip),
+                "subscriber" | "founder" => Some(Self::Subscriber),
+                _ => None,
+            })
+            .max()
+    }
+
+    pub const fn glyph(self) -> char {
+        match self {
+            Self::Broadcaster => BROADCASTER_BADGE,
+            Self::Moderator => MODERATOR_BADGE,
+            Self::Vip => VIP_BADGE,
+            Self::Subscriber => SUBSCRIBER_BADGE,
+        }
+    }
+}
+
+/// The tier and months of a subscriber, as far as the tags of their message tell.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Subscription {
+    pub tier: Option<u8>,
+    /// Cumulative months subscribed.
+    pub months: Option<u64>,
+}
+
+impl Subscription {
+    /// Read the subscription of the author of a message, where there is none if they are not subscribed.
+    ///
+    /// Resubscriptions tell the plan and months outright. Otherwise the version of the subscriber
+    /// badge tells the tier, 2000 and up being tier 2 and 3000 and up tier 3,
+    /// and its `badge-info` tells the months.
+    ///
+    /// <https://dev.twitch.tv/docs/irc/tags/#usernotice-tags>
+    pub fn from_tags(tags: &HashMap<&str, &str>) -> Option<Self> {
+        let badge = |tag: &str| {
+            tags.get(tag)?.split(',').find_map(|badge| {
+                let (name, version) = badge.split_once('/')?;
+
+                matches!(name, "subscriber" | "founder").then_some(version)
+            })
+        };
+
+        let plan_tier = tags.get("msg-param-sub-plan").map(|&plan| match plan {
+            "2000" => 2,
+            "3000" => 3,
+            // Prime subscriptions are tier 1.
+            _ => 1,
+        });
+
+        let badge_tier = badge("badges").map(|version| {
+            match version.parse::<u64>().unwrap_or_default() / 1000 {
+                tier @ (2 | 3) => u8::try_from(tier).unwrap_or(1),
+                _ => 1,
+            }
+        });
+
+        let months = tags
+            .get("msg-param-cumulative-months")
+            .copied()
+            .or_else(|| badge("badge-info"))
+            .and_then(|months| months.parse::<u64>().ok())
+            .filter(|&months| months > 0);
+
+        let subscription = Self {
+            tier: plan_tier.or(badge_tier),
+            months,
+        };
+
+        (subscription.tier.is_some() || subscription.months.is_some()).then_some(subscription)
+    }
+
+    /// How the subscription is shown after the name of the subscriber, such as `T2 14mo`.
+    pub fn label(&self) -> String {
+        [
+            self.tier.map(|tier| format!("T{tier}")),
+            self.months.map(|months| format!("{months}mo")),
+        ]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>()
+        .join(" ")
+    }
+}
+
+/// A name without the badges that were put in front of it.
+pub fn strip_badges(name: &str) -> &str {
+    name.trim_start_matches([
+        VIP_BADGE,
+        MODERATOR_BADGE,
+        SUBSCRIBER_BADGE,
+        PRIME_GAMING_BADGE,
+    ])
+}
 
 pub fn retrieve_user_badges(name: &mut String, message: &Message) {
     let mut badges = String::new();
@@ -13,15 +127,8 @@ pub fn retrieve_user_badges(name: &mut String, message: &Message) {
         let mut moderator_badge = None;
         let mut subscriber_badge = None;
         let mut prime_badge = None;
-        let mut display_name = None;
 
         for tag in tags {
-            if tag.0 == *"display-name" {
-                if let Some(ref value) = tag.1 {
-                    display_name = Some(value.to_string());
-                }
-            }
-
             if tag.0 == *"badges" {
                 if let Some(ref value) = tag.1 {
                     if !value.is_empty() && value.contains("vip") {
@@ -40,10 +147,6 @@ pub fn retrieve_user_badges(name: &mut String, message: &Message) {
             }
         }
 
-        if let Some(display_name) = display_name {
-            *name = display_name;
-        }
-
         if let Some(badge) = vip_badge {
             badges.push(badge);
         }
@@ -65,3 +168,88 @@ pub fn retrieve_user_badges(name: &mut String, message: &Message) {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_role_from_badges() {
+        assert_eq!(
+            Role::from_badges("subscriber/12,broadcaster/1"),
+            Some(Role::Broadcaster)
+        );
+        assert_eq!(
+            Role::from_badges("vip/1,moderator/1"),
+            Some(Role::Moderator)
+        );
+        assert_eq!(Role::from_badges("founder/0"), Some(Role::Subscriber));
+        assert_eq!(Role::from_badges("premium/1"), None);
+        assert_eq!(Role::from_badges(""), None);
+    }
+
+    fn subscription(tags: &[(&'static str, &'static str)]) -> Option<Subscription> {
+        Subscription::from_tags(&tags.iter().copied().collect())
+    }
+
+    #[test]
+    fn test_subscription_from_badges() {
+        let sub = subscription(&[
+            ("badge-info", "subscriber/14"),
+            ("badges", "vip/1,subscriber/2012"),
+        ])
+        .unwrap();
+
+        assert_eq!(sub.tier, Some(2));
+        assert_eq!(sub.months, Some(14));
+        assert_eq!(sub.label(), "T2 14mo");
+
+        assert_eq!(
+            subscription(&[("badge-info", "founder/3"), ("badges", "founder/0")]),
+            Some(Subscription {
+                tier: Some(1),
+                months: Some(3),
+            })
+        );
+        assert_eq!(
+            subscription(&[("badges", "subscriber/3000")]).map(|sub| sub.label()),
+            Some("T3".to_string())
+        );
+    }
+
+    #[test]
+    fn test_subscription_from_resub() {
+        let sub = subscription(&[
+            ("badge-info", "subscriber/6"),
+            ("badges", "subscriber/6"),
+            ("msg-id", "resub"),
+            ("msg-param-cumulative-months", "7"),
+            ("msg-param-sub-plan", "3000"),
+        ])
+        .unwrap();
+
+        assert_eq!(sub.label(), "T3 7mo");
+
+        assert_eq!(
+            subscription(&[
+                ("msg-param-cumulative-months", "2"),
+                ("msg-param-sub-plan", "Prime")
+            ])
+            .map(|sub| sub.label()),
+            Some("T1 2mo".to_string())
+        );
+    }
+
+    #[test]
+    fn test_no_subscription() {
+        assert_eq!(subscription(&[]), None);
+        assert_eq!(subscription(&[("badge-info", ""), ("badges", "")]), None);
+        assert_eq!(
+            subscription(&[
+                ("badge-info", "predictions/blue-1"),
+                ("badges", "moderator/1")
+            ]),
+            None
+        );
+    }
+}
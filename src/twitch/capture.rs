@@ -0,0 +1,154 @@
+use std::fs::read_to_string;
+
+use irc::proto::Message;
+use log::info;
+use tokio::sync::{
+    broadcast::{error::RecvError, Receiver},
+    mpsc::Sender,
+};
+
+use crate::{
+    handlers::{
+        config::CompleteConfig,
+        data::{DataBuilder, TwitchToTerminalAction},
+    },
+    twitch::{accounts::AccountAges, connection::parse_line, handle_message_command, TwitchAction},
+    utils::text::censor_patterns,
+};
+
+/// Read a capture of raw IRC lines, one per line as Twitch sent them.
+///
+/// Malformed lines are skipped, the same as when they come from Twitch.
+pub fn parse_capture(capture: &str) -> Vec<Message> {
+    capture
+        .lines()
+        .map(|line| line.trim_end_matches('\r'))
+        .filter(|line| !line.is_empty())
+        .filter_map(parse_line)
+        .collect()
+}
+
+/// Handle the messages of a capture the same way as the ones that are received from Twitch.
+async fn replay(
+    messages: Vec<Message>,
+    tx: &Sender<TwitchToTerminalAction>,
+    config: &CompleteConfig,
+) {
+    let data_builder = DataBuilder::new(&config.frontend.datetime_format);
+    let censor_terms = censor_patterns(&config.censor.terms);
+
+    // Accounts are never looked up, since nothing goes over the network.
+    let mut account_ages = AccountAges::new(None, 0, tx.clone());
+    let mut room_state_startup = false;
+
+    for message in messages {
+        if let Some(b) = handle_message_command(
+            message,
+            tx.clone(),
+            data_builder,
+            config,
+            &censor_terms,
+            &mut account_ages,
+            room_state_startup,
+        )
+        .await
+        {
+            room_state_startup = b;
+        }
+    }
+}
+
+/// Show chat from a capture of IRC lines instead of connecting to Twitch,
+/// such as to reproduce a bug with the lines that caused it.
+pub async fn replay_capture(
+    path: String,
+    config: CompleteConfig,
+    tx: Sender<TwitchToTerminalAction>,
+    mut rx: Receiver<TwitchAction>,
+) {
+    info!("Replaying IRC capture {path}.");
+
+    let data_builder = DataBuilder::new(&config.frontend.datetime_format);
+
+    match read_to_string(&path) {
+        Ok(capture) => {
+            let messages = parse_capture(&capture);
+
+            tx.send(data_builder.system(format!(
+                "Replaying {} lines from {path}, nothing is sent to Twitch.",
+                messages.len()
+            )))
+            .await
+            .unwrap();
+
+            replay(messages, &tx, &config).await;
+        }
+        Err(err) => {
+            tx.send(data_builder.system(format!("Unable to read IRC capture {path}: {err}")))
+                .await
+                .unwrap();
+        }
+    }
+
+    // The terminal expects someone to be listening to what it sends.
+    while !matches!(rx.recv().await, Err(RecvError::Closed)) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+
+    use tokio::sync::mpsc;
+
+    use super::*;
+    use crate::handlers::data::MessageData;
+
+    const CAPTURE: &str = "\
+@badges=;display-name=Foo;id=a1;user-id=1 :foo!foo@foo.tmi.twitch.tv PRIVMSG #xithrius :hello
+@badges=;display-name=Bar;id=b1;user-id=2 :bar!bar@bar.tmi.twitch.tv PRIVMSG #xithrius :spam
+:tmi.twitch.tv ::
+@badges=moderator/1;display-name=Mod;emotes=;id=c1;login=mod;msg-id=announcement;msg-param-color=BLUE;user-id=7 :tmi.twitch.tv USERNOTICE #xithrius :Stream starts soon!\r
+@badges=;display-name=Bar;id=b2;user-id=2 :bar!bar@bar.tmi.twitch.tv PRIVMSG #xithrius :more spam
+@ban-duration=600;room-id=9;target-user-id=2 :tmi.twitch.tv CLEARCHAT #xithrius :bar
+@login=foo;target-msg-id=a1 :tmi.twitch.tv CLEARMSG #xithrius :hello
+";
+
+    #[test]
+    fn test_parse_capture_skips_malformed_lines() {
+        assert_eq!(parse_capture(CAPTURE).len(), 6);
+    }
+
+    #[tokio::test]
+    async fn test_replay_capture() {
+        let (tx, mut rx) = mpsc::channel(100);
+
+        replay(parse_capture(CAPTURE), &tx, &CompleteConfig::default()).await;
+
+        // Apply what the terminal was sent to chat, which keeps messages from newest to oldest.
+        let mut buffer = VecDeque::<MessageData>::new();
+
+        while let Ok(action) = rx.try_recv() {
+            match action {
+                TwitchToTerminalAction::Message(message) => buffer.push_front(message),
+                TwitchToTerminalAction::ClearChat(Some(user_id)) => {
+                    buffer.retain(|m| m.user_id.as_ref() != Some(&user_id));
+                }
+                TwitchToTerminalAction::DeleteMessage(message_id) => {
+                    buffer.retain(|m| m.message_id.as_ref() != Some(&message_id));
+                }
+                _ => {}
+            }
+        }
+
+        assert_eq!(
+            buffer
+                .iter()
+                .map(|m| m.payload.as_str())
+                .collect::<Vec<_>>(),
+            vec!["User was timed out for 600 seconds", "Stream starts soon!"]
+        );
+        assert!(buffer[0].system);
+        assert_eq!(buffer[1].login.as_deref(), Some("mod"));
+        assert!(buffer[1].announcement.is_some());
+    }
+}
@@ -0,0 +1,93 @@
+use color_eyre::{eyre::eyre, Result};
+
+const MSG_COMMAND: &str = "/msg";
+
+/// A message to one of the joined channels, sent with `/msg <channel> <message>`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChannelMessage {
+    /// Without the leading `#`, in lowercase.
+    pub channel: String,
+    pub message: String,
+}
+
+impl ChannelMessage {
+    /// Read a channel message from the chat input, where there is none if the input is not the
+    /// message command. The channel has to be one of the joined channels.
+    pub fn parse(input: &str, joined: &[&str]) -> Option<Result<Self>> {
+        let args = input
+            .strip_prefix(MSG_COMMAND)
+            .filter(|args| args.is_empty() || args.starts_with(' '))?;
+
+        let mut args = args.trim_start().splitn(2, ' ');
+
+        let channel = args
+            .next()
+            .map(|channel| channel.trim_start_matches('#').to_lowercase())
+            .filter(|channel| !channel.is_empty());
+        let message = args.next().map(str::trim).filter(|m| !m.is_empty());
+
+        Some(match (channel, message) {
+            (Some(channel), _)
+                if !joined
+                    .iter()
+                    .any(|joined| joined.eq_ignore_ascii_case(&channel)) =>
+            {
+                Err(eyre!("Not in #{channel}, join it to send messages there"))
+            }
+            (Some(channel), Some(message)) => Ok(Self {
+                channel,
+                message: message.to_string(),
+            }),
+            (None, _) => Err(eyre!(
+                "Send to which channel? Use {MSG_COMMAND} <channel> <message>"
+            )),
+            (Some(_), None) => Err(eyre!("Send what? Use {MSG_COMMAND} <channel> <message>")),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const JOINED: &[&str] = &["other", "Xithrius"];
+
+    #[test]
+    fn test_parse_channel_message() {
+        assert_eq!(
+            ChannelMessage::parse("/msg #other hello", JOINED)
+                .unwrap()
+                .unwrap(),
+            ChannelMessage {
+                channel: "other".to_string(),
+                message: "hello".to_string(),
+            }
+        );
+
+        let message = ChannelMessage::parse("/msg xithrius  hello chat ", JOINED)
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(message.channel, "xithrius");
+        assert_eq!(message.message, "hello chat");
+
+        assert!(ChannelMessage::parse("/me hello", JOINED).is_none());
+        assert!(ChannelMessage::parse("/msgs other hello", JOINED).is_none());
+    }
+
+    #[test]
+    fn test_channel_not_joined() {
+        let err = ChannelMessage::parse("/msg #elsewhere hello", JOINED)
+            .unwrap()
+            .unwrap_err();
+
+        assert!(err.to_string().contains("Not in #elsewhere"));
+        assert!(ChannelMessage::parse("/msg", JOINED).unwrap().is_err());
+        assert!(ChannelMessage::parse("/msg #other", JOINED)
+            .unwrap()
+            .is_err());
+        assert!(ChannelMessage::parse("/msg # hello", JOINED)
+            .unwrap()
+            .is_err());
+    }
+}
@@ -7,7 +7,10 @@ use color_eyre::Result;
 use reqwest::Client;
 use serde::Deserialize;
 
-use crate::{handlers::config::TwitchConfig, ui::components::utils::SearchItemGetter};
+use crate::{
+    handlers::config::{AutoJoin, TwitchConfig},
+    ui::components::utils::SearchItemGetter,
+};
 
 use super::oauth::{get_channel_id, get_twitch_client};
 
@@ -75,6 +78,75 @@ pub async fn get_following(twitch_config: &TwitchConfig) -> Result<FollowingList
     get_user_following(&client, user_id).await
 }
 
+/// A followed channel that is currently live.
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct FollowedStream {
+    pub user_login: String,
+    pub viewer_count: u64,
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+struct FollowedStreamList {
+    data: Vec<FollowedStream>,
+}
+
+// https://dev.twitch.tv/docs/api/reference/#get-followed-streams
+pub async fn get_followed_live(twitch_config: &TwitchConfig) -> Result<Vec<FollowedStream>> {
+    let client = get_twitch_client(twitch_config.token.clone()).await?;
+
+    let user_id = get_channel_id(&client, &twitch_config.username).await?;
+
+    Ok(client
+        .get(format!(
+            "https://api.twitch.tv/helix/streams/followed?user_id={user_id}&first={FOLLOWER_COUNT}",
+        ))
+        .send()
+        .await?
+        .error_for_status()?
+        .json::<FollowedStreamList>()
+        .await?
+        .data)
+}
+
+/// What to do at startup, when no channel was given on the command line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StartupChannel {
+    Join(String),
+    /// Let the user pick one of their followed channels.
+    OpenSwitcher,
+}
+
+/// Pick the channel to join at startup from the followed channels that are live,
+/// which are ordered by viewer count, and the recently joined channels, from least to most recent.
+///
+/// If none of them fit, the configured channel is joined, if there is one.
+pub fn pick_startup_channel(
+    live: &[FollowedStream],
+    recent_channels: &[String],
+    twitch_config: &TwitchConfig,
+) -> StartupChannel {
+    let is_live = |channel: &&String| live.iter().any(|s| &s.user_login == *channel);
+
+    let picked = match twitch_config.auto_join {
+        AutoJoin::None => None,
+        AutoJoin::Live => live.first().map(|s| s.user_login.clone()),
+        AutoJoin::Recent => recent_channels
+            .iter()
+            .rev()
+            .find(is_live)
+            .cloned()
+            .or_else(|| live.first().map(|s| s.user_login.clone())),
+    };
+
+    match picked {
+        Some(channel) => StartupChannel::Join(channel),
+        None if !twitch_config.channel.is_empty() => {
+            StartupChannel::Join(twitch_config.channel.clone())
+        }
+        None => StartupChannel::OpenSwitcher,
+    }
+}
+
 impl Following {
     pub fn new(twitch_config: TwitchConfig) -> Self {
         Self {
@@ -96,3 +168,88 @@ impl SearchItemGetter<String> for Following {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn live(channels: &[&str]) -> Vec<FollowedStream> {
+        channels
+            .iter()
+            .map(|c| FollowedStream {
+                user_login: (*c).to_string(),
+                viewer_count: 0,
+            })
+            .collect()
+    }
+
+    fn config(auto_join: AutoJoin, channel: &str) -> TwitchConfig {
+        TwitchConfig {
+            channel: channel.to_string(),
+            auto_join,
+            ..Default::default()
+        }
+    }
+
+    fn recent(channels: &[&str]) -> Vec<String> {
+        channels.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn test_pick_first_live() {
+        assert_eq!(
+            pick_startup_channel(
+                &live(&["xithrius", "antvenom"]),
+                &recent(&["antvenom"]),
+                &config(AutoJoin::Live, "rocketleague")
+            ),
+            StartupChannel::Join("xithrius".to_string())
+        );
+    }
+
+    #[test]
+    fn test_pick_most_recent_live() {
+        let live = live(&["xithrius", "antvenom", "rocketleague"]);
+
+        assert_eq!(
+            pick_startup_channel(
+                &live,
+                &recent(&["rocketleague", "antvenom", "offline"]),
+                &config(AutoJoin::Recent, "")
+            ),
+            StartupChannel::Join("antvenom".to_string())
+        );
+
+        // None of the recent channels are live.
+        assert_eq!(
+            pick_startup_channel(&live, &recent(&["offline"]), &config(AutoJoin::Recent, "")),
+            StartupChannel::Join("xithrius".to_string())
+        );
+    }
+
+    #[test]
+    fn test_pick_fallback_when_none_live() {
+        for auto_join in [AutoJoin::Live, AutoJoin::Recent] {
+            assert_eq!(
+                pick_startup_channel(&[], &recent(&["xithrius"]), &config(auto_join, "antvenom")),
+                StartupChannel::Join("antvenom".to_string())
+            );
+            assert_eq!(
+                pick_startup_channel(&[], &recent(&["xithrius"]), &config(auto_join, "")),
+                StartupChannel::OpenSwitcher
+            );
+        }
+    }
+
+    #[test]
+    fn test_pick_without_auto_join() {
+        assert_eq!(
+            pick_startup_channel(
+                &live(&["xithrius"]),
+                &[],
+                &config(AutoJoin::None, "antvenom")
+            ),
+            StartupChannel::Join("antvenom".to_string())
+        );
+    }
+}
@@ -0,0 +1,345 @@
+use std::{collections::HashMap, hash::BuildHasher};
+
+use color_eyre::{
+    eyre::{bail, eyre},
+    Result,
+};
+use serde_json::{json, Value};
+
+use crate::{
+    handlers::config::TwitchConfig,
+    twitch::{
+        mod_log::parse_duration,
+        oauth::{get_channel_id, get_token_user_id, get_twitch_client},
+    },
+};
+
+const CHAT_SETTINGS_URL: &str = "https://api.twitch.tv/helix/chat/settings";
+
+/// How long slow mode makes chatters wait between messages if no time is given, in seconds.
+const DEFAULT_SLOW_WAIT: u64 = 30;
+
+/// <https://dev.twitch.tv/docs/api/reference/#update-chat-settings>
+const SLOW_WAIT_RANGE: std::ops::RangeInclusive<u64> = 3..=120;
+
+/// The longest that accounts can be made to follow before chatting, in minutes, being 3 months.
+const MAX_FOLLOW_MINUTES: u64 = 129_600;
+
+/// A change to the chat settings of the channel, typed as one of the room mode commands.
+///
+/// Twitch no longer takes these over IRC, so they go through the Helix API.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChatSetting {
+    EmoteOnly(bool),
+    /// Followers-only mode, for accounts that have followed for at least as many minutes.
+    /// There is none when it is turned off.
+    Followers(Option<u64>),
+    /// Slow mode, with the seconds to wait between messages, where there is none when it is turned off.
+    Slow(Option<u64>),
+    Subscribers(bool),
+    /// The mode where messages have to be unique, also known as R9K.
+    UniqueChat(bool),
+}
+
+impl ChatSetting {
+    /// Read a chat setting from the chat input, where there is none if the input is not
+    /// one of the room mode commands.
+    pub fn parse(input: &str) -> Option<Result<Self>> {
+        let input = input.strip_prefix('/')?.trim_end();
+
+        let (command, args) = input
+            .split_once(' ')
+            .map_or((input, ""), |(command, args)| (command, args.trim()));
+
+        let no_args = |setting| {
+            if args.is_empty() {
+                Ok(setting)
+            } else {
+                Err(eyre!("/{command} does not take any arguments"))
+            }
+        };
+
+        Some(match command {
+            "emoteonly" => no_args(Self::EmoteOnly(true)),
+            "emoteonlyoff" => no_args(Self::EmoteOnly(false)),
+            "followers" => parse_follow_minutes(args).map(|minutes| Self::Followers(Some(minutes))),
+            "followersoff" => no_args(Self::Followers(None)),
+            "slow" => parse_slow_wait(args).map(|wait| Self::Slow(Some(wait))),
+            "slowoff" => no_args(Self::Slow(None)),
+            "subscribers" => no_args(Self::Subscribers(true)),
+            "subscribersoff" => no_args(Self::Subscribers(false)),
+            "uniquechat" | "r9kbeta" => no_args(Self::UniqueChat(true)),
+            "uniquechatoff" | "r9kbetaoff" => no_args(Self::UniqueChat(false)),
+            _ => return None,
+        })
+    }
+
+    /// The body of the request that changes the setting.
+    pub fn body(self) -> Value {
+        match self {
+            Self::EmoteOnly(enabled) => json!({ "emote_mode": enabled }),
+            Self::Followers(Some(minutes)) => {
+                json!({ "follower_mode": true, "follower_mode_duration": minutes })
+            }
+            Self::Followers(None) => json!({ "follower_mode": false }),
+            Self::Slow(Some(wait)) => json!({ "slow_mode": true, "slow_mode_wait_time": wait }),
+            Self::Slow(None) => json!({ "slow_mode": false }),
+            Self::Subscribers(enabled) => json!({ "subscriber_mode": enabled }),
+            Self::UniqueChat(enabled) => json!({ "unique_chat_mode": enabled }),
+        }
+    }
+}
+
+/// Minutes of a followers-only duration, such as `30`, `30m`, `2h`, `1w`, `1mo` or `1 hour 30 minutes`,
+/// where a bare number counts minutes and nothing means no minimum.
+fn parse_follow_minutes(args: &str) -> Result<u64> {
+    if args.is_empty() {
+        return Ok(0);
+    }
+
+    let compact = args
+        .find(|c: char| !c.is_ascii_digit())
+        .map_or(Some((args, "m")), |split| Some(args.split_at(split)))
+        .filter(|(amount, _)| !amount.is_empty())
+        .and_then(|(amount, unit)| {
+            let minutes = match unit {
+                "m" => 1,
+                "h" => 60,
+                "d" => 24 * 60,
+                "w" => 7 * 24 * 60,
+                "mo" => 30 * 24 * 60,
+                _ => return None,
+            };
+
+            amount.parse::<u64>().ok()?.checked_mul(minutes)
+        });
+
+    let Some(minutes) = compact.or_else(|| parse_duration(args).map(|seconds| seconds / 60)) else {
+        bail!("Followers-only for how long? Use /followers [duration], such as /followers 10m");
+    };
+
+    if minutes > MAX_FOLLOW_MINUTES {
+        bail!("Followers-only can require following for at most 3 months");
+    }
+
+    Ok(minutes)
+}
+
+/// Seconds that slow mode waits between messages, where nothing is the default wait.
+fn parse_slow_wait(args: &str) -> Result<u64> {
+    if args.is_empty() {
+        return Ok(DEFAULT_SLOW_WAIT);
+    }
+
+    match args.trim_end_matches('s').parse::<u64>() {
+        Ok(wait) if SLOW_WAIT_RANGE.contains(&wait) => Ok(wait),
+        _ => bail!(
+            "Slow mode waits from {} to {} seconds, use /slow [seconds]",
+            SLOW_WAIT_RANGE.start(),
+            SLOW_WAIT_RANGE.end()
+        ),
+    }
+}
+
+/// Change a chat setting of the current channel, as the user, who has to be a moderator of it.
+pub async fn update_chat_settings(
+    twitch_config: &TwitchConfig,
+    setting: ChatSetting,
+) -> Result<()> {
+    let client = get_twitch_client(twitch_config.token.clone()).await?;
+    let broadcaster_id = get_channel_id(&client, &twitch_config.channel).await?;
+    let moderator_id = get_token_user_id(twitch_config.token.as_ref()).await?;
+
+    client
+        .patch(format!(
+            "{CHAT_SETTINGS_URL}?broadcaster_id={broadcaster_id}&moderator_id={moderator_id}"
+        ))
+        .json(&setting.body())
+        .send()
+        .await?
+        .error_for_status()?;
+
+    Ok(())
+}
+
+/// The room modes that the channel is in, as the ROOMSTATE tags tell.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct RoomModes {
+    emote_only: bool,
+    /// Minutes that accounts have to follow for before chatting, if followers-only.
+    followers: Option<u64>,
+    /// Seconds between messages, if in slow mode.
+    slow: Option<u64>,
+    subscribers: bool,
+    unique_chat: bool,
+}
+
+impl RoomModes {
+    /// Take in the tags of a ROOMSTATE, which only has the settings that changed after the first one.
+    ///
+    /// <https://dev.twitch.tv/docs/irc/tags/#roomstate-tags>
+    pub fn apply<S: BuildHasher>(&mut self, tags: &HashMap<String, String, S>) {
+        for (name, value) in tags {
+            match name.as_str() {
+                "emote-only" => self.emote_only = value == "1",
+                "followers-only" => {
+                    self.followers = value.parse::<u64>().ok();
+                }
+                "slow" => self.slow = value.parse::<u64>().ok().filter(|&wait| wait > 0),
+                "subs-only" => self.subscribers = value == "1",
+                "r9k" => self.unique_chat = value == "1",
+                _ => {}
+            }
+        }
+    }
+
+    /// The modes that are on, written out shortly for the title of chat.
+    pub fn summary(&self) -> Option<String> {
+        let modes = [
+            self.emote_only.then(|| "emote-only".to_string()),
+            self.followers.map(|minutes| {
+                if minutes == 0 {
+                    "followers".to_string()
+                } else {
+                    format!("followers {minutes}m")
+                }
+            }),
+            self.slow.map(|wait| format!("slow {wait}s")),
+            self.subscribers.then(|| "subs".to_string()),
+            self.unique_chat.then(|| "unique".to_string()),
+        ]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>();
+
+        (!modes.is_empty()).then(|| modes.join(", "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(input: &str) -> Option<ChatSetting> {
+        ChatSetting::parse(input).map(Result::unwrap)
+    }
+
+    #[test]
+    fn test_parse_toggles() {
+        assert_eq!(parse("/uniquechat"), Some(ChatSetting::UniqueChat(true)));
+        assert_eq!(
+            parse("/uniquechatoff"),
+            Some(ChatSetting::UniqueChat(false))
+        );
+        assert_eq!(parse("/r9kbeta"), Some(ChatSetting::UniqueChat(true)));
+        assert_eq!(parse("/emoteonly"), Some(ChatSetting::EmoteOnly(true)));
+        assert_eq!(parse("/emoteonlyoff "), Some(ChatSetting::EmoteOnly(false)));
+        assert_eq!(parse("/subscribers"), Some(ChatSetting::Subscribers(true)));
+        assert_eq!(
+            parse("/subscribersoff"),
+            Some(ChatSetting::Subscribers(false))
+        );
+        assert_eq!(parse("/followersoff"), Some(ChatSetting::Followers(None)));
+        assert_eq!(parse("/slowoff"), Some(ChatSetting::Slow(None)));
+
+        assert_eq!(parse("/me waves"), None);
+        assert_eq!(parse("/slowly"), None);
+        assert_eq!(parse("uniquechat"), None);
+        assert!(ChatSetting::parse("/emoteonly please").unwrap().is_err());
+    }
+
+    #[test]
+    fn test_parse_followers_duration() {
+        assert_eq!(parse("/followers"), Some(ChatSetting::Followers(Some(0))));
+        assert_eq!(
+            parse("/followers 10"),
+            Some(ChatSetting::Followers(Some(10)))
+        );
+        assert_eq!(
+            parse("/followers 30m"),
+            Some(ChatSetting::Followers(Some(30)))
+        );
+        assert_eq!(
+            parse("/followers 2h"),
+            Some(ChatSetting::Followers(Some(120)))
+        );
+        assert_eq!(
+            parse("/followers 1w"),
+            Some(ChatSetting::Followers(Some(10_080)))
+        );
+        assert_eq!(
+            parse("/followers 3mo"),
+            Some(ChatSetting::Followers(Some(MAX_FOLLOW_MINUTES)))
+        );
+        assert_eq!(
+            parse("/followers 1 hour 30 minutes"),
+            Some(ChatSetting::Followers(Some(90)))
+        );
+
+        assert!(ChatSetting::parse("/followers soon").unwrap().is_err());
+        assert!(ChatSetting::parse("/followers 4mo").unwrap().is_err());
+        assert!(ChatSetting::parse("/followers 10x").unwrap().is_err());
+    }
+
+    #[test]
+    fn test_parse_slow_wait() {
+        assert_eq!(
+            parse("/slow"),
+            Some(ChatSetting::Slow(Some(DEFAULT_SLOW_WAIT)))
+        );
+        assert_eq!(parse("/slow 10"), Some(ChatSetting::Slow(Some(10))));
+        assert_eq!(parse("/slow 120s"), Some(ChatSetting::Slow(Some(120))));
+
+        assert!(ChatSetting::parse("/slow 1").unwrap().is_err());
+        assert!(ChatSetting::parse("/slow 121").unwrap().is_err());
+        assert!(ChatSetting::parse("/slow fast").unwrap().is_err());
+    }
+
+    #[test]
+    fn test_chat_setting_body() {
+        assert_eq!(
+            ChatSetting::Followers(Some(10)).body(),
+            json!({ "follower_mode": true, "follower_mode_duration": 10 })
+        );
+        assert_eq!(
+            ChatSetting::Slow(None).body(),
+            json!({ "slow_mode": false })
+        );
+        assert_eq!(
+            ChatSetting::UniqueChat(true).body(),
+            json!({ "unique_chat_mode": true })
+        );
+    }
+
+    #[test]
+    fn test_room_modes() {
+        let mut modes = RoomModes::default();
+
+        let tags = |pairs: &[(&str, &str)]| {
+            pairs
+                .iter()
+                .map(|(k, v)| ((*k).to_string(), (*v).to_string()))
+                .collect::<HashMap<_, _>>()
+        };
+
+        modes.apply(&tags(&[
+            ("emote-only", "0"),
+            ("followers-only", "-1"),
+            ("r9k", "0"),
+            ("slow", "0"),
+            ("subs-only", "0"),
+        ]));
+        assert_eq!(modes.summary(), None);
+
+        modes.apply(&tags(&[("slow", "30")]));
+        modes.apply(&tags(&[("followers-only", "10")]));
+        modes.apply(&tags(&[("r9k", "1")]));
+        assert_eq!(
+            modes.summary().as_deref(),
+            Some("followers 10m, slow 30s, unique")
+        );
+
+        modes.apply(&tags(&[("slow", "0"), ("followers-only", "0")]));
+        assert_eq!(modes.summary().as_deref(), Some("followers, unique"));
+    }
+}
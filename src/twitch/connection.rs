@@ -1,53 +1,398 @@
 use std::cmp::min;
-use std::time::Duration;
+use std::str::FromStr;
+use std::time::{Duration, Instant, SystemTime};
 
 use irc::{
-    client::{prelude::Config, Client, ClientStream},
+    client::{
+        prelude::{Capability, Config},
+        Client, ClientStream,
+    },
     error::Error::{self, PingTimeout},
+    proto::{CapSubCommand, Command, Message},
 };
+use log::debug;
 use tokio::{sync::mpsc::Sender, time::sleep};
+use tui::style::Color;
 
-use crate::handlers::{
-    config::CompleteConfig,
-    data::{DataBuilder, TwitchToTerminalAction},
+use crate::{
+    handlers::{
+        config::{CompleteConfig, TwitchConfig},
+        data::{DataBuilder, TwitchToTerminalAction},
+    },
+    twitch::events::{ChatEvent, EVENTS},
+    utils::{
+        certificates::root_certificates,
+        proxy::{proxy, Proxy, ProxyKind},
+    },
 };
 
-/// Initialize the config and send it to the client to connect to an IRC channel.
-async fn create_client_stream(config: CompleteConfig) -> Result<(Client, ClientStream), Error> {
-    let irc_config = Config {
-        nickname: Some(config.twitch.username.clone()),
+/// How the connection to Twitch's IRC server is doing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ConnectionState {
+    /// No connection has been attempted yet.
+    #[default]
+    Disconnected,
+    /// Connected to the server, waiting for it to accept the login.
+    Authenticating,
+    Connected,
+    /// The connection was lost, and this is the attempt at getting it back.
+    Reconnecting(u32),
+    /// The server refused the login.
+    Failed,
+}
+
+impl ConnectionState {
+    pub fn label(self) -> String {
+        match self {
+            Self::Disconnected => "Disconnected".to_string(),
+            Self::Authenticating => "Authenticating".to_string(),
+            Self::Connected => "Connected".to_string(),
+            Self::Reconnecting(attempt) => format!("Reconnecting ({attempt})"),
+            Self::Failed => "Failed".to_string(),
+        }
+    }
+
+    pub const fn color(self) -> Color {
+        match self {
+            Self::Connected => Color::Green,
+            Self::Authenticating | Self::Reconnecting(_) => Color::Yellow,
+            Self::Disconnected | Self::Failed => Color::Red,
+        }
+    }
+
+    /// The state once the user asks to reconnect, where nothing changes if a connection
+    /// is already being made.
+    pub const fn manual_reconnect(self) -> Option<Self> {
+        match self {
+            Self::Authenticating | Self::Reconnecting(_) => None,
+            Self::Disconnected | Self::Connected | Self::Failed => Some(Self::Reconnecting(1)),
+        }
+    }
+}
+
+/// How often the clock is checked for having jumped ahead.
+pub const SLEEP_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Notices when the computer was asleep, which the connection does not survive.
+///
+/// Timers do not run while the computer is asleep, but the wall clock does, so the clock
+/// jumps ahead between two checks that are done at a regular interval.
+#[derive(Debug, Clone, Copy)]
+pub struct SleepDetector {
+    last_check: SystemTime,
+    threshold: Duration,
+}
+
+impl SleepDetector {
+    /// A detector that never triggers for a threshold of 0 seconds.
+    pub fn new(threshold: u64, now: SystemTime) -> Option<Self> {
+        (threshold > 0).then(|| Self {
+            last_check: now,
+            threshold: Duration::from_secs(threshold),
+        })
+    }
+
+    /// Record a check done at `now`, returning if the computer was asleep since the last check.
+    pub fn check(&mut self, now: SystemTime) -> bool {
+        let elapsed = now.duration_since(self.last_check);
+        self.last_check = now;
+
+        slept(
+            elapsed.unwrap_or_default(),
+            SLEEP_CHECK_INTERVAL,
+            self.threshold,
+        )
+    }
+}
+
+/// If more time passed between two checks than they are apart, by over the threshold.
+const fn slept(elapsed: Duration, interval: Duration, threshold: Duration) -> bool {
+    elapsed.saturating_sub(interval).as_secs() > threshold.as_secs()
+}
+
+/// How often the liveness probe checks if a PING is due or has gone unanswered.
+pub const LIVENESS_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The irc crate pings on its own too, which is left to once a day since the probe pings instead.
+const IRC_PING_TIME: u32 = 60 * 60 * 24;
+
+/// How often the irc crate pings, and how long it waits for the PONG, without the probe.
+const IRC_PING_FALLBACK: u32 = 10;
+
+/// What the liveness probe needs done after a check.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Probe {
+    /// Nothing, as the next PING is not due yet or the last one is still in time.
+    Wait,
+    /// Send a PING with this token, which Twitch answers with a PONG of the same token.
+    Ping(String),
+    /// The last PING was not answered in time, so the connection is dead.
+    Dead,
+}
+
+/// Tells that the connection is dead before the server does, by sending a PING every so often
+/// and expecting a PONG for it within a timeout.
+#[derive(Debug, Clone)]
+pub struct Liveness {
+    interval: Duration,
+    timeout: Duration,
+    next_ping: Instant,
+    /// The token of the PING waiting for its PONG, and when it was sent.
+    waiting: Option<(String, Instant)>,
+    sent: u64,
+}
+
+impl Liveness {
+    /// A probe that pings every `interval` seconds, where there is none for an interval of 0.
+    pub fn new(interval: u64, timeout: u64, now: Instant) -> Option<Self> {
+        (interval > 0).then(|| {
+            let interval = Duration::from_secs(interval);
+
+            Self {
+                interval,
+                timeout: Duration::from_secs(timeout.max(1)),
+                next_ping: now + interval,
+                waiting: None,
+                sent: 0,
+            }
+        })
+    }
+
+    /// Check on the connection at `now`.
+    pub fn check(&mut self, now: Instant) -> Probe {
+        if let Some((_, sent_at)) = &self.waiting {
+            if now.saturating_duration_since(*sent_at) < self.timeout {
+                return Probe::Wait;
+            }
+
+            self.reset(now);
+
+            return Probe::Dead;
+        }
+
+        if now < self.next_ping {
+            return Probe::Wait;
+        }
+
+        self.sent += 1;
+
+        let token = format!("twt-{}", self.sent);
+
+        self.waiting = Some((token.clone(), now));
+        self.next_ping = now + self.interval;
+
+        Probe::Ping(token)
+    }
+
+    /// Take in a PONG, returning if it answered the PING that is waiting.
+    pub fn pong(&mut self, token: &str) -> bool {
+        let answered = self.waiting.as_ref().is_some_and(|(t, _)| t == token);
+
+        if answered {
+            self.waiting = None;
+        }
+
+        answered
+    }
+
+    /// Start over on a new connection, where the PING to the old one no longer matters.
+    pub fn reset(&mut self, now: Instant) {
+        self.waiting = None;
+        self.next_ping = now + self.interval;
+    }
+}
+
+/// The token of a PONG, which Twitch sends as `PONG tmi.twitch.tv :<token>`.
+pub fn pong_token(command: &Command) -> Option<&str> {
+    match command {
+        Command::PONG(_, Some(token)) | Command::PONG(token, None) => Some(token),
+        _ => None,
+    }
+}
+
+const TAGS_CAPABILITY: &str = "twitch.tv/tags";
+const COMMANDS_CAPABILITY: &str = "twitch.tv/commands";
+const MEMBERSHIP_CAPABILITY: &str = "twitch.tv/membership";
+
+/// The capabilities to request from Twitch, being the ones that chat uses.
+/// Membership is only asked for when enabled, since it floods big channels with lines.
+fn capabilities(twitch_config: &TwitchConfig) -> Vec<Capability> {
+    let mut capabilities = vec![
+        Capability::Custom(COMMANDS_CAPABILITY),
+        Capability::Custom(TAGS_CAPABILITY),
+    ];
+
+    if twitch_config.membership {
+        capabilities.push(Capability::Custom(MEMBERSHIP_CAPABILITY));
+    }
+
+    capabilities
+}
+
+/// The CAP REQ that asks for the capabilities.
+fn capability_request(capabilities: &[Capability]) -> Command {
+    let names = capabilities
+        .iter()
+        .map(AsRef::as_ref)
+        .collect::<Vec<&str>>()
+        .join(" ");
+
+    Command::CAP(None, CapSubCommand::REQ, None, Some(names))
+}
+
+/// Twitch lets any user with this prefix and a number read chat, without a password.
+const ANONYMOUS_PREFIX: &str = "justinfan";
+
+/// If a JOIN is the one of the user, rather than of someone else that membership tells about.
+pub fn is_own_join(message: &Message, twitch_config: &TwitchConfig) -> bool {
+    let Some(nickname) = message.source_nickname() else {
+        return true;
+    };
+
+    if twitch_config.anonymous {
+        nickname.starts_with(ANONYMOUS_PREFIX)
+    } else {
+        nickname.eq_ignore_ascii_case(&twitch_config.username)
+    }
+}
+
+/// If a line says that the channel has been joined, so that messages sent to it are not lost.
+/// That is the JOIN of the user, or the ROOMSTATE that Twitch sends right after it.
+pub fn has_joined(message: &Message, channel: &str, twitch_config: &TwitchConfig) -> bool {
+    match &message.command {
+        Command::JOIN(joined, _, _) => {
+            joined.eq_ignore_ascii_case(channel) && is_own_join(message, twitch_config)
+        }
+        Command::Raw(command, args) if command == "ROOMSTATE" => args
+            .first()
+            .is_some_and(|joined| joined.eq_ignore_ascii_case(channel)),
+        _ => false,
+    }
+}
+
+/// A nickname to read chat anonymously with, numbered by `seed`.
+fn anonymous_nickname(seed: u32) -> String {
+    format!("{ANONYMOUS_PREFIX}{}", 10_000 + seed % 90_000)
+}
+
+/// The IRC config to connect with, which logs in as the user unless chat is read anonymously.
+fn irc_config(config: &CompleteConfig) -> Config {
+    let (nickname, password) = if config.twitch.anonymous {
+        let seed = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .subsec_nanos();
+
+        (anonymous_nickname(seed), None)
+    } else {
+        (config.twitch.username.clone(), config.twitch.token.clone())
+    };
+
+    // Without the probe, the irc crate is left to notice dead connections as it always did.
+    let (ping_time, ping_timeout) = if config.twitch.ping_interval == 0 {
+        (IRC_PING_FALLBACK, Some(IRC_PING_FALLBACK))
+    } else {
+        (
+            IRC_PING_TIME,
+            u32::try_from(config.twitch.ping_timeout).ok(),
+        )
+    };
+
+    Config {
+        nickname: Some(nickname),
         server: Some(config.twitch.server.clone()),
-        channels: vec![format!("#{}", config.twitch.channel)],
-        password: config.twitch.token.clone(),
+        // Without a channel, none is joined until one is picked.
+        channels: Some(&config.twitch.channel)
+            .filter(|channel| !channel.is_empty())
+            .map(|channel| format!("#{channel}"))
+            .into_iter()
+            .collect(),
+        password,
         port: Some(6697),
         use_tls: Some(true),
-        ping_timeout: Some(10),
-        ping_time: Some(10),
+        ping_timeout,
+        ping_time: Some(ping_time),
         ..Default::default()
-    };
+    }
+}
+
+/// Initialize the config and send it to the client to connect to an IRC channel.
+async fn create_client_stream(config: CompleteConfig) -> Result<(Client, ClientStream), Error> {
+    let mut irc_config = irc_config(&config);
+
+    if let Some(proxy) = proxy() {
+        proxy.apply_irc(&mut irc_config);
+    }
+
+    if let Some(certificates) = root_certificates() {
+        certificates.apply_irc(&mut irc_config);
+    }
+
+    connect(irc_config, &config.twitch).await
+}
 
-    let mut client = Client::from_config(irc_config.clone()).await?;
+/// Connect and log in, then request the capabilities that chat uses. Twitch forgets them along
+/// with the connection, so this is done again for every reconnect.
+async fn connect(
+    irc_config: Config,
+    twitch_config: &TwitchConfig,
+) -> Result<(Client, ClientStream), Error> {
+    let mut client = Client::from_config(irc_config).await?;
 
     client.identify()?;
+    client.send(capability_request(&capabilities(twitch_config)))?;
 
     let stream = client.stream()?;
 
     Ok((client, stream))
 }
 
+/// Keep trying to connect, where the attempts are counted as reconnects if `reconnecting` is set.
 pub async fn wait_client_stream(
     tx: Sender<TwitchToTerminalAction>,
     data_builder: DataBuilder<'_>,
     config: CompleteConfig,
+    reconnecting: bool,
 ) -> (Client, ClientStream) {
     let mut timeout = 1;
+    let mut attempt = u32::from(reconnecting);
 
     loop {
+        if attempt > 0 {
+            EVENTS.publish(|| ChatEvent::Connection(ConnectionState::Reconnecting(attempt)));
+
+            tx.send(TwitchToTerminalAction::Connection(
+                ConnectionState::Reconnecting(attempt),
+            ))
+            .await
+            .unwrap();
+        }
+
         match create_client_stream(config.clone()).await {
-            Ok(v) => return v,
+            Ok(v) => {
+                EVENTS.publish(|| ChatEvent::Connection(ConnectionState::Authenticating));
+
+                tx.send(TwitchToTerminalAction::Connection(
+                    ConnectionState::Authenticating,
+                ))
+                .await
+                .unwrap();
+
+                return v;
+            }
             Err(err) => match err {
                 Error::Io(io) => tx
-                    .send(data_builder.system(format!("Unable to connect: {io}")))
+                    .send(data_builder.system(format!(
+                        "Unable to connect: {io}{}",
+                        proxy().map_or_else(String::new, proxy_hint)
+                    )))
+                    .await
+                    .unwrap(),
+                Error::Proxy(err) => tx
+                    .send(data_builder.system(format!(
+                        "Unable to connect through the proxy at {}: {err}",
+                        proxy().map_or_else(String::new, Proxy::address)
+                    )))
                     .await
                     .unwrap(),
                 _ => {
@@ -61,16 +406,42 @@ pub async fn wait_client_stream(
         sleep(Duration::from_secs(timeout)).await;
 
         timeout = min(timeout * 2, 30);
+        attempt += 1;
     }
 }
 
-/// If an error of any kind occurs, attempt to reconnect to the IRC channel.
-pub async fn client_stream_reconnect(
+/// Why connecting might have failed with the proxy.
+fn proxy_hint(proxy: &Proxy) -> String {
+    match proxy.kind {
+        ProxyKind::Socks5 => format!(", through the proxy at {}", proxy.address()),
+        ProxyKind::Http => {
+            ". Chat can only connect through a SOCKS5 proxy, so the HTTP proxy was not used for it"
+                .to_string()
+        }
+    }
+}
+
+/// Read a raw line from Twitch, where a line that is not a valid IRC message is logged and skipped.
+pub fn parse_line(line: &str) -> Option<Message> {
+    Message::from_str(line)
+        .map_err(|err| debug!("Skipping malformed line {line:?}: {err}"))
+        .ok()
+}
+
+/// If the error only means that a single line from Twitch could not be read, so the connection
+/// is still fine and the line can be skipped.
+///
+/// The codec drops the line before parsing it, so the stream carries on with the next one.
+pub const fn is_malformed_line(err: &Error) -> bool {
+    matches!(err, Error::InvalidMessage { .. })
+}
+
+/// Tell why the connection was lost, before it is made again.
+pub async fn report_connection_error(
     err: Error,
-    tx: Sender<TwitchToTerminalAction>,
+    tx: &Sender<TwitchToTerminalAction>,
     data_builder: DataBuilder<'_>,
-    config: &CompleteConfig,
-) -> (Client, ClientStream) {
+) {
     match err {
         PingTimeout => {
             tx.send(data_builder.system("Ping to Twitch has timed out.".to_string()))
@@ -89,8 +460,379 @@ pub async fn client_stream_reconnect(
     tx.send(data_builder.system("Attempting reconnect...".to_string()))
         .await
         .unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::StreamExt;
+    use irc::proto::Command;
+
+    use super::*;
+
+    #[test]
+    fn test_capability_request() {
+        let mut twitch_config = TwitchConfig::default();
+
+        let request = |config: &TwitchConfig| {
+            Message::from(capability_request(&capabilities(config))).to_string()
+        };
+
+        assert_eq!(
+            request(&twitch_config),
+            "CAP REQ :twitch.tv/commands twitch.tv/tags\r\n"
+        );
+
+        twitch_config.membership = true;
+
+        assert_eq!(
+            request(&twitch_config),
+            "CAP REQ :twitch.tv/commands twitch.tv/tags twitch.tv/membership\r\n"
+        );
+    }
+
+    #[test]
+    fn test_own_join() {
+        let twitch_config = TwitchConfig {
+            username: "Xithrius".to_string(),
+            ..TwitchConfig::default()
+        };
+
+        let join = |line: &str| is_own_join(&line.parse().unwrap(), &twitch_config);
+
+        assert!(join(
+            ":xithrius!xithrius@xithrius.tmi.twitch.tv JOIN #xithrius"
+        ));
+        assert!(!join(
+            ":someone!someone@someone.tmi.twitch.tv JOIN #xithrius"
+        ));
+    }
+
+    #[test]
+    fn test_parse_line_unescapes_tags() {
+        // Raw lines, such as the recent messages and captures, get their tag values unescaped too.
+        let message = parse_line(
+            r"@system-msg=15\sraiders\:\sfrom\\there :tmi.twitch.tv USERNOTICE #xithrius",
+        )
+        .unwrap();
+
+        assert_eq!(
+            message.tags.unwrap()[0].1.as_deref(),
+            Some("15 raiders; from\\there")
+        );
+    }
+
+    #[test]
+    fn test_has_joined() {
+        let twitch_config = TwitchConfig {
+            username: "xithrius".to_string(),
+            ..TwitchConfig::default()
+        };
+
+        let joined = |line: &str| has_joined(&line.parse().unwrap(), "#xithrius", &twitch_config);
+
+        assert!(joined(
+            ":xithrius!xithrius@xithrius.tmi.twitch.tv JOIN #xithrius"
+        ));
+        assert!(joined(
+            "@emote-only=0;room-id=1 :tmi.twitch.tv ROOMSTATE #xithrius"
+        ));
+
+        // The capabilities being granted come first, before anything can be sent to the channel.
+        assert!(!joined(":tmi.twitch.tv CAP * ACK :twitch.tv/tags"));
+        assert!(!joined(
+            ":someone!someone@someone.tmi.twitch.tv JOIN #xithrius"
+        ));
+        assert!(!joined(
+            ":xithrius!xithrius@xithrius.tmi.twitch.tv JOIN #other"
+        ));
+        assert!(!joined(":tmi.twitch.tv ROOMSTATE #other"));
+    }
+
+    #[test]
+    fn test_anonymous_irc_config() {
+        let mut config = CompleteConfig::default();
+        config.twitch.username = "xithrius".to_string();
+        config.twitch.channel = "xithrius".to_string();
+        config.twitch.token = Some("oauth:abc".to_string());
+
+        let logged_in = irc_config(&config);
+
+        assert_eq!(logged_in.nickname.as_deref(), Some("xithrius"));
+        assert_eq!(logged_in.password.as_deref(), Some("oauth:abc"));
+
+        config.twitch.anonymous = true;
+
+        let anonymous = irc_config(&config);
+        let nickname = anonymous.nickname.unwrap();
+
+        assert!(nickname.starts_with(ANONYMOUS_PREFIX));
+        assert!(nickname[ANONYMOUS_PREFIX.len()..].parse::<u32>().is_ok());
+        assert_eq!(anonymous.password, None);
+        assert_eq!(anonymous.channels, vec!["#xithrius".to_string()]);
+
+        assert_eq!(anonymous.ping_time, Some(IRC_PING_TIME));
+
+        // Without the probe, the irc crate pings as often as it did before there was one.
+        config.twitch.ping_interval = 0;
+        let unprobed = irc_config(&config);
+        assert_eq!(unprobed.ping_time, Some(10));
+        assert_eq!(unprobed.ping_timeout, Some(10));
+
+        // Nothing is joined while the channel is still to be picked.
+        config.twitch.channel.clear();
+        assert!(irc_config(&config).channels.is_empty());
+
+        assert_eq!(anonymous_nickname(0), "justinfan10000");
+        assert_eq!(anonymous_nickname(u32::MAX).len(), "justinfan".len() + 5);
+    }
+
+    #[test]
+    fn test_slept() {
+        let interval = Duration::from_secs(5);
+        let threshold = Duration::from_secs(30);
+
+        assert!(!slept(Duration::from_secs(5), interval, threshold));
+        assert!(!slept(Duration::from_secs(35), interval, threshold));
+        assert!(slept(Duration::from_secs(36), interval, threshold));
+        assert!(slept(Duration::from_secs(3600), interval, threshold));
+        // A slow check is not a sleep.
+        assert!(!slept(Duration::from_secs(12), interval, threshold));
+    }
+
+    #[test]
+    fn test_manual_reconnect() {
+        assert_eq!(
+            ConnectionState::Connected.manual_reconnect(),
+            Some(ConnectionState::Reconnecting(1))
+        );
+        assert_eq!(
+            ConnectionState::Failed.manual_reconnect(),
+            Some(ConnectionState::Reconnecting(1))
+        );
+        assert_eq!(ConnectionState::Reconnecting(3).manual_reconnect(), None);
+        assert_eq!(ConnectionState::Authenticating.manual_reconnect(), None);
+    }
+
+    #[test]
+    fn test_sleep_detector() {
+        let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000);
+
+        assert!(SleepDetector::new(0, start).is_none());
+
+        let mut detector = SleepDetector::new(30, start).unwrap();
+
+        assert!(!detector.check(start + Duration::from_secs(5)));
+        assert!(detector.check(start + Duration::from_secs(605)));
+        assert!(!detector.check(start + Duration::from_secs(610)));
+        // The clock being set back is not a sleep either.
+        assert!(!detector.check(start));
+    }
 
-    let (client, stream) = wait_client_stream(tx, data_builder, config.clone()).await;
+    #[test]
+    fn test_liveness_answered() {
+        let start = Instant::now();
 
-    (client, stream)
+        assert!(Liveness::new(0, 10, start).is_none());
+
+        let mut liveness = Liveness::new(30, 10, start).unwrap();
+
+        assert_eq!(liveness.check(start + Duration::from_secs(29)), Probe::Wait);
+        assert_eq!(
+            liveness.check(start + Duration::from_secs(30)),
+            Probe::Ping("twt-1".to_string())
+        );
+        assert!(liveness.waiting.is_some());
+
+        // Only one PING waits at a time, and a PONG for another token does not answer it.
+        assert_eq!(liveness.check(start + Duration::from_secs(35)), Probe::Wait);
+        assert!(!liveness.pong("something else"));
+        assert!(liveness.pong("twt-1"));
+        assert!(liveness.waiting.is_none());
+
+        // The next PING goes out an interval after the last one, with a new token.
+        assert_eq!(liveness.check(start + Duration::from_secs(45)), Probe::Wait);
+        assert_eq!(
+            liveness.check(start + Duration::from_secs(60)),
+            Probe::Ping("twt-2".to_string())
+        );
+    }
+
+    #[test]
+    fn test_liveness_timeout() {
+        let start = Instant::now();
+        let mut liveness = Liveness::new(30, 10, start).unwrap();
+
+        let sent = start + Duration::from_secs(30);
+        assert!(matches!(liveness.check(sent), Probe::Ping(_)));
+
+        assert_eq!(liveness.check(sent + Duration::from_secs(9)), Probe::Wait);
+        assert_eq!(liveness.check(sent + Duration::from_secs(10)), Probe::Dead);
+
+        // After being found dead, it starts over instead of being dead on every check.
+        assert!(liveness.waiting.is_none());
+        assert_eq!(liveness.check(sent + Duration::from_secs(11)), Probe::Wait);
+        assert!(!liveness.pong("twt-1"));
+    }
+
+    #[test]
+    fn test_liveness_reset() {
+        let start = Instant::now();
+        let mut liveness = Liveness::new(30, 10, start).unwrap();
+
+        assert!(matches!(
+            liveness.check(start + Duration::from_secs(30)),
+            Probe::Ping(_)
+        ));
+
+        // A reconnect drops the PING to the old connection.
+        let reconnected = start + Duration::from_secs(35);
+        liveness.reset(reconnected);
+
+        assert_eq!(
+            liveness.check(reconnected + Duration::from_secs(20)),
+            Probe::Wait
+        );
+        assert_eq!(
+            liveness.check(reconnected + Duration::from_secs(30)),
+            Probe::Ping("twt-2".to_string())
+        );
+    }
+
+    #[test]
+    fn test_pong_token() {
+        let pong: Message = ":tmi.twitch.tv PONG tmi.twitch.tv :twt-1".parse().unwrap();
+        assert_eq!(pong_token(&pong.command), Some("twt-1"));
+
+        let bare: Message = "PONG :twt-2".parse().unwrap();
+        assert_eq!(pong_token(&bare.command), Some("twt-2"));
+
+        let server_ping: Message = "PING :tmi.twitch.tv".parse().unwrap();
+        assert_eq!(pong_token(&server_ping.command), None);
+    }
+
+    #[test]
+    fn test_skip_malformed_lines() {
+        let lines = [
+            "@badges=;display-name=Foo :foo!foo@foo.tmi.twitch.tv PRIVMSG #xithrius :hello",
+            "",
+            ":tmi.twitch.tv :",
+            "PING :tmi.twitch.tv",
+            ":tmi.twitch.tv ::",
+            ":bar!bar@bar.tmi.twitch.tv PRIVMSG #xithrius :still here",
+        ];
+
+        let messages = lines
+            .iter()
+            .filter_map(|line| parse_line(line))
+            .map(|message| message.command)
+            .collect::<Vec<_>>();
+
+        assert_eq!(messages.len(), 3);
+        assert!(matches!(messages[0], Command::PRIVMSG(_, ref msg) if msg == "hello"));
+        assert!(matches!(messages[1], Command::PING(..)));
+        assert!(matches!(messages[2], Command::PRIVMSG(_, ref msg) if msg == "still here"));
+    }
+
+    #[test]
+    fn test_malformed_line_keeps_connection() {
+        let err = Error::from(Message::from_str(":tmi.twitch.tv :").unwrap_err());
+
+        assert!(is_malformed_line(&err));
+        assert!(!is_malformed_line(&PingTimeout));
+    }
+
+    /// The lines that a client sends on each of `connections` connections to a local server,
+    /// up to and including the first CAP REQ.
+    fn serve(connections: usize) -> (u16, std::thread::JoinHandle<Vec<Vec<String>>>) {
+        use std::io::{BufRead, BufReader};
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let server = std::thread::spawn(move || {
+            (0..connections)
+                .map(|_| {
+                    let (socket, _) = listener.accept().unwrap();
+                    socket
+                        .set_read_timeout(Some(Duration::from_secs(5)))
+                        .unwrap();
+
+                    let mut lines = Vec::new();
+
+                    for line in BufReader::new(socket).lines() {
+                        let line = line.unwrap();
+                        let done = line.starts_with("CAP REQ");
+                        lines.push(line);
+
+                        if done {
+                            break;
+                        }
+                    }
+
+                    lines
+                })
+                .collect()
+        });
+
+        (port, server)
+    }
+
+    #[tokio::test]
+    async fn test_reconnect_requests_capabilities() {
+        let (port, server) = serve(2);
+
+        let irc_config = Config {
+            nickname: Some("justinfan123".to_string()),
+            server: Some("127.0.0.1".to_string()),
+            port: Some(port),
+            use_tls: Some(false),
+            ..Default::default()
+        };
+
+        // Connecting again, as a reconnect does, asks for the capabilities again.
+        for _ in 0..2 {
+            let (_client, mut stream) = connect(irc_config.clone(), &TwitchConfig::default())
+                .await
+                .unwrap();
+
+            // The stream is what writes the queued lines out.
+            let _ = tokio::time::timeout(Duration::from_millis(200), stream.next()).await;
+        }
+
+        let connections = server.join().unwrap();
+
+        assert_eq!(connections.len(), 2);
+
+        for lines in connections {
+            assert_eq!(
+                lines.last().map(String::as_str),
+                Some("CAP REQ :twitch.tv/commands twitch.tv/tags")
+            );
+            assert!(lines.iter().any(|line| line == "NICK justinfan123"));
+        }
+    }
+
+    #[test]
+    fn test_connection_state_label_and_color() {
+        let cases = [
+            (ConnectionState::Disconnected, "Disconnected", Color::Red),
+            (
+                ConnectionState::Authenticating,
+                "Authenticating",
+                Color::Yellow,
+            ),
+            (ConnectionState::Connected, "Connected", Color::Green),
+            (
+                ConnectionState::Reconnecting(3),
+                "Reconnecting (3)",
+                Color::Yellow,
+            ),
+            (ConnectionState::Failed, "Failed", Color::Red),
+        ];
+
+        for (state, label, color) in cases {
+            assert_eq!(state.label(), label);
+            assert_eq!(state.color(), color);
+        }
+    }
 }
@@ -0,0 +1,255 @@
+use std::{collections::HashMap, fs::OpenOptions, io};
+
+use log::warn;
+use once_cell::sync::Lazy;
+use serde_json::{json, Value};
+use tokio::{
+    fs::File,
+    io::AsyncWriteExt,
+    sync::broadcast::{self, error::RecvError, Receiver, Sender},
+};
+
+use crate::{handlers::data::MessageData, twitch::ConnectionState};
+
+/// How many events a subscriber can fall behind before it starts missing the oldest ones.
+const EVENT_CAPACITY: usize = 256;
+
+/// Every event received from chat, for anything that wants to react to it.
+pub static EVENTS: Lazy<EventBus> = Lazy::new(|| EventBus::new(EVENT_CAPACITY));
+
+/// Something that happened in chat, as parsed from the IRC connection.
+#[derive(Debug, Clone)]
+pub enum ChatEvent {
+    /// A message sent to chat (PRIVMSG).
+    Message(MessageData),
+    /// A subscription, raid, announcement or similar (USERNOTICE), by its `msg-id` tag.
+    UserNotice {
+        kind: String,
+        system_message: Option<String>,
+        message: Option<MessageData>,
+    },
+    /// Chat was cleared, or only the messages of a user who was timed out or banned (CLEARCHAT).
+    ClearChat {
+        user_id: Option<String>,
+        /// For how many seconds the user was timed out, where none means a ban.
+        duration: Option<u64>,
+    },
+    /// A single message was deleted (CLEARMSG).
+    ClearMessage {
+        message_id: String,
+    },
+    /// The settings of the channel, such as slow mode or emote-only mode (ROOMSTATE).
+    RoomState(HashMap<String, String>),
+    /// A message from Twitch itself (NOTICE).
+    Notice(String),
+    Connection(ConnectionState),
+}
+
+fn message_json(message: &MessageData) -> Value {
+    json!({
+        "time": message.time_sent.to_rfc3339(),
+        "author": message.author,
+        "login": message.login,
+        "user_id": message.user_id,
+        "message_id": message.message_id,
+        "text": message.payload,
+    })
+}
+
+impl ChatEvent {
+    /// The event as JSON, with its kind under `type`.
+    pub fn to_json(&self) -> Value {
+        match self {
+            Self::Message(message) => json!({
+                "type": "message",
+                "message": message_json(message),
+            }),
+            Self::UserNotice {
+                kind,
+                system_message,
+                message,
+            } => json!({
+                "type": "user_notice",
+                "kind": kind,
+                "system_message": system_message,
+                "message": message.as_ref().map(message_json),
+            }),
+            Self::ClearChat { user_id, duration } => json!({
+                "type": "clear_chat",
+                "user_id": user_id,
+                "duration": duration,
+            }),
+            Self::ClearMessage { message_id } => json!({
+                "type": "clear_message",
+                "message_id": message_id,
+            }),
+            Self::RoomState(tags) => json!({
+                "type": "room_state",
+                "tags": tags,
+            }),
+            Self::Notice(notice) => json!({
+                "type": "notice",
+                "message": notice,
+            }),
+            Self::Connection(state) => json!({
+                "type": "connection",
+                "state": state.label(),
+            }),
+        }
+    }
+}
+
+/// Broadcasts chat events to any amount of subscribers, without ever waiting on them.
+pub struct EventBus {
+    tx: Sender<ChatEvent>,
+}
+
+impl EventBus {
+    pub fn new(capacity: usize) -> Self {
+        let (tx, _) = broadcast::channel(capacity);
+
+        Self { tx }
+    }
+
+    /// Receive all events published from now on, in the order they were published.
+    pub fn subscribe(&self) -> Receiver<ChatEvent> {
+        self.tx.subscribe()
+    }
+
+    /// Publish an event, which is only built if there is someone to receive it.
+    pub fn publish(&self, event: impl FnOnce() -> ChatEvent) {
+        if self.tx.receiver_count() > 0 {
+            self.tx.send(event()).unwrap_or_default();
+        }
+    }
+}
+
+/// Append every event of `bus` to the file at `path` as a line of JSON, so that scripts can
+/// react to chat by following the file.
+pub fn emit_json_lines(bus: &EventBus, path: &str) -> io::Result<()> {
+    let mut file = File::from_std(OpenOptions::new().create(true).append(true).open(path)?);
+    let mut rx = bus.subscribe();
+    let path = path.to_string();
+
+    tokio::spawn(async move {
+        loop {
+            let event = match rx.recv().await {
+                Ok(event) => event,
+                Err(RecvError::Lagged(missed)) => {
+                    warn!("The event file fell behind, {missed} events were not written to it.");
+                    continue;
+                }
+                Err(RecvError::Closed) => return,
+            };
+
+            let line = format!("{}\n", event.to_json());
+
+            if let Err(err) = file.write_all(line.as_bytes()).await {
+                warn!("Unable to write events to {path}, no longer writing them: {err}");
+                return;
+            }
+        }
+    });
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use tokio::sync::broadcast::error::TryRecvError;
+
+    use super::*;
+
+    #[test]
+    fn test_events_published_in_order() {
+        let bus = EventBus::new(8);
+        let mut rx = bus.subscribe();
+
+        bus.publish(|| ChatEvent::Connection(ConnectionState::Connected));
+        bus.publish(|| ChatEvent::Notice("first".to_string()));
+        bus.publish(|| ChatEvent::ClearMessage {
+            message_id: "abc".to_string(),
+        });
+
+        assert!(matches!(
+            rx.try_recv(),
+            Ok(ChatEvent::Connection(ConnectionState::Connected))
+        ));
+        assert!(matches!(rx.try_recv(), Ok(ChatEvent::Notice(n)) if n == "first"));
+        assert!(
+            matches!(rx.try_recv(), Ok(ChatEvent::ClearMessage { message_id }) if message_id == "abc")
+        );
+        assert!(matches!(rx.try_recv(), Err(TryRecvError::Empty)));
+    }
+
+    #[test]
+    fn test_publish_without_subscribers() {
+        let bus = EventBus::new(8);
+        let mut built = false;
+
+        bus.publish(|| {
+            built = true;
+            ChatEvent::Notice("nobody listens".to_string())
+        });
+
+        assert!(!built);
+
+        let mut rx = bus.subscribe();
+        bus.publish(|| ChatEvent::Notice("now someone does".to_string()));
+
+        assert!(matches!(rx.try_recv(), Ok(ChatEvent::Notice(_))));
+    }
+
+    #[tokio::test]
+    async fn test_events_written_as_json_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("events.jsonl");
+        let path = path.to_str().unwrap();
+
+        let bus = EventBus::new(8);
+        emit_json_lines(&bus, path).unwrap();
+
+        let mut message = MessageData::new(
+            "Xithrius".to_string(),
+            Some("12345678".to_string()),
+            false,
+            "hello chat".to_string(),
+            Some("abc".to_string()),
+            false,
+        );
+        message.login = Some("xithrius".to_string());
+
+        bus.publish(|| ChatEvent::Message(message));
+        bus.publish(|| ChatEvent::ClearChat {
+            user_id: Some("12345678".to_string()),
+            duration: Some(600),
+        });
+        bus.publish(|| ChatEvent::Connection(ConnectionState::Reconnecting(2)));
+
+        let mut lines = vec![];
+
+        for _ in 0..100 {
+            lines = std::fs::read_to_string(path)
+                .unwrap()
+                .lines()
+                .map(|line| serde_json::from_str::<Value>(line).unwrap())
+                .collect::<Vec<_>>();
+
+            if lines.len() == 3 {
+                break;
+            }
+
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0]["type"], "message");
+        assert_eq!(lines[0]["message"]["login"], "xithrius");
+        assert_eq!(lines[0]["message"]["text"], "hello chat");
+        assert_eq!(lines[1]["type"], "clear_chat");
+        assert_eq!(lines[1]["duration"], 600);
+        assert_eq!(lines[2]["state"], "Reconnecting (2)");
+    }
+}
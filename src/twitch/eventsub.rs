@@ -0,0 +1,1233 @@
+use std::{
+    cmp::min,
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    time::Duration,
+};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use color_eyre::{
+    eyre::{bail, eyre, ContextCompat},
+    Report, Result,
+};
+use log::{debug, warn};
+use reqwest::{
+    header::{CONNECTION, SEC_WEBSOCKET_KEY, SEC_WEBSOCKET_VERSION, UPGRADE},
+    Client, StatusCode, Upgraded,
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWriteExt},
+    sync::mpsc::Sender,
+    task::JoinHandle,
+    time::{sleep, timeout},
+};
+
+use crate::{
+    handlers::{config::TwitchConfig, data::TwitchToTerminalAction},
+    twitch::{
+        mod_log::{ModAction, ModLogEntry},
+        oauth::{get_channel_id, get_token_user_id, get_twitch_client},
+    },
+    utils::{proxy::http_client, text::capitalize_first_char},
+};
+
+const EVENTSUB_URL: &str = "wss://eventsub.wss.twitch.tv/ws";
+
+const SUBSCRIPTIONS_URL: &str = "https://api.twitch.tv/helix/eventsub/subscriptions";
+
+// https://dev.twitch.tv/docs/eventsub/eventsub-subscription-types/
+const SUBSCRIPTION_TYPES: [&str; 15] = [
+    "channel.hype_train.begin",
+    "channel.hype_train.progress",
+    "channel.hype_train.end",
+    "channel.goal.begin",
+    "channel.goal.progress",
+    "channel.goal.end",
+    "channel.channel_points_custom_reward_redemption.add",
+    "channel.channel_points_automatic_reward_redemption.add",
+    "channel.poll.begin",
+    "channel.poll.progress",
+    "channel.poll.end",
+    "channel.prediction.begin",
+    "channel.prediction.progress",
+    "channel.prediction.lock",
+    "channel.prediction.end",
+];
+
+/// Moderation actions, which also need the ID of a moderator of the channel.
+const MODERATE_SUBSCRIPTION_TYPE: &str = "channel.moderate";
+
+/// The type of the automatic reward that highlights the message it is redeemed with.
+const HIGHLIGHTED_MESSAGE_REWARD: &str = "send_highlighted_message";
+
+/// Twitch sends keepalive messages every 10 seconds by default, so the connection is dead after this long.
+const KEEPALIVE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How long to wait before connecting again after the connection was lost, doubling each time.
+const RECONNECT_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_RECONNECT_BACKOFF: Duration = Duration::from_secs(30);
+
+/// Event messages are at most a few kilobytes, so anything far bigger is not worth reading.
+const MAX_FRAME_SIZE: u64 = 1 << 20;
+
+const OPCODE_CONTINUATION: u8 = 0x0;
+const OPCODE_TEXT: u8 = 0x1;
+const OPCODE_CLOSE: u8 = 0x8;
+const OPCODE_PING: u8 = 0x9;
+const OPCODE_PONG: u8 = 0xA;
+
+/// Payload lengths that mean the actual length follows in 2 or 8 bytes.
+const LENGTH_U16: u8 = 0x7E;
+const LENGTH_U64: u8 = 0x7F;
+
+/// The progress of a Hype Train that is going on.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct HypeTrain {
+    pub level: u64,
+    /// Points contributed over the whole Hype Train.
+    pub total: u64,
+    /// Points contributed towards the current level.
+    pub progress: u64,
+    /// Points needed to reach the next level.
+    pub goal: u64,
+}
+
+/// The progress of a creator goal, such as a follower or subscription goal.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Goal {
+    #[serde(rename = "type")]
+    pub kind: String,
+    #[serde(default)]
+    pub description: String,
+    pub current_amount: u64,
+    pub target_amount: u64,
+}
+
+/// One of the choices of a poll.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct PollChoice {
+    pub title: String,
+    #[serde(default)]
+    pub votes: u64,
+}
+
+/// A poll the streamer is running, or has just ended.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Poll {
+    pub id: String,
+    pub title: String,
+    pub choices: Vec<PollChoice>,
+    pub ended: bool,
+}
+
+#[derive(Deserialize)]
+struct RawPoll {
+    id: String,
+    title: String,
+    choices: Vec<PollChoice>,
+}
+
+/// One of the outcomes viewers can bet channel points on.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct PredictionOutcome {
+    pub id: String,
+    pub title: String,
+    /// How many viewers bet on the outcome.
+    #[serde(default)]
+    pub users: u64,
+    #[serde(default)]
+    pub channel_points: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PredictionState {
+    /// Viewers can still bet.
+    Active,
+    /// Bets are closed, waiting for the outcome.
+    Locked,
+    /// The outcome with this id won.
+    Resolved(String),
+    /// Everyone got their channel points back.
+    Canceled,
+}
+
+/// A prediction the streamer is running, or has just ended.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Prediction {
+    pub id: String,
+    pub title: String,
+    pub outcomes: Vec<PredictionOutcome>,
+    pub state: PredictionState,
+}
+
+#[derive(Deserialize)]
+struct RawPrediction {
+    id: String,
+    title: String,
+    outcomes: Vec<PredictionOutcome>,
+    status: Option<String>,
+    winning_outcome_id: Option<String>,
+}
+
+impl RawPrediction {
+    fn into_prediction(self, subscription_type: &str) -> Prediction {
+        let state = match (subscription_type, self.status.as_deref()) {
+            ("channel.prediction.lock", _) => PredictionState::Locked,
+            ("channel.prediction.end", Some("resolved")) => {
+                PredictionState::Resolved(self.winning_outcome_id.unwrap_or_default())
+            }
+            ("channel.prediction.end", _) => PredictionState::Canceled,
+            _ => PredictionState::Active,
+        };
+
+        Prediction {
+            id: self.id,
+            title: self.title,
+            outcomes: self.outcomes,
+            state,
+        }
+    }
+}
+
+/// The share of each count in the total, in whole percentages that add up to 100.
+///
+/// Rounding is done by largest remainder, so that the percentages shown never add up to 99 or 101.
+/// If nothing has been counted yet, every share is 0.
+pub fn percentages(counts: &[u64]) -> Vec<u64> {
+    let total = counts.iter().sum::<u64>();
+
+    if total == 0 {
+        return vec![0; counts.len()];
+    }
+
+    let mut shares = counts
+        .iter()
+        .map(|&c| {
+            let scaled = u128::from(c) * 100;
+            let total = u128::from(total);
+
+            ((scaled / total) as u64, scaled % total)
+        })
+        .collect::<Vec<_>>();
+
+    let missing = 100 - shares.iter().map(|(share, _)| share).sum::<u64>();
+
+    let mut by_remainder = (0..shares.len()).collect::<Vec<_>>();
+    by_remainder.sort_by(|&a, &b| shares[b].1.cmp(&shares[a].1));
+
+    for &i in by_remainder.iter().take(missing as usize) {
+        shares[i].0 += 1;
+    }
+
+    shares.into_iter().map(|(share, _)| share).collect()
+}
+
+/// A viewer spending channel points on a reward.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Redemption {
+    pub user_id: String,
+    pub user_name: String,
+    pub reward: String,
+    /// What the viewer entered along with the reward, if anything.
+    pub input: Option<String>,
+    /// If the reward highlights the chat message it was redeemed with.
+    pub highlight: bool,
+}
+
+impl Redemption {
+    /// The line shown in chat for the redemption.
+    pub fn summary(&self) -> String {
+        match &self.input {
+            Some(input) if !self.highlight => {
+                format!("{} redeemed {}: {input}", self.user_name, self.reward)
+            }
+            _ => format!("{} redeemed {}", self.user_name, self.reward),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct RawRedemption {
+    user_id: String,
+    user_name: String,
+    #[serde(default)]
+    user_input: String,
+    reward: RawReward,
+    /// Only sent for automatic rewards, which are redeemed with a message.
+    message: Option<RawRedemptionMessage>,
+}
+
+#[derive(Deserialize)]
+struct RawReward {
+    /// Only custom rewards have a title, automatic rewards only have a type.
+    title: Option<String>,
+    #[serde(rename = "type")]
+    kind: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct RawRedemptionMessage {
+    text: String,
+}
+
+impl From<RawRedemption> for Redemption {
+    fn from(raw: RawRedemption) -> Self {
+        let highlight = raw.reward.kind.as_deref() == Some(HIGHLIGHTED_MESSAGE_REWARD);
+
+        let reward = raw.reward.title.unwrap_or_else(|| {
+            capitalize_first_char(&raw.reward.kind.unwrap_or_default().replace('_', " "))
+        });
+
+        let input = raw
+            .message
+            .map(|m| m.text)
+            .or(Some(raw.user_input))
+            .filter(|input| !input.is_empty());
+
+        Self {
+            user_id: raw.user_id,
+            user_name: raw.user_name,
+            reward,
+            input,
+            highlight,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct RawModerate {
+    moderator_user_name: String,
+    action: String,
+    ban: Option<RawModerateTarget>,
+    unban: Option<RawModerateTarget>,
+    timeout: Option<RawModerateTimeout>,
+    untimeout: Option<RawModerateTarget>,
+    delete: Option<RawModerateDelete>,
+}
+
+#[derive(Deserialize)]
+struct RawModerateTarget {
+    user_login: String,
+    reason: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct RawModerateTimeout {
+    user_login: String,
+    reason: Option<String>,
+    expires_at: String,
+}
+
+#[derive(Deserialize)]
+struct RawModerateDelete {
+    user_login: String,
+    message_body: Option<String>,
+}
+
+impl RawModerate {
+    /// Actions that are not shown, such as adding a VIP, are left out.
+    fn into_entry(self) -> Option<ModLogEntry> {
+        let reason = |reason: Option<String>| reason.filter(|r| !r.is_empty());
+
+        let mode = |mode: &str, enabled: bool| ModAction::Mode {
+            mode: mode.to_string(),
+            enabled,
+        };
+
+        let action = match self.action.as_str() {
+            "ban" => self.ban.map(|b| ModAction::Ban {
+                user: b.user_login,
+                reason: reason(b.reason),
+            })?,
+            "unban" => self
+                .unban
+                .map(|b| ModAction::Unban { user: b.user_login })?,
+            "timeout" => self.timeout.map(|t| ModAction::Timeout {
+                user: t.user_login,
+                duration: chrono::DateTime::parse_from_rfc3339(&t.expires_at)
+                    .ok()
+                    .and_then(|expiry| {
+                        u64::try_from(
+                            (expiry.with_timezone(&chrono::Utc) - chrono::Utc::now()).num_seconds(),
+                        )
+                        .ok()
+                    }),
+                reason: reason(t.reason),
+            })?,
+            "untimeout" => self
+                .untimeout
+                .map(|t| ModAction::Untimeout { user: t.user_login })?,
+            "delete" => self.delete.map(|d| ModAction::Delete {
+                user: Some(d.user_login),
+                message: d.message_body,
+            })?,
+            "clear" => ModAction::Clear,
+            "slow" => mode("slow mode", true),
+            "slowoff" => mode("slow mode", false),
+            "emoteonly" => mode("emote-only mode", true),
+            "emoteonlyoff" => mode("emote-only mode", false),
+            "followers" => mode("followers-only mode", true),
+            "followersoff" => mode("followers-only mode", false),
+            "subscribers" => mode("subscribers-only mode", true),
+            "subscribersoff" => mode("subscribers-only mode", false),
+            "uniquechat" => mode("unique chat", true),
+            "uniquechatoff" => mode("unique chat", false),
+            _ => return None,
+        };
+
+        Some(ModLogEntry::new(Some(self.moderator_user_name), action))
+    }
+}
+
+/// Something that happened in the channel which is not sent over IRC.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EventSubEvent {
+    HypeTrain(HypeTrain),
+    HypeTrainEnded,
+    Goal(Goal),
+    GoalEnded,
+    Redemption(Redemption),
+    Poll(Poll),
+    Prediction(Prediction),
+    Moderation(ModLogEntry),
+    /// Another channel was joined, about which nothing is known yet.
+    Reset,
+}
+
+/// A message received over the event subscription socket.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EventSubMessage {
+    /// The connection is ready, and subscriptions can be created for the session.
+    Welcome {
+        session_id: String,
+    },
+    Keepalive,
+    /// The connection is about to be closed, and subscriptions move over to the new URL.
+    Reconnect {
+        url: String,
+    },
+    Notification(EventSubEvent),
+    /// A subscription was removed, or a notification of a type that is not shown.
+    Other,
+}
+
+#[derive(Deserialize)]
+struct RawMessage {
+    metadata: Metadata,
+    #[serde(default)]
+    payload: Payload,
+}
+
+#[derive(Deserialize)]
+struct Metadata {
+    message_type: String,
+    subscription_type: Option<String>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct Payload {
+    session: Option<Session>,
+    event: Option<Value>,
+}
+
+#[derive(Deserialize)]
+struct Session {
+    id: String,
+    reconnect_url: Option<String>,
+}
+
+// https://dev.twitch.tv/docs/eventsub/websocket-reference/
+pub fn parse_message(text: &str) -> Result<EventSubMessage> {
+    let message = serde_json::from_str::<RawMessage>(text)?;
+
+    let session = || {
+        message
+            .payload
+            .session
+            .as_ref()
+            .context("Session is missing")
+    };
+
+    Ok(match message.metadata.message_type.as_str() {
+        "session_welcome" => EventSubMessage::Welcome {
+            session_id: session()?.id.clone(),
+        },
+        "session_keepalive" => EventSubMessage::Keepalive,
+        "session_reconnect" => EventSubMessage::Reconnect {
+            url: session()?
+                .reconnect_url
+                .clone()
+                .context("Reconnect URL is missing")?,
+        },
+        "notification" => {
+            let event = message.payload.event.context("Event is missing")?;
+
+            let subscription_type = message.metadata.subscription_type.as_deref();
+
+            match subscription_type {
+                Some("channel.hype_train.begin" | "channel.hype_train.progress") => {
+                    EventSubMessage::Notification(EventSubEvent::HypeTrain(serde_json::from_value(
+                        event,
+                    )?))
+                }
+                Some("channel.hype_train.end") => {
+                    EventSubMessage::Notification(EventSubEvent::HypeTrainEnded)
+                }
+                Some("channel.goal.begin" | "channel.goal.progress") => {
+                    EventSubMessage::Notification(EventSubEvent::Goal(serde_json::from_value(
+                        event,
+                    )?))
+                }
+                Some("channel.goal.end") => EventSubMessage::Notification(EventSubEvent::GoalEnded),
+                Some(
+                    "channel.channel_points_custom_reward_redemption.add"
+                    | "channel.channel_points_automatic_reward_redemption.add",
+                ) => EventSubMessage::Notification(EventSubEvent::Redemption(
+                    serde_json::from_value::<RawRedemption>(event)?.into(),
+                )),
+                Some(
+                    kind @ ("channel.poll.begin" | "channel.poll.progress" | "channel.poll.end"),
+                ) => {
+                    let raw = serde_json::from_value::<RawPoll>(event)?;
+
+                    EventSubMessage::Notification(EventSubEvent::Poll(Poll {
+                        id: raw.id,
+                        title: raw.title,
+                        choices: raw.choices,
+                        ended: kind == "channel.poll.end",
+                    }))
+                }
+                Some(
+                    kind @ ("channel.prediction.begin"
+                    | "channel.prediction.progress"
+                    | "channel.prediction.lock"
+                    | "channel.prediction.end"),
+                ) => EventSubMessage::Notification(EventSubEvent::Prediction(
+                    serde_json::from_value::<RawPrediction>(event)?.into_prediction(kind),
+                )),
+                Some(MODERATE_SUBSCRIPTION_TYPE) => serde_json::from_value::<RawModerate>(event)?
+                    .into_entry()
+                    .map_or(EventSubMessage::Other, |entry| {
+                        EventSubMessage::Notification(EventSubEvent::Moderation(entry))
+                    }),
+                _ => EventSubMessage::Other,
+            }
+        }
+        _ => EventSubMessage::Other,
+    })
+}
+
+/// What is going on in the channel, to be shown along with chat.
+#[derive(Debug, Clone, Default)]
+pub struct LiveStatus {
+    hype_train: Option<HypeTrain>,
+    goal: Option<Goal>,
+}
+
+impl LiveStatus {
+    pub fn apply(&mut self, event: EventSubEvent) {
+        match event {
+            EventSubEvent::HypeTrain(hype_train) => self.hype_train = Some(hype_train),
+            EventSubEvent::HypeTrainEnded => self.hype_train = None,
+            EventSubEvent::Goal(goal) => self.goal = Some(goal),
+            EventSubEvent::GoalEnded => self.goal = None,
+            EventSubEvent::Reset => *self = Self::default(),
+            EventSubEvent::Redemption(_)
+            | EventSubEvent::Poll(_)
+            | EventSubEvent::Prediction(_)
+            | EventSubEvent::Moderation(_) => {}
+        }
+    }
+
+    /// A single line describing the Hype Train and goal, if there are any.
+    pub fn summary(&self) -> Option<String> {
+        let hype_train = self.hype_train.as_ref().map(|h| {
+            format!(
+                "Hype Train level {}: {}/{}",
+                h.level,
+                h.progress.min(h.goal),
+                h.goal
+            )
+        });
+
+        let goal = self.goal.as_ref().map(|g| {
+            let name = if g.description.is_empty() {
+                format!("{} goal", capitalize_first_char(&g.kind.replace('_', " ")))
+            } else {
+                g.description.clone()
+            };
+
+            format!("{name}: {}/{}", g.current_amount, g.target_amount)
+        });
+
+        match (hype_train, goal) {
+            (Some(h), Some(g)) => Some(format!("{h} | {g}")),
+            (h, g) => h.or(g),
+        }
+    }
+}
+
+/// A random value, which for web sockets only has to be unpredictable enough to avoid cache poisoning.
+fn random_bytes<const N: usize>() -> [u8; N] {
+    let mut bytes = [0; N];
+
+    for chunk in bytes.chunks_mut(8) {
+        let random = RandomState::new().build_hasher().finish().to_be_bytes();
+        chunk.copy_from_slice(&random[..chunk.len()]);
+    }
+
+    bytes
+}
+
+/// A frame sent from a client to a server, which has to be masked.
+fn encode_frame(opcode: u8, payload: &[u8], mask: [u8; 4]) -> Vec<u8> {
+    let mut frame = vec![0x80 | opcode];
+
+    match payload.len() {
+        len @ 0..=125 => frame.push(0x80 | len as u8),
+        len @ 126..=0xFFFF => {
+            frame.push(0x80 | LENGTH_U16);
+            frame.extend((len as u16).to_be_bytes());
+        }
+        len => {
+            frame.push(0x80 | LENGTH_U64);
+            frame.extend((len as u64).to_be_bytes());
+        }
+    }
+
+    frame.extend(mask);
+    frame.extend(payload.iter().enumerate().map(|(i, b)| b ^ mask[i % 4]));
+
+    frame
+}
+
+struct Frame {
+    fin: bool,
+    opcode: u8,
+    payload: Vec<u8>,
+}
+
+// https://datatracker.ietf.org/doc/html/rfc6455#section-5.2
+async fn read_frame<R: AsyncRead + Unpin>(reader: &mut R) -> Result<Frame> {
+    let mut header = [0; 2];
+    reader.read_exact(&mut header).await?;
+
+    let len = match header[1] & 0x7F {
+        LENGTH_U16 => {
+            let mut len = [0; 2];
+            reader.read_exact(&mut len).await?;
+            u64::from(u16::from_be_bytes(len))
+        }
+        LENGTH_U64 => {
+            let mut len = [0; 8];
+            reader.read_exact(&mut len).await?;
+            u64::from_be_bytes(len)
+        }
+        len => u64::from(len),
+    };
+
+    if len > MAX_FRAME_SIZE {
+        bail!("WebSocket frame of {len} bytes is too big");
+    }
+
+    let mask = if header[1] & 0x80 == 0 {
+        None
+    } else {
+        let mut mask = [0; 4];
+        reader.read_exact(&mut mask).await?;
+        Some(mask)
+    };
+
+    let mut payload = vec![0; len as usize];
+    reader.read_exact(&mut payload).await?;
+
+    if let Some(mask) = mask {
+        for (i, b) in payload.iter_mut().enumerate() {
+            *b ^= mask[i % 4];
+        }
+    }
+
+    Ok(Frame {
+        fin: header[0] & 0x80 != 0,
+        opcode: header[0] & 0x0F,
+        payload,
+    })
+}
+
+/// Just enough of a WebSocket client to receive text messages.
+struct WebSocket {
+    stream: Upgraded,
+}
+
+impl WebSocket {
+    async fn connect(url: &str) -> Result<Self> {
+        let url = url.replacen("wss://", "https://", 1);
+
+        // Upgrading a connection to a WebSocket is only possible over HTTP/1.1.
+        let response = http_client()
+            .http1_only()
+            .build()?
+            .get(url)
+            .header(CONNECTION, "Upgrade")
+            .header(UPGRADE, "websocket")
+            .header(SEC_WEBSOCKET_VERSION, "13")
+            .header(SEC_WEBSOCKET_KEY, STANDARD.encode(random_bytes::<16>()))
+            .send()
+            .await?;
+
+        if response.status() != StatusCode::SWITCHING_PROTOCOLS {
+            bail!("WebSocket connection refused with {}", response.status());
+        }
+
+        Ok(Self {
+            stream: response.upgrade().await?,
+        })
+    }
+
+    async fn send(&mut self, opcode: u8, payload: &[u8]) -> Result<()> {
+        self.stream
+            .write_all(&encode_frame(opcode, payload, random_bytes()))
+            .await?;
+
+        Ok(())
+    }
+
+    /// The next text message, or none if the server closed the connection.
+    async fn next_text(&mut self) -> Result<Option<String>> {
+        let mut message = vec![];
+
+        loop {
+            let frame = read_frame(&mut self.stream).await?;
+
+            match frame.opcode {
+                OPCODE_TEXT | OPCODE_CONTINUATION => {
+                    message.extend(frame.payload);
+
+                    if frame.fin {
+                        return Ok(Some(String::from_utf8(message)?));
+                    }
+                }
+                OPCODE_PING => self.send(OPCODE_PONG, &frame.payload).await?,
+                OPCODE_CLOSE => {
+                    self.send(OPCODE_CLOSE, &frame.payload).await.ok();
+
+                    return Ok(None);
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Subscribe the session to every event that is shown, returning how many subscriptions were created.
+///
+/// Moderation actions are only subscribed to if the ID of the user, who has to be a moderator, is known.
+async fn subscribe(
+    client: &Client,
+    session_id: &str,
+    broadcaster_id: i32,
+    moderator_id: Option<i32>,
+) -> usize {
+    let mut subscribed = 0;
+
+    let broadcaster_condition = json!({ "broadcaster_user_id": broadcaster_id.to_string() });
+
+    let subscriptions = SUBSCRIPTION_TYPES
+        .into_iter()
+        .map(|subscription_type| (subscription_type, broadcaster_condition.clone()))
+        .chain(moderator_id.map(|moderator_id| {
+            (
+                MODERATE_SUBSCRIPTION_TYPE,
+                json!({
+                    "broadcaster_user_id": broadcaster_id.to_string(),
+                    "moderator_user_id": moderator_id.to_string(),
+                }),
+            )
+        }));
+
+    for (subscription_type, condition) in subscriptions {
+        let response = client
+            .post(SUBSCRIPTIONS_URL)
+            .json(&json!({
+                "type": subscription_type,
+                "version": "1",
+                "condition": condition,
+                "transport": { "method": "websocket", "session_id": session_id },
+            }))
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status);
+
+        match response {
+            Ok(_) => subscribed += 1,
+            Err(err) => debug!("Unable to subscribe to {subscription_type}: {err}"),
+        }
+    }
+
+    subscribed
+}
+
+/// How a session of receiving events came to an end.
+enum SessionEnd {
+    /// Twitch asked to continue at another URL, where the subscriptions carry over.
+    Reconnect(String),
+    /// The connection was lost, so a new session has to subscribe again.
+    Lost(Report),
+    /// Nothing is listening for the events anymore.
+    Stopped,
+}
+
+/// Receive the events of one session, subscribing to them when it is a new one.
+async fn receive_session(
+    url: &str,
+    client: &Client,
+    broadcaster_id: i32,
+    moderator_id: Option<i32>,
+    subscribed: &mut bool,
+    tx: &Sender<TwitchToTerminalAction>,
+) -> Result<SessionEnd> {
+    let mut socket = match WebSocket::connect(url).await {
+        Ok(socket) => socket,
+        Err(err) => return Ok(SessionEnd::Lost(err)),
+    };
+
+    loop {
+        let text = match timeout(KEEPALIVE_TIMEOUT, socket.next_text()).await {
+            Ok(Ok(Some(text))) => text,
+            Ok(Ok(None)) => return Ok(SessionEnd::Lost(eyre!("Twitch closed the connection"))),
+            Ok(Err(err)) => return Ok(SessionEnd::Lost(err)),
+            Err(_) => return Ok(SessionEnd::Lost(eyre!("No keepalive from Twitch in time"))),
+        };
+
+        // A single message that cannot be read is not worth losing the others over.
+        let message = match parse_message(&text) {
+            Ok(message) => message,
+            Err(err) => {
+                debug!("Skipping EventSub message that could not be read: {err}");
+
+                continue;
+            }
+        };
+
+        match message {
+            // Subscriptions carry over to the session of a reconnect.
+            EventSubMessage::Welcome { session_id } if !*subscribed => {
+                if subscribe(client, &session_id, broadcaster_id, moderator_id).await == 0 {
+                    bail!("No events could be subscribed to, the token may be missing scopes");
+                }
+
+                *subscribed = true;
+            }
+            EventSubMessage::Reconnect { url } => return Ok(SessionEnd::Reconnect(url)),
+            EventSubMessage::Notification(event) => {
+                let Ok(()) = tx.send(TwitchToTerminalAction::EventSub(event)).await else {
+                    return Ok(SessionEnd::Stopped);
+                };
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Receive events until nothing listens for them anymore, connecting again with a backoff
+/// whenever the connection is lost, like chat does.
+async fn receive_events(
+    twitch_config: TwitchConfig,
+    tx: Sender<TwitchToTerminalAction>,
+) -> Result<()> {
+    let client = get_twitch_client(twitch_config.token.clone()).await?;
+    let broadcaster_id = get_channel_id(&client, &twitch_config.channel).await?;
+    let moderator_id = get_token_user_id(twitch_config.token.as_ref()).await.ok();
+
+    let mut url = EVENTSUB_URL.to_string();
+    let mut subscribed = false;
+    let mut backoff = RECONNECT_BACKOFF;
+
+    loop {
+        match receive_session(
+            &url,
+            &client,
+            broadcaster_id,
+            moderator_id,
+            &mut subscribed,
+            &tx,
+        )
+        .await?
+        {
+            SessionEnd::Reconnect(reconnect_url) => url = reconnect_url,
+            SessionEnd::Lost(err) => {
+                // A session that got going means the connection was fine up until now.
+                if subscribed {
+                    backoff = RECONNECT_BACKOFF;
+                }
+
+                debug!(
+                    "Lost the connection to EventSub, connecting again in {}s: {err}",
+                    backoff.as_secs()
+                );
+
+                url = EVENTSUB_URL.to_string();
+                subscribed = false;
+
+                sleep(backoff).await;
+                backoff = min(backoff * 2, MAX_RECONNECT_BACKOFF);
+            }
+            SessionEnd::Stopped => return Ok(()),
+        }
+    }
+}
+
+/// Receive Hype Train and goal events of the current channel, if enabled.
+///
+/// Nothing is shown if the events cannot be received.
+pub fn spawn_eventsub(
+    twitch_config: &TwitchConfig,
+    tx: Sender<TwitchToTerminalAction>,
+) -> Option<JoinHandle<()>> {
+    if !twitch_config.eventsub || twitch_config.channel.is_empty() {
+        return None;
+    }
+
+    let twitch_config = twitch_config.clone();
+
+    Some(tokio::spawn(async move {
+        if let Err(err) = receive_events(twitch_config, tx).await {
+            warn!("Stopped receiving Hype Train and goal events: {err}");
+        }
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HYPE_TRAIN_PROGRESS: &str = r#"{
+        "metadata": {
+            "message_id": "befa7b53-d79d-478f-86b9-120f112b044e",
+            "message_type": "notification",
+            "message_timestamp": "2022-11-16T10:11:12.464757833Z",
+            "subscription_type": "channel.hype_train.progress",
+            "subscription_version": "1"
+        },
+        "payload": {
+            "subscription": {
+                "id": "f1c2a387-161a-49f9-a165-0f21d7a4e1c4",
+                "type": "channel.hype_train.progress",
+                "version": "1",
+                "status": "enabled",
+                "cost": 0,
+                "condition": { "broadcaster_user_id": "1337" },
+                "transport": { "method": "websocket", "session_id": "AQoQexAWVYKSTIu4ec_2VAxyuhAB" },
+                "created_at": "2022-11-16T10:11:12.464757833Z"
+            },
+            "event": {
+                "id": "1b0AsbInCHZW2SQFQkCzqN07Ib2",
+                "broadcaster_user_id": "1337",
+                "broadcaster_user_login": "cool_user",
+                "broadcaster_user_name": "Cool_User",
+                "level": 2,
+                "total": 700,
+                "progress": 200,
+                "goal": 1000,
+                "top_contributions": [
+                    { "user_id": "123", "user_login": "pogchamp", "user_name": "PogChamp", "type": "bits", "total": 50 }
+                ],
+                "last_contribution": { "user_id": "123", "user_login": "pogchamp", "user_name": "PogChamp", "type": "bits", "total": 50 },
+                "started_at": "2020-07-15T17:16:03.17106713Z",
+                "expires_at": "2020-07-15T17:16:11.17106713Z"
+            }
+        }
+    }"#;
+
+    #[test]
+    fn test_parse_hype_train_progress() {
+        assert_eq!(
+            parse_message(HYPE_TRAIN_PROGRESS).unwrap(),
+            EventSubMessage::Notification(EventSubEvent::HypeTrain(HypeTrain {
+                level: 2,
+                total: 700,
+                progress: 200,
+                goal: 1000,
+            }))
+        );
+    }
+
+    #[test]
+    fn test_parse_session_messages() {
+        let welcome = r#"{
+            "metadata": { "message_id": "96a3f3b5", "message_type": "session_welcome", "message_timestamp": "2023-07-19T14:56:51.634234626Z" },
+            "payload": { "session": { "id": "AQoQILE98gtqShGmLD7AM6yJThAB", "status": "connected", "connected_at": "2023-07-19T14:56:51.616329898Z", "keepalive_timeout_seconds": 10, "reconnect_url": null } }
+        }"#;
+
+        assert_eq!(
+            parse_message(welcome).unwrap(),
+            EventSubMessage::Welcome {
+                session_id: "AQoQILE98gtqShGmLD7AM6yJThAB".to_string()
+            }
+        );
+
+        let keepalive = r#"{
+            "metadata": { "message_id": "84c1e79a", "message_type": "session_keepalive", "message_timestamp": "2023-07-19T10:11:12.634234626Z" },
+            "payload": {}
+        }"#;
+
+        assert_eq!(
+            parse_message(keepalive).unwrap(),
+            EventSubMessage::Keepalive
+        );
+    }
+
+    #[test]
+    fn test_parse_goal_progress() {
+        let goal = r#"{
+            "metadata": { "message_id": "abc", "message_type": "notification", "message_timestamp": "2023-07-19T10:11:12Z", "subscription_type": "channel.goal.progress", "subscription_version": "1" },
+            "payload": { "event": { "id": "12345-abc", "broadcaster_user_id": "141981764", "type": "new_subscription", "description": "", "current_amount": 120, "target_amount": 220, "started_at": "2021-07-15T17:16:03.17106713Z" } }
+        }"#;
+
+        let EventSubMessage::Notification(event) = parse_message(goal).unwrap() else {
+            panic!("Goal notification was not parsed");
+        };
+
+        let mut status = LiveStatus::default();
+        status.apply(event);
+
+        assert_eq!(
+            status.summary().as_deref(),
+            Some("New subscription goal: 120/220")
+        );
+    }
+
+    #[test]
+    fn test_parse_custom_reward_redemption() {
+        let redemption = r#"{
+            "metadata": { "message_id": "abc", "message_type": "notification", "message_timestamp": "2023-07-19T10:11:12Z", "subscription_type": "channel.channel_points_custom_reward_redemption.add", "subscription_version": "1" },
+            "payload": { "event": {
+                "id": "17fa2df1-ad76-4804-bfa5-a40ef63efe63",
+                "broadcaster_user_id": "1337",
+                "broadcaster_user_login": "cool_user",
+                "broadcaster_user_name": "Cool_User",
+                "user_id": "9001",
+                "user_login": "cooler_user",
+                "user_name": "Cooler_User",
+                "user_input": "pogchamp",
+                "status": "unfulfilled",
+                "reward": { "id": "92af127c-7326-4483-a52b-b0da0be61c01", "title": "Hydrate", "cost": 100, "prompt": "Make the streamer drink water" },
+                "redeemed_at": "2020-07-15T17:16:03.17106713Z"
+            } }
+        }"#;
+
+        let EventSubMessage::Notification(EventSubEvent::Redemption(redemption)) =
+            parse_message(redemption).unwrap()
+        else {
+            panic!("Redemption was not parsed");
+        };
+
+        assert_eq!(
+            redemption,
+            Redemption {
+                user_id: "9001".to_string(),
+                user_name: "Cooler_User".to_string(),
+                reward: "Hydrate".to_string(),
+                input: Some("pogchamp".to_string()),
+                highlight: false,
+            }
+        );
+        assert_eq!(
+            redemption.summary(),
+            "Cooler_User redeemed Hydrate: pogchamp"
+        );
+    }
+
+    #[test]
+    fn test_parse_highlighted_message_redemption() {
+        let redemption = r#"{
+            "metadata": { "message_id": "abc", "message_type": "notification", "message_timestamp": "2024-02-23T21:12:33Z", "subscription_type": "channel.channel_points_automatic_reward_redemption.add", "subscription_version": "1" },
+            "payload": { "event": {
+                "broadcaster_user_id": "12826",
+                "broadcaster_user_name": "Twitch",
+                "broadcaster_user_login": "twitch",
+                "user_id": "141981764",
+                "user_name": "TwitchDev",
+                "user_login": "twitchdev",
+                "id": "f024099a-e0aa-4339-a4ef-c2d5a9b2e3c5",
+                "reward": { "type": "send_highlighted_message", "cost": 100, "unlocked_emote": null },
+                "message": { "text": "Hello world! VoHiYo", "emotes": [{ "id": "81274", "begin": 13, "end": 18 }] },
+                "user_input": "Hello world! VoHiYo",
+                "redeemed_at": "2024-02-23T21:14:34.260398045Z"
+            } }
+        }"#;
+
+        let EventSubMessage::Notification(EventSubEvent::Redemption(redemption)) =
+            parse_message(redemption).unwrap()
+        else {
+            panic!("Redemption was not parsed");
+        };
+
+        assert!(redemption.highlight);
+        assert_eq!(redemption.input.as_deref(), Some("Hello world! VoHiYo"));
+        assert_eq!(
+            redemption.summary(),
+            "TwitchDev redeemed Send highlighted message"
+        );
+    }
+
+    #[test]
+    fn test_parse_poll_progress() {
+        let poll = r#"{
+            "metadata": { "message_id": "abc", "message_type": "notification", "message_timestamp": "2023-07-19T10:11:12Z", "subscription_type": "channel.poll.progress", "subscription_version": "1" },
+            "payload": { "event": {
+                "id": "1243456",
+                "broadcaster_user_id": "1337",
+                "broadcaster_user_login": "cool_user",
+                "broadcaster_user_name": "Cool_User",
+                "title": "Aren't shoes just really hard socks?",
+                "choices": [
+                    { "id": "123", "title": "Yeah!", "bits_votes": 5, "channel_points_votes": 7, "votes": 12 },
+                    { "id": "124", "title": "No!", "bits_votes": 10, "channel_points_votes": 4, "votes": 14 },
+                    { "id": "125", "title": "Maybe!", "bits_votes": 0, "channel_points_votes": 7, "votes": 7 }
+                ],
+                "bits_voting": { "is_enabled": true, "amount_per_vote": 10 },
+                "channel_points_voting": { "is_enabled": true, "amount_per_vote": 10 },
+                "started_at": "2020-07-15T17:16:03.17106713Z",
+                "ends_at": "2020-07-15T17:16:08.17106713Z"
+            } }
+        }"#;
+
+        let EventSubMessage::Notification(EventSubEvent::Poll(poll)) = parse_message(poll).unwrap()
+        else {
+            panic!("Poll was not parsed");
+        };
+
+        assert_eq!(poll.title, "Aren't shoes just really hard socks?");
+        assert!(!poll.ended);
+        assert_eq!(
+            poll.choices.iter().map(|c| c.votes).collect::<Vec<_>>(),
+            vec![12, 14, 7]
+        );
+    }
+
+    #[test]
+    fn test_parse_prediction_end() {
+        let prediction = r#"{
+            "metadata": { "message_id": "abc", "message_type": "notification", "message_timestamp": "2023-07-19T10:11:12Z", "subscription_type": "channel.prediction.end", "subscription_version": "1" },
+            "payload": { "event": {
+                "id": "1243456",
+                "broadcaster_user_id": "1337",
+                "broadcaster_user_login": "cool_user",
+                "broadcaster_user_name": "Cool_User",
+                "title": "Aren't shoes just really hard socks?",
+                "winning_outcome_id": "12345",
+                "outcomes": [
+                    { "id": "12345", "title": "Yeah!", "color": "blue", "users": 2, "channel_points": 15000, "top_predictors": [] },
+                    { "id": "22435", "title": "No!", "color": "pink", "users": 2, "channel_points": 200, "top_predictors": [] }
+                ],
+                "status": "resolved",
+                "started_at": "2020-07-15T17:16:03.17106713Z",
+                "ended_at": "2020-07-15T17:16:11.17106713Z"
+            } }
+        }"#;
+
+        let EventSubMessage::Notification(EventSubEvent::Prediction(prediction)) =
+            parse_message(prediction).unwrap()
+        else {
+            panic!("Prediction was not parsed");
+        };
+
+        assert_eq!(
+            prediction.state,
+            PredictionState::Resolved("12345".to_string())
+        );
+        assert_eq!(prediction.outcomes[0].channel_points, 15000);
+        assert_eq!(prediction.outcomes[1].title, "No!");
+    }
+
+    #[test]
+    fn test_parse_moderate_ban() {
+        let ban = r#"{
+            "metadata": { "message_id": "abc", "message_type": "notification", "message_timestamp": "2023-07-19T10:11:12Z", "subscription_type": "channel.moderate", "subscription_version": "1" },
+            "payload": { "event": {
+                "broadcaster_user_id": "1337",
+                "broadcaster_user_login": "cool_user",
+                "broadcaster_user_name": "Cool_User",
+                "moderator_user_id": "424596340",
+                "moderator_user_login": "quotrok",
+                "moderator_user_name": "quotrok",
+                "action": "ban",
+                "followers": null, "slow": null, "vip": null, "unvip": null, "mod": null, "unmod": null,
+                "ban": { "user_id": "1234", "user_login": "troll", "user_name": "Troll", "reason": "no reason" },
+                "unban": null, "timeout": null, "untimeout": null, "raid": null, "unraid": null,
+                "delete": null, "automod_terms": null, "unban_request": null
+            } }
+        }"#;
+
+        let EventSubMessage::Notification(EventSubEvent::Moderation(entry)) =
+            parse_message(ban).unwrap()
+        else {
+            panic!("Moderation action was not parsed");
+        };
+
+        assert_eq!(entry.moderator.as_deref(), Some("quotrok"));
+        assert_eq!(
+            entry.action,
+            ModAction::Ban {
+                user: "troll".to_string(),
+                reason: Some("no reason".to_string()),
+            }
+        );
+    }
+
+    #[test]
+    fn test_percentages() {
+        assert_eq!(percentages(&[12, 14, 7]), vec![36, 43, 21]);
+        assert_eq!(percentages(&[15000, 200]), vec![99, 1]);
+        assert_eq!(percentages(&[1, 1, 1]), vec![34, 33, 33]);
+        assert_eq!(percentages(&[5, 0]), vec![100, 0]);
+        assert_eq!(percentages(&[0, 0]), vec![0, 0]);
+        assert_eq!(percentages(&[]), Vec::<u64>::new());
+    }
+
+    #[test]
+    fn test_live_status_summary() {
+        let mut status = LiveStatus::default();
+        assert_eq!(status.summary(), None);
+
+        status.apply(EventSubEvent::HypeTrain(HypeTrain {
+            level: 3,
+            total: 2500,
+            progress: 400,
+            goal: 1800,
+        }));
+        status.apply(EventSubEvent::Goal(Goal {
+            kind: "follower".to_string(),
+            description: "Road to 1k".to_string(),
+            current_amount: 980,
+            target_amount: 1000,
+        }));
+
+        assert_eq!(
+            status.summary().as_deref(),
+            Some("Hype Train level 3: 400/1800 | Road to 1k: 980/1000")
+        );
+
+        status.apply(EventSubEvent::HypeTrainEnded);
+        assert_eq!(status.summary().as_deref(), Some("Road to 1k: 980/1000"));
+
+        status.apply(EventSubEvent::Reset);
+        assert_eq!(status.summary(), None);
+    }
+
+    #[tokio::test]
+    async fn test_frame_round_trip() {
+        let payload = "a".repeat(300);
+        let encoded = encode_frame(OPCODE_TEXT, payload.as_bytes(), [1, 2, 3, 4]);
+
+        let frame = read_frame(&mut encoded.as_slice()).await.unwrap();
+
+        assert!(frame.fin);
+        assert_eq!(frame.opcode, OPCODE_TEXT);
+        assert_eq!(frame.payload, payload.as_bytes());
+    }
+}
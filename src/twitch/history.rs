@@ -0,0 +1,147 @@
+use std::time::Duration;
+
+use chrono::{Local, TimeZone};
+use color_eyre::Result;
+use irc::proto::Command;
+use log::warn;
+use regex::Regex;
+use serde::Deserialize;
+
+use crate::{
+    handlers::{config::FrontendConfig, data::MessageData},
+    twitch::{connection::parse_line, message_tags, privmsg_data},
+    utils::proxy::http_client,
+};
+
+const RECENT_MESSAGES_URL: &str = "https://recent-messages.robotty.de/api/v2/recent-messages";
+
+#[derive(Deserialize, Debug, Clone, Default)]
+struct RecentMessages {
+    messages: Vec<String>,
+}
+
+/// Turn a response of the recent-messages API into chat messages, from oldest to newest.
+///
+/// Only chat messages are kept, and ones that were deleted by moderators are skipped.
+fn parse_recent_messages(
+    payload: &str,
+    frontend_config: &FrontendConfig,
+    censor_terms: &[Regex],
+) -> Result<Vec<MessageData>> {
+    let recent_messages = serde_json::from_str::<RecentMessages>(payload)?;
+
+    Ok(recent_messages
+        .messages
+        .iter()
+        .filter_map(|raw| parse_line(raw))
+        .filter_map(|message| {
+            let Command::PRIVMSG(_, ref msg) = message.command else {
+                return None;
+            };
+
+            let tags = message_tags(&message);
+
+            if tags.get("rm-deleted").is_some_and(|&d| d == "1") {
+                return None;
+            }
+
+            let mut message_data =
+                privmsg_data(&message, msg, &tags, frontend_config, censor_terms);
+
+            if let Some(time_sent) = tags
+                .get("tmi-sent-ts")
+                .and_then(|ts| ts.parse::<i64>().ok())
+                .and_then(|ts| Local.timestamp_millis_opt(ts).single())
+            {
+                message_data.time_sent = time_sent;
+            }
+
+            message_data.history = true;
+
+            Some(message_data)
+        })
+        .collect())
+}
+
+/// Fetch the latest messages of a channel, since joining the IRC channel does not replay any.
+///
+/// If the service is unavailable, no messages are returned.
+pub async fn get_recent_messages(
+    channel: &str,
+    limit: usize,
+    frontend_config: &FrontendConfig,
+    censor_terms: &[Regex],
+) -> Vec<MessageData> {
+    let response = async {
+        http_client()
+            .timeout(Duration::from_secs(5))
+            .build()?
+            .get(format!("{RECENT_MESSAGES_URL}/{channel}?limit={limit}"))
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await
+    }
+    .await;
+
+    match response
+        .map_err(Into::into)
+        .and_then(|payload| parse_recent_messages(&payload, frontend_config, censor_terms))
+    {
+        Ok(messages) => messages,
+        Err(err) => {
+            warn!("Unable to get recent messages of channel {channel}: {err}");
+
+            vec![]
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::emotes::EmoteProvider;
+
+    const SAMPLE_PAYLOAD: &str = r#"{
+        "messages": [
+            "@badge-info=;badges=;display-name=Foo;emotes=25:3-7;id=a1;rm-received-ts=1700000000100;tmi-sent-ts=1700000000000;user-id=1 :foo!foo@foo.tmi.twitch.tv PRIVMSG #xithrius :hi Kappa",
+            "@display-name=Bar;id=b2;rm-deleted=1;tmi-sent-ts=1700000001000;user-id=2 :bar!bar@bar.tmi.twitch.tv PRIVMSG #xithrius :deleted message",
+            "@room-id=3;tmi-sent-ts=1700000002000 :tmi.twitch.tv CLEARCHAT #xithrius",
+            "",
+            ":tmi.twitch.tv :",
+            "@display-name=Baz;id=c3;tmi-sent-ts=1700000003000;user-id=4 :baz!baz@baz.tmi.twitch.tv PRIVMSG #xithrius :\u0001ACTION waves\u0001"
+        ],
+        "error": null,
+        "error_code": null
+    }"#;
+
+    #[test]
+    fn test_parse_recent_messages() {
+        let messages =
+            parse_recent_messages(SAMPLE_PAYLOAD, &FrontendConfig::default(), &[]).unwrap();
+
+        assert_eq!(messages.len(), 2);
+
+        let first = &messages[0];
+        assert_eq!(first.author, "Foo");
+        assert_eq!(first.payload, "hi Kappa");
+        assert_eq!(first.message_id.as_deref(), Some("a1"));
+        assert_eq!(first.user_id.as_deref(), Some("1"));
+        assert_eq!(first.emote_ranges, vec![(3..8, EmoteProvider::Twitch)]);
+        assert_eq!(first.time_sent.timestamp_millis(), 1_700_000_000_000);
+        assert!(first.history);
+        assert!(!first.highlight);
+
+        let second = &messages[1];
+        assert_eq!(second.author, "Baz");
+        assert_eq!(second.payload, "waves");
+        assert!(second.history);
+        assert!(second.highlight);
+    }
+
+    #[test]
+    fn test_parse_recent_messages_invalid_payload() {
+        assert!(parse_recent_messages("<html>", &FrontendConfig::default(), &[]).is_err());
+    }
+}
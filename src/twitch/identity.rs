@@ -0,0 +1,207 @@
+use std::{collections::HashMap, str::FromStr};
+
+use tui::style::Color;
+
+use crate::{
+    handlers::{config::UsernameDisplay, data::MessageData},
+    twitch::{badges::Role, choose_username},
+};
+
+/// Who the user is, as Twitch tells it with GLOBALUSERSTATE once the login is accepted.
+///
+/// <https://dev.twitch.tv/docs/irc/tags/#globaluserstate-tags>
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SelfIdentity {
+    pub user_id: String,
+    pub display_name: Option<String>,
+    /// The color that the user picked for their name, if they picked one.
+    pub color: Option<Color>,
+}
+
+impl SelfIdentity {
+    /// The identity in the tags of a GLOBALUSERSTATE, where there is none without a user id.
+    pub fn from_tags(tags: &HashMap<&str, &str>) -> Option<Self> {
+        let user_id = tags.get("user-id").filter(|id| !id.is_empty())?;
+
+        Some(Self {
+            user_id: (*user_id).to_string(),
+            display_name: tags
+                .get("display-name")
+                .filter(|name| !name.is_empty())
+                .map(|&name| name.to_string()),
+            color: tags
+                .get("color")
+                .and_then(|&color| Color::from_str(color).ok()),
+        })
+    }
+
+    /// Show a message that the user sent as theirs, with the name and id that Twitch knows them by.
+    pub fn apply(
+        &self,
+        message: &mut MessageData,
+        login: &str,
+        username_display: &UsernameDisplay,
+    ) {
+        message.author = choose_username(login, self.display_name.as_deref(), username_display);
+        message.login = Some(login.to_string());
+        message.user_id = Some(self.user_id.clone());
+    }
+}
+
+/// What the user is in a channel, as Twitch tells it with USERSTATE after joining it and
+/// after each message that the user sends there.
+///
+/// <https://dev.twitch.tv/docs/irc/tags/#userstate-tags>
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChannelUserState {
+    /// The channel, without the `#`.
+    pub channel: String,
+    pub role: Option<Role>,
+    /// The ids of the emote sets that the user can use in the channel.
+    pub emote_sets: Vec<String>,
+}
+
+impl ChannelUserState {
+    /// The state in the tags of a USERSTATE for an IRC channel such as `#xithrius`.
+    pub fn from_tags(channel: &str, tags: &HashMap<&str, &str>) -> Self {
+        let badge_role = tags
+            .get("badges")
+            .and_then(|badges| Role::from_badges(badges));
+
+        // Moderators are also told apart by a tag of their own.
+        let mod_role = (tags.get("mod") == Some(&"1")).then_some(Role::Moderator);
+
+        Self {
+            channel: channel.trim_start_matches('#').to_lowercase(),
+            role: badge_role.max(mod_role),
+            emote_sets: tags
+                .get("emote-sets")
+                .map(|sets| {
+                    sets.split(',')
+                        .filter(|set| !set.is_empty())
+                        .map(ToString::to_string)
+                        .collect()
+                })
+                .unwrap_or_default(),
+        }
+    }
+
+    /// If the user can act as a moderator in the channel.
+    pub fn is_moderator(&self) -> bool {
+        self.role >= Some(Role::Moderator)
+    }
+}
+
+/// The latest state of the user in each channel that Twitch told it for.
+#[derive(Debug, Clone, Default)]
+pub struct UserStates {
+    states: HashMap<String, ChannelUserState>,
+}
+
+impl UserStates {
+    pub fn update(&mut self, state: ChannelUserState) {
+        self.states.insert(state.channel.clone(), state);
+    }
+
+    pub fn get(&self, channel: &str) -> Option<&ChannelUserState> {
+        self.states.get(&channel.to_lowercase())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use irc::proto::Message;
+
+    use super::*;
+    use crate::twitch::message_tags;
+
+    #[test]
+    fn test_identity_from_globaluserstate() {
+        let message: Message = "@badge-info=;badges=turbo/1;color=#0D4200;display-name=Xithrius;emote-sets=0,33,50,237;user-id=12345678;user-type= :tmi.twitch.tv GLOBALUSERSTATE"
+            .parse()
+            .unwrap();
+
+        let identity = SelfIdentity::from_tags(&message_tags(&message)).unwrap();
+
+        assert_eq!(identity.user_id, "12345678");
+        assert_eq!(identity.display_name.as_deref(), Some("Xithrius"));
+        assert_eq!(identity.color, Some(Color::Rgb(0x0D, 0x42, 0x00)));
+    }
+
+    #[test]
+    fn test_identity_without_color_or_id() {
+        let tags = HashMap::from([("user-id", "1"), ("color", ""), ("display-name", "")]);
+
+        let identity = SelfIdentity::from_tags(&tags).unwrap();
+
+        assert_eq!(identity.display_name, None);
+        assert_eq!(identity.color, None);
+
+        assert!(SelfIdentity::from_tags(&HashMap::from([("display-name", "Xithrius")])).is_none());
+    }
+
+    #[test]
+    fn test_apply_identity() {
+        let identity = SelfIdentity {
+            user_id: "1".to_string(),
+            display_name: Some("Xithrius".to_string()),
+            color: None,
+        };
+
+        let mut message = MessageData::new(
+            "xithrius".to_string(),
+            None,
+            false,
+            "hi".to_string(),
+            None,
+            false,
+        );
+
+        identity.apply(&mut message, "xithrius", &UsernameDisplay::Display);
+
+        assert_eq!(message.author, "Xithrius");
+        assert_eq!(message.user_id.as_deref(), Some("1"));
+        assert_eq!(message.login.as_deref(), Some("xithrius"));
+    }
+
+    #[test]
+    fn test_user_state_from_userstate() {
+        let message: Message = "@badge-info=;badges=moderator/1;color=;display-name=Xithrius;emote-sets=0,33,50,237,793;mod=1;subscriber=0;user-type=mod :tmi.twitch.tv USERSTATE #Xithrius"
+            .parse()
+            .unwrap();
+
+        let state = ChannelUserState::from_tags("#Xithrius", &message_tags(&message));
+
+        assert_eq!(state.channel, "xithrius");
+        assert_eq!(state.role, Some(Role::Moderator));
+        assert!(state.is_moderator());
+        assert_eq!(state.emote_sets, vec!["0", "33", "50", "237", "793"]);
+
+        let viewer = ChannelUserState::from_tags(
+            "#other",
+            &HashMap::from([("badges", "subscriber/3"), ("mod", "0"), ("emote-sets", "")]),
+        );
+
+        assert_eq!(viewer.role, Some(Role::Subscriber));
+        assert!(!viewer.is_moderator());
+        assert!(viewer.emote_sets.is_empty());
+    }
+
+    #[test]
+    fn test_user_states_update() {
+        let mut states = UserStates::default();
+
+        states.update(ChannelUserState::from_tags(
+            "#xithrius",
+            &HashMap::from([("mod", "0")]),
+        ));
+        assert!(!states.get("Xithrius").unwrap().is_moderator());
+
+        states.update(ChannelUserState::from_tags(
+            "#xithrius",
+            &HashMap::from([("mod", "1")]),
+        ));
+        assert!(states.get("xithrius").unwrap().is_moderator());
+        assert!(states.get("other").is_none());
+    }
+}
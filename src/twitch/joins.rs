@@ -0,0 +1,187 @@
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+/// How many channels Twitch lets a normal account join within [`JOIN_PERIOD`].
+///
+/// <https://dev.twitch.tv/docs/irc/#rate-limits>
+pub const JOIN_LIMIT: usize = 20;
+
+/// The period over which [`JOIN_LIMIT`] counts the joins.
+pub const JOIN_PERIOD: Duration = Duration::from_secs(10);
+
+/// How often the scheduler is asked for the next join while some are waiting.
+pub const JOIN_CHECK_INTERVAL: Duration = Duration::from_millis(100);
+
+/// How many times a channel is tried before giving up on joining it.
+const MAX_JOIN_ATTEMPTS: u32 = 3;
+
+/// A channel waiting to be joined.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PendingJoin {
+    /// Without the leading `#`.
+    pub channel: String,
+    attempts: u32,
+}
+
+/// Joining channels one after the other, spaced out by a delay and within the rate limit of Twitch.
+///
+/// The rate limit is kept with a bucket of [`JOIN_LIMIT`] tokens, where each token that a join
+/// takes comes back [`JOIN_PERIOD`] later. That way there are never more joins within any period
+/// than the limit allows. Joins that cannot be sent yet wait in order.
+#[derive(Debug, Clone)]
+pub struct JoinScheduler {
+    queue: VecDeque<PendingJoin>,
+    delay: Duration,
+    /// When the tokens that are out were taken, oldest first.
+    taken: VecDeque<Instant>,
+}
+
+impl JoinScheduler {
+    /// Scheduling joins at least `delay` apart.
+    pub const fn new(delay: Duration) -> Self {
+        Self {
+            queue: VecDeque::new(),
+            delay,
+            taken: VecDeque::new(),
+        }
+    }
+
+    /// If there are channels waiting to be joined.
+    pub fn pending(&self) -> bool {
+        !self.queue.is_empty()
+    }
+
+    /// Join a channel after the ones that are already waiting, unless it is one of them.
+    pub fn push(&mut self, channel: &str) {
+        if !self.queue.iter().any(|join| join.channel == channel) {
+            self.queue.push_back(PendingJoin {
+                channel: channel.to_string(),
+                attempts: 0,
+            });
+        }
+    }
+
+    /// Forget the channels that are waiting, such as when switching to another one.
+    pub fn clear(&mut self) {
+        self.queue.clear();
+    }
+
+    /// Tokens that are available at `now`.
+    fn tokens(&mut self, now: Instant) -> usize {
+        while self
+            .taken
+            .front()
+            .is_some_and(|&taken| now.duration_since(taken) >= JOIN_PERIOD)
+        {
+            self.taken.pop_front();
+        }
+
+        JOIN_LIMIT - self.taken.len()
+    }
+
+    /// The next channel to join, if one is waiting and may be joined at `now`.
+    pub fn next(&mut self, now: Instant) -> Option<PendingJoin> {
+        if self.queue.is_empty() || self.tokens(now) == 0 {
+            return None;
+        }
+
+        if self
+            .taken
+            .back()
+            .is_some_and(|&last| now.duration_since(last) < self.delay)
+        {
+            return None;
+        }
+
+        let mut join = self.queue.pop_front()?;
+
+        join.attempts += 1;
+        self.taken.push_back(now);
+
+        Some(join)
+    }
+
+    /// Try a join that failed again before any other one, returning if it will be tried again.
+    pub fn failed(&mut self, join: PendingJoin) -> bool {
+        if join.attempts >= MAX_JOIN_ATTEMPTS {
+            return false;
+        }
+
+        self.queue.push_front(join);
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_joins_within_rate_limit() {
+        let mut joins = JoinScheduler::new(Duration::ZERO);
+        let start = Instant::now();
+
+        let channels = (0..50).map(|i| format!("channel{i}")).collect::<Vec<_>>();
+
+        for channel in &channels {
+            joins.push(channel);
+        }
+
+        let mut sent = vec![];
+
+        for step in 0..400 {
+            let now = start + Duration::from_millis(step * 100);
+
+            while let Some(join) = joins.next(now) {
+                sent.push((now, join.channel));
+            }
+        }
+
+        // The queue is drained in order.
+        assert_eq!(
+            sent.iter().map(|(_, channel)| channel).collect::<Vec<_>>(),
+            channels.iter().collect::<Vec<_>>()
+        );
+
+        // No period ever has more joins than the limit.
+        for (i, (time, _)) in sent.iter().enumerate() {
+            let within = sent[i..]
+                .iter()
+                .take_while(|(later, _)| later.duration_since(*time) < JOIN_PERIOD)
+                .count();
+
+            assert!(within <= JOIN_LIMIT);
+        }
+
+        assert_eq!(sent[JOIN_LIMIT].0, start + JOIN_PERIOD);
+    }
+
+    #[test]
+    fn test_joins_delay_and_retry() {
+        let delay = Duration::from_millis(500);
+        let mut joins = JoinScheduler::new(delay);
+        let start = Instant::now();
+
+        joins.push("foo");
+        joins.push("bar");
+        joins.push("foo");
+
+        let foo = joins.next(start).unwrap();
+        assert_eq!(foo.channel, "foo");
+        assert_eq!(joins.next(start + Duration::from_millis(100)), None);
+
+        // A failed join goes before the ones still waiting, until it has been tried enough.
+        assert!(joins.failed(foo));
+        let foo = joins.next(start + delay).unwrap();
+        assert_eq!(foo.channel, "foo");
+        assert!(joins.failed(foo));
+        let foo = joins.next(start + delay * 2).unwrap();
+        assert!(!joins.failed(foo));
+
+        assert_eq!(joins.next(start + delay * 3).unwrap().channel, "bar");
+        assert!(!joins.pending());
+    }
+}
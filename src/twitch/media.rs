@@ -0,0 +1,179 @@
+/// Paths on `twitch.tv` that look like channels, but are not.
+const RESERVED_PATHS: [&str; 8] = [
+    "directory",
+    "downloads",
+    "jobs",
+    "p",
+    "search",
+    "settings",
+    "subscriptions",
+    "videos",
+];
+
+/// Something on Twitch that a message links to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MediaRef {
+    /// The slug of a clip.
+    Clip(String),
+    /// The id of a VOD, and where to start watching it, like `1h2m3s`.
+    Vod {
+        id: String,
+        timestamp: Option<String>,
+    },
+    /// The login of a channel.
+    Channel(String),
+}
+
+impl MediaRef {
+    pub fn url(&self) -> String {
+        match self {
+            Self::Clip(slug) => format!("https://clips.twitch.tv/{slug}"),
+            Self::Vod {
+                id,
+                timestamp: Some(t),
+            } => format!("https://www.twitch.tv/videos/{id}?t={t}"),
+            Self::Vod {
+                id,
+                timestamp: None,
+            } => format!("https://www.twitch.tv/videos/{id}"),
+            Self::Channel(login) => format!("https://www.twitch.tv/{login}"),
+        }
+    }
+}
+
+fn is_slug(s: &str) -> bool {
+    !s.is_empty()
+        && s.chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+fn is_login(s: &str) -> bool {
+    (1..=25).contains(&s.len()) && s.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn parse_link(link: &str) -> Option<MediaRef> {
+    let link = link
+        .strip_prefix("https://")
+        .or_else(|| link.strip_prefix("http://"))
+        .unwrap_or(link);
+
+    let (host, rest) = link.split_once('/')?;
+    let (path, query) = rest.split_once('?').unwrap_or((rest, ""));
+    let mut segments = path.split('/').filter(|s| !s.is_empty());
+
+    match host.to_ascii_lowercase().as_str() {
+        "clips.twitch.tv" => {
+            let slug = segments.next().filter(|s| is_slug(s))?;
+
+            Some(MediaRef::Clip(slug.to_string()))
+        }
+        "twitch.tv" | "www.twitch.tv" | "m.twitch.tv" => {
+            match (segments.next()?, segments.next(), segments.next()) {
+                ("videos", Some(id), None) if id.chars().all(|c| c.is_ascii_digit()) => {
+                    let timestamp = query
+                        .split('&')
+                        .find_map(|param| param.strip_prefix("t="))
+                        .filter(|t| !t.is_empty())
+                        .map(ToString::to_string);
+
+                    Some(MediaRef::Vod {
+                        id: id.to_string(),
+                        timestamp,
+                    })
+                }
+                (channel, Some("clip"), Some(slug)) if is_login(channel) && is_slug(slug) => {
+                    Some(MediaRef::Clip(slug.to_string()))
+                }
+                (channel, None, None)
+                    if is_login(channel)
+                        && !RESERVED_PATHS.contains(&channel.to_ascii_lowercase().as_str()) =>
+                {
+                    Some(MediaRef::Channel(channel.to_ascii_lowercase()))
+                }
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Find all links to clips, VODs and channels on Twitch within a message, in order.
+pub fn extract_twitch_media(text: &str) -> Vec<MediaRef> {
+    text.split_whitespace()
+        .map(|word| {
+            word.trim_matches(|c: char| {
+                matches!(c, '(' | ')' | '<' | '>' | '"' | '\'' | ',' | '.' | '!')
+            })
+        })
+        .filter_map(parse_link)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_clips() {
+        assert_eq!(
+            extract_twitch_media(
+                "look https://clips.twitch.tv/FunnyClipSlug-abc123 and www.twitch.tv/xithrius/clip/OtherClip_x"
+            ),
+            vec![
+                MediaRef::Clip("FunnyClipSlug-abc123".to_string()),
+                MediaRef::Clip("OtherClip_x".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_extract_vods() {
+        assert_eq!(
+            extract_twitch_media(
+                "(https://www.twitch.tv/videos/123456789?t=1h2m3s) twitch.tv/videos/42"
+            ),
+            vec![
+                MediaRef::Vod {
+                    id: "123456789".to_string(),
+                    timestamp: Some("1h2m3s".to_string()),
+                },
+                MediaRef::Vod {
+                    id: "42".to_string(),
+                    timestamp: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_extract_channels() {
+        assert_eq!(
+            extract_twitch_media("go to https://twitch.tv/Xithrius/, not twitch.tv/directory"),
+            vec![MediaRef::Channel("xithrius".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_non_twitch_urls_ignored() {
+        assert!(extract_twitch_media(
+            "https://youtube.com/videos/123 https://nottwitch.tv/xithrius twitch.tv hello"
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn test_media_url() {
+        assert_eq!(
+            MediaRef::Vod {
+                id: "42".to_string(),
+                timestamp: Some("10s".to_string())
+            }
+            .url(),
+            "https://www.twitch.tv/videos/42?t=10s"
+        );
+        assert_eq!(
+            MediaRef::Clip("Slug".to_string()).url(),
+            "https://clips.twitch.tv/Slug"
+        );
+    }
+}
@@ -1,30 +1,78 @@
-mod badges;
+mod accounts;
+mod announcement;
+pub mod badges;
+pub mod capture;
+pub mod channel_message;
 pub mod channels;
+pub mod chat_settings;
 mod connection;
+pub mod events;
+pub mod eventsub;
+mod history;
+pub mod identity;
+mod joins;
+pub mod media;
+pub mod mod_log;
+pub mod moderation;
 pub mod oauth;
+mod outbox;
+pub mod raid;
+pub mod raw;
+pub mod roles;
+pub mod shoutout;
+mod tags;
+pub mod whisper;
+
+pub use connection::ConnectionState;
 
 use ::std::hash::BuildHasher;
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant, SystemTime},
+};
 
 use futures::StreamExt;
 use irc::{
-    client::prelude::Capability,
-    proto::{Command, Message},
+    client::{Client, ClientStream},
+    proto::{CapSubCommand, Command, Message, Response},
 };
 use log::{debug, info};
-use tokio::sync::{broadcast::Receiver, mpsc::Sender};
+use regex::Regex;
+use tokio::{
+    sync::{broadcast::Receiver, mpsc::Sender},
+    task::JoinHandle,
+    time::{interval, MissedTickBehavior},
+};
 
 use crate::{
+    emotes::EmoteProvider,
     handlers::{
-        config::CompleteConfig,
-        data::{DataBuilder, TwitchToTerminalAction},
+        config::{CompleteConfig, FrontendConfig, UsernameDisplay},
+        data::{message_to_plain, DataBuilder, MessageData, TwitchToTerminalAction},
         state::State,
     },
     twitch::{
-        badges::retrieve_user_badges,
-        connection::{client_stream_reconnect, wait_client_stream},
+        accounts::AccountAges,
+        announcement::announcement_data,
+        badges::{retrieve_user_badges, Role, Subscription},
+        chat_settings::{update_chat_settings, ChatSetting},
+        connection::{
+            has_joined, is_malformed_line, is_own_join, pong_token, report_connection_error,
+            wait_client_stream, Liveness, Probe, SleepDetector, LIVENESS_CHECK_INTERVAL,
+            SLEEP_CHECK_INTERVAL,
+        },
+        events::{ChatEvent, EVENTS},
+        eventsub::{spawn_eventsub, EventSubEvent},
+        history::get_recent_messages,
+        identity::{ChannelUserState, SelfIdentity},
+        joins::{JoinScheduler, JOIN_CHECK_INTERVAL},
+        mod_log::{ModAction, ModLogEntry},
+        outbox::Outbox,
+        raid::Raid,
+        shoutout::{needs_chat_command, send_shoutout},
+        tags::parse_emote_ranges,
     },
-    utils::text::clean_message,
+    utils::text::{censor, censor_patterns, clean_message, is_ascii_art},
 };
 
 #[derive(Debug, Clone)]
@@ -32,6 +80,28 @@ pub enum TwitchAction {
     Privmsg(String),
     Join(String),
     ClearMessages,
+    /// Change a chat setting of the current channel through the Helix API.
+    ChatSettings(ChatSetting),
+    /// Drop the connection to IRC and make a new one.
+    Reconnect,
+    /// Send a line as it is, see [`raw::RawLine`].
+    Raw(raw::RawLine),
+    /// Shout out a channel through the Helix API, or else with the chat command.
+    Shoutout(shoutout::Shoutout),
+}
+
+impl TwitchAction {
+    /// If the action sends something to the channel, which anonymous connections cannot do.
+    pub const fn sends(&self) -> bool {
+        match self {
+            Self::Privmsg(_)
+            | Self::ClearMessages
+            | Self::ChatSettings(_)
+            | Self::Raw(_)
+            | Self::Shoutout(_) => true,
+            Self::Join(_) | Self::Reconnect => false,
+        }
+    }
 }
 
 pub async fn twitch_irc(
@@ -57,32 +127,47 @@ pub async fn twitch_irc(
     }
 
     let data_builder = DataBuilder::new(&config.frontend.datetime_format);
+    let censor_terms = censor_patterns(&config.censor.terms);
     let mut room_state_startup = false;
 
     let (mut client, mut stream) =
-        wait_client_stream(tx.clone(), data_builder, config.clone()).await;
-
-    let sender = client.sender();
-
-    // Request commands capabilities
-    if client
-        .send_cap_req(&[
-            Capability::Custom("twitch.tv/commands"),
-            Capability::Custom("twitch.tv/tags"),
-        ])
-        .is_err()
-    {
-        tx.send(
-            data_builder.system(
-                "Unable to request commands/tags capability, certain features may be affected."
-                    .to_string(),
-            ),
-        )
-        .await
-        .unwrap();
-    }
+        wait_client_stream(tx.clone(), data_builder, config.clone(), false).await;
+
+    send_recent_messages(&tx, &config, &censor_terms).await;
+
+    let mut account_ages = AccountAges::new(
+        config.twitch.token.clone(),
+        config.frontend.new_account_days,
+        tx.clone(),
+    );
+
+    let mut eventsub = spawn_eventsub(&config.twitch, tx.clone());
 
     let mut connected = true;
+    // If the channel has been joined on this connection, as messages are only sent once it is.
+    let mut joined = true;
+
+    // Messages sent while disconnected wait for the connection to come back.
+    let mut outbox = Outbox::new(
+        config.twitch.queue_size,
+        Duration::from_secs(config.twitch.queue_max_age),
+    );
+
+    let mut sleep_detector = SleepDetector::new(config.twitch.sleep_threshold, SystemTime::now());
+    let mut sleep_check = interval(SLEEP_CHECK_INTERVAL);
+    sleep_check.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+    let mut liveness = Liveness::new(
+        config.twitch.ping_interval,
+        config.twitch.ping_timeout,
+        Instant::now(),
+    );
+    let mut liveness_check = interval(LIVENESS_CHECK_INTERVAL);
+    liveness_check.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+    let mut joins = JoinScheduler::new(Duration::from_millis(config.twitch.join_delay));
+    let mut join_check = interval(JOIN_CHECK_INTERVAL);
+    join_check.set_missed_tick_behavior(MissedTickBehavior::Delay);
 
     loop {
         tokio::select! {
@@ -92,6 +177,29 @@ pub async fn twitch_irc(
                 let current_channel = format!("#{}", config.twitch.channel);
 
                 match action {
+                    action if config.twitch.anonymous && action.sends() => {
+                        debug!("Not sending {:?} while reading chat anonymously", action);
+
+                        tx.send(data_builder.system(
+                            "Chat is read-only while anonymous, log in with a token to send messages.".to_string()
+                        )).await.unwrap();
+                    }
+                    TwitchAction::Privmsg(message) if !joined && outbox.enabled() => {
+                        debug!("Queueing message while disconnected: {}", message);
+
+                        let dropped = outbox.push(current_channel, message, Instant::now());
+
+                        let dropped_notice = if dropped > 0 {
+                            format!(" Dropped {dropped} older queued messages.")
+                        } else {
+                            String::new()
+                        };
+
+                        tx.send(data_builder.system(format!(
+                            "Queued, will send on reconnect ({} waiting).{dropped_notice}",
+                            outbox.len()
+                        ))).await.unwrap();
+                    }
                     TwitchAction::Privmsg(message) => {
                         debug!("Sending message to Twitch: {}", message);
 
@@ -102,24 +210,99 @@ pub async fn twitch_irc(
                     TwitchAction::Join(channel) => {
                         debug!("Switching to channel {channel}");
 
-                        let channel_list = format!("#{channel}");
-
                         // Leave previous channel
-                        if let Err(err) = sender.send_part(current_channel) {
+                        if let Err(err) = client.send_part(current_channel) {
                             tx.send(data_builder.twitch(err.to_string())).await.unwrap();
                         }
 
-                        // Join specified channel
-                        if let Err(err) = sender.send_join(&channel_list) {
-                            tx.send(data_builder.twitch(err.to_string())).await.unwrap();
-                        }
+                        // Join specified channel, once the rate limit allows it.
+                        // Only one channel is joined at a time, so the ones that are still waiting are left.
+                        joins.clear();
+                        joins.push(&channel);
+
+                        // Messages that were waiting were meant for the channel that was left.
+                        outbox.clear();
 
                         // Set old channel to new channel
                         config.twitch.channel = channel;
+
+                        send_recent_messages(&tx, &config, &censor_terms).await;
+
+                        if let Some(task) = eventsub.take() {
+                            task.abort();
+                            tx.send(TwitchToTerminalAction::EventSub(EventSubEvent::Reset)).await.unwrap();
+                        }
+
+                        eventsub = spawn_eventsub(&config.twitch, tx.clone());
                     }
                     TwitchAction::ClearMessages => {
                         client.send(Command::Raw("CLEARCHAT".to_string(), vec![])).unwrap();
                     }
+                    TwitchAction::ChatSettings(setting) => {
+                        debug!("Changing chat setting: {:?}", setting);
+
+                        let twitch_config = config.twitch.clone();
+                        let datetime_format = config.frontend.datetime_format.clone();
+                        let tx = tx.clone();
+
+                        // The channel changing the setting is told over IRC, with a NOTICE and a ROOMSTATE.
+                        tokio::spawn(async move {
+                            if let Err(err) = update_chat_settings(&twitch_config, setting).await {
+                                let message = format!("Unable to change the chat settings: {err}");
+
+                                tx.send(DataBuilder::new(&datetime_format).twitch(message)).await.ok();
+                            }
+                        });
+                    }
+                    TwitchAction::Raw(raw) => {
+                        debug!("Sending raw line to Twitch: {}", raw.line);
+
+                        if let Err(err) = client.send(raw.message()) {
+                            tx.send(data_builder.system(format!("Unable to send the raw line: {err}"))).await.unwrap();
+                        }
+                    }
+                    TwitchAction::Shoutout(shoutout) => {
+                        debug!("Shouting out {}", shoutout.to);
+
+                        if config.twitch.token.is_none() {
+                            client.send_privmsg(current_channel, shoutout.command()).unwrap();
+                        } else {
+                            let twitch_config = config.twitch.clone();
+                            let datetime_format = config.frontend.datetime_format.clone();
+                            let sender = client.sender();
+                            let tx = tx.clone();
+
+                            tokio::spawn(async move {
+                                match send_shoutout(&twitch_config, &shoutout).await {
+                                    Ok(()) => {}
+                                    Err(err) if needs_chat_command(&err) => {
+                                        debug!("Unable to shout out through the API, using the chat command: {}", err);
+
+                                        sender.send_privmsg(current_channel, shoutout.command()).ok();
+                                    }
+                                    Err(err) => {
+                                        let message = format!("Unable to shout out {}: {err}", shoutout.to);
+
+                                        tx.send(DataBuilder::new(&datetime_format).twitch(message)).await.ok();
+                                    }
+                                }
+                            });
+                        }
+                    }
+                    TwitchAction::Reconnect => {
+                        // Until the new connection gets its first message, it is still being made.
+                        if connected {
+                            debug!("Reconnecting to Twitch on request.");
+
+                            tx.send(data_builder.system("Reconnecting to Twitch.".to_string())).await.unwrap();
+
+                            connected = false;
+                            joined = false;
+                            (client, stream) = reconnect(&tx, data_builder, &config, &mut eventsub).await;
+                        } else {
+                            tx.send(data_builder.system("Already reconnecting.".to_string())).await.unwrap();
+                        }
+                    }
                 }
             }
             Some(message) = stream.next() => {
@@ -128,20 +311,101 @@ pub async fn twitch_irc(
                         if !connected {
                             tx.send(data_builder.system("Reconnect succcessful.".to_string())).await.unwrap();
                             connected = true;
+
+                            if let Some(liveness) = liveness.as_mut() {
+                                liveness.reset(Instant::now());
+                            }
+                        }
+
+                        // The first lines are the login and capabilities, which come before the channel is joined.
+                        if !joined && has_joined(&message, &format!("#{}", config.twitch.channel), &config.twitch) {
+                            joined = true;
+
+                            let (queued, stale) = outbox.flush(Instant::now());
+
+                            if !queued.is_empty() || stale > 0 {
+                                tx.send(data_builder.system(format!(
+                                    "Sending {} queued messages, {stale} were too old to send.",
+                                    queued.len()
+                                ))).await.unwrap();
+                            }
+
+                            for queued in queued {
+                                if let Err(err) = client.send_privmsg(queued.channel, queued.message) {
+                                    tx.send(data_builder.system(format!("Unable to send a queued message: {err}"))).await.unwrap();
+                                }
+                            }
+                        }
+
+                        if let (Some(liveness), Some(token)) = (liveness.as_mut(), pong_token(&message.command)) {
+                            liveness.pong(token);
                         }
 
-                        if let Some(b) = handle_message_command(message, tx.clone(), data_builder, config.frontend.badges, room_state_startup).await {
+                        if let Some(b) = handle_message_command(message, tx.clone(), data_builder, &config, &censor_terms, &mut account_ages, room_state_startup).await {
                             room_state_startup = b;
                         }
                     }
+                    Err(err) if is_malformed_line(&err) => {
+                        debug!("Skipping malformed line from Twitch: {}", err);
+                    }
                     Err(err) => {
                         connected = false;
+                        joined = false;
 
                         debug!("Twitch connection error encountered: {}, attempting to reconnect.", err);
 
-                        (client, stream) = client_stream_reconnect(err, tx.clone(), data_builder, &config).await;
+                        report_connection_error(err, &tx, data_builder).await;
+                        (client, stream) = reconnect(&tx, data_builder, &config, &mut eventsub).await;
+
+                    }
+                }
+            }
+            _ = join_check.tick(), if joins.pending() => {
+                while let Some(join) = joins.next(Instant::now()) {
+                    if let Err(err) = client.send_join(format!("#{}", join.channel)) {
+                        let retried = joins.failed(join);
+
+                        let message = if retried {
+                            format!("{err}, trying to join again.")
+                        } else {
+                            format!("{err}, giving up on joining.")
+                        };
+
+                        tx.send(data_builder.twitch(message)).await.unwrap();
+                    }
+                }
+            }
+            _ = sleep_check.tick(), if sleep_detector.is_some() => {
+                if sleep_detector.as_mut().is_some_and(|d| d.check(SystemTime::now())) {
+                    debug!("Clock jumped ahead, reconnecting after sleep.");
+
+                    tx.send(data_builder.system("Resumed from sleep, reconnecting.".to_string())).await.unwrap();
+
+                    connected = false;
+                    joined = false;
+                    (client, stream) = reconnect(&tx, data_builder, &config, &mut eventsub).await;
 
+                    // Reconnecting can take a while, which is not a sleep.
+                    sleep_detector = SleepDetector::new(config.twitch.sleep_threshold, SystemTime::now());
+                }
+            }
+            _ = liveness_check.tick(), if connected && liveness.is_some() => {
+                match liveness.as_mut().map(|l| l.check(Instant::now())) {
+                    Some(Probe::Ping(token)) => {
+                        if let Err(err) = client.send(Command::PING(token, None)) {
+                            debug!("Unable to send a PING to Twitch: {err}");
+                        }
                     }
+                    Some(Probe::Dead) => {
+                        debug!("No PONG from Twitch in time, reconnecting.");
+
+                        tx.send(data_builder.system("Twitch stopped answering, reconnecting.".to_string())).await.unwrap();
+
+                        connected = false;
+                        joined = false;
+                        (client, stream) = reconnect(&tx, data_builder, &config, &mut eventsub).await;
+                    }
+                    Some(Probe::Wait) | None => {}
                 }
             }
             else => {}
@@ -149,62 +413,161 @@ pub async fn twitch_irc(
     }
 }
 
+/// Make a new connection to Twitch, where the events from the API are subscribed to again too.
+async fn reconnect(
+    tx: &Sender<TwitchToTerminalAction>,
+    data_builder: DataBuilder<'_>,
+    config: &CompleteConfig,
+    eventsub: &mut Option<JoinHandle<()>>,
+) -> (Client, ClientStream) {
+    let connection = wait_client_stream(tx.clone(), data_builder, config.clone(), true).await;
+
+    if let Some(task) = eventsub.take() {
+        task.abort();
+    }
+
+    *eventsub = spawn_eventsub(&config.twitch, tx.clone());
+
+    connection
+}
+
 async fn handle_message_command(
     message: Message,
     tx: Sender<TwitchToTerminalAction>,
     data_builder: DataBuilder<'_>,
-    badges: bool,
+    config: &CompleteConfig,
+    censor_terms: &[Regex],
+    account_ages: &mut AccountAges,
     room_state_startup: bool,
 ) -> Option<bool> {
-    let mut tags: HashMap<&str, &str> = HashMap::new();
-
-    if let Some(ref ref_tags) = message.tags {
-        for tag in ref_tags {
-            if let Some(ref tag_value) = tag.1 {
-                tags.insert(&tag.0, tag_value);
-            }
-        }
-    }
+    let frontend_config = &config.frontend;
+    let tags = message_tags(&message);
 
     match message.command {
         Command::PRIVMSG(ref _target, ref msg) => {
-            // lowercase username from message
-            let mut name = message.source_nickname().unwrap().to_string();
+            let message_data = privmsg_data(&message, msg, &tags, frontend_config, censor_terms);
+
+            debug!(
+                "Message received from twitch: {} - {:?}",
+                message_data.author,
+                message_to_plain(&message_data, config.terminal.log_emotes)
+            );
+
+            if let Some(user_id) = &message_data.user_id {
+                account_ages.check(user_id);
+            }
 
-            if badges {
-                retrieve_user_badges(&mut name, &message);
+            EVENTS.publish(|| ChatEvent::Message(message_data.clone()));
+
+            tx.send(TwitchToTerminalAction::Message(message_data))
+                .await
+                .unwrap();
+        }
+        Command::NOTICE(ref _target, ref msg) => {
+            EVENTS.publish(|| ChatEvent::Notice(msg.to_string()));
+
+            if let Some(&msg_id) = tags.get("msg-id") {
+                if let Some(action) = ModAction::from_notice(msg_id, msg) {
+                    // Twitch only confirms actions to the moderator who did them.
+                    let moderator = msg_id
+                        .ends_with("_success")
+                        .then(|| config.twitch.username.clone());
+
+                    tx.send(TwitchToTerminalAction::Moderation(ModLogEntry::new(
+                        moderator, action,
+                    )))
+                    .await
+                    .unwrap();
+                }
             }
 
-            // Remove invalid unicode characters from the message.
-            let (cleaned_message, highlight) = clean_message(msg);
+            // Twitch refuses the login with one of these notices, then closes the connection.
+            if msg == "Login authentication failed" || msg == "Improperly formatted auth" {
+                EVENTS.publish(|| ChatEvent::Connection(ConnectionState::Failed));
 
-            let message_id = tags.get("id").map(|&s| s.to_string());
-            let user_id = tags.get("user-id").map(|&s| s.to_string());
+                tx.send(TwitchToTerminalAction::Connection(ConnectionState::Failed))
+                    .await
+                    .unwrap();
+            }
 
-            debug!("Message received from twitch: {name} - {cleaned_message:?}");
+            tx.send(data_builder.twitch(msg.to_string())).await.unwrap();
+        }
+        Command::Response(Response::RPL_WELCOME, _) => {
+            EVENTS.publish(|| ChatEvent::Connection(ConnectionState::Connected));
 
-            tx.send(DataBuilder::user(
-                name,
-                user_id,
-                cleaned_message,
-                message_id,
-                highlight,
+            tx.send(TwitchToTerminalAction::Connection(
+                ConnectionState::Connected,
             ))
             .await
             .unwrap();
         }
-        Command::NOTICE(ref _target, ref msg) => {
-            tx.send(data_builder.twitch(msg.to_string())).await.unwrap();
-        }
-        Command::JOIN(ref channel, _, _) => {
+        // With the membership capability, the joins of everyone else come through as well.
+        Command::JOIN(ref channel, _, _) if is_own_join(&message, &config.twitch) => {
             tx.send(data_builder.twitch(format!("Joined {}", *channel)))
                 .await
                 .unwrap();
         }
-        Command::Raw(ref cmd, ref _items) => {
+        Command::CAP(_, CapSubCommand::ACK, _, ref capabilities) => {
+            debug!("Twitch granted the capabilities {:?}", capabilities);
+        }
+        Command::CAP(_, CapSubCommand::NAK, _, ref capabilities) => {
+            tx.send(data_builder.system(format!(
+                "Twitch refused the capabilities {}, certain features may be affected.",
+                capabilities.as_deref().unwrap_or_default()
+            )))
+            .await
+            .unwrap();
+        }
+        Command::Raw(ref cmd, ref items) => {
             match cmd.as_ref() {
+                // https://dev.twitch.tv/docs/irc/tags/#globaluserstate-tags
+                "GLOBALUSERSTATE" if config.twitch.self_identity => {
+                    if let Some(identity) = SelfIdentity::from_tags(&tags) {
+                        debug!(
+                            "Twitch knows the user as {:?} with id {} and color {:?}",
+                            identity.display_name, identity.user_id, identity.color
+                        );
+
+                        tx.send(TwitchToTerminalAction::SelfIdentity(identity))
+                            .await
+                            .unwrap();
+                    }
+                }
+                // https://dev.twitch.tv/docs/irc/tags/#userstate-tags
+                "USERSTATE" => {
+                    if let Some(channel) = items.first() {
+                        let state = ChannelUserState::from_tags(channel, &tags);
+
+                        debug!(
+                            "In {} the user is {:?} with emote sets {:?}",
+                            state.channel, state.role, state.emote_sets
+                        );
+
+                        tx.send(TwitchToTerminalAction::UserState(state))
+                            .await
+                            .unwrap();
+                    }
+                }
                 // https://dev.twitch.tv/docs/irc/tags/#roomstate-tags
                 "ROOMSTATE" => {
+                    let room_state = tags
+                        .iter()
+                        .map(|(k, v)| ((*k).to_string(), (*v).to_string()))
+                        .collect::<HashMap<_, _>>();
+
+                    EVENTS.publish(|| ChatEvent::RoomState(room_state.clone()));
+
+                    tx.send(TwitchToTerminalAction::RoomState(room_state))
+                        .await
+                        .unwrap();
+
+                    // Only the settings that changed are sent after the first ROOMSTATE.
+                    if let Some(&emote_only) = tags.get("emote-only") {
+                        tx.send(TwitchToTerminalAction::EmoteOnly(emote_only == "1"))
+                            .await
+                            .unwrap();
+                    }
+
                     // Only display roomstate on startup, since twitch
                     // sends a NOTICE whenever roomstate changes.
                     if !room_state_startup {
@@ -215,15 +578,70 @@ async fn handle_message_command(
                 }
                 // https://dev.twitch.tv/docs/irc/tags/#usernotice-tags
                 "USERNOTICE" => {
-                    if let Some(value) = tags.get("system-msg") {
-                        tx.send(data_builder.twitch((*value).to_string()))
-                            .await
-                            .unwrap();
+                    let announcement =
+                        announcement_data(&message, &tags, frontend_config, censor_terms);
+
+                    EVENTS.publish(|| ChatEvent::UserNotice {
+                        kind: tags
+                            .get("msg-id")
+                            .map(|&s| s.to_string())
+                            .unwrap_or_default(),
+                        system_message: tags.get("system-msg").map(|&s| s.to_string()),
+                        message: announcement.clone(),
+                    });
+
+                    let kind = tags.get("msg-id").copied();
+
+                    let notice = announcement.map_or_else(
+                        || {
+                            tags.get("system-msg")
+                                .map(|value| data_builder.twitch((*value).to_string()))
+                        },
+                        |message_data| Some(TwitchToTerminalAction::Message(message_data)),
+                    );
+
+                    // Raid digests show the raid themselves, and condense new chatters.
+                    let notice = match notice {
+                        Some(_)
+                            if kind == Some("raid") && frontend_config.raid_digest_window > 0 =>
+                        {
+                            None
+                        }
+                        Some(TwitchToTerminalAction::Message(message_data))
+                            if kind == Some("ritual") =>
+                        {
+                            Some(TwitchToTerminalAction::NewChatter(message_data))
+                        }
+                        notice => notice,
+                    };
+
+                    if let Some(notice) = notice {
+                        tx.send(notice).await.unwrap();
+                    }
+
+                    if let Some(raid) = Raid::from_tags(&tags) {
+                        tx.send(TwitchToTerminalAction::Raid(raid)).await.unwrap();
                     }
                 }
                 // https://dev.twitch.tv/docs/irc/tags/#clearchat-tags
                 "CLEARCHAT" => {
                     let user_id = tags.get("target-user-id").map(|&s| s.to_string());
+                    let duration = tags.get("ban-duration").and_then(|d| d.parse().ok());
+
+                    EVENTS.publish(|| ChatEvent::ClearChat {
+                        user_id: user_id.clone(),
+                        duration,
+                    });
+
+                    // The login of the user is the last argument, after the channel.
+                    let login = user_id.as_ref().and_then(|_| items.get(1));
+
+                    tx.send(TwitchToTerminalAction::Moderation(ModLogEntry::new(
+                        None,
+                        ModAction::from_clearchat(login.map(String::as_str), duration),
+                    )))
+                    .await
+                    .unwrap();
 
                     tx.send(TwitchToTerminalAction::ClearChat(user_id.clone()))
                         .await
@@ -259,9 +677,23 @@ async fn handle_message_command(
                 // https://dev.twitch.tv/docs/irc/tags/#clearmsg-tags
                 "CLEARMSG" => {
                     if let Some(id) = tags.get("target-msg-id") {
+                        EVENTS.publish(|| ChatEvent::ClearMessage {
+                            message_id: (*id).to_string(),
+                        });
+
                         tx.send(TwitchToTerminalAction::DeleteMessage((*id).to_string()))
                             .await
                             .unwrap();
+
+                        tx.send(TwitchToTerminalAction::Moderation(ModLogEntry::new(
+                            None,
+                            ModAction::Delete {
+                                user: tags.get("login").map(|&s| s.to_string()),
+                                message: items.get(1).cloned(),
+                            },
+                        )))
+                        .await
+                        .unwrap();
                     }
                 }
                 _ => (),
@@ -273,6 +705,135 @@ async fn handle_message_command(
     None
 }
 
+/// Load the messages sent to the current channel before it was joined, if enabled.
+async fn send_recent_messages(
+    tx: &Sender<TwitchToTerminalAction>,
+    config: &CompleteConfig,
+    censor_terms: &[Regex],
+) {
+    if config.twitch.recent_messages == 0 {
+        return;
+    }
+
+    let messages = get_recent_messages(
+        &config.twitch.channel,
+        config.twitch.recent_messages,
+        &config.frontend,
+        censor_terms,
+    )
+    .await;
+
+    if !messages.is_empty() {
+        tx.send(TwitchToTerminalAction::History(messages))
+            .await
+            .unwrap();
+    }
+}
+
+/// All tags of a message that have a value.
+fn message_tags(message: &Message) -> HashMap<&str, &str> {
+    let mut tags: HashMap<&str, &str> = HashMap::new();
+
+    if let Some(ref ref_tags) = message.tags {
+        for tag in ref_tags {
+            if let Some(ref tag_value) = tag.1 {
+                tags.insert(&tag.0, tag_value);
+            }
+        }
+    }
+
+    tags
+}
+
+/// Turn the contents of a PRIVMSG into a chat message.
+fn privmsg_data(
+    message: &Message,
+    msg: &str,
+    tags: &HashMap<&str, &str>,
+    frontend_config: &FrontendConfig,
+    censor_terms: &[Regex],
+) -> MessageData {
+    // lowercase username from message, which notices only have as a tag
+    let login = tags
+        .get("login")
+        .copied()
+        .or_else(|| message.source_nickname())
+        .unwrap_or_default();
+
+    let mut name = frontend_config.control_characters.apply(&choose_username(
+        login,
+        tags.get("display-name").copied(),
+        &frontend_config.username_display,
+    ));
+
+    if frontend_config.badges {
+        retrieve_user_badges(&mut name, message);
+    }
+
+    // Remove invalid unicode characters from the message.
+    let (cleaned_message, highlight) = clean_message(msg);
+
+    let message_id = tags.get("id").map(|&s| s.to_string());
+    let user_id = tags.get("user-id").map(|&s| s.to_string());
+
+    // Censoring keeps the amount of code points, so emote indices still line up.
+    let cleaned_message = censor(&cleaned_message, censor_terms);
+
+    let emote_ranges = tags
+        .get("emotes")
+        .map(|emotes| {
+            parse_emote_ranges(emotes, &cleaned_message)
+                .into_iter()
+                .map(|range| (range, EmoteProvider::Twitch))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let mut message_data =
+        MessageData::new(name, user_id, false, cleaned_message, message_id, highlight);
+    message_data.login = (!login.is_empty()).then(|| login.to_string());
+    message_data.badge_role = tags
+        .get("badges")
+        .and_then(|badges| Role::from_badges(badges));
+    message_data.role = message_data.badge_role;
+    message_data.subscription = frontend_config
+        .sub_info
+        .then(|| Subscription::from_tags(tags))
+        .flatten();
+    message_data.emote_ranges = emote_ranges;
+    message_data.guard_control_characters(frontend_config.control_characters);
+    message_data.ascii_art = frontend_config.collapse_ascii_art
+        && is_ascii_art(&message_data.payload, frontend_config.ascii_art_ratio);
+    message_data.collapsed = message_data.ascii_art;
+
+    message_data
+}
+
+/// Pick the name to show for a user, from their lowercase login and the `display-name` tag.
+///
+/// Display names that only differ from the login by case are not counted as different.
+pub fn choose_username(
+    login: &str,
+    display_name: Option<&str>,
+    username_display: &UsernameDisplay,
+) -> String {
+    let Some(display_name) = display_name.filter(|d| !d.is_empty()) else {
+        return login.to_string();
+    };
+
+    match username_display {
+        UsernameDisplay::Login => login.to_string(),
+        UsernameDisplay::Display => display_name.to_string(),
+        UsernameDisplay::Both => {
+            if display_name.to_lowercase() == login.to_lowercase() {
+                display_name.to_string()
+            } else {
+                format!("{display_name} ({login})")
+            }
+        }
+    }
+}
+
 pub async fn handle_roomstate<S: BuildHasher>(
     tx: &Sender<TwitchToTerminalAction>,
     tags: &HashMap<&str, &str, S>,
@@ -318,3 +879,72 @@ pub async fn handle_roomstate<S: BuildHasher>(
     .await
     .unwrap();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_choose_username_matching_names() {
+        for username_display in [
+            UsernameDisplay::Display,
+            UsernameDisplay::Login,
+            UsernameDisplay::Both,
+        ] {
+            assert_eq!(
+                choose_username("xithrius", Some("xithrius"), &username_display),
+                "xithrius"
+            );
+        }
+    }
+
+    #[test]
+    fn test_choose_username_case_only_difference() {
+        let login = "xithrius";
+        let display_name = Some("Xithrius");
+
+        assert_eq!(
+            choose_username(login, display_name, &UsernameDisplay::Display),
+            "Xithrius"
+        );
+        assert_eq!(
+            choose_username(login, display_name, &UsernameDisplay::Login),
+            "xithrius"
+        );
+        assert_eq!(
+            choose_username(login, display_name, &UsernameDisplay::Both),
+            "Xithrius"
+        );
+    }
+
+    #[test]
+    fn test_choose_username_fully_different_names() {
+        let login = "nanashi";
+        let display_name = Some("名無し");
+
+        assert_eq!(
+            choose_username(login, display_name, &UsernameDisplay::Display),
+            "名無し"
+        );
+        assert_eq!(
+            choose_username(login, display_name, &UsernameDisplay::Login),
+            "nanashi"
+        );
+        assert_eq!(
+            choose_username(login, display_name, &UsernameDisplay::Both),
+            "名無し (nanashi)"
+        );
+    }
+
+    #[test]
+    fn test_choose_username_missing_display_name() {
+        assert_eq!(
+            choose_username("xithrius", None, &UsernameDisplay::Both),
+            "xithrius"
+        );
+        assert_eq!(
+            choose_username("xithrius", Some(""), &UsernameDisplay::Display),
+            "xithrius"
+        );
+    }
+}
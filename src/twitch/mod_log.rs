@@ -0,0 +1,360 @@
+use std::{collections::VecDeque, mem::discriminant};
+
+use chrono::{DateTime, Duration, Local};
+
+/// How many moderation actions are kept.
+const MOD_LOG_CAPACITY: usize = 100;
+
+/// How many seconds apart the same action can be received from different sources,
+/// such as a NOTICE and a CLEARCHAT for one timeout.
+const DUPLICATE_WINDOW: i64 = 5;
+
+/// Something a moderator did in chat.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ModAction {
+    Ban {
+        user: String,
+        reason: Option<String>,
+    },
+    Unban {
+        user: String,
+    },
+    Timeout {
+        user: String,
+        /// In seconds, if known.
+        duration: Option<u64>,
+        reason: Option<String>,
+    },
+    Untimeout {
+        user: String,
+    },
+    Delete {
+        user: Option<String>,
+        message: Option<String>,
+    },
+    /// Every message of chat was cleared.
+    Clear,
+    /// A chat mode, such as slow mode, was turned on or off.
+    Mode {
+        mode: String,
+        enabled: bool,
+    },
+}
+
+impl ModAction {
+    /// The action announced by a NOTICE, by its `msg-id` tag.
+    pub fn from_notice(msg_id: &str, text: &str) -> Option<Self> {
+        // The user comes first in all of these notices.
+        let user = || text.split_whitespace().next().map(ToString::to_string);
+
+        let mode = |mode: &str, enabled: bool| Self::Mode {
+            mode: mode.to_string(),
+            enabled,
+        };
+
+        Some(match msg_id {
+            "ban_success" => Self::Ban {
+                user: user()?,
+                reason: None,
+            },
+            "unban_success" => Self::Unban { user: user()? },
+            "timeout_success" => Self::Timeout {
+                user: user()?,
+                duration: text
+                    .split_once(" timed out for ")
+                    .and_then(|(_, duration)| parse_duration(duration.trim_end_matches('.'))),
+                reason: None,
+            },
+            "untimeout_success" => Self::Untimeout { user: user()? },
+            "delete_message_success" => Self::Delete {
+                user: text
+                    .strip_prefix("The message from ")
+                    .and_then(|rest| rest.split_whitespace().next())
+                    .map(ToString::to_string),
+                message: None,
+            },
+            "slow_on" => mode("slow mode", true),
+            "slow_off" => mode("slow mode", false),
+            "emote_only_on" => mode("emote-only mode", true),
+            "emote_only_off" => mode("emote-only mode", false),
+            "followers_on" | "followers_on_zero" => mode("followers-only mode", true),
+            "followers_off" => mode("followers-only mode", false),
+            "subs_on" => mode("subscribers-only mode", true),
+            "subs_off" => mode("subscribers-only mode", false),
+            "r9k_on" => mode("unique chat", true),
+            "r9k_off" => mode("unique chat", false),
+            _ => return None,
+        })
+    }
+
+    /// The action of a CLEARCHAT, which is a ban or timeout if it has a target.
+    pub fn from_clearchat(user: Option<&str>, duration: Option<u64>) -> Self {
+        match (user, duration) {
+            (Some(user), Some(duration)) => Self::Timeout {
+                user: user.to_string(),
+                duration: Some(duration),
+                reason: None,
+            },
+            (Some(user), None) => Self::Ban {
+                user: user.to_string(),
+                reason: None,
+            },
+            (None, _) => Self::Clear,
+        }
+    }
+
+    fn user(&self) -> Option<&str> {
+        match self {
+            Self::Ban { user, .. }
+            | Self::Unban { user }
+            | Self::Timeout { user, .. }
+            | Self::Untimeout { user } => Some(user),
+            Self::Delete { user, .. } => user.as_deref(),
+            Self::Clear | Self::Mode { .. } => None,
+        }
+    }
+
+    /// If both are the same action on the same user, even if one is known in more detail.
+    fn same_as(&self, other: &Self) -> bool {
+        if let (
+            Self::Mode { mode, enabled },
+            Self::Mode {
+                mode: m,
+                enabled: e,
+            },
+        ) = (self, other)
+        {
+            return mode == m && enabled == e;
+        }
+
+        discriminant(self) == discriminant(other)
+            && self
+                .user()
+                .map(str::to_lowercase)
+                .eq(&other.user().map(str::to_lowercase))
+    }
+
+    pub fn describe(&self) -> String {
+        let reason = |reason: &Option<String>| {
+            reason
+                .as_ref()
+                .map(|r| format!(": {r}"))
+                .unwrap_or_default()
+        };
+
+        match self {
+            Self::Ban { user, reason: r } => format!("banned {user}{}", reason(r)),
+            Self::Unban { user } => format!("unbanned {user}"),
+            Self::Timeout {
+                user,
+                duration,
+                reason: r,
+            } => format!(
+                "timed out {user}{}{}",
+                duration.map(|d| format!(" for {d}s")).unwrap_or_default(),
+                reason(r)
+            ),
+            Self::Untimeout { user } => format!("removed the timeout of {user}"),
+            Self::Delete { user, message } => format!(
+                "deleted a message{}{}",
+                user.as_ref()
+                    .map(|u| format!(" of {u}"))
+                    .unwrap_or_default(),
+                message
+                    .as_ref()
+                    .map(|m| format!(": {m}"))
+                    .unwrap_or_default()
+            ),
+            Self::Clear => "cleared chat".to_string(),
+            Self::Mode { mode, enabled } => {
+                format!("turned {} {mode}", if *enabled { "on" } else { "off" })
+            }
+        }
+    }
+}
+
+/// Seconds of a duration like "10 minutes" or "1 hour 30 minutes", as Twitch writes them out.
+pub fn parse_duration(text: &str) -> Option<u64> {
+    let words = text.split_whitespace().collect::<Vec<_>>();
+
+    if words.is_empty() || words.len() % 2 != 0 {
+        return None;
+    }
+
+    words.chunks(2).try_fold(0, |total, pair| {
+        let amount = pair[0].parse::<u64>().ok()?;
+
+        let unit = match pair[1].trim_end_matches(['s', ',']) {
+            "second" => 1,
+            "minute" => 60,
+            "hour" => 60 * 60,
+            "day" => 24 * 60 * 60,
+            "week" => 7 * 24 * 60 * 60,
+            _ => return None,
+        };
+
+        Some(total + amount * unit)
+    })
+}
+
+/// A moderation action, and who did it if that is known.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModLogEntry {
+    pub time: DateTime<Local>,
+    pub moderator: Option<String>,
+    pub action: ModAction,
+}
+
+impl ModLogEntry {
+    pub fn new(moderator: Option<String>, action: ModAction) -> Self {
+        Self {
+            time: Local::now(),
+            moderator,
+            action,
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        format!(
+            "{} {}",
+            self.moderator.as_deref().unwrap_or("A moderator"),
+            self.action.describe()
+        )
+    }
+}
+
+/// The latest moderation actions in the channel, newest first.
+#[derive(Debug, Clone, Default)]
+pub struct ModLog {
+    entries: VecDeque<ModLogEntry>,
+}
+
+impl ModLog {
+    /// Add an action, merging it into the same one if it was already received from another source.
+    pub fn push(&mut self, entry: ModLogEntry) {
+        let window = Duration::seconds(DUPLICATE_WINDOW);
+
+        if let Some(existing) = self
+            .entries
+            .iter_mut()
+            .find(|e| (entry.time - e.time).abs() <= window && e.action.same_as(&entry.action))
+        {
+            // Event subscriptions know the most about an action, along with who did it.
+            if entry.moderator.is_some() {
+                existing.moderator = entry.moderator;
+                existing.action = entry.action;
+            }
+
+            return;
+        }
+
+        self.entries.push_front(entry);
+        self.entries.truncate(MOD_LOG_CAPACITY);
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = &ModLogEntry> {
+        self.entries.iter()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timeout_notice() {
+        assert_eq!(
+            ModAction::from_notice(
+                "timeout_success",
+                "spammer has been timed out for 10 minutes."
+            ),
+            Some(ModAction::Timeout {
+                user: "spammer".to_string(),
+                duration: Some(600),
+                reason: None,
+            })
+        );
+        assert_eq!(
+            ModAction::from_notice(
+                "timeout_success",
+                "spammer has been timed out for 1 hour 30 minutes."
+            ),
+            Some(ModAction::Timeout {
+                user: "spammer".to_string(),
+                duration: Some(5400),
+                reason: None,
+            })
+        );
+    }
+
+    #[test]
+    fn test_ban_notice() {
+        assert_eq!(
+            ModAction::from_notice("ban_success", "troll is now banned from this channel."),
+            Some(ModAction::Ban {
+                user: "troll".to_string(),
+                reason: None,
+            })
+        );
+        assert_eq!(
+            ModAction::from_notice("slow_on", "This room is now in slow mode."),
+            Some(ModAction::Mode {
+                mode: "slow mode".to_string(),
+                enabled: true,
+            })
+        );
+        assert_eq!(
+            ModAction::from_notice("msg_banned", "You are banned."),
+            None
+        );
+    }
+
+    #[test]
+    fn test_clearchat() {
+        assert_eq!(
+            ModAction::from_clearchat(Some("troll"), None),
+            ModAction::Ban {
+                user: "troll".to_string(),
+                reason: None,
+            }
+        );
+        assert_eq!(
+            ModAction::from_clearchat(Some("spammer"), Some(30)),
+            ModAction::Timeout {
+                user: "spammer".to_string(),
+                duration: Some(30),
+                reason: None,
+            }
+        );
+        assert_eq!(ModAction::from_clearchat(None, None), ModAction::Clear);
+    }
+
+    #[test]
+    fn test_mod_log_merges_duplicates() {
+        let mut log = ModLog::default();
+
+        log.push(ModLogEntry::new(
+            None,
+            ModAction::from_clearchat(Some("troll"), None),
+        ));
+        log.push(ModLogEntry::new(
+            Some("mod_person".to_string()),
+            ModAction::from_notice("ban_success", "Troll is now banned from this channel.")
+                .unwrap(),
+        ));
+        log.push(ModLogEntry::new(
+            None,
+            ModAction::from_clearchat(Some("other"), None),
+        ));
+
+        let entries = log.entries().collect::<Vec<_>>();
+
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].describe(), "A moderator banned other");
+        assert_eq!(entries[1].describe(), "mod_person banned Troll");
+    }
+}
@@ -0,0 +1,201 @@
+use crate::handlers::{config::ModerationConfig, data::MessageData};
+
+/// The scope that the token needs to have for each action, which depends on how it is sent.
+/// Actions on messages are chat commands sent over IRC, while chat settings and shoutouts
+/// go through Helix.
+///
+/// <https://dev.twitch.tv/docs/authentication/scopes/>
+const REQUIRED_SCOPES: [(ModCommand, &str); 5] = [
+    (ModCommand::Delete, "channel:moderate"),
+    (ModCommand::Timeout, "channel:moderate"),
+    (ModCommand::Ban, "channel:moderate"),
+    (ModCommand::ChatSettings, "moderator:manage:chat_settings"),
+    (ModCommand::Shoutout, "moderator:manage:shoutouts"),
+];
+
+/// Something a moderator can do, either about a message with a key or from the chat input.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ModCommand {
+    Delete,
+    Timeout,
+    Ban,
+    ChatSettings,
+    Shoutout,
+}
+
+impl ModCommand {
+    /// The action bound to a key, if any.
+    pub const fn from_key(config: &ModerationConfig, key: char) -> Option<Self> {
+        if key == config.delete_key {
+            Some(Self::Delete)
+        } else if key == config.timeout_key {
+            Some(Self::Timeout)
+        } else if key == config.ban_key {
+            Some(Self::Ban)
+        } else {
+            None
+        }
+    }
+
+    /// The scope that the token needs to perform the action.
+    pub fn required_scope(self) -> &'static str {
+        REQUIRED_SCOPES
+            .iter()
+            .find_map(|&(command, scope)| (command == self).then_some(scope))
+            .unwrap_or_default()
+    }
+
+    /// If a token with these scopes can perform the action, where it is assumed that it can
+    /// while the scopes are not known yet.
+    pub fn allowed(self, scopes: Option<&[String]>) -> bool {
+        let required = self.required_scope();
+
+        scopes.map_or(true, |scopes| scopes.iter().any(|scope| scope == required))
+    }
+
+    /// Something that can be done when the action is done without the scope for it.
+    pub const fn describe(self) -> &'static str {
+        match self {
+            Self::Delete => "delete messages",
+            Self::Timeout => "time out users",
+            Self::Ban => "ban users",
+            Self::ChatSettings => "change the chat settings",
+            Self::Shoutout => "shout out channels",
+        }
+    }
+
+    /// The chat command that performs the action on a message.
+    ///
+    /// There is none for system messages, nor for deleting a message without an ID,
+    /// nor for the actions that are not about a message.
+    pub fn command(self, message: &MessageData, timeout_duration: u64) -> Option<String> {
+        if message.system {
+            return None;
+        }
+
+        // The author is addressed by login, since display names can differ from it.
+        let user = message.login.as_deref().unwrap_or(&message.author);
+
+        match self {
+            Self::Delete => message
+                .message_id
+                .as_ref()
+                .map(|id| format!("/delete {id}")),
+            Self::Timeout => Some(format!("/timeout {user} {timeout_duration}")),
+            Self::Ban => Some(format!("/ban {user}")),
+            Self::ChatSettings | Self::Shoutout => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn selected_message() -> MessageData {
+        let mut message = MessageData::new(
+            "Foo".to_string(),
+            Some("1234".to_string()),
+            false,
+            "spam".to_string(),
+            Some("abc-123".to_string()),
+            false,
+        );
+        message.login = Some("foo".to_string());
+
+        message
+    }
+
+    #[test]
+    fn test_mod_command_commands() {
+        let message = selected_message();
+
+        assert_eq!(
+            ModCommand::Delete.command(&message, 600).as_deref(),
+            Some("/delete abc-123")
+        );
+        assert_eq!(
+            ModCommand::Timeout.command(&message, 600).as_deref(),
+            Some("/timeout foo 600")
+        );
+        assert_eq!(
+            ModCommand::Ban.command(&message, 600).as_deref(),
+            Some("/ban foo")
+        );
+    }
+
+    #[test]
+    fn test_mod_command_without_tags() {
+        let mut message = selected_message();
+        message.login = None;
+        message.message_id = None;
+
+        assert_eq!(ModCommand::Delete.command(&message, 600), None);
+        assert_eq!(
+            ModCommand::Timeout.command(&message, 30).as_deref(),
+            Some("/timeout Foo 30")
+        );
+    }
+
+    #[test]
+    fn test_mod_command_on_system_message() {
+        let mut message = selected_message();
+        message.system = true;
+
+        assert_eq!(ModCommand::Ban.command(&message, 600), None);
+    }
+
+    #[test]
+    fn test_mod_command_allowed() {
+        let scopes = |scopes: &[&str]| scopes.iter().map(ToString::to_string).collect::<Vec<_>>();
+
+        let chat_only = scopes(&["chat:read", "chat:edit"]);
+        // What a token from the Twitch chat OAuth generator has.
+        let tmi = scopes(&["channel:moderate", "chat:edit", "chat:read"]);
+        let settings = scopes(&["chat:read", "moderator:manage:chat_settings"]);
+
+        for command in [ModCommand::Delete, ModCommand::Timeout, ModCommand::Ban] {
+            assert!(!command.allowed(Some(&chat_only)));
+            assert!(command.allowed(Some(&tmi)));
+            assert!(command.allowed(None));
+        }
+
+        assert!(!ModCommand::ChatSettings.allowed(Some(&tmi)));
+        assert!(ModCommand::ChatSettings.allowed(Some(&settings)));
+        assert!(!ModCommand::Shoutout.allowed(Some(&settings)));
+        assert!(!ModCommand::Ban.allowed(Some(&[])));
+    }
+
+    #[test]
+    fn test_every_mod_command_has_a_scope() {
+        let commands = [
+            ModCommand::Delete,
+            ModCommand::Timeout,
+            ModCommand::Ban,
+            ModCommand::ChatSettings,
+            ModCommand::Shoutout,
+        ];
+
+        for command in commands {
+            assert!(!command.required_scope().is_empty(), "{command:?}");
+        }
+
+        assert_eq!(
+            ModCommand::ChatSettings.command(&selected_message(), 600),
+            None
+        );
+    }
+
+    #[test]
+    fn test_mod_command_from_key() {
+        let config = ModerationConfig::default();
+
+        assert_eq!(ModCommand::from_key(&config, 'd'), Some(ModCommand::Delete));
+        assert_eq!(
+            ModCommand::from_key(&config, 't'),
+            Some(ModCommand::Timeout)
+        );
+        assert_eq!(ModCommand::from_key(&config, 'b'), Some(ModCommand::Ban));
+        assert_eq!(ModCommand::from_key(&config, 'x'), None);
+    }
+}
@@ -1,9 +1,13 @@
 use color_eyre::{eyre::ContextCompat, Result};
+use log::warn;
 use reqwest::{
     header::{HeaderMap, HeaderValue, AUTHORIZATION},
     Client,
 };
 use serde::Deserialize;
+use tokio::sync::oneshot::{channel, Receiver};
+
+use crate::utils::proxy::http_client;
 
 #[derive(Deserialize)]
 #[allow(dead_code)]
@@ -15,28 +19,73 @@ pub struct ClientId {
     expires_in: i32,
 }
 
-pub async fn get_twitch_client_id(token: &str) -> Result<String> {
-    let client = Client::new();
+async fn validate_token(token: &str) -> Result<ClientId> {
+    let client = http_client().build()?;
 
     let data = client
         .get("https://id.twitch.tv/oauth2/validate")
         .header(AUTHORIZATION, &format!("OAuth {token}"))
         .send()
         .await?
-        .error_for_status()
-        .unwrap();
+        .error_for_status()?;
 
-    let text = data.json::<ClientId>().await?;
+    Ok(data.json::<ClientId>().await?)
+}
 
-    Ok(text.client_id)
+pub async fn get_twitch_client_id(token: &str) -> Result<String> {
+    Ok(validate_token(token).await?.client_id)
 }
 
-pub async fn get_twitch_client(oauth_token: Option<String>) -> Result<Client> {
-    let token = oauth_token
-        .as_ref()
+/// The ID of the user that the token belongs to, which is who moderates through the API,
+/// whatever the configured username is.
+pub async fn get_token_user_id(oauth_token: Option<&String>) -> Result<i32> {
+    Ok(validate_token(strip_token(oauth_token)?)
+        .await?
+        .user_id
+        .parse()?)
+}
+
+fn strip_token(oauth_token: Option<&String>) -> Result<&str> {
+    oauth_token
         .context("Twitch token is empty")?
         .strip_prefix("oauth:")
-        .context("token does not start with `oauth:`")?;
+        .context("token does not start with `oauth:`")
+}
+
+/// What validating the token tells about it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TokenInfo {
+    /// The login of the user that the token belongs to.
+    pub login: String,
+    /// What the token is allowed to do.
+    pub scopes: Vec<String>,
+}
+
+/// Look up the login and scopes of the token in the background,
+/// since the configured username can differ from the login.
+pub fn query_token_info(oauth_token: Option<String>) -> Receiver<TokenInfo> {
+    let (tx, rx) = channel();
+
+    tokio::spawn(async move {
+        let info = async { validate_token(strip_token(oauth_token.as_ref())?).await }.await;
+
+        match info {
+            Ok(client_id) => {
+                tx.send(TokenInfo {
+                    login: client_id.login,
+                    scopes: client_id.scopes,
+                })
+                .ok();
+            }
+            Err(err) => warn!("Unable to validate the token: {err}"),
+        }
+    });
+
+    rx
+}
+
+pub async fn get_twitch_client(oauth_token: Option<String>) -> Result<Client> {
+    let token = strip_token(oauth_token.as_ref())?;
 
     let client_id = get_twitch_client_id(token).await?;
 
@@ -47,7 +96,7 @@ pub async fn get_twitch_client(oauth_token: Option<String>) -> Result<Client> {
     );
     headers.insert("Client-Id", HeaderValue::from_str(&client_id)?);
 
-    Ok(Client::builder().default_headers(headers).build()?)
+    Ok(http_client().default_headers(headers).build()?)
 }
 
 #[derive(Deserialize)]
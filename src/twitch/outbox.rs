@@ -0,0 +1,169 @@
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+/// A message that was sent while disconnected, to go out once the connection is back.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueuedMessage {
+    /// The IRC channel that the message goes to, such as `#xithrius`.
+    pub channel: String,
+    pub message: String,
+    queued_at: Instant,
+}
+
+/// Messages waiting for the connection to Twitch to come back, oldest first.
+///
+/// The queue holds only so many messages, dropping the oldest for new ones, and messages
+/// that waited too long are dropped instead of sent, since they would be out of place by then.
+#[derive(Debug, Clone)]
+pub struct Outbox {
+    max_len: usize,
+    max_age: Duration,
+    queue: VecDeque<QueuedMessage>,
+}
+
+impl Outbox {
+    pub const fn new(max_len: usize, max_age: Duration) -> Self {
+        Self {
+            max_len,
+            max_age,
+            queue: VecDeque::new(),
+        }
+    }
+
+    /// If messages are queued at all, where nothing is with no room for any.
+    pub const fn enabled(&self) -> bool {
+        self.max_len > 0
+    }
+
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    fn forget_stale(&mut self, now: Instant) -> usize {
+        let before = self.queue.len();
+
+        self.queue
+            .retain(|queued| now.saturating_duration_since(queued.queued_at) <= self.max_age);
+
+        before - self.queue.len()
+    }
+
+    /// Queue a message, returning how many older messages were dropped to make room for it
+    /// or for having waited too long.
+    pub fn push(&mut self, channel: String, message: String, now: Instant) -> usize {
+        if !self.enabled() {
+            return 0;
+        }
+
+        let mut dropped = self.forget_stale(now);
+
+        while self.queue.len() >= self.max_len {
+            self.queue.pop_front();
+            dropped += 1;
+        }
+
+        self.queue.push_back(QueuedMessage {
+            channel,
+            message,
+            queued_at: now,
+        });
+
+        dropped
+    }
+
+    /// The messages to send now that the connection is back, in the order that they were
+    /// sent in, along with how many were too old to send.
+    pub fn flush(&mut self, now: Instant) -> (Vec<QueuedMessage>, usize) {
+        let stale = self.forget_stale(now);
+
+        (self.queue.drain(..).collect(), stale)
+    }
+
+    pub fn clear(&mut self) {
+        self.queue.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn messages(queued: &[QueuedMessage]) -> Vec<&str> {
+        queued.iter().map(|q| q.message.as_str()).collect()
+    }
+
+    #[test]
+    fn test_flush_in_order() {
+        let mut outbox = Outbox::new(10, Duration::from_secs(60));
+        let now = Instant::now();
+
+        for message in ["first", "second", "third"] {
+            assert_eq!(
+                outbox.push("#xithrius".to_string(), message.to_string(), now),
+                0
+            );
+        }
+
+        assert_eq!(outbox.len(), 3);
+
+        let (queued, stale) = outbox.flush(now + Duration::from_secs(1));
+
+        assert_eq!(messages(&queued), vec!["first", "second", "third"]);
+        assert_eq!(queued[0].channel, "#xithrius");
+        assert_eq!(stale, 0);
+        assert_eq!(outbox.len(), 0);
+    }
+
+    #[test]
+    fn test_size_eviction() {
+        let mut outbox = Outbox::new(2, Duration::from_secs(60));
+        let now = Instant::now();
+
+        outbox.push("#a".to_string(), "1".to_string(), now);
+        outbox.push("#a".to_string(), "2".to_string(), now);
+
+        assert_eq!(outbox.push("#a".to_string(), "3".to_string(), now), 1);
+        assert_eq!(messages(&outbox.flush(now).0), vec!["2", "3"]);
+    }
+
+    #[test]
+    fn test_age_eviction() {
+        let mut outbox = Outbox::new(10, Duration::from_secs(30));
+        let now = Instant::now();
+
+        outbox.push("#a".to_string(), "old".to_string(), now);
+        outbox.push(
+            "#a".to_string(),
+            "newer".to_string(),
+            now + Duration::from_secs(20),
+        );
+
+        let (queued, stale) = outbox.flush(now + Duration::from_secs(40));
+
+        assert_eq!(messages(&queued), vec!["newer"]);
+        assert_eq!(stale, 1);
+
+        // An old message also makes way when a new one is queued.
+        outbox.push("#a".to_string(), "old".to_string(), now);
+        assert_eq!(
+            outbox.push(
+                "#a".to_string(),
+                "new".to_string(),
+                now + Duration::from_secs(31)
+            ),
+            1
+        );
+    }
+
+    #[test]
+    fn test_disabled() {
+        let mut outbox = Outbox::new(0, Duration::from_secs(30));
+        let now = Instant::now();
+
+        assert!(!outbox.enabled());
+        assert_eq!(outbox.push("#a".to_string(), "lost".to_string(), now), 0);
+        assert!(outbox.flush(now).0.is_empty());
+    }
+}
@@ -0,0 +1,440 @@
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use crate::{twitch::shoutout::Shoutout, utils::text::format_thousands};
+
+/// How long Twitch makes the channel wait between any two shoutouts.
+const SHOUTOUT_COOLDOWN: Duration = Duration::from_secs(2 * 60);
+
+/// How long Twitch makes the channel wait before shouting out the same channel again.
+const SAME_CHANNEL_COOLDOWN: Duration = Duration::from_secs(60 * 60);
+
+/// Another channel raiding the current one.
+///
+/// <https://dev.twitch.tv/docs/irc/tags/#usernotice-tags>
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Raid {
+    pub from: String,
+    /// The login of the raiding channel, which commands take.
+    pub login: String,
+    pub viewers: u64,
+}
+
+impl Raid {
+    /// Read a raid from the tags of a `USERNOTICE`, if it is one.
+    pub fn from_tags(tags: &HashMap<&str, &str>) -> Option<Self> {
+        if tags.get("msg-id") != Some(&"raid") {
+            return None;
+        }
+
+        let login = tags.get("msg-param-login");
+
+        let from = tags.get("msg-param-displayName").or(login)?;
+
+        let viewers = tags
+            .get("msg-param-viewerCount")
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or_default();
+
+        Some(Self {
+            from: (*from).to_string(),
+            login: login.unwrap_or(from).to_lowercase(),
+            viewers,
+        })
+    }
+
+    /// Text of the banner announcing the raid.
+    pub fn banner(&self) -> String {
+        format!(
+            "Raid from {} with {} {}",
+            self.from,
+            self.viewers,
+            if self.viewers == 1 {
+                "viewer"
+            } else {
+                "viewers"
+            }
+        )
+    }
+}
+
+/// Shouting out channels within the rate limits of shoutouts, either by hand or
+/// automatically after a raid.
+#[derive(Debug, Clone, Default)]
+pub struct Shoutouts {
+    last: Option<Instant>,
+    /// When each channel was last shouted out.
+    channels: HashMap<String, Instant>,
+    /// The channel to shout out, and when.
+    pending: Option<(String, Instant)>,
+}
+
+impl Shoutouts {
+    /// How long until a shoutout to the channel is allowed, where there is nothing to wait for if it is now.
+    pub fn cooldown(&self, login: &str, now: Instant) -> Option<Duration> {
+        let left = |time: &Instant, cooldown: Duration| {
+            cooldown.saturating_sub(now.saturating_duration_since(*time))
+        };
+
+        let wait = self
+            .last
+            .map(|last| left(&last, SHOUTOUT_COOLDOWN))
+            .max(
+                self.channels
+                    .get(login)
+                    .map(|last| left(last, SAME_CHANNEL_COOLDOWN)),
+            )
+            .unwrap_or_default();
+
+        (!wait.is_zero()).then_some(wait)
+    }
+
+    /// If a shoutout to the channel would be allowed now, where none are while another one is waiting.
+    pub fn should_shoutout(&self, login: &str, now: Instant) -> bool {
+        self.pending.is_none() && self.cooldown(login, now).is_none()
+    }
+
+    fn shouted_out(&mut self, login: &str, now: Instant) {
+        self.last = Some(now);
+        self.channels.insert(login.to_string(), now);
+    }
+
+    /// Count a shoutout that was asked for as done, if the cooldowns allow it, or else
+    /// how long is left of them. A raider that is waiting to be shouted out is then not,
+    /// since the cooldown would turn it down.
+    pub fn shout_out(&mut self, shoutout: &Shoutout, now: Instant) -> Result<(), Duration> {
+        if let Some(wait) = self.cooldown(&shoutout.to, now) {
+            return Err(wait);
+        }
+
+        self.pending = None;
+        self.shouted_out(&shoutout.to, now);
+
+        Ok(())
+    }
+
+    /// Shout out the raiding channel after the delay, if that is allowed.
+    pub fn raided(&mut self, raid: &Raid, delay: Duration, now: Instant) -> bool {
+        if !self.should_shoutout(&raid.login, now) {
+            return false;
+        }
+
+        self.pending = Some((raid.login.clone(), now + delay));
+
+        true
+    }
+
+    /// The shoutout that is due, which is then counted as done.
+    pub fn take_due(&mut self, now: Instant) -> Option<Shoutout> {
+        if !self.pending.as_ref().is_some_and(|(_, due)| *due <= now) {
+            return None;
+        }
+
+        let (login, _) = self.pending.take()?;
+
+        self.shouted_out(&login, now);
+
+        Some(Shoutout::new(&login))
+    }
+}
+
+/// How long is left of a cooldown, written out for the user, such as `1m 30s`.
+pub fn format_cooldown(wait: Duration) -> String {
+    // Part of a second left still has to be waited for.
+    let seconds = wait.as_secs() + u64::from(wait.subsec_nanos() > 0);
+
+    match (seconds / 60, seconds % 60) {
+        (0, seconds) => format!("{seconds}s"),
+        (minutes, 0) => format!("{minutes}m"),
+        (minutes, seconds) => format!("{minutes}m {seconds}s"),
+    }
+}
+
+/// The raiders and new chatters of a raid, shown in chat as one line that keeps being updated.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Digest {
+    /// Counts the raid windows, so that each of them gets its own line in chat.
+    pub id: usize,
+    /// The channels that raided, in the order that they did.
+    pub channels: Vec<String>,
+    pub raiders: u64,
+    pub new_chatters: u64,
+}
+
+impl Digest {
+    /// The id of the message that shows the digest in chat.
+    pub fn message_id(&self) -> String {
+        format!("raid-digest-{}", self.id)
+    }
+
+    /// Text of the message that shows the digest in chat.
+    pub fn line(&self) -> String {
+        let channels = match self.channels.as_slice() {
+            [] => String::new(),
+            [channel] => channel.clone(),
+            [rest @ .., last] => format!("{} and {last}", rest.join(", ")),
+        };
+
+        let new_chatters = match self.new_chatters {
+            0 => String::new(),
+            1 => ", 1 new chatter".to_string(),
+            count => format!(", {} new chatters", format_thousands(count)),
+        };
+
+        format!(
+            "+{} {} from {channels}{new_chatters}",
+            format_thousands(self.raiders),
+            if self.raiders == 1 {
+                "raider"
+            } else {
+                "raiders"
+            }
+        )
+    }
+}
+
+/// Condensing the notices of raids into a digest, for a while after each raid.
+#[derive(Debug, Clone, Default)]
+pub struct RaidDigest {
+    window: Duration,
+    /// The digest of the running raid window, and when it ends.
+    current: Option<(Digest, Instant)>,
+    windows: usize,
+}
+
+impl RaidDigest {
+    /// Raid notices are condensed for `window` after the last raid, or never if it is zero.
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            ..Self::default()
+        }
+    }
+
+    pub const fn enabled(&self) -> bool {
+        !self.window.is_zero()
+    }
+
+    /// The digest of the raid window that is running, where one that ran out ends.
+    fn active(&mut self, now: Instant) -> Option<&mut Digest> {
+        if self.current.as_ref().is_some_and(|(_, end)| *end <= now) {
+            self.current = None;
+        }
+
+        self.current.as_mut().map(|(digest, _)| digest)
+    }
+
+    /// Count the raid into the running digest, or start a new one, keeping it going for the window.
+    pub fn raided(&mut self, raid: &Raid, now: Instant) -> &Digest {
+        if self.active(now).is_none() {
+            self.windows += 1;
+        }
+
+        let end = now + self.window;
+        let id = self.windows;
+
+        let (digest, until) = self.current.get_or_insert_with(|| {
+            (
+                Digest {
+                    id,
+                    channels: vec![],
+                    raiders: 0,
+                    new_chatters: 0,
+                },
+                end,
+            )
+        });
+
+        *until = end;
+        digest.raiders += raid.viewers;
+
+        if !digest.channels.contains(&raid.from) {
+            digest.channels.push(raid.from.clone());
+        }
+
+        digest
+    }
+
+    /// Count a new chatter into the running digest, if a raid window is running.
+    pub fn new_chatter(&mut self, now: Instant) -> Option<&Digest> {
+        let digest = self.active(now)?;
+
+        digest.new_chatters += 1;
+
+        Some(digest)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_raid_banner_from_tags() {
+        let tags = HashMap::from([
+            ("msg-id", "raid"),
+            ("msg-param-displayName", "TestChannel"),
+            ("msg-param-login", "testchannel"),
+            ("msg-param-viewerCount", "15"),
+        ]);
+
+        let raid = Raid::from_tags(&tags).unwrap();
+
+        assert_eq!(
+            raid,
+            Raid {
+                from: "TestChannel".to_string(),
+                login: "testchannel".to_string(),
+                viewers: 15
+            }
+        );
+        assert_eq!(raid.banner(), "Raid from TestChannel with 15 viewers");
+    }
+
+    #[test]
+    fn test_raid_banner_single_viewer_login_fallback() {
+        let tags = HashMap::from([
+            ("msg-id", "raid"),
+            ("msg-param-login", "testchannel"),
+            ("msg-param-viewerCount", "1"),
+        ]);
+
+        assert_eq!(
+            Raid::from_tags(&tags).unwrap().banner(),
+            "Raid from testchannel with 1 viewer"
+        );
+    }
+
+    #[test]
+    fn test_not_a_raid() {
+        let tags = HashMap::from([("msg-id", "sub"), ("msg-param-displayName", "Foo")]);
+
+        assert_eq!(Raid::from_tags(&tags), None);
+    }
+
+    fn raid(login: &str) -> Raid {
+        Raid {
+            from: login.to_string(),
+            login: login.to_string(),
+            viewers: 10,
+        }
+    }
+
+    #[test]
+    fn test_shoutout_after_delay() {
+        let mut shoutouts = Shoutouts::default();
+        let start = Instant::now();
+        let delay = Duration::from_secs(5);
+
+        assert!(shoutouts.raided(&raid("foo"), delay, start));
+        assert_eq!(shoutouts.take_due(start + Duration::from_secs(1)), None);
+        assert_eq!(
+            shoutouts
+                .take_due(start + delay)
+                .map(|shoutout| shoutout.command())
+                .as_deref(),
+            Some("/shoutout foo")
+        );
+        assert_eq!(shoutouts.take_due(start + delay * 2), None);
+    }
+
+    #[test]
+    fn test_shoutout_cooldowns() {
+        let mut shoutouts = Shoutouts::default();
+        let start = Instant::now();
+
+        assert!(shoutouts.raided(&raid("foo"), Duration::ZERO, start));
+        assert!(!shoutouts.should_shoutout("bar", start));
+        shoutouts.take_due(start);
+
+        let later = start + Duration::from_secs(60);
+        assert!(!shoutouts.should_shoutout("bar", later));
+
+        let after_cooldown = start + SHOUTOUT_COOLDOWN;
+        assert!(shoutouts.should_shoutout("bar", after_cooldown));
+        assert!(!shoutouts.should_shoutout("foo", after_cooldown));
+        assert!(shoutouts.should_shoutout("foo", start + SAME_CHANNEL_COOLDOWN));
+    }
+
+    #[test]
+    fn test_shoutouts_by_hand() {
+        let mut shoutouts = Shoutouts::default();
+        let start = Instant::now();
+
+        assert_eq!(shoutouts.shout_out(&Shoutout::new("foo"), start), Ok(()));
+
+        let later = start + Duration::from_secs(30);
+        assert_eq!(
+            shoutouts.shout_out(&Shoutout::new("bar"), later),
+            Err(Duration::from_secs(90))
+        );
+        assert_eq!(
+            shoutouts.shout_out(&Shoutout::new("foo"), start + SHOUTOUT_COOLDOWN),
+            Err(Duration::from_secs(58 * 60))
+        );
+
+        // A raider waiting to be shouted out is left for the one asked for.
+        let after_cooldown = start + SHOUTOUT_COOLDOWN * 2;
+        assert!(shoutouts.raided(&raid("baz"), Duration::from_secs(5), after_cooldown));
+        assert_eq!(
+            shoutouts.shout_out(&Shoutout::new("bar"), after_cooldown),
+            Ok(())
+        );
+        assert_eq!(shoutouts.take_due(after_cooldown + SHOUTOUT_COOLDOWN), None);
+    }
+
+    #[test]
+    fn test_format_cooldown() {
+        assert_eq!(format_cooldown(Duration::from_secs(90)), "1m 30s");
+        assert_eq!(format_cooldown(Duration::from_secs(120)), "2m");
+        assert_eq!(format_cooldown(Duration::from_millis(4200)), "5s");
+        assert_eq!(format_cooldown(Duration::from_secs(3540)), "59m");
+    }
+
+    #[test]
+    fn test_raid_digest_counts() {
+        let mut digest = RaidDigest::new(Duration::from_secs(60));
+        let start = Instant::now();
+
+        assert_eq!(digest.new_chatter(start), None);
+
+        let mut big = raid("foo");
+        big.viewers = 1332;
+
+        digest.raided(&big, start);
+        digest.new_chatter(start + Duration::from_secs(1));
+
+        let current = digest.raided(&raid("bar"), start + Duration::from_secs(2));
+
+        assert_eq!(current.raiders, 1342);
+        assert_eq!(
+            current.line(),
+            "+1,342 raiders from foo and bar, 1 new chatter"
+        );
+        assert_eq!(current.message_id(), "raid-digest-1");
+    }
+
+    #[test]
+    fn test_raid_digest_window_expiry() {
+        let window = Duration::from_secs(60);
+        let mut digest = RaidDigest::new(window);
+        let start = Instant::now();
+
+        digest.raided(&raid("foo"), start);
+
+        // Every raid keeps the window going, new chatters do not.
+        digest.raided(&raid("foo"), start + Duration::from_secs(30));
+        assert!(digest.new_chatter(start + window).is_some());
+        assert!(digest
+            .new_chatter(start + Duration::from_secs(30) + window)
+            .is_none());
+
+        let next = digest.raided(&raid("bar"), start + window * 2);
+
+        assert_eq!(next.id, 2);
+        assert_eq!(next.line(), "+10 raiders from bar");
+        assert!(!RaidDigest::default().enabled());
+    }
+}
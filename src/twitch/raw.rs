@@ -0,0 +1,89 @@
+use std::str::FromStr;
+
+use color_eyre::{eyre::eyre, Result};
+use irc::proto::Message;
+
+const RAW_COMMAND: &str = "/raw";
+
+/// Why a line cannot be sent as it is, if it could smuggle in more lines or cut itself short.
+///
+/// IRC ends every line with CRLF, so a line with either character in it would be sent as more
+/// than one command, and a NUL is not allowed anywhere in a line.
+pub fn reject_line_breaks(line: &str) -> Result<()> {
+    line.find(['\r', '\n', '\0']).map_or(Ok(()), |index| {
+        Err(eyre!(
+            "Raw IRC lines cannot have line breaks or NUL characters in them (at character {})",
+            line[..index].chars().count() + 1
+        ))
+    })
+}
+
+/// A literal IRC line, sent with `/raw <line>`. This skips all the checks of regular messages,
+/// which is why it is turned off unless `raw_commands` is enabled.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RawLine {
+    pub line: String,
+}
+
+impl RawLine {
+    /// Read a raw line from the chat input, where there is none if the input is not the raw command.
+    pub fn parse(input: &str) -> Option<Result<Self>> {
+        let line = input
+            .strip_prefix(RAW_COMMAND)
+            .filter(|args| args.is_empty() || args.starts_with(' '))?
+            .trim_start();
+
+        Some(Self::new(line))
+    }
+
+    fn new(line: &str) -> Result<Self> {
+        reject_line_breaks(line)?;
+
+        if line.trim().is_empty() {
+            return Err(eyre!("Send what? Use {RAW_COMMAND} <IRC line>"));
+        }
+
+        Message::from_str(line).map_err(|err| eyre!("That is not an IRC line: {err}"))?;
+
+        Ok(Self {
+            line: line.to_string(),
+        })
+    }
+
+    /// The message to send over the connection.
+    pub fn message(&self) -> Message {
+        // The line was parsed when it was read, so this cannot fail.
+        Message::from_str(&self.line).expect("Raw lines are checked when they are read")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reject_line_breaks() {
+        assert!(reject_line_breaks("CAP REQ :twitch.tv/membership").is_ok());
+        assert!(reject_line_breaks("PRIVMSG #channel :hi\r\nPART #channel").is_err());
+        assert!(reject_line_breaks("PRIVMSG #channel :hi\nQUIT").is_err());
+        assert!(reject_line_breaks("PRIVMSG #channel :hi\r").is_err());
+        assert!(reject_line_breaks("PING\0").is_err());
+    }
+
+    #[test]
+    fn test_parse_raw_line() {
+        assert_eq!(
+            RawLine::parse("/raw CAP REQ :twitch.tv/membership")
+                .unwrap()
+                .unwrap()
+                .line,
+            "CAP REQ :twitch.tv/membership"
+        );
+        assert!(RawLine::parse("/raw").unwrap().is_err());
+        assert!(RawLine::parse("/raw   ").unwrap().is_err());
+        assert!(RawLine::parse("/raw JOIN #a\r\nJOIN #b").unwrap().is_err());
+
+        assert!(RawLine::parse("/rawr hello").is_none());
+        assert!(RawLine::parse("hello /raw PING").is_none());
+    }
+}
@@ -0,0 +1,228 @@
+use std::collections::HashSet;
+
+use color_eyre::Result;
+use log::{debug, warn};
+use reqwest::Client;
+use serde::Deserialize;
+use tokio::sync::oneshot::{channel, Receiver};
+
+use crate::{
+    handlers::{config::TwitchConfig, data::MessageData},
+    twitch::{
+        badges::Role,
+        oauth::{get_channel_id, get_twitch_client},
+    },
+};
+
+/// The most users that a page of a list holds.
+const USERS_PER_PAGE: usize = 100;
+
+#[derive(Deserialize, Debug, Clone)]
+struct User {
+    user_login: String,
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+struct Pagination {
+    cursor: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+struct UserList {
+    data: Vec<User>,
+    #[serde(default)]
+    pagination: Pagination,
+}
+
+/// The moderators and VIPs of a channel, as Twitch lists them, so that their role is known
+/// even on messages that do not have their badges.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ChannelRoles {
+    /// The login of the channel, being the broadcaster.
+    pub channel: String,
+    pub moderators: HashSet<String>,
+    pub vips: HashSet<String>,
+}
+
+impl ChannelRoles {
+    /// The role that a login has in the channel, if it is listed.
+    pub fn role(&self, login: &str) -> Option<Role> {
+        let login = login.to_lowercase();
+
+        if login == self.channel {
+            Some(Role::Broadcaster)
+        } else if self.moderators.contains(&login) {
+            Some(Role::Moderator)
+        } else if self.vips.contains(&login) {
+            Some(Role::Vip)
+        } else {
+            None
+        }
+    }
+
+    /// Give the author of a message their listed role, where the highest of it and the one
+    /// of their badges counts. A role that is no longer listed is taken away again.
+    pub fn apply(&self, message: &mut MessageData) {
+        if message.system {
+            return;
+        }
+
+        let listed = message.login.as_deref().and_then(|login| self.role(login));
+        let role = message.badge_role.max(listed);
+
+        if message.role != role {
+            message.role = role;
+            message.invalidate_lines();
+        }
+    }
+}
+
+/// Every login of a paginated list of users.
+async fn get_logins(client: &Client, url: &str) -> Result<HashSet<String>> {
+    let mut logins = HashSet::new();
+    let mut cursor: Option<String> = None;
+
+    loop {
+        let after = cursor
+            .as_ref()
+            .map(|cursor| format!("&after={cursor}"))
+            .unwrap_or_default();
+
+        let page = client
+            .get(format!("{url}&first={USERS_PER_PAGE}{after}"))
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<UserList>()
+            .await?;
+
+        logins.extend(page.data.into_iter().map(|user| user.user_login));
+
+        match page.pagination.cursor {
+            Some(next) if !next.is_empty() => cursor = Some(next),
+            _ => return Ok(logins),
+        }
+    }
+}
+
+// https://dev.twitch.tv/docs/api/reference/#get-moderators
+// https://dev.twitch.tv/docs/api/reference/#get-vips
+async fn get_channel_roles(twitch_config: &TwitchConfig) -> Result<ChannelRoles> {
+    let client = get_twitch_client(twitch_config.token.clone()).await?;
+    let broadcaster_id = get_channel_id(&client, &twitch_config.channel).await?;
+
+    // Each list needs its own scope, so a list that cannot be read is left empty.
+    let list = |kind: &'static str, url: String| {
+        let client = &client;
+
+        async move {
+            get_logins(client, &url).await.unwrap_or_else(|err| {
+                debug!("Unable to get the {kind} of the channel, skipping them: {err}");
+
+                HashSet::new()
+            })
+        }
+    };
+
+    Ok(ChannelRoles {
+        channel: twitch_config.channel.to_lowercase(),
+        moderators: list(
+            "moderators",
+            format!(
+                "https://api.twitch.tv/helix/moderation/moderators?broadcaster_id={broadcaster_id}"
+            ),
+        )
+        .await,
+        vips: list(
+            "VIPs",
+            format!("https://api.twitch.tv/helix/channels/vips?broadcaster_id={broadcaster_id}"),
+        )
+        .await,
+    })
+}
+
+/// Look up the moderators and VIPs of the current channel in the background.
+pub fn query_channel_roles(twitch_config: TwitchConfig) -> Receiver<ChannelRoles> {
+    let (tx, rx) = channel();
+
+    tokio::spawn(async move {
+        match get_channel_roles(&twitch_config).await {
+            Ok(roles) => {
+                tx.send(roles).ok();
+            }
+            Err(err) => warn!("Unable to look up the roles of the channel: {err}"),
+        }
+    });
+
+    rx
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn roles() -> ChannelRoles {
+        ChannelRoles {
+            channel: "xithrius".to_string(),
+            moderators: HashSet::from(["modder".to_string(), "both".to_string()]),
+            vips: HashSet::from(["vipper".to_string(), "both".to_string()]),
+        }
+    }
+
+    #[test]
+    fn test_role_lookup() {
+        let roles = roles();
+
+        assert_eq!(roles.role("xithrius"), Some(Role::Broadcaster));
+        assert_eq!(roles.role("Modder"), Some(Role::Moderator));
+        assert_eq!(roles.role("vipper"), Some(Role::Vip));
+        assert_eq!(roles.role("both"), Some(Role::Moderator));
+        assert_eq!(roles.role("viewer"), None);
+
+        assert_eq!(ChannelRoles::default().role("modder"), None);
+    }
+
+    #[test]
+    fn test_apply_roles() {
+        let roles = roles();
+
+        let mut message = MessageData::new(
+            "Vipper".to_string(),
+            Some("2".to_string()),
+            false,
+            "hello".to_string(),
+            None,
+            false,
+        );
+        message.login = Some("vipper".to_string());
+
+        roles.apply(&mut message);
+        assert_eq!(message.role, Some(Role::Vip));
+
+        // Badges that tell more are kept.
+        message.badge_role = Some(Role::Moderator);
+        roles.apply(&mut message);
+        assert_eq!(message.role, Some(Role::Moderator));
+
+        // Once the lists no longer have the author, only their badges count.
+        message.badge_role = Some(Role::Subscriber);
+        roles.apply(&mut message);
+        assert_eq!(message.role, Some(Role::Vip));
+
+        ChannelRoles::default().apply(&mut message);
+        assert_eq!(message.role, Some(Role::Subscriber));
+
+        let mut system = MessageData::new(
+            "xithrius".to_string(),
+            None,
+            true,
+            "notice".to_string(),
+            None,
+            false,
+        );
+        system.login = Some("xithrius".to_string());
+
+        roles.apply(&mut system);
+        assert_eq!(system.role, None);
+    }
+}
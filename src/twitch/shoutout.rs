@@ -0,0 +1,113 @@
+use color_eyre::{eyre::eyre, Report, Result};
+use reqwest::StatusCode;
+
+use crate::{
+    handlers::config::TwitchConfig,
+    twitch::oauth::{get_channel_id, get_token_user_id, get_twitch_client},
+};
+
+const SHOUTOUTS_URL: &str = "https://api.twitch.tv/helix/chat/shoutouts";
+
+const SHOUTOUT_COMMANDS: [&str; 2] = ["/shoutout", "/so"];
+
+/// A shoutout to another channel, sent with `/shoutout <user>` or `/so <user>`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Shoutout {
+    /// The login of the channel, which the API and the chat command take.
+    pub to: String,
+}
+
+impl Shoutout {
+    pub fn new(login: &str) -> Self {
+        Self {
+            to: login.to_lowercase(),
+        }
+    }
+
+    /// Read a shoutout from the chat input, where there is none if the input is not a shoutout command.
+    pub fn parse(input: &str) -> Option<Result<Self>> {
+        let args = SHOUTOUT_COMMANDS.iter().find_map(|command| {
+            input
+                .strip_prefix(command)
+                .filter(|args| args.is_empty() || args.starts_with(' '))
+        })?;
+
+        let mut args = args.split_whitespace();
+
+        let to = args.next().map(|to| to.trim_start_matches('@'));
+
+        Some(match (to.filter(|to| !to.is_empty()), args.next()) {
+            (Some(to), None) if to.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') => {
+                Ok(Self::new(to))
+            }
+            (Some(to), None) => Err(eyre!("'{to}' is not the name of a channel")),
+            (None, _) => Err(eyre!("Shout out who? Use /shoutout <user>")),
+            (Some(_), Some(_)) => Err(eyre!("Shoutouts are to one channel, use /shoutout <user>")),
+        })
+    }
+
+    /// The chat command that sends the shoutout, for when the API cannot be used.
+    pub fn command(&self) -> String {
+        format!("/shoutout {}", self.to)
+    }
+}
+
+/// If the API turned down a shoutout because of the token, which the chat command is tried for instead.
+pub fn needs_chat_command(err: &Report) -> bool {
+    matches!(
+        err.downcast_ref::<reqwest::Error>()
+            .and_then(reqwest::Error::status),
+        Some(StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN)
+    )
+}
+
+/// Shout out a channel in the current channel, as the user, who has to be a moderator of it
+/// with a token that has the "moderator:manage:shoutouts" scope.
+///
+/// <https://dev.twitch.tv/docs/api/reference/#send-a-shoutout>
+pub async fn send_shoutout(twitch_config: &TwitchConfig, shoutout: &Shoutout) -> Result<()> {
+    let client = get_twitch_client(twitch_config.token.clone()).await?;
+    let from_id = get_channel_id(&client, &twitch_config.channel).await?;
+    let to_id = get_channel_id(&client, &shoutout.to).await?;
+    let moderator_id = get_token_user_id(twitch_config.token.as_ref()).await?;
+
+    client
+        .post(format!(
+            "{SHOUTOUTS_URL}?from_broadcaster_id={from_id}&to_broadcaster_id={to_id}&moderator_id={moderator_id}"
+        ))
+        .send()
+        .await?
+        .error_for_status()?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_shoutout() {
+        assert_eq!(
+            Shoutout::parse("/so @Xithrius").unwrap().unwrap(),
+            Shoutout::new("xithrius")
+        );
+        assert_eq!(
+            Shoutout::parse("/shoutout  some_one ").unwrap().unwrap(),
+            Shoutout::new("some_one")
+        );
+        assert_eq!(Shoutout::new("some_one").command(), "/shoutout some_one");
+    }
+
+    #[test]
+    fn test_parse_malformed_shoutout() {
+        assert!(Shoutout::parse("/so").unwrap().is_err());
+        assert!(Shoutout::parse("/shoutout @").unwrap().is_err());
+        assert!(Shoutout::parse("/so foo bar").unwrap().is_err());
+        assert!(Shoutout::parse("/so f.o").unwrap().is_err());
+
+        assert!(Shoutout::parse("/sos foo").is_none());
+        assert!(Shoutout::parse("/shoutouts foo").is_none());
+        assert!(Shoutout::parse("so foo").is_none());
+    }
+}
@@ -0,0 +1,105 @@
+use std::{iter, ops::Range};
+
+/// Parse the value of the `emotes` tag into byte ranges of `message`.
+///
+/// The tag has the format `<id>:<start>-<end>,<start>-<end>/<id>:<start>-<end>`,
+/// where the inclusive indices count unicode code points, not bytes or graphemes.
+///
+/// Ranges that are malformed or out of bounds of the message are skipped,
+/// and the output is sorted without any overlaps.
+///
+/// <https://dev.twitch.tv/docs/irc/tags/#privmsg-tags>
+pub fn parse_emote_ranges(tag: &str, message: &str) -> Vec<Range<usize>> {
+    let mut char_ranges = tag
+        .split('/')
+        .filter_map(|emote| emote.split_once(':'))
+        .flat_map(|(_, positions)| positions.split(','))
+        .filter_map(|position| {
+            let (start, end) = position.split_once('-')?;
+
+            Some((start.parse::<usize>().ok()?, end.parse::<usize>().ok()?))
+        })
+        .filter(|(start, end)| start <= end)
+        .collect::<Vec<(usize, usize)>>();
+
+    char_ranges.sort_unstable();
+
+    // Byte offset of every code point, with the end of the message as the last item.
+    let offsets = message
+        .char_indices()
+        .map(|(i, _)| i)
+        .chain(iter::once(message.len()))
+        .collect::<Vec<usize>>();
+
+    let mut ranges: Vec<Range<usize>> = Vec::with_capacity(char_ranges.len());
+
+    for (start, end) in char_ranges {
+        let (Some(&start), Some(&end)) = (offsets.get(start), offsets.get(end + 1)) else {
+            continue;
+        };
+
+        if ranges.last().map_or(true, |last| last.end <= start) {
+            ranges.push(start..end);
+        }
+    }
+
+    ranges
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn substrings<'a>(message: &'a str, ranges: &[Range<usize>]) -> Vec<&'a str> {
+        ranges.iter().map(|r| &message[r.clone()]).collect()
+    }
+
+    #[test]
+    fn test_emote_ranges_multiple_ranges_single_id() {
+        let message = "Kappa hello Kappa";
+
+        let ranges = parse_emote_ranges("25:0-4,12-16", message);
+
+        assert_eq!(ranges, vec![0..5, 12..17]);
+        assert_eq!(substrings(message, &ranges), vec!["Kappa", "Kappa"]);
+    }
+
+    #[test]
+    fn test_emote_ranges_multiple_ids_unordered() {
+        let message = "PogChamp Kappa PogChamp";
+
+        let ranges = parse_emote_ranges("25:9-13/305954156:15-22,0-7", message);
+
+        assert_eq!(
+            substrings(message, &ranges),
+            vec!["PogChamp", "Kappa", "PogChamp"]
+        );
+    }
+
+    #[test]
+    fn test_emote_ranges_code_point_indices() {
+        // "é" is two bytes, "绝" and "对" are three bytes each, and "🐸" is four bytes.
+        let message = "Kappa héllo Kappa 绝对 PogChamp 🐸 Kappa";
+
+        let ranges = parse_emote_ranges("25:0-4,12-16,32-36/305954156:21-28", message);
+
+        assert_eq!(
+            substrings(message, &ranges),
+            vec!["Kappa", "Kappa", "PogChamp", "Kappa"]
+        );
+    }
+
+    #[test]
+    fn test_emote_ranges_out_of_bounds_and_malformed() {
+        let message = "Kappa";
+
+        let ranges = parse_emote_ranges("25:0-4,3-1,4-9,a-b/1", message);
+
+        assert_eq!(ranges, vec![0..5]);
+    }
+
+    #[test]
+    fn test_emote_ranges_empty_tag() {
+        assert!(parse_emote_ranges("", "Kappa").is_empty());
+    }
+}
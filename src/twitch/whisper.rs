@@ -0,0 +1,81 @@
+use color_eyre::{eyre::eyre, Result};
+
+const WHISPER_COMMAND: &str = "/w";
+
+/// A private message to another user, sent with `/w <user> <message>`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Whisper {
+    pub to: String,
+    pub message: String,
+}
+
+impl Whisper {
+    /// Read a whisper from the chat input, where there is none if the input is not the whisper command.
+    pub fn parse(input: &str) -> Option<Result<Self>> {
+        let args = input
+            .strip_prefix(WHISPER_COMMAND)
+            .filter(|args| args.is_empty() || args.starts_with(' '))?;
+
+        let mut args = args.trim_start().splitn(2, ' ');
+
+        let to = args.next().map(|to| to.trim_start_matches('@'));
+        let message = args.next().map(str::trim).filter(|m| !m.is_empty());
+
+        Some(match (to.filter(|to| !to.is_empty()), message) {
+            (Some(to), Some(message)) => Ok(Self {
+                to: to.to_string(),
+                message: message.to_string(),
+            }),
+            (None, _) => Err(eyre!(
+                "Whisper to who? Use {WHISPER_COMMAND} <user> <message>"
+            )),
+            (Some(_), None) => Err(eyre!(
+                "Whisper what? Use {WHISPER_COMMAND} <user> <message>"
+            )),
+        })
+    }
+
+    /// The command that sends the whisper.
+    pub fn command(&self) -> String {
+        format!("{WHISPER_COMMAND} {} {}", self.to, self.message)
+    }
+
+    /// How the sent whisper is shown in chat.
+    pub fn echo(&self) -> String {
+        format!("(whisper to {}) {}", self.to, self.message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_whisper() {
+        let whisper = Whisper::parse("/w user hello world").unwrap().unwrap();
+
+        assert_eq!(whisper.to, "user");
+        assert_eq!(whisper.message, "hello world");
+        assert_eq!(whisper.command(), "/w user hello world");
+
+        assert_eq!(
+            Whisper::parse("/w  @user   hi ").unwrap().unwrap(),
+            Whisper {
+                to: "user".to_string(),
+                message: "hi".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_malformed_whisper() {
+        assert!(Whisper::parse("/w").unwrap().is_err());
+        assert!(Whisper::parse("/w ").unwrap().is_err());
+        assert!(Whisper::parse("/w user").unwrap().is_err());
+        assert!(Whisper::parse("/w user   ").unwrap().is_err());
+        assert!(Whisper::parse("/w @ hello").unwrap().is_err());
+
+        assert!(Whisper::parse("/warn user").is_none());
+        assert!(Whisper::parse("hello /w user hi").is_none());
+    }
+}
@@ -1,5 +1,5 @@
-use fuzzy_matcher::{skim::SkimMatcherV2, FuzzyMatcher};
-use once_cell::sync::Lazy;
+use std::cmp::Reverse;
+
 use regex::Regex;
 use tui::{
     layout::Rect,
@@ -16,6 +16,7 @@ use tui::{
 
 use crate::{
     handlers::{
+        channel_history::SharedChannelHistory,
         config::SharedCompleteConfig,
         storage::SharedStorage,
         user_input::events::{Event, Key},
@@ -26,17 +27,17 @@ use crate::{
         components::{utils::InputWidget, Component},
         statics::{NAME_MAX_CHARACTERS, NAME_RESTRICTION_REGEX},
     },
-    utils::text::{first_similarity, title_line, TitleStyle},
+    utils::text::{first_similarity, fuzzy_indices, title_line, TitleStyle},
 };
 
 use super::utils::centered_rect;
 
-static FUZZY_FINDER: Lazy<SkimMatcherV2> = Lazy::new(SkimMatcherV2::default);
-
 pub struct ChannelSwitcherWidget {
     config: SharedCompleteConfig,
     focused: bool,
     storage: SharedStorage,
+    /// Forgets the channels that are removed, so that going back never goes to them.
+    channel_history: SharedChannelHistory,
     search_input: InputWidget<SharedStorage>,
     list_state: ListState,
     filtered_channels: Option<Vec<String>>,
@@ -45,7 +46,11 @@ pub struct ChannelSwitcherWidget {
 }
 
 impl ChannelSwitcherWidget {
-    pub fn new(config: SharedCompleteConfig, storage: SharedStorage) -> Self {
+    pub fn new(
+        config: SharedCompleteConfig,
+        storage: SharedStorage,
+        channel_history: SharedChannelHistory,
+    ) -> Self {
         let input_validator = Box::new(|_, s: String| -> bool {
             Regex::new(&NAME_RESTRICTION_REGEX)
                 .unwrap()
@@ -80,6 +85,7 @@ impl ChannelSwitcherWidget {
             config,
             focused: false,
             storage,
+            channel_history,
             search_input,
             list_state: ListState::default(),
             filtered_channels: None,
@@ -157,22 +163,20 @@ impl Component for ChannelSwitcherWidget {
 
             self.filtered_channels = None;
         } else {
-            let channel_filter = |c: String| -> Vec<usize> {
-                FUZZY_FINDER
-                    .fuzzy_indices(&c, &current_input)
-                    .map(|(_, indices)| indices)
-                    .unwrap_or_default()
-            };
+            let mut scored = channels
+                .iter()
+                .filter_map(|channel| {
+                    fuzzy_indices(channel, &current_input)
+                        .map(|(score, indices)| (score, channel.clone(), indices))
+                })
+                .collect::<Vec<_>>();
 
-            let mut matched = vec![];
+            // Best match first, where channels of the same score keep their order.
+            scored.sort_by_key(|(score, _, _)| Reverse(*score));
 
-            for channel in channels.clone() {
-                let matched_indices = channel_filter(channel.clone());
-
-                if matched_indices.is_empty() {
-                    continue;
-                }
+            let mut matched = vec![];
 
+            for (_, channel, matched_indices) in scored {
                 let search_theme = Style::default().fg(Color::Red).add_modifier(Modifier::BOLD);
 
                 let line = channel
@@ -277,12 +281,14 @@ impl Component for ChannelSwitcherWidget {
                                 self.storage
                                     .borrow_mut()
                                     .remove_inner_with("channels", value);
+                                self.channel_history.borrow_mut().close(value);
                             }
                         } else if let Some(value) = self.storage.borrow().get("channels").get(index)
                         {
                             self.storage
                                 .borrow_mut()
                                 .remove_inner_with("channels", value);
+                            self.channel_history.borrow_mut().close(value);
                         }
                     }
                 }
@@ -1,36 +1,85 @@
-use std::{collections::VecDeque, slice::Iter};
+use std::{
+    cell::Ref,
+    collections::VecDeque,
+    ops::Range,
+    time::{Duration, Instant},
+};
 
-use chrono::Local;
+use chrono::{DateTime, Local};
 use tui::{
     layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span, Text},
-    widgets::{block::Position, Block, Borders, List, ListItem},
+    widgets::{
+        block::{Position, Title},
+        Block, Borders, List, ListItem, Paragraph, Wrap,
+    },
     Frame,
 };
 
 use crate::{
-    emotes::SharedEmotes,
+    emotes::{emote_info_at, SharedEmotes},
     handlers::{
+        activity::{recent_activity, Activity},
         app::SharedMessages,
-        config::SharedCompleteConfig,
-        data::MessageData,
+        away::{CatchUp, CATCH_UP_ID},
+        channel_history::SharedChannelHistory,
+        chat_stats::{ChatStats, TOP_TALKERS},
+        config::{AlertsConfig, MissingScope, SharedCompleteConfig},
+        data::{message_to_quote, MessageData},
         filters::SharedFilters,
-        state::State,
+        keymap::{Keymap, NormalAction},
+        name_width::{sampled_name_width, NameWidth},
+        speakers::{RecentSpeakers, Speaker},
+        state::{InputMode, State},
         storage::SharedStorage,
         user_input::{
             events::{Event, Key},
-            scrolling::Scrolling,
+            scrolling::{ScrollState, Scrolling},
         },
     },
     terminal::TerminalAction,
-    ui::components::{
-        following::FollowingWidget, ChannelSwitcherWidget, ChatInputWidget, Component,
-        MessageSearchWidget,
+    twitch::{
+        chat_settings::RoomModes,
+        eventsub::{EventSubEvent, LiveStatus},
+        identity::UserStates,
+        media::extract_twitch_media,
+        mod_log::ModLog,
+        moderation::ModCommand,
+        raid::Digest,
+        ConnectionState, TwitchAction,
+    },
+    ui::{
+        components::{
+            command_palette::CommandPaletteWidget,
+            following::FollowingWidget,
+            pinned::PinnedMessage,
+            poll::PollWidget,
+            stats::StatsWidget,
+            utils::{
+                classify, reading_column, segments, status_spans, thumb, word_at, words,
+                BufferRows, ClickTarget, HitRegion, ScreenLayout, Segment, StatusValues,
+            },
+            ChannelSwitcherWidget, ChatInputWidget, Component, MessageSearchWidget,
+        },
+        statics::NAME_MAX_CHARACTERS,
+    },
+    utils::{
+        clipboard::copy_to_clipboard,
+        language::language_shown,
+        text::{title_line, TitleStyle},
     },
-    utils::text::{title_line, TitleStyle},
 };
 
+/// How long a banner stays on top of the chat.
+const BANNER_DURATION: Duration = Duration::from_secs(15);
+
+/// What the part of the scrollbar that shows the view is drawn with.
+const SCROLLBAR_THUMB: &str = "┃";
+
+/// How many columns the list of recent speakers takes up, including its border.
+const SPEAKERS_WIDTH: u16 = 24;
+
 pub struct ChatWidget {
     config: SharedCompleteConfig,
     messages: SharedMessages,
@@ -39,7 +88,45 @@ pub struct ChatWidget {
     search_input: MessageSearchWidget,
     following: FollowingWidget,
     filters: SharedFilters,
+    channel_history: SharedChannelHistory,
     pub scroll_offset: Scrolling,
+    /// Text announced over the chat, and when it should go away.
+    banner: Option<(String, Instant)>,
+    connection_state: ConnectionState,
+    /// If messages that look like ASCII art are shown in full.
+    ascii_art_expanded: bool,
+    /// Hype Train and goal progress of the channel.
+    pub live_status: LiveStatus,
+    /// The running poll or prediction of the channel.
+    poll: PollWidget,
+    /// Recent moderation actions in the channel.
+    pub mod_log: ModLog,
+    /// The latest people to talk in the channel.
+    pub speakers: RecentSpeakers,
+    pub pinned: PinnedMessage,
+    stats: StatsWidget,
+    command_palette: CommandPaletteWidget,
+    /// Only the messages of this author are shown, by their login.
+    author_filter: Option<String>,
+    /// Where the messages were drawn in the last frame.
+    hit_regions: Vec<HitRegion>,
+    /// The index of the message that was last clicked among the shown ones, and the byte
+    /// position in it of the word that was clicked, to tell which emote is meant.
+    emote_click: Option<(usize, usize)>,
+    /// The room modes that the channel is in.
+    pub room_modes: RoomModes,
+    /// The scopes of the token, once it has been validated.
+    pub scopes: Option<Vec<String>>,
+    /// What the user is in the channels that Twitch told it for.
+    pub user_states: UserStates,
+    keymap: Keymap,
+    /// The parts of the title, from the config.
+    status_segments: Vec<Segment>,
+    /// If only the messages are shown, without anything around them.
+    focus: bool,
+    /// The width of the column of right aligned names, when it is sized to the names in chat.
+    name_width: NameWidth,
+    emotes: SharedEmotes,
     // theme: Theme,
 }
 
@@ -50,15 +137,34 @@ impl ChatWidget {
         storage: &SharedStorage,
         emotes: &SharedEmotes,
         filters: SharedFilters,
+        channel_history: SharedChannelHistory,
     ) -> Self {
         let chat_input = ChatInputWidget::new(config.clone(), storage.clone(), emotes.clone());
-        let channel_input = ChannelSwitcherWidget::new(config.clone(), storage.clone());
+        let channel_input =
+            ChannelSwitcherWidget::new(config.clone(), storage.clone(), channel_history.clone());
         let search_input = MessageSearchWidget::new(config.clone());
         let following = FollowingWidget::new(config.clone());
 
         let scroll_offset = Scrolling::new(config.borrow().frontend.inverted_scrolling);
+        let keymap = Keymap::new(config.borrow().terminal.key_scheme);
+        let status_segments = segments(&config.borrow().frontend.status_segments);
+        let name_width = NameWidth::new(config.borrow().frontend.max_name_width, Instant::now());
 
         Self {
+            pinned: PinnedMessage::new(config.clone()),
+            stats: StatsWidget::new(config.clone()),
+            command_palette: CommandPaletteWidget::new(config.clone(), &keymap),
+            author_filter: None,
+            hit_regions: vec![],
+            emote_click: None,
+            room_modes: RoomModes::default(),
+            scopes: None,
+            user_states: UserStates::default(),
+            keymap,
+            status_segments,
+            focus: false,
+            name_width,
+            emotes: emotes.clone(),
             config,
             messages,
             chat_input,
@@ -66,8 +172,537 @@ impl ChatWidget {
             search_input,
             following,
             filters,
+            channel_history,
             scroll_offset,
+            banner: None,
+            connection_state: ConnectionState::default(),
+            ascii_art_expanded: false,
+            live_status: LiveStatus::default(),
+            poll: PollWidget::default(),
+            mod_log: ModLog::default(),
+            speakers: RecentSpeakers::default(),
+        }
+    }
+
+    /// If chat is in focus mode, which the rest of the screen is left out for too.
+    pub const fn focused(&self) -> bool {
+        self.focus
+    }
+
+    pub const fn connection_state(&self) -> ConnectionState {
+        self.connection_state
+    }
+
+    pub fn set_connection_state(&mut self, state: ConnectionState) {
+        self.connection_state = state;
+    }
+
+    /// Announce something over the chat for a short while.
+    pub fn show_banner(&mut self, text: String) {
+        self.banner = Some((text, Instant::now() + BANNER_DURATION));
+    }
+
+    /// Update what is shown of the channel with something received from event subscriptions.
+    pub fn apply_eventsub(&mut self, event: EventSubEvent) {
+        if let EventSubEvent::Moderation(entry) = event {
+            self.mod_log.push(entry);
+
+            return;
+        }
+
+        self.poll.apply(&event);
+        self.live_status.apply(event);
+    }
+
+    /// Add a new message to chat, which waits for chat to be resumed if it is paused.
+    pub fn push_message(&mut self, mut message: MessageData) {
+        message.collapsed = message.ascii_art && !self.ascii_art_expanded;
+
+        let config = self.config.borrow();
+
+        if let Some(speaker) =
+            Speaker::from_message(&message, &config.frontend).filter(|_| config.frontend.speakers)
+        {
+            self.speakers.push(speaker);
+        }
+
+        drop(config);
+
+        self.scroll_offset
+            .push(&mut self.messages.borrow_mut(), message);
+    }
+
+    /// Update the line of a raid digest in chat, or add it if it is not there yet.
+    pub fn show_raid_digest(&mut self, digest: &Digest) {
+        let message_id = digest.message_id();
+
+        if let Some(message) = self
+            .messages
+            .borrow_mut()
+            .iter_mut()
+            .find(|m| m.message_id.as_ref() == Some(&message_id))
+        {
+            message.payload = digest.line();
+            message.invalidate_lines();
+
+            return;
+        }
+
+        self.push_message(MessageData::new(
+            "Twitch".to_string(),
+            None,
+            true,
+            digest.line(),
+            Some(message_id),
+            false,
+        ));
+    }
+
+    /// Sum up what was said since the user went away, in a line where they left off.
+    pub fn show_catch_up(&self, since: DateTime<Local>, login: &str, alerts: &AlertsConfig) {
+        let mut messages = self.messages.borrow_mut();
+
+        messages.retain(|m| m.message_id.as_deref() != Some(CATCH_UP_ID));
+
+        let catch_up = CatchUp::count(messages.iter(), since, login, alerts);
+
+        if catch_up.messages == 0 {
+            return;
+        }
+
+        let index = messages.iter().take_while(|m| m.time_sent > since).count();
+
+        let mut line = MessageData::new(
+            "Twitch".to_string(),
+            None,
+            true,
+            catch_up.line(),
+            Some(CATCH_UP_ID.to_string()),
+            false,
+        );
+        line.time_sent = since;
+
+        messages.insert(index, line);
+    }
+
+    pub const fn ascii_art_expanded(&self) -> bool {
+        self.ascii_art_expanded
+    }
+
+    /// Show all messages that look like ASCII art in full, or collapse them again.
+    fn toggle_ascii_art(&mut self) {
+        self.ascii_art_expanded = !self.ascii_art_expanded;
+
+        for message in self
+            .messages
+            .borrow_mut()
+            .iter_mut()
+            .filter(|m| m.ascii_art)
+        {
+            message.collapsed = !self.ascii_art_expanded;
+            message.invalidate_lines();
+        }
+    }
+
+    /// Where keys go, apart from the statistics popup which takes any key to close.
+    pub const fn mode(&self) -> InputMode {
+        if self.chat_input.is_focused() {
+            InputMode::Insert
+        } else if self.channel_input.is_focused()
+            || self.search_input.is_focused()
+            || self.following.is_focused()
+        {
+            InputMode::Search
+        } else {
+            InputMode::Normal
+        }
+    }
+
+    /// If a popup is open over chat.
+    pub const fn modal_open(&self) -> bool {
+        self.channel_input.is_focused()
+            || self.following.is_focused()
+            || self.stats.is_focused()
+            || self.command_palette.is_focused()
+    }
+
+    /// Keep chat from moving while a popup is open, see [`Scrolling::set_modal`].
+    pub fn set_modal(&mut self, open: bool) {
+        self.scroll_offset
+            .set_modal(open, &mut self.messages.borrow_mut());
+    }
+
+    /// Show any messages held back while paused, and follow chat from the newest message.
+    pub fn resume(&mut self) {
+        self.scroll_offset.resume(&mut self.messages.borrow_mut());
+    }
+
+    /// If the lowest message shown is selected, to be acted upon as a moderator,
+    /// or to be shown in full if it was cut short.
+    fn selecting(&self) -> bool {
+        let config = self.config.borrow();
+
+        (config.moderation.enabled
+            || config.frontend.truncate_lines > 0
+            || self.scroll_offset.is_selected())
+            && self.scroll_offset.state() != ScrollState::Following
+    }
+
+    /// If a message is shown in chat, which is not the case when it is filtered out,
+    /// or when only the messages of another author are shown.
+    fn shown(&self, data: &MessageData) -> bool {
+        !self.filters.borrow().contaminated(&data.payload)
+            && language_shown(
+                data.language.as_deref(),
+                &self.config.borrow().filters.languages,
+            )
+            && self.author_filter.as_ref().map_or(true, |author| {
+                !data.system
+                    && data
+                        .login
+                        .as_deref()
+                        .unwrap_or(&data.author)
+                        .eq_ignore_ascii_case(author)
+            })
+    }
+
+    /// Show only the messages of an author, or all messages again if they were already the only ones.
+    fn toggle_author_filter(&mut self, author: String) {
+        self.author_filter = if self.author_filter.as_ref() == Some(&author) {
+            None
+        } else {
+            Some(author)
+        };
+
+        // The offset counts the shown messages, which are now different ones.
+        self.resume();
+    }
+
+    /// Act on a click on chat, by what it landed on in the last frame.
+    fn click(&mut self, column: u16, row: u16) {
+        if !self.config.borrow().frontend.mouse_clicks {
+            return;
         }
+
+        let Some((index, target)) = classify(&self.hit_regions, column, row) else {
+            return;
+        };
+
+        match target {
+            ClickTarget::Name => {
+                let author = self
+                    .messages
+                    .borrow()
+                    .iter()
+                    .filter(|data| self.shown(data))
+                    .nth(index)
+                    .map(|data| data.login.clone().unwrap_or_else(|| data.author.clone()));
+
+                if let Some(author) = author {
+                    self.toggle_author_filter(author);
+                }
+            }
+            ClickTarget::Timestamp => {
+                self.emote_click = None;
+                self.scroll_offset.select_at(index);
+            }
+            ClickTarget::Body => {
+                let position = self
+                    .hit_regions
+                    .iter()
+                    .find(|region| region.index == index)
+                    .and_then(|region| word_at(region, column, row))
+                    .and_then(|(word, before)| {
+                        let messages = self.messages.borrow();
+                        let message = messages.iter().filter(|data| self.shown(data)).nth(index)?;
+
+                        let position = words(&message.payload)
+                            .filter(|(_, other)| *other == word)
+                            .nth(before)
+                            .map(|(position, _)| position);
+
+                        position
+                    });
+
+                self.emote_click = position.map(|position| (index, position));
+                self.scroll_offset.select_at(index);
+            }
+        }
+    }
+
+    /// Where the selected message is among all messages, or the newest shown one while following chat.
+    fn selected_index(&self) -> Option<usize> {
+        let offset = if self.scroll_offset.state() == ScrollState::Following {
+            0
+        } else {
+            self.scroll_offset.get_offset()
+        };
+
+        self.messages
+            .borrow()
+            .iter()
+            .enumerate()
+            .filter(|(_, data)| self.shown(data))
+            .nth(offset)
+            .map(|(index, _)| index)
+    }
+
+    /// The selected message, or the newest shown one while following chat.
+    fn selected_message(&self) -> Option<Ref<'_, MessageData>> {
+        let index = self.selected_index()?;
+
+        Some(Ref::map(self.messages.borrow(), |messages| {
+            &messages[index]
+        }))
+    }
+
+    /// Move the selection through the messages shown in chat, to older ones when `step` is positive.
+    fn select(&mut self, step: isize) {
+        let count = self
+            .messages
+            .borrow()
+            .iter()
+            .filter(|data| self.shown(data))
+            .count();
+
+        self.emote_click = None;
+        self.scroll_offset.select(step, count);
+    }
+
+    /// Show the selected message in full, or cut it short again.
+    fn toggle_selected_expanded(&self) {
+        if !self.selecting() {
+            return;
+        }
+
+        if let Some(index) = self.selected_index() {
+            let message = &mut self.messages.borrow_mut()[index];
+
+            message.expanded = !message.expanded;
+            message.invalidate_lines();
+        }
+    }
+
+    /// Unpin the pinned message, or else pin the selected message while scrolled up,
+    /// or the latest one of the streamer while following chat.
+    fn toggle_pin(&mut self) {
+        if self.pinned.is_pinned() {
+            self.pinned.unpin();
+            return;
+        }
+
+        let index = if self.scroll_offset.state() == ScrollState::Following {
+            let channel = &self.config.borrow().twitch.channel;
+
+            self.messages.borrow().iter().position(|data| {
+                self.shown(data)
+                    && !data.system
+                    && data
+                        .login
+                        .as_deref()
+                        .unwrap_or(&data.author)
+                        .eq_ignore_ascii_case(channel)
+            })
+        } else {
+            self.selected_index()
+        };
+
+        let messages = self.messages.borrow();
+
+        if let Some(message) = index.map(|i| &messages[i]).filter(|data| !data.system) {
+            self.pinned.pin(message);
+        }
+    }
+
+    /// Copy the selected message, or the latest one while following chat, as a quote to share.
+    fn copy_quote(&mut self) {
+        let quote = {
+            let config = self.config.borrow();
+
+            self.selected_message()
+                .filter(|data| !data.system)
+                .map(|data| {
+                    message_to_quote(
+                        &data,
+                        &config.twitch.channel,
+                        &config.frontend.datetime_format,
+                    )
+                })
+        };
+
+        let Some(quote) = quote else {
+            return;
+        };
+
+        match copy_to_clipboard(&quote) {
+            Ok(()) => self.show_banner("Copied the message as a quote".to_string()),
+            Err(err) => self.show_banner(format!("Unable to copy the message: {err}")),
+        }
+    }
+
+    /// The moderator action bound to a key, and the command that performs it on the selected message.
+    fn moderation_command(&self, key: Key) -> Option<(ModCommand, String)> {
+        // Keys that start typing always do, so that what is typed is never taken as an action.
+        if self.keymap.next_mode(InputMode::Normal, key) != InputMode::Normal {
+            return None;
+        }
+
+        let Key::Char(key) = key else {
+            return None;
+        };
+
+        if !self.selecting() {
+            return None;
+        }
+
+        let config = self.config.borrow();
+
+        if !config.moderation.enabled {
+            return None;
+        }
+        let action = ModCommand::from_key(&config.moderation, key)?;
+
+        self.selected_message()
+            .and_then(|message| action.command(&message, config.moderation.timeout_duration))
+            .map(|command| (action, command))
+    }
+
+    /// If a moderator action should be sent, saying so when the user is not a moderator of the
+    /// channel or the token lacks the scope for it.
+    fn permitted(&mut self, action: ModCommand) -> bool {
+        let missing_scope = self.config.borrow().moderation.missing_scope;
+
+        if missing_scope == MissingScope::Ignore {
+            return true;
+        }
+
+        // Twitch tells if the user is a moderator of the channel once it is joined.
+        let not_moderator = self
+            .user_states
+            .get(&self.config.borrow().twitch.channel)
+            .is_some_and(|state| !state.is_moderator());
+
+        if not_moderator {
+            self.show_banner(format!(
+                "You are not a moderator of this channel, so you cannot {}",
+                action.describe()
+            ));
+
+            return missing_scope != MissingScope::Block;
+        }
+
+        if !action.allowed(self.scopes.as_deref()) {
+            self.show_banner(format!(
+                "The token needs the {} scope to {}",
+                action.required_scope(),
+                action.describe()
+            ));
+
+            return missing_scope != MissingScope::Block;
+        }
+
+        true
+    }
+
+    /// Do what a key is bound to in normal mode.
+    async fn normal_action(&mut self, action: NormalAction) -> Option<TerminalAction> {
+        match action {
+            NormalAction::Insert => self.chat_input.toggle_focus(),
+            NormalAction::InsertMention => self.chat_input.toggle_focus_with("@"),
+            NormalAction::InsertCommand => self.chat_input.toggle_focus_with("/"),
+            NormalAction::SwitchChannel => self.channel_input.toggle_focus(),
+            NormalAction::SearchMessages => self.search_input.toggle_focus(),
+            NormalAction::Following => self.following.toggle_focus().await,
+            NormalAction::ToggleFilter => self.filters.borrow_mut().toggle(),
+            NormalAction::ReverseFilter => self.filters.borrow_mut().reverse(),
+            NormalAction::PreviousChannel => {
+                let previous_channel = self
+                    .channel_history
+                    .borrow()
+                    .previous()
+                    .map(ToString::to_string);
+
+                if let Some(channel) = previous_channel {
+                    self.config.borrow_mut().twitch.channel.clone_from(&channel);
+
+                    return Some(TerminalAction::Enter(TwitchAction::Join(channel)));
+                }
+            }
+            NormalAction::Dashboard => return Some(TerminalAction::SwitchState(State::Dashboard)),
+            NormalAction::Help => return Some(TerminalAction::SwitchState(State::Help)),
+            NormalAction::Quit => return Some(TerminalAction::Quit),
+            NormalAction::OpenPlayer => self.open_in_browser(),
+            NormalAction::OpenLatestMedia => self.open_latest_media(),
+            NormalAction::ToggleAsciiArt => self.toggle_ascii_art(),
+            NormalAction::DismissPoll => self.poll.dismiss(),
+            NormalAction::TogglePin => self.toggle_pin(),
+            NormalAction::CopyQuote => self.copy_quote(),
+            NormalAction::SelectOlder => self.select(1),
+            NormalAction::SelectNewer => self.select(-1),
+            NormalAction::ToggleExpanded => self.toggle_selected_expanded(),
+            NormalAction::ToggleFocus => self.focus = !self.focus,
+            NormalAction::Resume => self.resume(),
+            NormalAction::TogglePause => {
+                if self.scroll_offset.state() == ScrollState::Paused {
+                    self.resume();
+                } else {
+                    self.scroll_offset.pause();
+                }
+            }
+            NormalAction::Top => {
+                // TODO: Make this not jump to nothingness
+                self.scroll_offset.jump_to(self.messages.borrow().len());
+            }
+            NormalAction::Back => {
+                // A filtered view of chat is left before chat itself.
+                if self.author_filter.take().is_none()
+                    && self.scroll_offset.state() == ScrollState::Following
+                {
+                    return Some(TerminalAction::BackOneLayer);
+                }
+
+                self.resume();
+            }
+            NormalAction::CommandPalette => self.command_palette.toggle_focus(),
+            NormalAction::Panic => panic!("Manual panic triggered by user."),
+        }
+
+        None
+    }
+
+    /// What the emote of the selected message is, to be shown while it is selected. That is the
+    /// emote nearest to where the message was clicked, or else its first one.
+    fn selected_emote_info(&self) -> Option<String> {
+        if !self.config.borrow().frontend.emote_info || !self.selecting() {
+            return None;
+        }
+
+        let position = self
+            .emote_click
+            .filter(|(index, _)| *index == self.scroll_offset.get_offset())
+            .map_or(0, |(_, position)| position);
+
+        let message = self.selected_message()?;
+
+        emote_info_at(&message, &self.emotes.emotes.borrow(), position).map(|info| info.line())
+    }
+
+    /// The unsent message in the chat input.
+    pub fn input_draft(&self) -> String {
+        self.chat_input.to_string()
+    }
+
+    pub fn set_input_draft(&mut self, draft: &str) {
+        self.chat_input.update(draft);
+    }
+
+    /// If what is typed is only kept until quitting.
+    pub const fn is_history_ephemeral(&self) -> bool {
+        self.chat_input.is_history_ephemeral()
+    }
+
+    pub fn save_input_history(&self) {
+        self.chat_input.save_history();
     }
 
     pub fn open_in_browser(&self) {
@@ -76,47 +711,137 @@ impl ChatWidget {
             self.config.borrow().twitch.channel).as_str()).unwrap();
     }
 
+    /// Open the most recent clip, VOD or channel that was linked in chat.
+    pub fn open_latest_media(&mut self) {
+        let media = self
+            .messages
+            .borrow()
+            .iter()
+            .filter(|m| !m.system)
+            .find_map(|m| extract_twitch_media(&m.payload).pop());
+
+        if let Some(media) = media {
+            if let Err(err) = webbrowser::open(&media.url()) {
+                self.show_banner(format!("Unable to open {}: {err}", media.url()));
+            }
+        }
+    }
+
+    /// The latest moderation actions, newest first.
+    fn draw_mod_feed(&self, f: &mut Frame, area: Rect) {
+        let lines = self
+            .mod_log
+            .entries()
+            .map(|entry| {
+                Line::from(vec![
+                    Span::styled(
+                        entry.time.format("%H:%M:%S ").to_string(),
+                        Style::default().fg(Color::DarkGray),
+                    ),
+                    Span::raw(entry.describe()),
+                ])
+            })
+            .collect::<Vec<_>>();
+
+        let title = [TitleStyle::Single("Moderation")];
+
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_type(self.config.borrow().frontend.border_type.clone().into())
+            .title(title_line(
+                &title,
+                Style::default().add_modifier(Modifier::BOLD),
+            ));
+
+        f.render_widget(
+            Paragraph::new(lines).block(block).wrap(Wrap { trim: true }),
+            area,
+        );
+    }
+
+    /// The latest people to talk, most recent first, with the glyph of their role.
+    fn draw_speakers(&self, f: &mut Frame, area: Rect) {
+        let lines = self
+            .speakers
+            .entries()
+            .map(|speaker| {
+                Line::from(Span::styled(
+                    speaker.label(),
+                    Style::default().fg(speaker.color),
+                ))
+            })
+            .collect::<Vec<_>>();
+
+        let title = [TitleStyle::Single("Speakers")];
+
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_type(self.config.borrow().frontend.border_type.clone().into())
+            .title(title_line(
+                &title,
+                Style::default().add_modifier(Modifier::BOLD),
+            ));
+
+        f.render_widget(Paragraph::new(lines).block(block), area);
+    }
+
+    pub fn set_emote_only(&mut self, emote_only: bool) {
+        self.chat_input.set_emote_only(emote_only);
+    }
+
     pub fn get_messages<'a>(
         &self,
-        frame: &Frame,
         area: Rect,
         messages_data: &'a VecDeque<MessageData>,
-    ) -> VecDeque<Line<'a>> {
+    ) -> (VecDeque<Line<'a>>, Vec<HitRegion>, BufferRows) {
         // Accounting for not all heights of rows to be the same due to text wrapping,
         // so extra space needs to be used in order to scroll correctly.
         let mut total_row_height: usize = 0;
 
         let mut messages = VecDeque::new();
+        let mut hit_regions = vec![];
+
+        // The scrollbar needs the rows of every message, not only of the ones in view.
+        let mut buffer_rows = BufferRows::default();
+        let mut full = false;
 
         let general_chunk_height = area.height as usize - 2;
 
         let mut scroll = self.scroll_offset.get_offset();
 
-        // Horizontal chunks represents the list within the main chat window.
-        let h_chunk = Layout::default()
-            .direction(Direction::Horizontal)
-            .constraints([Constraint::Min(1)])
-            .split(frame.size());
+        let config = self.config.borrow();
 
-        let message_chunk_width = h_chunk[0].width as usize;
+        // Messages subtract the margins from the width themselves.
+        let message_chunk_width = area.width as usize + usize::from(config.frontend.margin) * 2;
 
-        let config = self.config.borrow();
+        let group_window = (config.frontend.group_messages_window > 0)
+            .then(|| Duration::from_secs(config.frontend.group_messages_window));
 
-        'outer: for data in messages_data {
-            if self
-                .filters
-                .borrow()
-                .contaminated(data.payload.clone().as_str())
-            {
-                continue;
-            }
+        let mut selecting = self.selecting();
 
-            // Offsetting of messages for scrolling through said messages
-            if scroll > 0 {
-                scroll -= 1;
+        let name_width = if config.frontend.auto_name_width {
+            self.name_width.width()
+        } else {
+            NAME_MAX_CHARACTERS
+        };
 
-                continue;
-            }
+        // The rows of chat on screen start after the border, if there is one.
+        let border = u16::from(!config.frontend.hide_chat_border);
+        let (screen_x, screen_y) = (area.x + border, area.y + border);
+
+        let mut visible_messages = messages_data
+            .iter()
+            .filter(|data| self.shown(data))
+            .enumerate()
+            .peekable();
+
+        while let Some((index, data)) = visible_messages.next() {
+            // Messages are from newest to oldest, so the previous message is the next one.
+            let grouped = group_window.is_some_and(|window| {
+                visible_messages
+                    .peek()
+                    .is_some_and(|(_, previous)| data.continues_group(previous, window))
+            });
 
             let username_highlight: Option<&str> = if config.frontend.username_highlight {
                 Some(config.twitch.username.as_str())
@@ -126,25 +851,111 @@ impl ChatWidget {
 
             let search = self.search_input.to_string();
 
-            let lines = data.to_vec(
-                &self.config.borrow().frontend,
-                message_chunk_width,
-                if self.search_input.is_focused() {
-                    Some(&search)
-                } else {
-                    None
-                },
-                username_highlight,
-            );
+            let wrap = || {
+                data.wrapped_lines(
+                    &config.frontend,
+                    message_chunk_width,
+                    name_width,
+                    if self.search_input.is_focused() {
+                        Some(&search)
+                    } else {
+                        None
+                    },
+                    username_highlight,
+                    grouped,
+                )
+            };
+
+            // Offsetting of messages for scrolling through said messages
+            if scroll > 0 || full {
+                if !config.frontend.scrollbar {
+                    if full {
+                        break;
+                    }
+
+                    scroll -= 1;
+
+                    continue;
+                }
+
+                let height = wrap().len();
+
+                buffer_rows.total += height;
+
+                if scroll > 0 {
+                    buffer_rows.below += height;
+                    scroll -= 1;
+                }
+
+                continue;
+            }
+
+            let mut lines = wrap();
+
+            // The selected message is the first one after the offset.
+            if std::mem::take(&mut selecting) {
+                lines = lines
+                    .into_iter()
+                    .map(|line| line.patch_style(Modifier::REVERSED))
+                    .collect();
+            }
+
+            let before = total_row_height;
+            let line_count = lines.len();
+
+            // The header is only known to be in view when the whole message is.
+            let (timestamp, name) = if line_count <= general_chunk_height - before {
+                lines.first().map_or((None, None), |first_row| {
+                    data.header_columns(&config.frontend, first_row, grouped)
+                })
+            } else {
+                (None, None)
+            };
+
+            let texts = lines
+                .iter()
+                .map(|line| {
+                    line.spans
+                        .iter()
+                        .map(|span| span.content.as_ref())
+                        .collect::<String>()
+                })
+                .collect::<Vec<_>>();
+
+            let mut pushed = 0;
 
             for span in lines.into_iter().rev() {
                 if total_row_height < general_chunk_height {
                     messages.push_front(span);
                     total_row_height += 1;
+                    pushed += 1;
                 } else {
-                    break 'outer;
+                    break;
                 }
             }
+
+            if pushed > 0 {
+                // Chat is drawn from the bottom up, so the rows are counted from the bottom.
+                let bottom = general_chunk_height - before;
+                let to_screen = |columns: Range<usize>| {
+                    screen_x + columns.start as u16..screen_x + columns.end as u16
+                };
+
+                hit_regions.push(HitRegion {
+                    index,
+                    rows: screen_y + (bottom - pushed) as u16..screen_y + bottom as u16,
+                    timestamp: timestamp.map(to_screen),
+                    name: name.map(to_screen),
+                    left: screen_x,
+                    lines: texts,
+                });
+            }
+
+            buffer_rows.total += line_count;
+
+            if pushed < line_count {
+                full = true;
+            }
         }
 
         // Padding with empty rows so chat can go from bottom to top.
@@ -154,31 +965,100 @@ impl ChatWidget {
             }
         }
 
-        messages
+        (messages, hit_regions, buffer_rows)
+    }
+}
+
+/// A scrollbar on the right edge of chat, over the border if there is one, showing which part of
+/// the buffer is in view.
+fn draw_scrollbar(f: &mut Frame, area: Rect, rows: BufferRows) {
+    let track = area.height.saturating_sub(2);
+    let viewport = usize::from(track);
+
+    let Some(thumb) = thumb(rows, viewport, track) else {
+        return;
+    };
+
+    let x = area.right().saturating_sub(1);
+    let buffer = f.buffer_mut();
+
+    for row in thumb {
+        buffer
+            .get_mut(x, area.y + 1 + row)
+            .set_symbol(SCROLLBAR_THUMB)
+            .set_style(Style::default().fg(Color::Gray));
     }
 }
 
 impl Component for ChatWidget {
     fn draw(&mut self, f: &mut Frame, area: Option<Rect>) {
-        let r = area.map_or_else(|| f.size(), |a| a);
-
         let config = self.config.borrow();
 
-        let mut v_constraints = vec![Constraint::Min(1)];
+        let r = reading_column(
+            area.map_or_else(|| f.size(), |a| a),
+            config.frontend.max_width,
+            config.frontend.column_alignment,
+        );
 
-        if self.chat_input.is_focused() || self.search_input.is_focused() {
-            v_constraints.push(Constraint::Length(3));
-        }
+        let input_height =
+            (self.chat_input.is_focused() || self.search_input.is_focused()).then_some(3);
+
+        let layout = if self.focus {
+            ScreenLayout::focused(r, config.frontend.input_position, input_height)
+        } else {
+            ScreenLayout::new(
+                r,
+                config.frontend.input_position,
+                config.frontend.margin,
+                input_height,
+                false,
+            )
+        };
+
+        let speakers_shown = config.frontend.speakers && !self.focus;
+        let feed_shown = config.moderation.feed && !self.focus;
+
+        let (chat_area, speakers_area, feed_area) = {
+            let mut constraints = vec![Constraint::Min(1)];
+
+            if speakers_shown {
+                constraints.push(Constraint::Length(SPEAKERS_WIDTH));
+            }
+            if feed_shown {
+                constraints.push(Constraint::Percentage(30));
+            }
+
+            let h_chunks = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints(constraints)
+                .split(layout.chat);
+            let mut sidebars = h_chunks[1..].iter().copied();
+
+            (
+                h_chunks[0],
+                speakers_shown.then(|| sidebars.next()).flatten(),
+                feed_shown.then(|| sidebars.next()).flatten(),
+            )
+        };
+
+        let pinned_height = if self.focus {
+            0
+        } else {
+            self.pinned.height(chat_area.width)
+        };
 
-        let v_chunks_binding = Layout::default()
-            .direction(Direction::Vertical)
-            .margin(self.config.borrow().frontend.margin)
-            .constraints(v_constraints)
-            .split(r);
+        let (pinned_area, chat_area) = if pinned_height > 0 {
+            let v_chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Length(pinned_height), Constraint::Min(1)])
+                .split(chat_area);
 
-        let mut v_chunks: Iter<Rect> = v_chunks_binding.iter();
+            (Some(v_chunks[0]), v_chunks[1])
+        } else {
+            (None, chat_area)
+        };
 
-        let first_v_chunk = v_chunks.next().unwrap();
+        let first_v_chunk = &chat_area;
 
         if self.messages.borrow().len() > self.config.borrow().terminal.maximum_messages {
             self.messages
@@ -188,32 +1068,94 @@ impl Component for ChatWidget {
 
         let messages_data = self.messages.borrow();
 
-        let messages = self.get_messages(f, *first_v_chunk, &messages_data);
+        if config.frontend.auto_name_width {
+            let sample = sampled_name_width(
+                messages_data.iter().filter(|data| self.shown(data)),
+                config.frontend.max_name_width,
+            );
+
+            self.name_width
+                .update(sample, config.frontend.max_name_width, Instant::now());
+        }
+
+        let (messages, hit_regions, buffer_rows) =
+            self.get_messages(*first_v_chunk, &messages_data);
+        self.hit_regions = hit_regions;
 
         let current_time = Local::now()
             .format(&config.frontend.datetime_format)
             .to_string();
 
-        let spans = [
-            TitleStyle::Combined("Time", &current_time),
-            TitleStyle::Combined("Channel", config.twitch.channel.as_str()),
-            TitleStyle::Custom(Span::styled(
-                if self.filters.borrow().reversed() {
-                    "retliF"
-                } else {
-                    "Filter"
-                },
-                Style::default().add_modifier(Modifier::BOLD).fg(
-                    if self.filters.borrow().enabled() {
-                        Color::Green
+        let connection_label = self.connection_state.label();
+
+        let channel_style = if config.frontend.activity_style {
+            let recent = recent_activity(
+                messages_data.iter(),
+                Duration::from_secs(config.frontend.activity_window),
+                Local::now(),
+            );
+
+            Activity::from_count(
+                recent,
+                config.frontend.quiet_activity,
+                config.frontend.busy_activity,
+            )
+            .style()
+        } else {
+            Style::default()
+        };
+
+        let held_binding = (self.scroll_offset.state() == ScrollState::Paused)
+            .then(|| format!("{} new", self.scroll_offset.held()));
+
+        let mode = self.mode();
+
+        let filters = self.filters.borrow();
+        let modes_binding = self.room_modes.summary();
+
+        let spans = status_spans(
+            &self.status_segments,
+            &StatusValues {
+                mode: Span::styled(
+                    mode.label(),
+                    Style::default()
+                        .add_modifier(Modifier::BOLD | Modifier::REVERSED)
+                        .fg(mode.color()),
+                ),
+                connection: Span::styled(
+                    connection_label.as_str(),
+                    Style::default()
+                        .add_modifier(Modifier::BOLD)
+                        .fg(self.connection_state.color()),
+                ),
+                time: &current_time,
+                channel: Span::styled(
+                    config.twitch.channel.as_str(),
+                    config
+                        .channel_accent(&config.twitch.channel)
+                        .map_or(channel_style, |accent| channel_style.fg(accent)),
+                ),
+                filter: Span::styled(
+                    if filters.reversed() {
+                        "retliF"
                     } else {
-                        Color::Red
+                        "Filter"
                     },
+                    Style::default()
+                        .add_modifier(Modifier::BOLD)
+                        .fg(if filters.enabled() {
+                            Color::Green
+                        } else {
+                            Color::Red
+                        }),
                 ),
-            )),
-        ];
+                paused: held_binding.as_deref(),
+                author: self.author_filter.as_deref(),
+                modes: modes_binding.as_deref(),
+            },
+        );
 
-        let chat_title = if self.config.borrow().frontend.title_shown {
+        let chat_title = if self.config.borrow().frontend.title_shown && !self.focus {
             Line::from(title_line(
                 &spans,
                 Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
@@ -222,28 +1164,113 @@ impl Component for ChatWidget {
             Line::default()
         };
 
+        if self
+            .banner
+            .as_ref()
+            .is_some_and(|(_, expiry)| *expiry <= Instant::now())
+        {
+            self.banner = None;
+        }
+
+        let banner_binding = self
+            .banner
+            .as_ref()
+            .map(|(text, _)| [TitleStyle::Single(text)]);
+
+        let banner_title = banner_binding.as_ref().map(|banner| {
+            Title::from(Line::from(title_line(
+                banner,
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD | Modifier::REVERSED),
+            )))
+            .alignment(Alignment::Center)
+        });
+
+        let live_summary = self.live_status.summary().filter(|_| !self.focus);
+        let live_binding = live_summary.as_deref().map(|s| [TitleStyle::Single(s)]);
+
+        let live_title = live_binding.as_ref().map(|live| {
+            Title::from(Line::from(title_line(
+                live,
+                Style::default()
+                    .fg(Color::Magenta)
+                    .add_modifier(Modifier::BOLD),
+            )))
+            .position(Position::Bottom)
+            .alignment(Alignment::Left)
+        });
+
+        let emote_info = self.selected_emote_info().filter(|_| !self.focus);
+        let emote_info_binding = emote_info.as_deref().map(|s| [TitleStyle::Single(s)]);
+
+        let emote_info_title = emote_info_binding.as_ref().map(|info| {
+            Title::from(Line::from(title_line(
+                info,
+                Style::default().fg(Color::Cyan),
+            )))
+            .position(Position::Bottom)
+            .alignment(Alignment::Right)
+        });
+
         let mut final_messages = vec![];
 
         for item in messages {
             final_messages.push(ListItem::new(Text::from(item)));
         }
 
+        let mut block = if self.config.borrow().frontend.hide_chat_border {
+            Block::default()
+        } else {
+            let block = Block::default()
+                .borders(Borders::ALL)
+                .border_type(self.config.borrow().frontend.border_type.clone().into());
+
+            // Without an accent of its own, the border is left to the terminal.
+            match config.channel_accent(&config.twitch.channel) {
+                Some(accent) => block.border_style(Style::default().fg(accent)),
+                None => block,
+            }
+        }
+        .title(chat_title);
+
+        if let Some(banner_title) = banner_title {
+            block = block.title(banner_title);
+        }
+
+        if let Some(live_title) = live_title.clone() {
+            block = block.title(live_title);
+        }
+
+        if let Some(emote_info_title) = emote_info_title {
+            block = block.title(emote_info_title);
+        }
+
         let list = List::new(final_messages)
-            .block(
-                if self.config.borrow().frontend.hide_chat_border {
-                    Block::default()
-                } else {
-                    Block::default()
-                        .borders(Borders::ALL)
-                        .border_type(self.config.borrow().frontend.border_type.clone().into())
-                }
-                .title(chat_title),
-            )
+            .block(block)
             .style(Style::default().fg(Color::White));
 
         f.render_widget(list, *first_v_chunk);
 
-        if self.config.borrow().frontend.show_scroll_offset {
+        if config.frontend.scrollbar {
+            draw_scrollbar(f, *first_v_chunk, buffer_rows);
+        }
+
+        if !self.focus {
+            self.poll.draw(f, Some(*first_v_chunk));
+        }
+
+        self.pinned.draw(f, pinned_area);
+
+        if let Some(speakers_area) = speakers_area {
+            self.draw_speakers(f, speakers_area);
+        }
+
+        if let Some(feed_area) = feed_area {
+            self.draw_mod_feed(f, feed_area);
+        }
+
+        if self.config.borrow().frontend.show_scroll_offset && !self.focus {
             // Cannot scroll past the first message
             let message_amount = messages_data.len().saturating_sub(1);
 
@@ -255,13 +1282,18 @@ impl Component for ChatWidget {
 
             let title = [TitleStyle::Single(&title_binding)];
 
-            let bottom_block = Block::default()
+            let mut bottom_block = Block::default()
                 .borders(Borders::BOTTOM | Borders::LEFT | Borders::RIGHT)
                 .border_type(self.config.borrow().frontend.border_type.clone().into())
                 .title(title_line(&title, Style::default()))
                 .title_position(Position::Bottom)
                 .title_alignment(Alignment::Right);
 
+            // The bottom border is drawn over, along with what was shown on it.
+            if let Some(live_title) = live_title {
+                bottom_block = bottom_block.title(live_title);
+            }
+
             let rect = Rect::new(
                 first_v_chunk.x,
                 first_v_chunk.bottom() - 1,
@@ -272,12 +1304,20 @@ impl Component for ChatWidget {
             f.render_widget(bottom_block, rect);
         }
 
+        if self.stats.is_focused() {
+            self.stats.draw(f, None);
+        }
+
+        if self.command_palette.is_focused() {
+            self.command_palette.draw(f, None);
+        }
+
         if self.chat_input.is_focused() {
-            self.chat_input.draw(f, v_chunks.next().copied());
+            self.chat_input.draw(f, layout.input);
         } else if self.channel_input.is_focused() {
             self.channel_input.draw(f, None);
         } else if self.search_input.is_focused() {
-            self.search_input.draw(f, v_chunks.next().copied());
+            self.search_input.draw(f, layout.input);
         } else if self.following.is_focused() {
             self.following.draw(f, None);
         }
@@ -288,63 +1328,89 @@ impl Component for ChatWidget {
             let limit =
                 self.scroll_offset.get_offset() < self.messages.borrow().len().saturating_sub(1);
 
-            if self.chat_input.is_focused() {
-                self.chat_input.event(event).await
-            } else if self.channel_input.is_focused() {
-                self.channel_input.event(event).await
-            } else if self.search_input.is_focused() {
-                self.search_input.event(event).await
-            } else if self.following.is_focused() {
-                self.following.event(event).await
-            } else {
-                match key {
-                    Key::Char('i' | 'c') => self.chat_input.toggle_focus(),
-                    Key::Char('@') => self.chat_input.toggle_focus_with("@"),
-                    Key::Char('/') => self.chat_input.toggle_focus_with("/"),
-                    Key::Char('s') => self.channel_input.toggle_focus(),
-                    Key::Ctrl('f') => self.search_input.toggle_focus(),
-                    Key::Char('f') => self.following.toggle_focus().await,
-                    Key::Ctrl('t') => self.filters.borrow_mut().toggle(),
-                    Key::Ctrl('r') => self.filters.borrow_mut().reverse(),
-                    Key::Char('S') => return Some(TerminalAction::SwitchState(State::Dashboard)),
-                    Key::Char('?' | 'h') => return Some(TerminalAction::SwitchState(State::Help)),
-                    Key::Char('q') => return Some(TerminalAction::Quit),
-                    Key::Char('o') => self.open_in_browser(),
-                    Key::Char('G') => {
-                        self.scroll_offset.jump_to(0);
+            if self.stats.is_focused() {
+                return self.stats.event(event).await;
+            }
+
+            // A picked action runs as if its key was pressed in normal mode.
+            if self.command_palette.is_focused() {
+                let action = self.command_palette.key(*key)?;
+
+                return self.normal_action(action).await;
+            }
+
+            match self.mode() {
+                InputMode::Insert => {
+                    let action = self.chat_input.event(event).await;
+
+                    let moderation = match &action {
+                        Some(TerminalAction::Enter(TwitchAction::ChatSettings(_))) => {
+                            Some(ModCommand::ChatSettings)
+                        }
+                        Some(TerminalAction::Enter(TwitchAction::Shoutout(_))) => {
+                            Some(ModCommand::Shoutout)
+                        }
+                        _ => None,
+                    };
+
+                    if moderation.is_some_and(|moderation| !self.permitted(moderation)) {
+                        return None;
                     }
-                    Key::Char('g') => {
-                        // TODO: Make this not jump to nothingness
-                        self.scroll_offset.jump_to(self.messages.borrow().len());
+
+                    if matches!(action, Some(TerminalAction::ShowStats)) {
+                        self.stats
+                            .show(ChatStats::new(self.messages.borrow().iter(), TOP_TALKERS));
+
+                        return None;
                     }
-                    Key::Esc => {
-                        if self.scroll_offset.get_offset() == 0 {
-                            return Some(TerminalAction::BackOneLayer);
-                        }
 
-                        self.scroll_offset.jump_to(0);
+                    action
+                }
+                InputMode::Search => {
+                    if self.channel_input.is_focused() {
+                        self.channel_input.event(event).await
+                    } else if self.search_input.is_focused() {
+                        self.search_input.event(event).await
+                    } else {
+                        self.following.event(event).await
                     }
-                    Key::Ctrl('p') => panic!("Manual panic triggered by user."),
-                    Key::ScrollUp => {
-                        if limit {
-                            self.scroll_offset.up();
-                        } else if self.scroll_offset.is_inverted() {
-                            self.scroll_offset.down();
+                }
+                InputMode::Normal => {
+                    if let Some((action, command)) = self.moderation_command(*key) {
+                        if !self.permitted(action) {
+                            return None;
                         }
+
+                        return Some(TerminalAction::Enter(TwitchAction::Privmsg(command)));
                     }
-                    Key::ScrollDown => {
-                        if self.scroll_offset.is_inverted() {
+
+                    if let Some(action) = self.keymap.action(*key) {
+                        return self.normal_action(action).await;
+                    }
+
+                    match key {
+                        Key::Click(column, row) => self.click(*column, *row),
+                        Key::ScrollUp => {
                             if limit {
                                 self.scroll_offset.up();
+                            } else if self.scroll_offset.is_inverted() {
+                                self.scroll_offset.down();
+                            }
+                        }
+                        Key::ScrollDown => {
+                            if self.scroll_offset.is_inverted() {
+                                if limit {
+                                    self.scroll_offset.up();
+                                }
+                            } else {
+                                self.scroll_offset.down();
                             }
-                        } else {
-                            self.scroll_offset.down();
                         }
+                        _ => {}
                     }
-                    _ => {}
-                }
 
-                None
+                    None
+                }
             }
         } else {
             None
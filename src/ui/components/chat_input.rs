@@ -1,26 +1,56 @@
+use std::ops::Range;
+
 use tui::{layout::Rect, Frame};
 
 use crate::{
     emotes::SharedEmotes,
     handlers::{
         config::SharedCompleteConfig,
+        data::emote_only_rejections,
+        input_history::{HistorySearch, InputHistory, HISTORY_FILE},
         storage::SharedStorage,
         user_input::events::{Event, Key},
     },
     terminal::TerminalAction,
-    twitch::TwitchAction,
+    twitch::{
+        channel_message::ChannelMessage, chat_settings::ChatSetting, raw::RawLine,
+        shoutout::Shoutout, whisper::Whisper, TwitchAction,
+    },
     ui::{
         components::{emote_picker::EmotePickerWidget, utils::InputWidget, Component},
         statics::{COMMANDS, TWITCH_MESSAGE_LIMIT},
     },
-    utils::{emotes::emotes_enabled, text::first_similarity},
+    utils::{
+        emotes::emotes_enabled,
+        pathing::config_path,
+        text::{fill_template, first_similarity},
+    },
 };
 
+const INPUT_TITLE: &str = "Chat";
+
+const EPHEMERAL_TITLE: &str = "Chat (ephemeral history)";
+
+const EMOTE_ONLY_TITLE: &str = "Emote-only chat, press Enter again to send anyway";
+
+const READ_ONLY_TITLE: &str =
+    "Chat is read-only while anonymous, log in with a token to send messages";
+
 pub struct ChatInputWidget {
     config: SharedCompleteConfig,
     storage: SharedStorage,
     input: InputWidget<SharedStorage>,
     emote_picker: EmotePickerWidget,
+    emotes: SharedEmotes,
+    /// If the channel only allows messages made of emotes.
+    emote_only: bool,
+    /// The input that was warned about not being emote-only, to send if Enter is pressed again.
+    emote_only_warned: Option<String>,
+    /// If the title shows why the input cannot be sent, or what a command did.
+    error_shown: bool,
+    history: InputHistory,
+    /// The ongoing search through the history, and the input from before it started.
+    history_search: Option<(HistorySearch, String)>,
 }
 
 impl ChatInputWidget {
@@ -63,22 +93,85 @@ impl ChatInputWidget {
 
         let input = InputWidget::new(
             config.clone(),
-            "Chat",
+            INPUT_TITLE,
             Some((storage.clone(), input_validator)),
             Some(visual_indicator),
             Some((storage.clone(), input_suggester)),
         );
 
-        let emote_picker = EmotePickerWidget::new(config.clone(), emotes);
+        let emote_picker = EmotePickerWidget::new(config.clone(), emotes.clone());
 
-        Self {
+        let history = InputHistory::load(
+            &config_path(HISTORY_FILE),
+            config.borrow().storage.ephemeral_history,
+        );
+
+        let mut widget = Self {
             config,
             storage,
             input,
             emote_picker,
+            emotes,
+            emote_only: false,
+            emote_only_warned: None,
+            error_shown: false,
+            history,
+            history_search: None,
+        };
+
+        widget.input.set_title(widget.title());
+
+        widget
+    }
+
+    /// The title of the input, which tells if the history is ephemeral.
+    const fn title(&self) -> &'static str {
+        if self.history.is_ephemeral() {
+            EPHEMERAL_TITLE
+        } else {
+            INPUT_TITLE
         }
     }
 
+    pub const fn is_history_ephemeral(&self) -> bool {
+        self.history.is_ephemeral()
+    }
+
+    /// Save the input history, unless it is ephemeral.
+    pub fn save_history(&self) {
+        self.history.save(&config_path(HISTORY_FILE));
+    }
+
+    /// Run a command that is about the input history, returning if the input was one.
+    fn history_command(&mut self, input: &str) -> bool {
+        let path = config_path(HISTORY_FILE);
+
+        let status = match input.trim_end() {
+            "/clearhistory" => {
+                self.history.wipe(&path);
+
+                "Input history cleared"
+            }
+            "/ephemeral" => {
+                self.history
+                    .set_ephemeral(!self.history.is_ephemeral(), &path);
+
+                if self.history.is_ephemeral() {
+                    "Input history is now only kept until quitting"
+                } else {
+                    "Input history is now saved on quitting"
+                }
+            }
+            _ => return false,
+        };
+
+        self.input.update("");
+        self.error_shown = true;
+        self.input.set_title(status);
+
+        true
+    }
+
     pub const fn is_focused(&self) -> bool {
         self.input.is_focused()
     }
@@ -90,6 +183,120 @@ impl ChatInputWidget {
     pub fn toggle_focus_with(&mut self, s: &str) {
         self.input.toggle_focus_with(s);
     }
+
+    pub fn update(&mut self, s: &str) {
+        self.input.replace(s);
+    }
+
+    pub fn set_emote_only(&mut self, emote_only: bool) {
+        self.emote_only = emote_only;
+    }
+
+    /// The words of the input that are not emotes, if they would get it rejected.
+    fn emote_only_rejections(&self) -> Vec<Range<usize>> {
+        let frontend = &self.config.borrow().frontend;
+
+        // Nothing is known to be an emote without them.
+        if !self.emote_only || !frontend.emote_only_warning || !emotes_enabled(frontend) {
+            return vec![];
+        }
+
+        let emotes = self.emotes.emotes.borrow();
+
+        emote_only_rejections(&self.input.to_string(), |word| emotes.contains_key(word))
+    }
+
+    /// Tell that nothing can be sent while reading chat anonymously, returning if that is so.
+    /// The input is kept, to send once logged in.
+    fn refuse_anonymous(&mut self) -> bool {
+        if !self.config.borrow().twitch.anonymous {
+            return false;
+        }
+
+        self.error_shown = true;
+        self.input.set_title(READ_ONLY_TITLE);
+
+        true
+    }
+
+    fn clear_warning(&mut self) {
+        let warned = self.emote_only_warned.take().is_some();
+
+        if warned || std::mem::take(&mut self.error_shown) {
+            self.input.set_title(self.title());
+        }
+    }
+
+    /// Replace the input with a canned response, filling in the user from either
+    /// the given one, or the mention that is currently in the input.
+    fn insert_canned_response(&mut self, index: usize, user: Option<&str>) {
+        let Some(template) = self
+            .config
+            .borrow()
+            .frontend
+            .canned_responses
+            .get(index)
+            .cloned()
+        else {
+            return;
+        };
+
+        let current_input = self.input.to_string();
+
+        let mentioned_user = current_input
+            .trim()
+            .strip_prefix('@')
+            .filter(|user| !user.is_empty() && !user.contains(char::is_whitespace));
+
+        self.input
+            .replace(&fill_template(&template, user.or(mentioned_user)));
+    }
+
+    /// Show the selected match of the history search in the input.
+    fn update_history_search(&mut self) {
+        let Some((search, original)) = &self.history_search else {
+            return;
+        };
+
+        let selected = search
+            .selected(&self.history)
+            .unwrap_or(original)
+            .to_string();
+        let title = format!("Search sent messages: {}", search.query);
+
+        self.input.replace(&selected);
+        self.input.set_title(&title);
+    }
+
+    fn end_history_search(&mut self, keep_match: bool) {
+        if let Some((_, original)) = self.history_search.take() {
+            if !keep_match {
+                self.input.replace(&original);
+            }
+        }
+
+        self.input.set_title(self.title());
+    }
+
+    /// Handle a key while searching the history, where typing edits the query.
+    fn history_search_event(&mut self, key: Key) {
+        let Some((search, _)) = &mut self.history_search else {
+            return;
+        };
+
+        match key {
+            Key::Char(c) => search.push(c),
+            Key::Backspace => search.pop(),
+            Key::Ctrl('r') => search.next(),
+            Key::Enter | Key::Esc => {
+                self.end_history_search(matches!(key, Key::Enter));
+                return;
+            }
+            _ => return,
+        }
+
+        self.update_history_search();
+    }
 }
 
 impl ToString for ChatInputWidget {
@@ -100,6 +307,7 @@ impl ToString for ChatInputWidget {
 
 impl Component for ChatInputWidget {
     fn draw(&mut self, f: &mut Frame, area: Option<Rect>) {
+        self.input.set_highlights(self.emote_only_rejections());
         self.input.draw(f, area);
 
         if self.emote_picker.is_focused() {
@@ -115,41 +323,190 @@ impl Component for ChatInputWidget {
                 self.input.insert(&emote);
                 self.input.insert(" ");
             }
+        } else if let (Some(_), Event::Input(key)) = (&self.history_search, event) {
+            self.history_search_event(*key);
         } else if let Event::Input(key) = event {
             match key {
+                Key::Ctrl('r') => {
+                    self.history_search = Some((HistorySearch::default(), self.input.to_string()));
+                    self.update_history_search();
+                }
                 Key::Enter => {
-                    if self.input.is_valid() {
-                        let current_input = self.input.to_string();
+                    if let Some(args) = self
+                        .input
+                        .to_string()
+                        .strip_prefix("/canned")
+                        .filter(|args| args.is_empty() || args.starts_with(' '))
+                    {
+                        let mut args = args.split_whitespace();
 
-                        let action =
-                            TerminalAction::Enter(TwitchAction::Privmsg(current_input.clone()));
+                        if let Some(index) = args
+                            .next()
+                            .and_then(|i| i.parse::<usize>().ok())
+                            .and_then(|i| i.checked_sub(1))
+                        {
+                            self.insert_canned_response(index, args.next());
+                        }
+                    } else if !self.history_command(&self.input.to_string())
+                        && self.input.is_valid()
+                    {
+                        let mut current_input = self.input.to_string();
+
+                        match RawLine::parse(&current_input) {
+                            Some(Ok(raw)) if self.config.borrow().twitch.raw_commands => {
+                                if self.refuse_anonymous() {
+                                    return None;
+                                }
+
+                                self.input.update("");
+                                self.history.push(&current_input);
+
+                                return Some(TerminalAction::Enter(TwitchAction::Raw(raw)));
+                            }
+                            Some(Ok(_)) => {
+                                self.error_shown = true;
+                                self.input.set_title(
+                                    "Sending raw IRC lines is turned off, see raw_commands in the config",
+                                );
+
+                                return None;
+                            }
+                            Some(Err(err)) => {
+                                self.error_shown = true;
+                                self.input.set_title(&err.to_string());
+
+                                return None;
+                            }
+                            None => {}
+                        }
+
+                        match Shoutout::parse(&current_input) {
+                            Some(Ok(shoutout)) => {
+                                if self.refuse_anonymous() {
+                                    return None;
+                                }
+
+                                self.input.update("");
+                                self.history.push(&current_input);
+
+                                return Some(TerminalAction::Enter(TwitchAction::Shoutout(
+                                    shoutout,
+                                )));
+                            }
+                            Some(Err(err)) => {
+                                self.error_shown = true;
+                                self.input.set_title(&err.to_string());
+
+                                return None;
+                            }
+                            None => {}
+                        }
+
+                        match ChatSetting::parse(&current_input) {
+                            Some(Ok(setting)) => {
+                                if self.refuse_anonymous() {
+                                    return None;
+                                }
+
+                                self.input.update("");
+                                self.history.push(&current_input);
+
+                                return Some(TerminalAction::Enter(TwitchAction::ChatSettings(
+                                    setting,
+                                )));
+                            }
+                            Some(Err(err)) => {
+                                self.error_shown = true;
+                                self.input.set_title(&err.to_string());
+
+                                return None;
+                            }
+                            None => {}
+                        }
+
+                        // Only the channel in view is joined, which is where the message goes.
+                        let channel = self.config.borrow().twitch.channel.clone();
+
+                        let parsed = Whisper::parse(&current_input)
+                            .map(|whisper| whisper.map(|w| w.command()))
+                            .or_else(|| {
+                                ChannelMessage::parse(&current_input, &[&channel])
+                                    .map(|message| message.map(|m| m.message))
+                            });
+
+                        match parsed {
+                            Some(Ok(command)) => current_input = command,
+                            Some(Err(err)) => {
+                                self.error_shown = true;
+                                self.input.set_title(&err.to_string());
+
+                                return None;
+                            }
+                            None => {}
+                        }
+
+                        if self.emote_only_warned.as_ref() != Some(&current_input)
+                            && !self.emote_only_rejections().is_empty()
+                        {
+                            self.emote_only_warned = Some(current_input);
+                            self.input.set_title(EMOTE_ONLY_TITLE);
+
+                            return None;
+                        }
+
+                        // Commands that the client runs itself, which work while anonymous too.
+                        let local =
+                            current_input.strip_prefix('/').and_then(|command| {
+                                match command.trim_end() {
+                                    "clear" => Some(TerminalAction::ClearMessages),
+                                    "stats" => Some(TerminalAction::ShowStats),
+                                    "reconnect" => {
+                                        Some(TerminalAction::Enter(TwitchAction::Reconnect))
+                                    }
+                                    _ => None,
+                                }
+                            });
+
+                        if local.is_none() && self.refuse_anonymous() {
+                            return None;
+                        }
+
+                        self.clear_warning();
 
                         self.input.update("");
+                        self.history.push(&current_input);
 
                         if let Some(message) = current_input.strip_prefix('@') {
-                            if self.config.borrow().storage.mentions {
+                            if self.config.borrow().storage.mentions && !self.history.is_ephemeral()
+                            {
                                 self.storage
                                     .borrow_mut()
                                     .add("mentions", message.to_string());
                             }
-                        } else if let Some(message) = current_input.strip_prefix('/') {
-                            if message == "clear" {
-                                return Some(TerminalAction::ClearMessages);
-                            }
                         }
 
-                        return Some(action);
+                        return Some(local.unwrap_or(TerminalAction::Enter(
+                            TwitchAction::Privmsg(current_input),
+                        )));
                     }
                 }
                 Key::Alt('e') => {
-                    if emotes_enabled(&self.config.borrow().frontend) {
+                    // The picker shows the images of the emotes.
+                    if self.emotes.cell_size.get().is_some() {
                         self.emote_picker.toggle_focus();
                     }
                 }
+                Key::Alt(c @ '1'..='9') => {
+                    if let Some(index) = c.to_digit(10) {
+                        self.insert_canned_response(index as usize - 1, None);
+                    }
+                }
                 Key::Esc => {
+                    self.clear_warning();
                     self.input.toggle_focus();
                 }
                 _ => {
+                    self.clear_warning();
                     self.input.event(event).await;
                 }
             }
@@ -0,0 +1,286 @@
+use std::cmp::Reverse;
+
+use tui::{
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph},
+    Frame,
+};
+
+use crate::{
+    handlers::{
+        config::SharedCompleteConfig,
+        keymap::{key_label, Keymap, NormalAction},
+        user_input::events::{Event, Key},
+    },
+    terminal::TerminalAction,
+    ui::components::{utils::centered_rect, Component},
+    utils::text::{fuzzy_score, title_line, TitleStyle},
+};
+
+/// The most actions that the palette shows at once.
+const PALETTE_HEIGHT: u16 = 15;
+
+/// An action of normal mode as the command palette lists it, with the keys that do it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PaletteEntry {
+    pub action: NormalAction,
+    pub keys: String,
+}
+
+/// Every action that can be run from the palette, taken from the same bindings as the keys,
+/// so that the keys it shows are the ones that do the action.
+fn palette_entries(keymap: &Keymap) -> Vec<PaletteEntry> {
+    NormalAction::ALL
+        .into_iter()
+        .filter(|action| !matches!(action, NormalAction::CommandPalette | NormalAction::Panic))
+        .map(|action| PaletteEntry {
+            action,
+            keys: keymap
+                .keys(action)
+                .into_iter()
+                .map(key_label)
+                .collect::<Vec<_>>()
+                .join(" or "),
+        })
+        .collect()
+}
+
+/// The entries whose name fuzzily matches the search, best match first, or all of them without a search.
+fn filter_entries<'a>(entries: &'a [PaletteEntry], search: &str) -> Vec<&'a PaletteEntry> {
+    if search.is_empty() {
+        return entries.iter().collect();
+    }
+
+    let mut matched = entries
+        .iter()
+        .filter_map(|entry| fuzzy_score(entry.action.name(), search).map(|score| (score, entry)))
+        .collect::<Vec<_>>();
+
+    // Sorting is stable, so entries of the same score keep their order.
+    matched.sort_by_key(|(score, _)| Reverse(*score));
+
+    matched.into_iter().map(|(_, entry)| entry).collect()
+}
+
+/// A popup over chat to run any action of normal mode by its name.
+pub struct CommandPaletteWidget {
+    config: SharedCompleteConfig,
+    entries: Vec<PaletteEntry>,
+    focused: bool,
+    search: String,
+    selected: usize,
+}
+
+impl CommandPaletteWidget {
+    pub fn new(config: SharedCompleteConfig, keymap: &Keymap) -> Self {
+        Self {
+            config,
+            entries: palette_entries(keymap),
+            focused: false,
+            search: String::new(),
+            selected: 0,
+        }
+    }
+
+    pub const fn is_focused(&self) -> bool {
+        self.focused
+    }
+
+    pub fn toggle_focus(&mut self) {
+        self.focused = !self.focused;
+        self.search.clear();
+        self.selected = 0;
+    }
+
+    fn matches(&self) -> Vec<&PaletteEntry> {
+        filter_entries(&self.entries, &self.search)
+    }
+
+    /// Take a key while the palette is open, returning the action to run once one is picked.
+    pub fn key(&mut self, key: Key) -> Option<NormalAction> {
+        match key {
+            Key::Esc => self.toggle_focus(),
+            Key::Up | Key::ScrollUp => self.selected = self.selected.saturating_sub(1),
+            Key::Down | Key::ScrollDown => {
+                self.selected = (self.selected + 1).min(self.matches().len().saturating_sub(1));
+            }
+            Key::Enter => {
+                let action = self.matches().get(self.selected).map(|entry| entry.action);
+
+                if action.is_some() {
+                    self.toggle_focus();
+                }
+
+                return action;
+            }
+            Key::Backspace => {
+                self.search.pop();
+                self.selected = 0;
+            }
+            Key::Char(c) => {
+                self.search.push(c);
+                self.selected = 0;
+            }
+            Key::Ctrl('p') => panic!("Manual panic triggered by user."),
+            _ => {}
+        }
+
+        None
+    }
+}
+
+impl Component for CommandPaletteWidget {
+    fn draw(&mut self, f: &mut Frame, area: Option<Rect>) {
+        let r = area.unwrap_or_else(|| centered_rect(60, 60, PALETTE_HEIGHT + 3, f.size()));
+
+        let border_type = self.config.borrow().frontend.border_type.clone();
+        let key_style = Style::default().fg(Color::DarkGray);
+
+        let items = self
+            .matches()
+            .into_iter()
+            .map(|entry| {
+                let mut spans = vec![Span::raw(entry.action.name())];
+
+                if !entry.keys.is_empty() {
+                    spans.push(Span::styled(format!("  {}", entry.keys), key_style));
+                }
+
+                ListItem::new(Line::from(spans))
+            })
+            .collect::<Vec<_>>();
+
+        let mut list_state = ListState::default();
+        list_state.select((!items.is_empty()).then_some(self.selected));
+
+        let title = [TitleStyle::Single("Command palette")];
+
+        let list = List::new(items)
+            .block(
+                Block::default()
+                    .title(title_line(
+                        &title,
+                        Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+                    ))
+                    .borders(Borders::ALL)
+                    .border_type(border_type.clone().into()),
+            )
+            .highlight_style(
+                Style::default()
+                    .bg(Color::LightGreen)
+                    .add_modifier(Modifier::BOLD),
+            );
+
+        let list_rect = Rect::new(r.x, r.y, r.width, r.height.saturating_sub(3));
+        let input_rect = Rect::new(r.x, list_rect.bottom(), r.width, 3);
+
+        f.render_widget(Clear, r);
+        f.render_stateful_widget(list, list_rect, &mut list_state);
+
+        let input = Paragraph::new(format!("{}▏", self.search)).block(
+            Block::default()
+                .title("Action")
+                .borders(Borders::ALL)
+                .border_type(border_type.into()),
+        );
+
+        f.render_widget(input, input_rect);
+    }
+
+    async fn event(&mut self, event: &Event) -> Option<TerminalAction> {
+        if let Event::Input(key) = event {
+            self.key(*key);
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handlers::config::{CompleteConfig, KeyScheme};
+    use std::{cell::RefCell, rc::Rc};
+
+    fn palette(scheme: KeyScheme) -> CommandPaletteWidget {
+        CommandPaletteWidget::new(
+            Rc::new(RefCell::new(CompleteConfig::default())),
+            &Keymap::new(scheme),
+        )
+    }
+
+    fn type_in(palette: &mut CommandPaletteWidget, text: &str) {
+        for c in text.chars() {
+            palette.key(Key::Char(c));
+        }
+    }
+
+    #[test]
+    fn test_filter_narrows_actions() {
+        let mut palette = palette(KeyScheme::Default);
+        palette.toggle_focus();
+
+        let all = palette.matches().len();
+        assert_eq!(all, NormalAction::ALL.len() - 2);
+
+        type_in(&mut palette, "filter");
+
+        let names = palette
+            .matches()
+            .iter()
+            .map(|entry| entry.action)
+            .collect::<Vec<_>>();
+
+        assert!(names.contains(&NormalAction::ToggleFilter));
+        assert!(names.contains(&NormalAction::ReverseFilter));
+        assert!(names.len() < all);
+
+        type_in(&mut palette, "zzzz");
+        assert!(palette.matches().is_empty());
+        assert_eq!(palette.key(Key::Enter), None);
+        assert!(palette.is_focused());
+    }
+
+    #[test]
+    fn test_selection_clamps() {
+        let mut palette = palette(KeyScheme::Default);
+        palette.toggle_focus();
+
+        type_in(&mut palette, "filter");
+        let len = palette.matches().len();
+
+        for _ in 0..len + 5 {
+            palette.key(Key::Down);
+        }
+        assert_eq!(palette.selected, len - 1);
+
+        for _ in 0..len + 5 {
+            palette.key(Key::Up);
+        }
+        assert_eq!(palette.selected, 0);
+    }
+
+    #[test]
+    fn test_execute_matches_keybind() {
+        let keymap = Keymap::new(KeyScheme::Vi);
+        let mut palette = palette(KeyScheme::Vi);
+
+        // The keys listed next to an action are the ones that do it.
+        for entry in palette_entries(&keymap) {
+            for key in keymap.keys(entry.action) {
+                assert_eq!(keymap.action(key), Some(entry.action));
+            }
+        }
+
+        palette.toggle_focus();
+        type_in(&mut palette, "toggle focus");
+
+        let action = palette.key(Key::Enter);
+
+        assert_eq!(action, Some(NormalAction::ToggleFocus));
+        assert_eq!(action, keymap.action(Key::Char('z')));
+        assert!(!palette.is_focused());
+    }
+}
@@ -10,6 +10,7 @@ use tui::{
 
 use crate::{
     handlers::{
+        channel_history::SharedChannelHistory,
         config::SharedCompleteConfig,
         state::State,
         storage::SharedStorage,
@@ -39,8 +40,13 @@ pub struct DashboardWidget {
 }
 
 impl DashboardWidget {
-    pub fn new(config: SharedCompleteConfig, storage: SharedStorage) -> Self {
-        let channel_input = ChannelSwitcherWidget::new(config.clone(), storage.clone());
+    pub fn new(
+        config: SharedCompleteConfig,
+        storage: SharedStorage,
+        channel_history: SharedChannelHistory,
+    ) -> Self {
+        let channel_input =
+            ChannelSwitcherWidget::new(config.clone(), storage.clone(), channel_history);
         let following = FollowingWidget::new(config.clone());
 
         Self {
@@ -51,6 +57,13 @@ impl DashboardWidget {
         }
     }
 
+    /// Show the followed channel switcher, if it is not already.
+    pub async fn open_following(&mut self) {
+        if !self.following.is_focused() {
+            self.following.toggle_focus().await;
+        }
+    }
+
     fn create_interactive_list_widget<'a>(
         &'a self,
         items: &'a [String],
@@ -50,6 +50,10 @@ impl DebugWidget {
             ("Storage Config".to_string(), c.storage.to_vec()),
             ("Filter Config".to_string(), c.filters.to_vec()),
             ("Frontend Config".to_string(), c.frontend.to_vec()),
+            ("Alerts Config".to_string(), c.alerts.to_vec()),
+            ("Censor Config".to_string(), c.censor.to_vec()),
+            ("Moderation Config".to_string(), c.moderation.to_vec()),
+            ("Responses Config".to_string(), c.responses.to_vec()),
         ]
     }
 }
@@ -1,6 +1,5 @@
 use log::warn;
-use memchr::memmem;
-use std::cmp::max;
+use std::cmp::{max, Reverse};
 use tui::{
     layout::Rect,
     style::{Color, Modifier, Style},
@@ -27,10 +26,40 @@ use crate::{
     utils::{
         colors::u32_to_color,
         emotes::UnicodePlaceholder,
-        text::{first_similarity_iter, title_line, TitleStyle},
+        text::{first_similarity_iter, fuzzy_indices, title_line, TitleStyle},
     },
 };
 
+/// The names of the emotes that fuzzily match the search, best match first, along with
+/// the indices of the characters that matched. An empty search matches every emote.
+fn rank_emotes<'a>(
+    names: impl IntoIterator<Item = &'a String>,
+    search: &str,
+) -> Vec<(&'a str, Vec<usize>)> {
+    let mut ranked = names
+        .into_iter()
+        .filter_map(|name| {
+            fuzzy_indices(name, search).map(|(score, indices)| (score, name.as_str(), indices))
+        })
+        .collect::<Vec<_>>();
+
+    // Sorting is stable, so emotes of the same score keep their order.
+    ranked.sort_by_key(|(score, _, _)| Reverse(*score));
+
+    ranked
+        .into_iter()
+        .map(|(_, name, indices)| (name, indices))
+        .collect()
+}
+
+/// Keep the selection within a list of `len` emotes, where nothing is selected in an empty list.
+fn clamp_selection(selected: Option<usize>, len: usize) -> Option<usize> {
+    match len {
+        0 => None,
+        _ => selected.map(|i| i.min(len - 1)),
+    }
+}
+
 pub struct EmotePickerWidget {
     config: SharedCompleteConfig,
     emotes: SharedEmotes,
@@ -72,27 +101,20 @@ impl EmotePickerWidget {
         }
     }
     fn next(&mut self) {
-        let i = match self.list_state.selected() {
-            Some(i) => {
-                if i >= self.filtered_emotes.len() - 1 {
-                    self.filtered_emotes.len() - 1
-                } else {
-                    i + 1
-                }
-            }
-            None => 0,
-        };
+        let i = self.list_state.selected().map_or(0, |i| i + 1);
 
-        self.list_state.select(Some(i));
+        self.list_state
+            .select(clamp_selection(Some(i), self.filtered_emotes.len()));
     }
 
     fn previous(&mut self) {
         let i = self
             .list_state
             .selected()
-            .map_or(0, |i| if i == 0 { 0 } else { i - 1 });
+            .map_or(0, |i| i.saturating_sub(1));
 
-        self.list_state.select(Some(i));
+        self.list_state
+            .select(clamp_selection(Some(i), self.filtered_emotes.len()));
     }
 
     fn unselect(&mut self) {
@@ -122,7 +144,7 @@ impl Component for EmotePickerWidget {
         let mut items = Vec::with_capacity(max_len);
         let mut bad_emotes = vec![];
 
-        let mut current_input = self.input.to_string();
+        let current_input = self.input.to_string();
 
         let cell_size = *self
             .emotes
@@ -130,23 +152,14 @@ impl Component for EmotePickerWidget {
             .get()
             .expect("Terminal cell size should be set when emotes are enabled.");
 
-        let finder = if current_input.is_empty() {
-            None
-        } else {
-            current_input.make_ascii_lowercase();
-            Some(memmem::Finder::new(&current_input))
-        };
+        let emotes = self.emotes.emotes.borrow();
 
-        for (name, (filename, zero_width)) in self.emotes.emotes.borrow().iter() {
+        for (name, matched_indices) in rank_emotes(emotes.keys(), &current_input) {
             if items.len() >= max_len {
                 break;
             }
 
-            // Skip emotes that do not contain the current input, if it is not empty.
-            let Some(pos) = finder
-                .as_ref()
-                .map_or_else(|| Some(0), |f| f.find(name.to_ascii_lowercase().as_bytes()))
-            else {
+            let Some((filename, zero_width, _)) = emotes.get(name) else {
                 continue;
             };
 
@@ -158,19 +171,25 @@ impl Component for EmotePickerWidget {
                 cell_size,
             )
             .map_err(|e| warn!("{e}")) else {
-                bad_emotes.push(name.clone());
+                bad_emotes.push(name.to_string());
                 continue;
             };
 
             let cols = (loaded_emote.width as f32 / cell_size.0).ceil() as u16;
 
-            let row = vec![
-                Span::raw(name[0..pos].to_owned()),
-                Span::styled(
-                    name[pos..(pos + current_input.len())].to_owned(),
-                    self.search_theme,
-                ),
-                Span::raw(name[(pos + current_input.len())..].to_owned()),
+            let mut row = name
+                .chars()
+                .enumerate()
+                .map(|(i, c)| {
+                    if matched_indices.contains(&i) {
+                        Span::styled(c.to_string(), self.search_theme)
+                    } else {
+                        Span::raw(c.to_string())
+                    }
+                })
+                .collect::<Vec<Span>>();
+
+            row.extend([
                 Span::raw(" - "),
                 Span::styled(
                     UnicodePlaceholder::new(cols as usize).string(),
@@ -178,11 +197,13 @@ impl Component for EmotePickerWidget {
                         .fg(u32_to_color(loaded_emote.hash))
                         .underline_color(u32_to_color(1)),
                 ),
-            ];
+            ]);
 
-            items.push((name.clone(), ListItem::new(vec![Line::from(row)])));
+            items.push((name.to_string(), ListItem::new(vec![Line::from(row)])));
         }
 
+        drop(emotes);
+
         // Remove emotes that could not be loaded from list of emotes
         for emote in bad_emotes {
             self.emotes.info.borrow_mut().remove(&emote);
@@ -191,6 +212,10 @@ impl Component for EmotePickerWidget {
 
         let (names, list_items) = items.into_iter().unzip();
         self.filtered_emotes = names;
+        self.list_state.select(clamp_selection(
+            self.list_state.selected(),
+            self.filtered_emotes.len(),
+        ));
 
         let title_binding = [TitleStyle::Single("Emotes")];
 
@@ -234,9 +259,11 @@ impl Component for EmotePickerWidget {
                 Key::ScrollDown | Key::Down => self.next(),
                 Key::ScrollUp | Key::Up => self.previous(),
                 Key::Enter => {
-                    if let Some(idx) = self.list_state.selected() {
-                        let emote = self.filtered_emotes[idx].clone();
-
+                    if let Some(emote) = self
+                        .list_state
+                        .selected()
+                        .and_then(|idx| self.filtered_emotes.get(idx).cloned())
+                    {
                         self.toggle_focus();
                         self.input.update("");
                         self.unselect();
@@ -260,3 +287,40 @@ impl Component for EmotePickerWidget {
         None
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filter(names: &[String], search: &str) -> Vec<String> {
+        rank_emotes(names, search)
+            .into_iter()
+            .map(|(name, _)| name.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_search_narrows_emotes() {
+        let names = ["Kappa", "KappaPride", "PogChamp", "LUL"].map(String::from);
+
+        assert_eq!(filter(&names, ""), names);
+        assert_eq!(filter(&names, "kappa"), vec!["Kappa", "KappaPride"]);
+        assert_eq!(filter(&names, "PRIDE"), vec!["KappaPride"]);
+        assert!(filter(&names, "monka").is_empty());
+
+        // Emotes that start with the search come before ones that merely contain its letters.
+        assert_eq!(filter(&names, "pc"), vec!["PogChamp"]);
+        assert_eq!(filter(&names, "p"), vec!["PogChamp", "KappaPride", "Kappa"]);
+
+        let ranked = rank_emotes(&names, "champ");
+        assert_eq!(ranked, vec![("PogChamp", vec![3, 4, 5, 6, 7])]);
+    }
+
+    #[test]
+    fn test_selection_clamps() {
+        assert_eq!(clamp_selection(Some(5), 3), Some(2));
+        assert_eq!(clamp_selection(Some(1), 3), Some(1));
+        assert_eq!(clamp_selection(None, 3), None);
+        assert_eq!(clamp_selection(Some(0), 0), None);
+    }
+}
@@ -1,13 +1,18 @@
 mod channel_switcher;
 mod chat;
 mod chat_input;
+mod command_palette;
 mod dashboard;
 mod debug;
 mod error;
 mod following;
 mod help;
 mod message_search;
+mod pinned;
+mod poll;
+mod quit_prompt;
 mod state_tabs;
+mod stats;
 
 mod emote_picker;
 pub mod utils;
@@ -21,6 +26,7 @@ pub use error::ErrorWidget;
 pub use help::HelpWidget;
 pub use message_search::MessageSearchWidget;
 use once_cell::sync::Lazy;
+pub use quit_prompt::QuitPromptWidget;
 pub use state_tabs::StateTabsWidget;
 
 use chrono::{DateTime, Local};
@@ -30,6 +36,7 @@ use crate::{
     emotes::SharedEmotes,
     handlers::{
         app::SharedMessages,
+        channel_history::SharedChannelHistory,
         config::SharedCompleteConfig,
         filters::SharedFilters,
         storage::SharedStorage,
@@ -71,6 +78,7 @@ pub struct Components {
     // Partial window widgets
     pub tabs: StateTabsWidget,
     pub debug: DebugWidget,
+    pub quit_prompt: QuitPromptWidget,
 
     // Full window widgets
     pub chat: ChatWidget,
@@ -88,6 +96,7 @@ impl Components {
         filters: SharedFilters,
         messages: SharedMessages,
         emotes: &SharedEmotes,
+        channel_history: SharedChannelHistory,
         startup_time: DateTime<Local>,
     ) -> Self {
         let window_size_error = ErrorWidget::new(WINDOW_SIZE_TOO_SMALL_ERROR.to_vec());
@@ -95,9 +104,17 @@ impl Components {
         Self {
             tabs: StateTabsWidget::new(config.clone()),
             debug: DebugWidget::new(config.clone(), startup_time),
+            quit_prompt: QuitPromptWidget::new(config.clone()),
 
-            chat: ChatWidget::new(config.clone(), messages, &storage, emotes, filters),
-            dashboard: DashboardWidget::new(config.clone(), storage),
+            chat: ChatWidget::new(
+                config.clone(),
+                messages,
+                &storage,
+                emotes,
+                filters,
+                channel_history.clone(),
+            ),
+            dashboard: DashboardWidget::new(config.clone(), storage, channel_history),
             help: HelpWidget::new(config.clone()),
             window_size_error,
         }
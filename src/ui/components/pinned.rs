@@ -0,0 +1,166 @@
+use tui::{
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::Line,
+    widgets::{Block, Borders, Paragraph},
+    Frame,
+};
+
+use crate::{
+    handlers::{config::SharedCompleteConfig, data::MessageData},
+    ui::components::Component,
+    utils::{
+        emotes::{PRIVATE_USE_UNICODE, ZERO_WIDTH_SPACE},
+        text::{title_line, TitleStyle},
+    },
+};
+
+/// At most this many lines of the pinned message are shown, the rest is cut off.
+const MAX_LINES: usize = 3;
+
+/// A message kept above chat, so that it stays visible while chat moves on.
+pub struct PinnedMessage {
+    config: SharedCompleteConfig,
+    /// The author and text of the message.
+    pinned: Option<(String, String)>,
+}
+
+impl PinnedMessage {
+    pub const fn new(config: SharedCompleteConfig) -> Self {
+        Self {
+            config,
+            pinned: None,
+        }
+    }
+
+    /// Pin a message, replacing the one that was pinned before.
+    ///
+    /// Emotes shown as images are left out, since the images are only placed in chat.
+    pub fn pin(&mut self, message: &MessageData) {
+        let text = message
+            .payload
+            .chars()
+            .filter(|&c| c != PRIVATE_USE_UNICODE && c != ZERO_WIDTH_SPACE)
+            .collect::<String>();
+
+        self.pinned = Some((message.author.clone(), text));
+    }
+
+    pub fn unpin(&mut self) {
+        self.pinned = None;
+    }
+
+    pub const fn is_pinned(&self) -> bool {
+        self.pinned.is_some()
+    }
+
+    /// The pinned message wrapped to fit in the width, inside of the border of the header.
+    fn lines(&self, width: u16) -> Vec<String> {
+        let Some((author, text)) = &self.pinned else {
+            return vec![];
+        };
+
+        let width = usize::from(width.saturating_sub(2)).max(1);
+
+        let mut lines = textwrap::wrap(&format!("{author}: {text}"), width)
+            .into_iter()
+            .map(std::borrow::Cow::into_owned)
+            .collect::<Vec<_>>();
+
+        if lines.len() > MAX_LINES {
+            lines.truncate(MAX_LINES);
+
+            if let Some(last) = lines.last_mut() {
+                last.pop();
+                last.push('…');
+            }
+        }
+
+        lines
+    }
+
+    /// The rows that the header takes at this width, including its border, none without a pin.
+    pub fn height(&self, width: u16) -> u16 {
+        let lines = self.lines(width).len() as u16;
+
+        if lines == 0 {
+            0
+        } else {
+            lines + 2
+        }
+    }
+}
+
+impl Component for PinnedMessage {
+    fn draw(&mut self, f: &mut Frame, area: Option<Rect>) {
+        let Some(area) = area.filter(|_| self.is_pinned()) else {
+            return;
+        };
+
+        let lines = self
+            .lines(area.width)
+            .into_iter()
+            .map(Line::from)
+            .collect::<Vec<_>>();
+
+        let title = [TitleStyle::Single("Pinned")];
+
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_type(self.config.borrow().frontend.border_type.clone().into())
+            .border_style(Style::default().fg(Color::Yellow))
+            .title(title_line(
+                &title,
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD),
+            ));
+
+        f.render_widget(Paragraph::new(lines).block(block), area);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, rc::Rc};
+
+    use unicode_width::UnicodeWidthStr;
+
+    use super::*;
+    use crate::handlers::config::CompleteConfig;
+
+    fn message(text: &str) -> MessageData {
+        MessageData::new(
+            "streamer".to_string(),
+            None,
+            false,
+            text.to_string(),
+            None,
+            false,
+        )
+    }
+
+    #[test]
+    fn test_pinned_message_fits_width() {
+        let mut pinned = PinnedMessage::new(Rc::new(RefCell::new(CompleteConfig::default())));
+
+        pinned.pin(&message(
+            "Giveaway at the end of the stream, type !enter in chat to join the draw",
+        ));
+
+        let lines = pinned.lines(24);
+
+        assert_eq!(lines.len(), MAX_LINES);
+        assert!(lines.iter().all(|line| line.width() <= 22));
+        assert!(lines[0].starts_with("streamer: "));
+        assert!(lines[2].ends_with('…'));
+        assert_eq!(pinned.height(24), MAX_LINES as u16 + 2);
+        assert_eq!(pinned.height(200), 3);
+
+        pinned.unpin();
+
+        assert!(!pinned.is_pinned());
+        assert!(pinned.lines(24).is_empty());
+        assert_eq!(pinned.height(24), 0);
+    }
+}
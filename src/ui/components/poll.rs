@@ -0,0 +1,293 @@
+use std::time::{Duration, Instant};
+
+use tui::{
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{
+        block::{Position, Title},
+        Block, Borders, Clear, Paragraph,
+    },
+    Frame,
+};
+
+use crate::{
+    twitch::eventsub::{percentages, EventSubEvent, Poll, Prediction, PredictionState},
+    ui::components::Component,
+    utils::text::{format_thousands, title_line, TitleStyle},
+};
+
+/// How long the outcome of a poll or prediction stays shown after it ended.
+const OUTCOME_DURATION: Duration = Duration::from_secs(15);
+
+/// How many cells the distribution bar of an option takes.
+const BAR_WIDTH: usize = 10;
+
+const MAX_WIDTH: u16 = 50;
+
+#[derive(Debug, Clone)]
+enum Vote {
+    Poll(Poll),
+    Prediction(Prediction),
+}
+
+impl Vote {
+    fn id(&self) -> &str {
+        match self {
+            Self::Poll(poll) => &poll.id,
+            Self::Prediction(prediction) => &prediction.id,
+        }
+    }
+
+    fn title(&self) -> &str {
+        match self {
+            Self::Poll(poll) => &poll.title,
+            Self::Prediction(prediction) => &prediction.title,
+        }
+    }
+
+    const fn ended(&self) -> bool {
+        match self {
+            Self::Poll(poll) => poll.ended,
+            Self::Prediction(prediction) => matches!(
+                prediction.state,
+                PredictionState::Resolved(_) | PredictionState::Canceled
+            ),
+        }
+    }
+
+    const fn status(&self) -> &'static str {
+        match self {
+            Self::Poll(poll) if poll.ended => "Poll ended",
+            Self::Poll(_) => "Poll",
+            Self::Prediction(prediction) => match prediction.state {
+                PredictionState::Active => "Prediction",
+                PredictionState::Locked => "Prediction locked",
+                PredictionState::Resolved(_) => "Prediction ended",
+                PredictionState::Canceled => "Prediction canceled",
+            },
+        }
+    }
+
+    /// The title of each option, its count as shown, its share of the total, and if it won.
+    fn options(&self) -> Vec<(String, String, u64, bool)> {
+        match self {
+            Self::Poll(poll) => {
+                let votes = poll.choices.iter().map(|c| c.votes).collect::<Vec<_>>();
+                let most = votes.iter().max().copied().unwrap_or_default();
+
+                poll.choices
+                    .iter()
+                    .zip(percentages(&votes))
+                    .map(|(choice, share)| {
+                        (
+                            choice.title.clone(),
+                            format!("{} votes", format_thousands(choice.votes)),
+                            share,
+                            poll.ended && most > 0 && choice.votes == most,
+                        )
+                    })
+                    .collect()
+            }
+            Self::Prediction(prediction) => {
+                let points = prediction
+                    .outcomes
+                    .iter()
+                    .map(|o| o.channel_points)
+                    .collect::<Vec<_>>();
+
+                prediction
+                    .outcomes
+                    .iter()
+                    .zip(percentages(&points))
+                    .map(|(outcome, share)| {
+                        (
+                            outcome.title.clone(),
+                            format!(
+                                "{} points, {} users",
+                                format_thousands(outcome.channel_points),
+                                format_thousands(outcome.users)
+                            ),
+                            share,
+                            prediction.state == PredictionState::Resolved(outcome.id.clone()),
+                        )
+                    })
+                    .collect()
+            }
+        }
+    }
+}
+
+/// The distribution of votes or channel points of the running poll or prediction.
+#[derive(Debug, Clone, Default)]
+pub struct PollWidget {
+    current: Option<Vote>,
+    /// When the outcome should stop being shown, once it has ended.
+    expiry: Option<Instant>,
+    /// The one the user does not want to see anymore.
+    dismissed: Option<String>,
+}
+
+impl PollWidget {
+    pub fn apply(&mut self, event: &EventSubEvent) {
+        let vote = match event {
+            EventSubEvent::Poll(poll) => Vote::Poll(poll.clone()),
+            EventSubEvent::Prediction(prediction) => Vote::Prediction(prediction.clone()),
+            EventSubEvent::Reset => {
+                *self = Self::default();
+
+                return;
+            }
+            _ => return,
+        };
+
+        if self.dismissed.as_deref() == Some(vote.id()) {
+            return;
+        }
+
+        self.expiry = vote.ended().then(|| Instant::now() + OUTCOME_DURATION);
+        self.current = Some(vote);
+    }
+
+    /// Stop showing the current poll or prediction, until another one starts.
+    pub fn dismiss(&mut self) {
+        if let Some(vote) = self.current.take() {
+            self.dismissed = Some(vote.id().to_string());
+        }
+    }
+
+    pub fn is_shown(&mut self) -> bool {
+        if self.expiry.is_some_and(|expiry| expiry <= Instant::now()) {
+            self.current = None;
+            self.expiry = None;
+        }
+
+        self.current.is_some()
+    }
+}
+
+impl Component for PollWidget {
+    /// Drawn in the top right corner of the given area, which is expected to have a border.
+    fn draw(&mut self, f: &mut Frame, area: Option<Rect>) {
+        if !self.is_shown() {
+            return;
+        }
+
+        let (Some(vote), Some(area)) = (&self.current, area) else {
+            return;
+        };
+
+        let options = vote.options();
+
+        let lines = options
+            .iter()
+            .map(|(title, count, share, won)| {
+                let filled = (*share as usize * BAR_WIDTH).div_ceil(100);
+
+                let style = if *won {
+                    Style::default()
+                        .fg(Color::Green)
+                        .add_modifier(Modifier::BOLD)
+                } else {
+                    Style::default()
+                };
+
+                Line::from(vec![
+                    Span::styled(format!("{share:>3}% "), style),
+                    Span::styled("█".repeat(filled), Style::default().fg(Color::Magenta)),
+                    Span::raw(" ".repeat(BAR_WIDTH - filled)),
+                    Span::styled(format!(" {title}"), style),
+                    Span::styled(format!(" ({count})"), Style::default().fg(Color::DarkGray)),
+                ])
+            })
+            .collect::<Vec<_>>();
+
+        let width = lines
+            .iter()
+            .map(Line::width)
+            .chain([vote.title().chars().count() + 4])
+            .max()
+            .unwrap_or_default() as u16
+            + 2;
+
+        let width = width.min(MAX_WIDTH).min(area.width.saturating_sub(2));
+        let height = (lines.len() as u16 + 2).min(area.height.saturating_sub(2));
+
+        if width < 3 || height < 3 {
+            return;
+        }
+
+        let rect = Rect::new(area.right() - width - 1, area.y + 1, width, height);
+
+        let title = [TitleStyle::Single(vote.title())];
+        let status = [TitleStyle::Single(vote.status())];
+
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Magenta))
+            .title(title_line(
+                &title,
+                Style::default().add_modifier(Modifier::BOLD),
+            ))
+            .title(Title::from(title_line(&status, Style::default())).position(Position::Bottom));
+
+        f.render_widget(Clear, rect);
+        f.render_widget(Paragraph::new(lines).block(block), rect);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::twitch::eventsub::PollChoice;
+
+    fn poll(id: &str, ended: bool) -> EventSubEvent {
+        EventSubEvent::Poll(Poll {
+            id: id.to_string(),
+            title: "Best emote?".to_string(),
+            choices: vec![
+                PollChoice {
+                    title: "Kappa".to_string(),
+                    votes: 3,
+                },
+                PollChoice {
+                    title: "PogChamp".to_string(),
+                    votes: 1,
+                },
+            ],
+            ended,
+        })
+    }
+
+    #[test]
+    fn test_dismissed_until_next_poll() {
+        let mut widget = PollWidget::default();
+
+        widget.apply(&poll("1", false));
+        assert!(widget.is_shown());
+
+        widget.dismiss();
+        widget.apply(&poll("1", false));
+        assert!(!widget.is_shown());
+
+        widget.apply(&poll("2", false));
+        assert!(widget.is_shown());
+    }
+
+    #[test]
+    fn test_poll_winner() {
+        let mut widget = PollWidget::default();
+
+        widget.apply(&poll("1", true));
+
+        let options = widget.current.as_ref().unwrap().options();
+
+        assert_eq!(options[0].2, 75);
+        assert!(options[0].3);
+        assert!(!options[1].3);
+        assert!(widget.is_shown());
+
+        widget.apply(&EventSubEvent::Reset);
+        assert!(!widget.is_shown());
+    }
+}
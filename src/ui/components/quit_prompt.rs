@@ -0,0 +1,147 @@
+use tui::{
+    layout::{Alignment, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+
+use crate::{
+    handlers::{
+        config::SharedCompleteConfig,
+        user_input::events::{Event, Key},
+    },
+    terminal::TerminalAction,
+    ui::components::{utils::centered_rect, Component},
+};
+
+/// Asks for confirmation before quitting, so that the quit key is not hit by accident.
+#[derive(Debug, Clone)]
+pub struct QuitPromptWidget {
+    config: SharedCompleteConfig,
+    focused: bool,
+}
+
+impl QuitPromptWidget {
+    pub const fn new(config: SharedCompleteConfig) -> Self {
+        Self {
+            config,
+            focused: false,
+        }
+    }
+
+    pub const fn is_focused(&self) -> bool {
+        self.focused
+    }
+
+    /// Ask to quit, returning if the application should quit right away.
+    /// Otherwise, the prompt is shown and the answer comes through [`Component::event`].
+    pub fn request(&mut self) -> bool {
+        if !self.config.borrow().terminal.confirm_quit || self.focused {
+            return true;
+        }
+
+        self.focused = true;
+
+        false
+    }
+}
+
+impl Component for QuitPromptWidget {
+    fn draw(&mut self, f: &mut Frame, area: Option<Rect>) {
+        let r = area.unwrap_or_else(|| centered_rect(30, 20, 3, f.size()));
+
+        let paragraph = Paragraph::new(Line::from(vec![
+            Span::raw("Quit twitch-tui? "),
+            Span::styled("y/N", Style::default().add_modifier(Modifier::BOLD)),
+        ]))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Yellow)),
+        )
+        .alignment(Alignment::Center);
+
+        f.render_widget(Clear, r);
+        f.render_widget(paragraph, r);
+    }
+
+    async fn event(&mut self, event: &Event) -> Option<TerminalAction> {
+        if let Event::Input(key) = event {
+            match key {
+                Key::Char('y' | 'Y') | Key::Enter => {
+                    self.focused = false;
+
+                    return Some(TerminalAction::Quit);
+                }
+                Key::Char('n' | 'N') | Key::Esc => {
+                    self.focused = false;
+                }
+                _ => {}
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, rc::Rc};
+
+    use super::*;
+    use crate::handlers::config::CompleteConfig;
+
+    fn prompt(confirm_quit: bool) -> QuitPromptWidget {
+        let mut config = CompleteConfig::default();
+        config.terminal.confirm_quit = confirm_quit;
+
+        QuitPromptWidget::new(Rc::new(RefCell::new(config)))
+    }
+
+    fn key(key: Key) -> Event {
+        Event::Input(key)
+    }
+
+    #[tokio::test]
+    async fn test_quit_confirmed() {
+        let mut prompt = prompt(true);
+
+        assert!(!prompt.request());
+        assert!(prompt.is_focused());
+
+        assert!(prompt.event(&key(Key::Char('x'))).await.is_none());
+        assert!(prompt.is_focused());
+
+        assert!(matches!(
+            prompt.event(&key(Key::Char('y'))).await,
+            Some(TerminalAction::Quit)
+        ));
+        assert!(!prompt.is_focused());
+
+        assert!(!prompt.request());
+        assert!(matches!(
+            prompt.event(&key(Key::Enter)).await,
+            Some(TerminalAction::Quit)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_quit_cancelled() {
+        let mut prompt = prompt(true);
+
+        for cancel in [Key::Esc, Key::Char('n')] {
+            assert!(!prompt.request());
+            assert!(prompt.event(&key(cancel)).await.is_none());
+            assert!(!prompt.is_focused());
+        }
+    }
+
+    #[test]
+    fn test_quit_without_confirmation() {
+        let mut prompt = prompt(false);
+
+        assert!(prompt.request());
+        assert!(!prompt.is_focused());
+    }
+}
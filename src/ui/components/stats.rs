@@ -0,0 +1,100 @@
+use tui::{
+    layout::{Constraint, Rect},
+    style::{Color, Modifier, Style},
+    widgets::{Block, Borders, Clear, Row, Table},
+    Frame,
+};
+
+use crate::{
+    handlers::{
+        chat_stats::ChatStats,
+        config::SharedCompleteConfig,
+        user_input::events::{Event, Key},
+    },
+    terminal::TerminalAction,
+    ui::components::{utils::centered_rect, Component},
+    utils::text::{format_thousands, title_line, TitleStyle},
+};
+
+/// The statistics of chat, shown over it until closed.
+#[derive(Debug, Clone)]
+pub struct StatsWidget {
+    config: SharedCompleteConfig,
+    stats: Option<ChatStats>,
+}
+
+impl StatsWidget {
+    pub const fn new(config: SharedCompleteConfig) -> Self {
+        Self {
+            config,
+            stats: None,
+        }
+    }
+
+    pub const fn is_focused(&self) -> bool {
+        self.stats.is_some()
+    }
+
+    pub fn show(&mut self, stats: ChatStats) {
+        self.stats = Some(stats);
+    }
+}
+
+impl Component for StatsWidget {
+    fn draw(&mut self, f: &mut Frame, area: Option<Rect>) {
+        let Some(stats) = &self.stats else {
+            return;
+        };
+
+        let bold = Style::default().add_modifier(Modifier::BOLD);
+
+        let mut rows = vec![
+            Row::new(vec![
+                "Messages".to_string(),
+                format_thousands(stats.messages as u64),
+            ]),
+            Row::new(vec![
+                "Chatters".to_string(),
+                format_thousands(stats.chatters as u64),
+            ]),
+            Row::new(vec![
+                "Messages per minute".to_string(),
+                format!("{:.1}", stats.messages_per_minute),
+            ]),
+        ];
+
+        if !stats.top_talkers.is_empty() {
+            rows.push(Row::new::<Vec<String>>(vec![]));
+            rows.push(Row::new(vec!["Top chatters".to_string()]).style(bold));
+            rows.extend(stats.top_talkers.iter().map(|(author, count)| {
+                Row::new(vec![author.clone(), format_thousands(*count as u64)])
+            }));
+        }
+
+        let r = area.unwrap_or_else(|| centered_rect(40, 40, rows.len() as u16 + 2, f.size()));
+
+        let title = [TitleStyle::Single("Chat statistics")];
+
+        let table = Table::new(rows, &[Constraint::Min(20), Constraint::Length(10)]).block(
+            Block::default()
+                .title(title_line(&title, bold.fg(Color::Red)))
+                .borders(Borders::ALL)
+                .border_type(self.config.borrow().frontend.border_type.clone().into()),
+        );
+
+        f.render_widget(Clear, r);
+        f.render_widget(table, r);
+    }
+
+    async fn event(&mut self, event: &Event) -> Option<TerminalAction> {
+        if let Event::Input(key) = event {
+            match key {
+                Key::Char('q') | Key::Esc | Key::Enter => self.stats = None,
+                Key::Ctrl('p') => panic!("Manual panic triggered by user."),
+                _ => {}
+            }
+        }
+
+        None
+    }
+}
@@ -0,0 +1,145 @@
+use std::ops::Range;
+
+use crate::utils::text::display_width;
+
+/// The part of a message that a click landed on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClickTarget {
+    Timestamp,
+    Name,
+    Body,
+}
+
+/// Where a message was drawn in the last frame, to tell what a click lands on.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct HitRegion {
+    /// The index of the message among the ones shown, as the scroll offset counts them.
+    pub index: usize,
+    /// The rows that the message takes up on screen.
+    pub rows: Range<u16>,
+    /// The columns of the timestamp on the first row, when it is in view.
+    pub timestamp: Option<Range<u16>>,
+    /// The columns of the name of the author on the first row, when it is in view.
+    pub name: Option<Range<u16>>,
+    /// The column that the rows start at.
+    pub left: u16,
+    /// What every row of the message says, the last of which are the ones in view.
+    pub lines: Vec<String>,
+}
+
+/// The words of a text, with the byte position that each of them starts at.
+pub fn words(text: &str) -> impl Iterator<Item = (usize, &str)> {
+    text.split(' ')
+        .scan(0, |start, word| {
+            let position = *start;
+            *start += word.len() + 1;
+
+            Some((position, word))
+        })
+        .filter(|(_, word)| !word.is_empty())
+}
+
+/// The word of a message under a click, and how many times that word came before it in the message.
+pub fn word_at(region: &HitRegion, column: u16, row: u16) -> Option<(&str, usize)> {
+    if !region.rows.contains(&row) {
+        return None;
+    }
+
+    let hidden = region.lines.len().checked_sub(region.rows.len())?;
+    let line = hidden + usize::from(row - region.rows.start);
+    let column = usize::from(column.checked_sub(region.left)?);
+
+    let text = region.lines.get(line)?;
+    let (position, word) = words(text).find(|(position, word)| {
+        let start = display_width(&text[..*position]);
+
+        (start..start + display_width(word)).contains(&column)
+    })?;
+
+    let before = region.lines[..line]
+        .iter()
+        .flat_map(|line| words(line))
+        .chain(words(&text[..position]))
+        .filter(|(_, other)| *other == word)
+        .count();
+
+    Some((word, before))
+}
+
+/// The index of the message under a click, and the part of it that was clicked.
+pub fn classify(regions: &[HitRegion], column: u16, row: u16) -> Option<(usize, ClickTarget)> {
+    let region = regions.iter().find(|region| region.rows.contains(&row))?;
+
+    let on_header = |columns: &Option<Range<u16>>| {
+        row == region.rows.start
+            && columns
+                .as_ref()
+                .is_some_and(|columns| columns.contains(&column))
+    };
+
+    let target = if on_header(&region.timestamp) {
+        ClickTarget::Timestamp
+    } else if on_header(&region.name) {
+        ClickTarget::Name
+    } else {
+        ClickTarget::Body
+    };
+
+    Some((region.index, target))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_click() {
+        let regions = vec![
+            HitRegion {
+                index: 1,
+                rows: 3..5,
+                timestamp: Some(1..9),
+                name: Some(10..16),
+                ..HitRegion::default()
+            },
+            HitRegion {
+                index: 0,
+                rows: 5..6,
+                ..HitRegion::default()
+            },
+        ];
+
+        assert_eq!(classify(&regions, 4, 3), Some((1, ClickTarget::Timestamp)));
+        assert_eq!(classify(&regions, 12, 3), Some((1, ClickTarget::Name)));
+        assert_eq!(classify(&regions, 20, 3), Some((1, ClickTarget::Body)));
+        // The header columns only count on the first row of the message.
+        assert_eq!(classify(&regions, 12, 4), Some((1, ClickTarget::Body)));
+        assert_eq!(classify(&regions, 4, 5), Some((0, ClickTarget::Body)));
+        assert_eq!(classify(&regions, 4, 8), None);
+    }
+
+    #[test]
+    fn test_word_at_click() {
+        // The first row of the message was scrolled out of view.
+        let region = HitRegion {
+            index: 0,
+            rows: 6..8,
+            left: 2,
+            lines: vec![
+                "12:00 Alice: gg Kappa".to_string(),
+                "wow Kappa".to_string(),
+                "Kappa gg".to_string(),
+            ],
+            ..HitRegion::default()
+        };
+
+        assert_eq!(word_at(&region, 2, 6), Some(("wow", 0)));
+        assert_eq!(word_at(&region, 7, 6), Some(("Kappa", 1)));
+        assert_eq!(word_at(&region, 4, 7), Some(("Kappa", 2)));
+        assert_eq!(word_at(&region, 9, 7), Some(("gg", 1)));
+        // Between the words, and outside of the message.
+        assert_eq!(word_at(&region, 5, 6), None);
+        assert_eq!(word_at(&region, 1, 6), None);
+        assert_eq!(word_at(&region, 2, 5), None);
+    }
+}
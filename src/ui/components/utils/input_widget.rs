@@ -1,3 +1,5 @@
+use std::ops::Range;
+
 use rustyline::{line_buffer::LineBuffer, At, Word};
 use tui::{
     layout::Rect,
@@ -32,6 +34,8 @@ pub struct InputWidget<T: Clone> {
     visual_indicator: Option<VisualValidator>,
     input_suggester: Option<(T, InputSuggester<T>)>,
     suggestion: Option<String>,
+    /// Byte ranges of the input to draw in a warning color.
+    highlights: Vec<Range<usize>>,
 }
 
 impl<T: Clone> InputWidget<T> {
@@ -51,6 +55,7 @@ impl<T: Clone> InputWidget<T> {
             visual_indicator,
             input_suggester,
             suggestion: None,
+            highlights: vec![],
         }
     }
 
@@ -58,6 +63,19 @@ impl<T: Clone> InputWidget<T> {
         self.input.update(s, 0);
     }
 
+    /// Replace the input, putting the cursor at the end of it.
+    pub fn replace(&mut self, s: &str) {
+        self.input.update(s, s.len());
+    }
+
+    pub fn set_title(&mut self, title: &str) {
+        title.clone_into(&mut self.title);
+    }
+
+    pub fn set_highlights(&mut self, highlights: Vec<Range<usize>>) {
+        self.highlights = highlights;
+    }
+
     pub const fn is_focused(&self) -> bool {
         self.focused
     }
@@ -141,21 +159,43 @@ impl<T: Clone> Component for InputWidget<T> {
                     .add_modifier(Modifier::BOLD),
             ));
 
-        let paragraph_lines = Line::from(vec![
-            Span::raw(current_input),
-            Span::styled(
-                self.suggestion
-                    .as_ref()
-                    .map_or_else(String::new, |suggestion_buffer| {
-                        if suggestion_buffer.len() > current_input.len() {
-                            suggestion_buffer[current_input.len()..].to_string()
-                        } else {
-                            String::new()
-                        }
-                    }),
-                Style::default().add_modifier(Modifier::DIM),
-            ),
-        ]);
+        let mut spans = vec![];
+        let mut end = 0;
+
+        for range in &self.highlights {
+            let (Some(before), Some(highlighted)) = (
+                current_input.get(end..range.start),
+                current_input.get(range.clone()),
+            ) else {
+                continue;
+            };
+
+            spans.push(Span::raw(before));
+            spans.push(Span::styled(
+                highlighted,
+                Style::default()
+                    .fg(Color::Red)
+                    .add_modifier(Modifier::UNDERLINED),
+            ));
+            end = range.end;
+        }
+
+        spans.push(Span::raw(&current_input[end..]));
+
+        spans.push(Span::styled(
+            self.suggestion
+                .as_ref()
+                .map_or_else(String::new, |suggestion_buffer| {
+                    if suggestion_buffer.len() > current_input.len() {
+                        suggestion_buffer[current_input.len()..].to_string()
+                    } else {
+                        String::new()
+                    }
+                }),
+            Style::default().add_modifier(Modifier::DIM),
+        ));
+
+        let paragraph_lines = Line::from(spans);
 
         let paragraph = Paragraph::new(paragraph_lines)
             .block(block)
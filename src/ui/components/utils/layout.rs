@@ -0,0 +1,181 @@
+use tui::layout::{Constraint, Direction, Layout, Margin, Rect};
+
+use crate::handlers::config::{ColumnAlignment, InputPosition};
+
+/// Where each part of the screen goes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScreenLayout {
+    /// Everything but the state tabs, where the current state is drawn.
+    pub content: Rect,
+    pub chat: Rect,
+    pub input: Option<Rect>,
+    pub tabs: Option<Rect>,
+}
+
+impl ScreenLayout {
+    /// Split the screen into chat, the input box if it is shown, and the state tabs if they are shown.
+    ///
+    /// The input box and state tabs go together on the edge of the screen given by `position`,
+    /// with the tabs on the very edge.
+    pub fn new(
+        area: Rect,
+        position: InputPosition,
+        margin: u16,
+        input_height: Option<u16>,
+        tabs: bool,
+    ) -> Self {
+        let (content, tabs) = if tabs {
+            let (content, tabs) = split_edge(area, 1, position);
+
+            (content, Some(tabs))
+        } else {
+            (area, None)
+        };
+
+        let inner = content.inner(&Margin {
+            horizontal: margin,
+            vertical: margin,
+        });
+
+        let (chat, input) = input_height.map_or((inner, None), |height| {
+            let (chat, input) = split_edge(inner, height, position);
+
+            (chat, Some(input))
+        });
+
+        Self {
+            content,
+            chat,
+            input,
+            tabs,
+        }
+    }
+
+    /// The layout of focus mode, where chat takes up the whole area, without the margin or
+    /// the state tabs. The input box is still there while typing, so that search keeps working.
+    pub fn focused(area: Rect, position: InputPosition, input_height: Option<u16>) -> Self {
+        Self::new(area, position, 0, input_height, false)
+    }
+}
+
+/// How wide chat is on a terminal `width` columns wide, capped at `max_width` unless that is 0.
+pub fn effective_width(width: u16, max_width: u16) -> u16 {
+    if max_width == 0 {
+        width
+    } else {
+        width.min(max_width)
+    }
+}
+
+/// The column of an area that chat is drawn in, at most `max_width` wide.
+pub fn reading_column(area: Rect, max_width: u16, alignment: ColumnAlignment) -> Rect {
+    let width = effective_width(area.width, max_width);
+
+    let x = match alignment {
+        ColumnAlignment::Left => area.x,
+        ColumnAlignment::Center => area.x + (area.width - width) / 2,
+    };
+
+    Rect { x, width, ..area }
+}
+
+/// Take `height` rows off the edge of an area, returning the rest and the rows taken.
+fn split_edge(area: Rect, height: u16, position: InputPosition) -> (Rect, Rect) {
+    let rest = Constraint::Min(1);
+    let edge = Constraint::Length(height);
+
+    let (constraints, edge_index) = match position {
+        InputPosition::Top => ([edge, rest], 0),
+        InputPosition::Bottom => ([rest, edge], 1),
+    };
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(constraints)
+        .split(area);
+
+    (chunks[1 - edge_index], chunks[edge_index])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCREEN: Rect = Rect {
+        x: 0,
+        y: 0,
+        width: 80,
+        height: 24,
+    };
+
+    #[test]
+    fn test_input_at_bottom() {
+        let layout = ScreenLayout::new(SCREEN, InputPosition::Bottom, 1, Some(3), true);
+
+        assert_eq!(layout.tabs, Some(Rect::new(0, 23, 80, 1)));
+        assert_eq!(layout.content, Rect::new(0, 0, 80, 23));
+        assert_eq!(layout.chat, Rect::new(1, 1, 78, 18));
+        assert_eq!(layout.input, Some(Rect::new(1, 19, 78, 3)));
+    }
+
+    #[test]
+    fn test_input_at_top() {
+        let layout = ScreenLayout::new(SCREEN, InputPosition::Top, 1, Some(3), true);
+
+        assert_eq!(layout.tabs, Some(Rect::new(0, 0, 80, 1)));
+        assert_eq!(layout.content, Rect::new(0, 1, 80, 23));
+        assert_eq!(layout.input, Some(Rect::new(1, 2, 78, 3)));
+        assert_eq!(layout.chat, Rect::new(1, 5, 78, 18));
+    }
+
+    #[test]
+    fn test_focused() {
+        let layout = ScreenLayout::focused(SCREEN, InputPosition::Bottom, None);
+
+        assert_eq!(layout.content, SCREEN);
+        assert_eq!(layout.chat, SCREEN);
+        assert_eq!(layout.input, None);
+        assert_eq!(layout.tabs, None);
+
+        let searching = ScreenLayout::focused(SCREEN, InputPosition::Bottom, Some(3));
+
+        assert_eq!(searching.chat, Rect::new(0, 0, 80, 21));
+        assert_eq!(searching.input, Some(Rect::new(0, 21, 80, 3)));
+    }
+
+    #[test]
+    fn test_effective_width() {
+        assert_eq!(effective_width(80, 0), 80);
+        assert_eq!(effective_width(80, 100), 80);
+        assert_eq!(effective_width(250, 100), 100);
+        assert_eq!(effective_width(100, 100), 100);
+    }
+
+    #[test]
+    fn test_reading_column() {
+        let wide = Rect::new(0, 1, 250, 24);
+
+        assert_eq!(
+            reading_column(wide, 100, ColumnAlignment::Left),
+            Rect::new(0, 1, 100, 24)
+        );
+        assert_eq!(
+            reading_column(wide, 100, ColumnAlignment::Center),
+            Rect::new(75, 1, 100, 24)
+        );
+        assert_eq!(reading_column(SCREEN, 100, ColumnAlignment::Center), SCREEN);
+        assert_eq!(reading_column(SCREEN, 0, ColumnAlignment::Left), SCREEN);
+    }
+
+    #[test]
+    fn test_without_input_or_tabs() {
+        for position in [InputPosition::Top, InputPosition::Bottom] {
+            let layout = ScreenLayout::new(SCREEN, position, 0, None, false);
+
+            assert_eq!(layout.content, SCREEN);
+            assert_eq!(layout.chat, SCREEN);
+            assert_eq!(layout.input, None);
+            assert_eq!(layout.tabs, None);
+        }
+    }
+}
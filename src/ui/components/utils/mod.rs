@@ -1,7 +1,15 @@
+mod hit_regions;
 mod input_widget;
+mod layout;
 mod popups;
+mod scrollbar;
 mod search_widget;
+mod status;
 
+pub use hit_regions::{classify, word_at, words, ClickTarget, HitRegion};
 pub use input_widget::InputWidget;
+pub use layout::{reading_column, ScreenLayout};
 pub use popups::centered_rect;
+pub use scrollbar::{thumb, BufferRows};
 pub use search_widget::{SearchItemGetter, SearchWidget};
+pub use status::{segments, status_spans, Segment, StatusValues};
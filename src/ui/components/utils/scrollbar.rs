@@ -0,0 +1,69 @@
+use std::ops::Range;
+
+/// How many rows all of the shown messages take up once wrapped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct BufferRows {
+    pub total: usize,
+    /// The rows of the messages that are scrolled past, below the view.
+    pub below: usize,
+}
+
+/// The rows of a track of `track` rows that the thumb of the scrollbar takes up, for a view of
+/// `viewport` rows over the buffer. There is no thumb when the whole buffer fits in the view.
+///
+/// Chat is read from the bottom up, so the thumb is at the bottom of the track when not scrolled.
+pub fn thumb(rows: BufferRows, viewport: usize, track: u16) -> Option<Range<u16>> {
+    if track == 0 || rows.total <= viewport {
+        return None;
+    }
+
+    let track = usize::from(track);
+
+    let length = (track * viewport)
+        .div_ceil(rows.total)
+        .clamp(1, track.saturating_sub(1).max(1));
+
+    let scrollable = rows.total - viewport;
+    let above = scrollable.saturating_sub(rows.below);
+
+    // Rounded to the nearest row, so that the very top and bottom are only reached at the ends.
+    let start = (above * (track - length) + scrollable / 2) / scrollable;
+
+    Some(start as u16..(start + length) as u16)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const fn rows(total: usize, below: usize) -> BufferRows {
+        BufferRows { total, below }
+    }
+
+    #[test]
+    fn test_thumb_fits_in_view() {
+        assert_eq!(thumb(rows(10, 0), 20, 20), None);
+        assert_eq!(thumb(rows(20, 0), 20, 20), None);
+        assert_eq!(thumb(rows(100, 0), 20, 0), None);
+    }
+
+    #[test]
+    fn test_thumb_position_and_size() {
+        // A fifth of the buffer is in view, at the bottom of it.
+        assert_eq!(thumb(rows(100, 0), 20, 20), Some(16..20));
+        // Scrolled all the way up.
+        assert_eq!(thumb(rows(100, 80), 20, 20), Some(0..4));
+        // Halfway.
+        assert_eq!(thumb(rows(100, 40), 20, 20), Some(8..12));
+        // Scrolling past the top keeps the thumb there.
+        assert_eq!(thumb(rows(100, 95), 20, 20), Some(0..4));
+    }
+
+    #[test]
+    fn test_thumb_of_huge_buffer() {
+        // The thumb never gets thinner than a row, and always leaves room to tell where it is.
+        assert_eq!(thumb(rows(100_000, 0), 20, 20), Some(19..20));
+        assert_eq!(thumb(rows(21, 0), 20, 20), Some(1..20));
+        assert_eq!(thumb(rows(21, 1), 20, 20), Some(0..19));
+    }
+}
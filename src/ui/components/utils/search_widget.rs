@@ -1,8 +1,6 @@
-use std::{clone::Clone, convert::From, iter::Iterator, vec::Vec};
+use std::{clone::Clone, cmp::Reverse, convert::From, iter::Iterator, vec::Vec};
 
 use color_eyre::Result;
-use fuzzy_matcher::{skim::SkimMatcherV2, FuzzyMatcher};
-use once_cell::sync::Lazy;
 use tui::{
     layout::Rect,
     prelude::{Alignment, Margin},
@@ -24,13 +22,11 @@ use crate::{
     terminal::TerminalAction,
     twitch::TwitchAction,
     ui::components::{Component, ErrorWidget},
-    utils::text::{title_line, TitleStyle},
+    utils::text::{fuzzy_indices, title_line, TitleStyle},
 };
 
 use super::{centered_rect, InputWidget};
 
-static FUZZY_FINDER: Lazy<SkimMatcherV2> = Lazy::new(SkimMatcherV2::default);
-
 pub trait SearchItemGetter<T>
 where
     T: ToString,
@@ -166,22 +162,20 @@ where
 
             self.filtered_items = None;
         } else {
-            let item_filter = |c: String| -> Vec<usize> {
-                FUZZY_FINDER
-                    .fuzzy_indices(&c, &current_input)
-                    .map(|(_, indices)| indices)
-                    .unwrap_or_default()
-            };
-
-            let mut matched = vec![];
+            let mut scored = current_items
+                .iter()
+                .filter_map(|item| {
+                    fuzzy_indices(&item.to_string(), &current_input)
+                        .map(|(score, indices)| (score, item.clone(), indices))
+                })
+                .collect::<Vec<_>>();
 
-            for item in current_items.clone() {
-                let matched_indices = item_filter(item.to_string());
+            // Best match first, where items of the same score keep their order.
+            scored.sort_by_key(|(score, _, _)| Reverse(*score));
 
-                if matched_indices.is_empty() {
-                    continue;
-                }
+            let mut matched = vec![];
 
+            for (_, item, matched_indices) in scored {
                 let search_theme = Style::default().fg(Color::Red).add_modifier(Modifier::BOLD);
 
                 let line = item
@@ -0,0 +1,167 @@
+use std::str::FromStr;
+
+use color_eyre::eyre::{bail, Error, Result};
+use log::warn;
+use tui::text::Span;
+
+use crate::utils::text::TitleStyle;
+
+/// A part of the status line over chat, which can be picked and ordered in the config.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Segment {
+    Mode,
+    Connection,
+    Time,
+    Channel,
+    Filter,
+    /// How many messages are held back, only while paused.
+    Paused,
+    /// Whose messages are shown, only while filtering by author.
+    Author,
+    /// The room modes, only when the channel is in any.
+    Modes,
+}
+
+impl Segment {
+    /// All segments, in the order that they are shown in by default.
+    pub const ALL: [Self; 8] = [
+        Self::Mode,
+        Self::Connection,
+        Self::Time,
+        Self::Channel,
+        Self::Filter,
+        Self::Paused,
+        Self::Author,
+        Self::Modes,
+    ];
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Mode => "mode",
+            Self::Connection => "connection",
+            Self::Time => "time",
+            Self::Channel => "channel",
+            Self::Filter => "filter",
+            Self::Paused => "paused",
+            Self::Author => "author",
+            Self::Modes => "modes",
+        }
+    }
+}
+
+impl FromStr for Segment {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = s.trim().to_lowercase();
+
+        match Self::ALL.into_iter().find(|segment| segment.name() == name) {
+            Some(segment) => Ok(segment),
+            None => bail!("Status segment '{}' does not exist", s),
+        }
+    }
+}
+
+/// The segments of the configured names, in their order. Unknown names are skipped with a warning.
+pub fn segments(names: &[String]) -> Vec<Segment> {
+    names
+        .iter()
+        .filter_map(|name| {
+            name.parse()
+                .map_err(|err| warn!("Skipping status segment: {err}"))
+                .ok()
+        })
+        .collect()
+}
+
+/// What each segment shows, where the ones that are `None` are left out.
+#[derive(Debug, Clone)]
+pub struct StatusValues<'a> {
+    pub mode: Span<'a>,
+    pub connection: Span<'a>,
+    pub time: &'a str,
+    pub channel: Span<'a>,
+    pub filter: Span<'a>,
+    pub paused: Option<&'a str>,
+    pub author: Option<&'a str>,
+    pub modes: Option<&'a str>,
+}
+
+/// The status line made of the given segments, in their order.
+pub fn status_spans<'a>(segments: &[Segment], values: &StatusValues<'a>) -> Vec<TitleStyle<'a>> {
+    segments
+        .iter()
+        .filter_map(|segment| match segment {
+            Segment::Mode => Some(TitleStyle::Custom(values.mode.clone())),
+            Segment::Connection => Some(TitleStyle::Custom(values.connection.clone())),
+            Segment::Time => Some(TitleStyle::Combined("Time", values.time)),
+            Segment::Channel => Some(TitleStyle::CombinedStyled(
+                "Channel",
+                values.channel.clone(),
+            )),
+            Segment::Filter => Some(TitleStyle::Custom(values.filter.clone())),
+            Segment::Paused => values
+                .paused
+                .map(|held| TitleStyle::Combined("Paused", held)),
+            Segment::Author => values
+                .author
+                .map(|author| TitleStyle::Combined("Author", author)),
+            Segment::Modes => values
+                .modes
+                .map(|modes| TitleStyle::Combined("Modes", modes)),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use tui::style::Style;
+
+    use super::*;
+    use crate::utils::text::title_line;
+
+    fn values() -> StatusValues<'static> {
+        StatusValues {
+            mode: Span::raw("NORMAL"),
+            connection: Span::raw("Connected"),
+            time: "12:00",
+            channel: Span::raw("xithrius"),
+            filter: Span::raw("Filter"),
+            paused: None,
+            author: Some("nanashi"),
+            modes: None,
+        }
+    }
+
+    fn text(spans: &[TitleStyle]) -> String {
+        title_line(spans, Style::default())
+            .iter()
+            .map(|span| span.content.as_ref())
+            .collect()
+    }
+
+    #[test]
+    fn test_segments_in_configured_order() {
+        let names = ["channel", "Mode", "paused", "author"].map(str::to_string);
+
+        assert_eq!(
+            text(&status_spans(&segments(&names), &values())),
+            "[ Channel: xithrius ] [ NORMAL ] [ Author: nanashi ]"
+        );
+    }
+
+    #[test]
+    fn test_unknown_segments_are_skipped() {
+        let names = ["latency", "time", "", "connection"].map(str::to_string);
+
+        assert_eq!(segments(&names), vec![Segment::Time, Segment::Connection]);
+        assert!("latency".parse::<Segment>().is_err());
+    }
+
+    #[test]
+    fn test_default_segments() {
+        let names = Segment::ALL.map(|segment| segment.name().to_string());
+
+        assert_eq!(segments(&names), Segment::ALL);
+    }
+}
@@ -32,9 +32,35 @@ pub static HELP_KEYBINDS: Lazy<Vec<(&str, Vec<(&str, &str)>)>> = Lazy::new(|| {
                 ("s", "Open the recent channel search widget"),
                 ("f", "Open the followed channel search widget"),
                 ("S", "Go to the dashboard screen (start screen)"),
+                ("Tab", "Switch to the previously active channel"),
+                ("l", "Open the latest Twitch link from chat in the browser"),
+                ("e", "Expand or collapse messages that look like ASCII art"),
+                ("x", "Dismiss the running poll or prediction"),
+                ("k or Up, j or Down", "Select an older or a newer message"),
+                (
+                    "Click a name or timestamp",
+                    "Show only that author's messages, or select the message",
+                ),
+                (
+                    "P",
+                    "Pin the selected or the streamer's latest message, or unpin it",
+                ),
+                ("y", "Copy the selected or the latest message as a quote"),
+                (
+                    "Enter",
+                    "Show the selected message in full, or cut it short again",
+                ),
+                ("z", "Show only chat, or bring back everything around it"),
+                ("p", "Pause chat, or resume it if paused"),
+                ("End or G", "Resume following chat at the newest message"),
+                (
+                    "d, t or b",
+                    "Delete, time out or ban the selected message, in moderator mode",
+                ),
                 ("Ctrl + f", "Search messages"),
                 ("Ctrl + t", "Toggle the message filter"),
                 ("Ctrl + r", "Reverse the message filter"),
+                ("Ctrl + k", "Run any action by its name"),
                 ("Ctrl + p", "Manually crash the application"),
                 ("Esc", "Go back to the previous window"),
             ],
@@ -60,6 +86,8 @@ pub static HELP_KEYBINDS: Lazy<Vec<(&str, Vec<(&str, &str)>)>> = Lazy::new(|| {
                 ("Alt + b", "Move to the start of the previous word"),
                 ("Alt + t", "Swap previous word with current word"),
                 ("Alt + e", "Toggle emote picker"),
+                ("Alt + 1-9", "Insert a canned response from the config"),
+                ("Ctrl + r", "Search sent messages, again for older matches"),
             ],
         ),
     ]
@@ -68,14 +96,22 @@ pub static HELP_KEYBINDS: Lazy<Vec<(&str, Vec<(&str, &str)>)>> = Lazy::new(|| {
 // https://help.twitch.tv/s/article/chat-commands?language=en_US
 pub static COMMANDS: Lazy<Vec<&str>> = Lazy::new(|| {
     vec![
+        "announce",
+        "announceblue",
+        "announcegreen",
+        "announceorange",
+        "announcepurple",
         "ban",
         "unban",
+        "canned",
         "clear",
+        "clearhistory",
         "color",
         "commercial",
         "delete",
         "disconnect",
         "emoteonly",
+        "ephemeral",
         "emoteonlyoff",
         "followers",
         "followersoff",
@@ -87,16 +123,24 @@ pub static COMMANDS: Lazy<Vec<&str>> = Lazy::new(|| {
         "mod",
         "unmod",
         "mods",
+        "msg",
         "r9kbeta",
         "r9kbetaoff",
         "raid",
+        "raw",
         "unraid",
+        "reconnect",
+        "shoutout",
         "slow",
         "slowoff",
+        "so",
+        "stats",
         "subscribers",
         "subscribersoff",
         "timeout",
         "untimeout",
+        "uniquechat",
+        "uniquechatoff",
         "vip",
         "unvip",
         "vips",
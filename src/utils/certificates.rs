@@ -0,0 +1,206 @@
+use std::{fs, sync::OnceLock};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use color_eyre::{
+    eyre::{bail, eyre},
+    Result,
+};
+use irc::client::prelude::Config;
+use log::warn;
+use reqwest::Certificate;
+
+use crate::{
+    handlers::config::TwitchConfig,
+    utils::pathing::{cache_path, capabilities},
+};
+
+const PEM_BEGIN: &str = "-----BEGIN CERTIFICATE-----";
+const PEM_END: &str = "-----END CERTIFICATE-----";
+
+/// Where the certificate for the IRC connection is saved, which it can only read from a file.
+const IRC_CERTIFICATE_FILE: &str = "root_certificate.der";
+
+static ROOT_CERTIFICATES: OnceLock<RootCertificates> = OnceLock::new();
+
+/// Certificates to trust besides the ones of the system, such as the one of a network
+/// that intercepts TLS.
+#[derive(Debug, Clone, Default)]
+pub struct RootCertificates {
+    certificates: Vec<Certificate>,
+    /// The same certificates in the DER format.
+    der: Vec<Vec<u8>>,
+    /// The file the first certificate was saved to for the IRC connection.
+    irc_path: Option<String>,
+}
+
+impl RootCertificates {
+    /// The certificates of the PEM file from the config, if there is one.
+    pub fn from_config(config: &TwitchConfig) -> Result<Self> {
+        match config.root_certificates.as_deref() {
+            Some(path) if !path.is_empty() => Self::load(path),
+            _ => Ok(Self::default()),
+        }
+    }
+
+    /// Read a PEM file of one or more certificates.
+    pub fn load(path: &str) -> Result<Self> {
+        let pem = fs::read_to_string(path)
+            .map_err(|err| eyre!("Root certificates at '{path}' cannot be read: {err}"))?;
+
+        let der = parse_pem(&pem).map_err(|err| eyre!("Root certificates at '{path}': {err}"))?;
+
+        let certificates = der
+            .iter()
+            .enumerate()
+            .map(|(i, der)| {
+                Certificate::from_der(der).map_err(|err| {
+                    eyre!("Root certificate {} at '{path}' is invalid: {err}", i + 1)
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self {
+            certificates,
+            der,
+            irc_path: None,
+        })
+    }
+
+    pub fn certificates(&self) -> &[Certificate] {
+        &self.certificates
+    }
+
+    /// Make the IRC connection trust the first certificate, as it cannot trust more than one.
+    pub fn apply_irc(&self, config: &mut Config) {
+        config.cert_path.clone_from(&self.irc_path);
+    }
+
+    /// Save the first certificate for the IRC connection to the cache directory.
+    fn save_for_irc(&mut self) {
+        let Some(der) = self.der.first() else {
+            return;
+        };
+
+        if !capabilities().cache {
+            warn!("The cache directory cannot be written to, chat does not trust the root certificates.");
+            return;
+        }
+
+        let path = cache_path(IRC_CERTIFICATE_FILE);
+
+        match fs::write(&path, der) {
+            Ok(()) => self.irc_path = Some(path),
+            Err(err) => warn!("Unable to save the root certificate for chat to {path}: {err}"),
+        }
+
+        if self.der.len() > 1 {
+            warn!("Chat only trusts the first of the root certificates.");
+        }
+    }
+}
+
+/// The DER encoded certificates of a PEM file, in order.
+fn parse_pem(pem: &str) -> Result<Vec<Vec<u8>>> {
+    let mut certificates = vec![];
+    let mut rest = pem;
+
+    while let Some(start) = rest.find(PEM_BEGIN) {
+        let body = &rest[start + PEM_BEGIN.len()..];
+
+        let Some(end) = body.find(PEM_END) else {
+            bail!("A certificate is missing its '{PEM_END}' line");
+        };
+
+        let base64 = body[..end]
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .collect::<String>();
+
+        certificates.push(
+            STANDARD
+                .decode(base64)
+                .map_err(|err| eyre!("A certificate is not valid base64: {err}"))?,
+        );
+
+        rest = &body[end + PEM_END.len()..];
+    }
+
+    if certificates.is_empty() {
+        bail!("No certificates were found, they need to be in the PEM format");
+    }
+
+    Ok(certificates)
+}
+
+/// Set the certificates that [`super::proxy::http_client`] and the IRC connection trust,
+/// which can only be done once.
+pub fn init_root_certificates(mut certificates: RootCertificates) {
+    ROOT_CERTIFICATES.get_or_init(|| {
+        certificates.save_for_irc();
+        certificates
+    });
+}
+
+/// The trusted certificates, where there are none before [`init_root_certificates`] is called.
+pub fn root_certificates() -> Option<&'static RootCertificates> {
+    ROOT_CERTIFICATES.get()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use tempfile::NamedTempFile;
+
+    use super::*;
+
+    const CERTIFICATE: &str = "-----BEGIN CERTIFICATE-----
+MIIBjzCCATWgAwIBAgIUJa+lzXdhhkpso2S2KafCuEU4hHwwCgYIKoZIzj0EAwIw
+HTEbMBkGA1UEAwwSdHdpdGNoLXR1aSB0ZXN0IENBMB4XDTI2MTAxNDExMDUxN1oX
+DTM2MTAxMTExMDUxN1owHTEbMBkGA1UEAwwSdHdpdGNoLXR1aSB0ZXN0IENBMFkw
+EwYHKoZIzj0CAQYIKoZIzj0DAQcDQgAEpKPBevRb08u4k+RCScnPHUSZbTQ7Svbz
+5Mb5cqaLURoOiwVG3D+IdIIWSnJLFX930UwXBG1jETBchrZBzUSF/qNTMFEwHQYD
+VR0OBBYEFNvPFvQDJk3h8+iRLrq8v9PEQMLLMB8GA1UdIwQYMBaAFNvPFvQDJk3h
+8+iRLrq8v9PEQMLLMA8GA1UdEwEB/wQFMAMBAf8wCgYIKoZIzj0EAwIDSAAwRQIh
+ALW3GYFP+/Jsbrj9PMDOUVSLK6lH2j1M+lbUlVqKstUVAiApvzKKFslCTB81PgWy
+S4Cr2Q0gElPmqqBhImjdtYNA/A==
+-----END CERTIFICATE-----
+";
+
+    fn pem_file(contents: &str) -> NamedTempFile {
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(contents.as_bytes()).unwrap();
+
+        file
+    }
+
+    #[test]
+    fn test_load_certificates() {
+        let file = pem_file(&format!("Test CA\n{CERTIFICATE}\n{CERTIFICATE}"));
+
+        let certificates = RootCertificates::load(file.path().to_str().unwrap()).unwrap();
+
+        assert_eq!(certificates.certificates().len(), 2);
+        assert_eq!(certificates.der[0], certificates.der[1]);
+        assert!(reqwest::Client::builder()
+            .add_root_certificate(certificates.certificates()[0].clone())
+            .build()
+            .is_ok());
+    }
+
+    #[test]
+    fn test_invalid_certificates_rejected() {
+        let missing = RootCertificates::load("/nonexistent/certificates.pem").unwrap_err();
+        assert!(missing.to_string().contains("cannot be read"));
+
+        let empty = pem_file("not a certificate");
+        assert!(RootCertificates::load(empty.path().to_str().unwrap()).is_err());
+
+        let truncated = pem_file(&CERTIFICATE[..200]);
+        assert!(RootCertificates::load(truncated.path().to_str().unwrap()).is_err());
+
+        let garbage = pem_file(&format!("{PEM_BEGIN}\nAAAA\n{PEM_END}\n"));
+        let err = RootCertificates::load(garbage.path().to_str().unwrap()).unwrap_err();
+        assert!(err.to_string().contains("is invalid"));
+    }
+}
@@ -0,0 +1,28 @@
+use std::io::{stdout, Write};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+
+/// The escape sequence (OSC 52) that has the terminal put text on the clipboard.
+///
+/// The terminal does the copying, so this also works over SSH, but only in terminals that support it.
+pub fn osc52(text: &str) -> String {
+    format!("\x1b]52;c;{}\x07", STANDARD.encode(text))
+}
+
+/// Copy text to the clipboard through the terminal.
+pub fn copy_to_clipboard(text: &str) -> std::io::Result<()> {
+    let mut out = stdout();
+
+    out.write_all(osc52(text).as_bytes())
+        .and_then(|()| out.flush())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_osc52() {
+        assert_eq!(osc52("hi"), "\x1b]52;c;aGk=\x07");
+    }
+}
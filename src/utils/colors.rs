@@ -32,6 +32,114 @@ pub fn hsl_to_rgb(hue: f64, saturation: f64, lightness: f64) -> [u8; 3] {
     [red as u8, green as u8, blue as u8]
 }
 
+/// The hue, saturation and lightness of an RGB color, the inverse of [`hsl_to_rgb`].
+pub fn rgb_to_hsl(rgb: [u8; 3]) -> (f64, f64, f64) {
+    let [red, green, blue] = rgb.map(|c| f64::from(c) / 255.);
+
+    let (min, max) = (
+        rgb.into_iter().min().unwrap_or_default(),
+        rgb.into_iter().max().unwrap_or_default(),
+    );
+
+    let chroma = f64::from(max - min) / 255.;
+    let lightness = f64::from(u16::from(max) + u16::from(min)) / 510.;
+
+    if min == max {
+        return (0., 0., lightness);
+    }
+
+    let saturation = chroma / (1. - (2. * lightness - 1.).abs());
+
+    let hue = if max == rgb[0] {
+        60. * ((green - blue) / chroma).rem_euclid(6.)
+    } else if max == rgb[1] {
+        60. * ((blue - red) / chroma + 2.)
+    } else {
+        60. * ((red - green) / chroma + 4.)
+    };
+
+    (hue, saturation.min(1.), lightness)
+}
+
+/// <https://www.w3.org/TR/WCAG21/#dfn-relative-luminance>
+fn relative_luminance([red, green, blue]: [u8; 3]) -> f64 {
+    let channel = |c: u8| {
+        let c = f64::from(c) / 255.;
+
+        if c <= 0.039_28 {
+            c / 12.92
+        } else {
+            ((c + 0.055) / 1.055).powf(2.4)
+        }
+    };
+
+    0.0722f64.mul_add(
+        channel(blue),
+        0.2126f64.mul_add(channel(red), 0.7152 * channel(green)),
+    )
+}
+
+/// How well two colors stand out from each other, from 1 for the same color to 21 for black on white.
+///
+/// <https://www.w3.org/TR/WCAG21/#dfn-contrast-ratio>
+pub fn contrast_ratio(first: [u8; 3], second: [u8; 3]) -> f64 {
+    let (first, second) = (relative_luminance(first), relative_luminance(second));
+
+    (first.max(second) + 0.05) / (first.min(second) + 0.05)
+}
+
+/// Lighten or darken a color, away from the background, until it has at least `min_ratio`
+/// of contrast with it. The hue and saturation are kept, and the lightness is changed as
+/// little as needed. Only RGB colors are changed, as the others depend on the terminal.
+pub fn ensure_contrast(color: Color, background: Color, min_ratio: f64) -> Color {
+    let (Rgb(r, g, b), Rgb(br, bg, bb)) = (color, background) else {
+        return color;
+    };
+
+    let (rgb, background) = ([r, g, b], [br, bg, bb]);
+
+    if contrast_ratio(rgb, background) >= min_ratio {
+        return color;
+    }
+
+    let (hue, saturation, lightness) = rgb_to_hsl(rgb);
+
+    // Lighter colors stand out from dark backgrounds, and darker ones from light backgrounds.
+    let lighten = relative_luminance(background) < 0.5;
+
+    let (mut low, mut high) = if lighten {
+        (lightness, 1.)
+    } else {
+        (0., lightness)
+    };
+
+    let contrast =
+        |lightness: f64| contrast_ratio(hsl_to_rgb(hue, saturation, lightness), background);
+
+    let furthest = if lighten { high } else { low };
+
+    if contrast(furthest) < min_ratio {
+        let [r, g, b] = hsl_to_rgb(hue, saturation, furthest);
+
+        return Rgb(r, g, b);
+    }
+
+    // The closest lightness to the original one that still has enough contrast.
+    for _ in 0..16 {
+        let middle = (low + high) / 2.;
+
+        if (contrast(middle) >= min_ratio) == lighten {
+            high = middle;
+        } else {
+            low = middle;
+        }
+    }
+
+    let [r, g, b] = hsl_to_rgb(hue, saturation, if lighten { high } else { low });
+
+    Rgb(r, g, b)
+}
+
 // Convert a u32 to RGB values, discarding the highest byte.
 pub const fn u32_to_color(value: u32) -> Color {
     Rgb((value >> 16) as u8, (value >> 8) as u8, value as u8)
@@ -62,6 +170,64 @@ mod tests {
         assert_eq!(Rgb(0, 0, 0), black_rgb);
     }
 
+    #[test]
+    fn test_rgb_to_hsl() {
+        let (hue, saturation, lightness) = rgb_to_hsl([138, 46, 160]);
+        let [r, g, b] = hsl_to_rgb(hue, saturation, lightness);
+
+        assert!((hue - 288.4).abs() < 0.1);
+        assert!([r.abs_diff(138), g.abs_diff(46), b.abs_diff(160)]
+            .iter()
+            .all(|d| *d <= 1));
+        assert_eq!(rgb_to_hsl([255, 255, 255]), (0., 0., 1.));
+    }
+
+    #[test]
+    fn test_contrast_ratio() {
+        assert!((contrast_ratio([0, 0, 0], [255, 255, 255]) - 21.).abs() < 0.01);
+        assert!((contrast_ratio([10, 20, 30], [10, 20, 30]) - 1.).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_ensure_contrast_adjusts_dark_color() {
+        let black = Rgb(0, 0, 0);
+        let dark_blue = Rgb(20, 20, 90);
+
+        let Rgb(r, g, b) = ensure_contrast(dark_blue, black, 4.5) else {
+            panic!("Expected an RGB color");
+        };
+
+        assert!(contrast_ratio([r, g, b], [0, 0, 0]) >= 4.5);
+        assert!((rgb_to_hsl([r, g, b]).0 - rgb_to_hsl([20, 20, 90]).0).abs() < 2.);
+        assert_eq!(ensure_contrast(dark_blue, black, 4.5), Rgb(r, g, b));
+    }
+
+    #[test]
+    fn test_ensure_contrast_adjusts_light_color() {
+        let white = Rgb(255, 255, 255);
+
+        let Rgb(r, g, b) = ensure_contrast(Rgb(255, 250, 150), white, 3.) else {
+            panic!("Expected an RGB color");
+        };
+
+        assert!(contrast_ratio([r, g, b], [255, 255, 255]) >= 3.);
+    }
+
+    #[test]
+    fn test_ensure_contrast_keeps_readable_color() {
+        let black = Rgb(0, 0, 0);
+
+        assert_eq!(
+            ensure_contrast(Rgb(255, 200, 0), black, 4.5),
+            Rgb(255, 200, 0)
+        );
+        assert_eq!(ensure_contrast(Color::Blue, black, 4.5), Color::Blue);
+        assert_eq!(
+            ensure_contrast(Rgb(0, 0, 1), black, 30.),
+            Rgb(255, 255, 255)
+        );
+    }
+
     #[test]
     fn test_u32_to_color() {
         let rgb = u32_to_color(126_496_416);
@@ -0,0 +1,77 @@
+/// Messages with fewer letters than this are too short to tell their language.
+pub const MIN_DETECTION_CHARS: usize = 10;
+
+/// The ISO 639-3 code of the language that text is written in, such as `eng`,
+/// if it is long enough to tell reliably.
+///
+/// Always unknown when built without the `language-detection` feature.
+pub fn detect_language(text: &str) -> Option<String> {
+    if text.chars().filter(|c| c.is_alphabetic()).count() < MIN_DETECTION_CHARS {
+        return None;
+    }
+
+    detect(text)
+}
+
+#[cfg(feature = "language-detection")]
+fn detect(text: &str) -> Option<String> {
+    whatlang::detect(text)
+        .filter(whatlang::Info::is_reliable)
+        .map(|info| info.lang().code().to_string())
+}
+
+#[cfg(not(feature = "language-detection"))]
+const fn detect(_text: &str) -> Option<String> {
+    None
+}
+
+/// If a message in this language is shown, given the languages that are, where all are if none are given.
+/// Messages whose language is unknown are always shown.
+pub fn language_shown(language: Option<&str>, languages: &[String]) -> bool {
+    languages.is_empty()
+        || language.map_or(true, |language| {
+            languages
+                .iter()
+                .any(|shown| shown.eq_ignore_ascii_case(language))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "language-detection")]
+    #[test]
+    fn test_detect_english() {
+        assert_eq!(
+            detect_language("I think this is the best stream I have watched all week").as_deref(),
+            Some("eng")
+        );
+    }
+
+    #[cfg(feature = "language-detection")]
+    #[test]
+    fn test_detect_japanese() {
+        assert_eq!(
+            detect_language("今日の配信はとても楽しかったです、また見に来ますね").as_deref(),
+            Some("jpn")
+        );
+    }
+
+    #[test]
+    fn test_too_short_is_unknown() {
+        assert_eq!(detect_language("lol gg"), None);
+        assert_eq!(detect_language("123456789012345 !!!"), None);
+    }
+
+    #[test]
+    fn test_language_shown() {
+        let languages = vec!["eng".to_string(), "jpn".to_string()];
+
+        assert!(language_shown(Some("eng"), &languages));
+        assert!(language_shown(Some("JPN"), &languages));
+        assert!(!language_shown(Some("deu"), &languages));
+        assert!(language_shown(None, &languages));
+        assert!(language_shown(Some("deu"), &[]));
+    }
+}
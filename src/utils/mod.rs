@@ -1,5 +1,9 @@
+pub mod certificates;
+pub mod clipboard;
 pub mod colors;
 pub mod emotes;
+pub mod language;
 pub mod pathing;
+pub mod proxy;
 pub mod styles;
 pub mod text;
@@ -2,14 +2,95 @@ use color_eyre::{eyre::anyhow, Result};
 use std::{
     env,
     fs::remove_file,
-    fs::File,
+    fs::{create_dir_all, File},
     io::Write,
     mem::drop,
     path::{Path, PathBuf},
+    sync::OnceLock,
 };
 
 const BINARY_NAME: &str = env!("CARGO_BIN_NAME");
 
+static CAPABILITIES: OnceLock<Capabilities> = OnceLock::new();
+
+/// What can be kept on disk, depending on which directories can be written to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Capabilities {
+    /// If emotes and images can be downloaded to the cache directory.
+    pub cache: bool,
+    /// If the storage and session files can be saved next to the config.
+    pub data: bool,
+    /// If the configured log file can be written to.
+    pub log_file: bool,
+}
+
+impl Default for Capabilities {
+    fn default() -> Self {
+        Self {
+            cache: true,
+            data: true,
+            log_file: true,
+        }
+    }
+}
+
+impl Capabilities {
+    fn detect(log_file: Option<&str>) -> Self {
+        let config_file = config_path("");
+
+        Self {
+            cache: is_writable(Path::new(&cache_path(""))),
+            data: is_writable(Path::new(&config_file)),
+            log_file: log_file
+                .filter(|path| !path.is_empty())
+                .and_then(|path| Path::new(path).parent())
+                .map_or(true, |dir| dir.as_os_str().is_empty() || is_writable(dir)),
+        }
+    }
+
+    /// What is disabled because it cannot be written to disk, if anything.
+    pub fn warning(self) -> Option<String> {
+        let disabled = [
+            (self.cache, "emotes and image previews"),
+            (
+                self.data,
+                "saving channels, mentions, input history and the session",
+            ),
+            (self.log_file, "the log file"),
+        ]
+        .into_iter()
+        .filter(|(capable, _)| !capable)
+        .map(|(_, feature)| feature)
+        .collect::<Vec<_>>();
+
+        (!disabled.is_empty()).then(|| {
+            format!(
+                "Some directories cannot be written to, disabled {}.",
+                disabled.join(", ")
+            )
+        })
+    }
+}
+
+/// Check which directories can be written to, which is only done once.
+pub fn init_capabilities(log_file: Option<&str>) -> Capabilities {
+    *CAPABILITIES.get_or_init(|| Capabilities::detect(log_file))
+}
+
+/// What can be kept on disk, where everything can be before [`init_capabilities`] is called.
+pub fn capabilities() -> Capabilities {
+    CAPABILITIES.get().copied().unwrap_or_default()
+}
+
+/// If files can be created in a directory, which is created first if it does not exist.
+pub fn is_writable(dir: &Path) -> bool {
+    create_dir_all(dir).is_ok()
+        && tempfile::Builder::new()
+            .prefix(".write-test")
+            .tempfile_in(dir)
+            .is_ok()
+}
+
 pub fn config_path(file: &str) -> String {
     match env::consts::OS {
         "linux" | "macos" => format!(
@@ -107,6 +188,39 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_writable_dir() {
+        let dir = tempfile::tempdir().unwrap();
+
+        assert!(is_writable(dir.path()));
+        assert!(is_writable(&dir.path().join("nested").join("dirs")));
+    }
+
+    #[test]
+    fn test_unwritable_dir() {
+        // A directory cannot be created inside of a file, whatever the permissions are.
+        let file = tempfile::NamedTempFile::new().unwrap();
+
+        assert!(!is_writable(file.path()));
+        assert!(!is_writable(&file.path().join("cache")));
+    }
+
+    #[test]
+    fn test_capabilities_warning() {
+        assert_eq!(Capabilities::default().warning(), None);
+
+        let capabilities = Capabilities {
+            cache: false,
+            data: true,
+            log_file: false,
+        };
+
+        assert_eq!(
+            capabilities.warning().as_deref(),
+            Some("Some directories cannot be written to, disabled emotes and image previews, the log file.")
+        );
+    }
+
     #[test]
     #[should_panic]
     #[cfg(any(
@@ -65,6 +65,59 @@ pub const SYSTEM_CHAT: Style = Style {
     sub_modifier: Modifier::empty(),
 };
 
+pub const REMOVED_MARKER: Style = Style {
+    fg: Some(Color::Red),
+    bg: None,
+    underline_color: None,
+    add_modifier: Modifier::ITALIC,
+    sub_modifier: Modifier::empty(),
+};
+
+pub const EDITED_MARKER: Style = Style {
+    fg: Some(Color::DarkGray),
+    bg: None,
+    underline_color: None,
+    add_modifier: Modifier::ITALIC,
+    sub_modifier: Modifier::empty(),
+};
+
+pub const ASCII_ART_PLACEHOLDER: Style = Style {
+    fg: Some(Color::DarkGray),
+    bg: None,
+    underline_color: None,
+    add_modifier: Modifier::ITALIC,
+    sub_modifier: Modifier::empty(),
+};
+
+pub const SHOW_MORE_MARKER: Style = Style {
+    fg: Some(Color::DarkGray),
+    bg: None,
+    underline_color: None,
+    add_modifier: Modifier::ITALIC,
+    sub_modifier: Modifier::empty(),
+};
+
+/// Marks messages that contain links.
+pub const LINK_MARKER: Style = Style {
+    fg: Some(Color::Cyan),
+    bg: None,
+    underline_color: None,
+    add_modifier: Modifier::empty(),
+    sub_modifier: Modifier::empty(),
+};
+
+/// Mentions of users in the body of a message.
+pub const MENTION: Style = Style {
+    fg: None,
+    bg: None,
+    underline_color: None,
+    add_modifier: Modifier::BOLD.union(Modifier::UNDERLINED),
+    sub_modifier: Modifier::empty(),
+};
+
+/// Border of messages that were highlighted with channel points, like on Twitch.
+pub const HIGHLIGHTED_MESSAGE: Color = Color::Rgb(117, 94, 188);
+
 pub const DASHBOARD_TITLE_COLOR: Style = Style {
     fg: Some(Color::Rgb(135, 120, 165)),
     bg: None,
@@ -1,11 +1,20 @@
 use memchr::memmem::Finder;
 use once_cell::sync::Lazy;
+use regex::{escape, Regex};
 use rustyline::line_buffer::LineBuffer;
 use std::borrow::Cow;
+use textwrap::{
+    core::Fragment,
+    word_splitters::split_words,
+    wrap_algorithms::{wrap_first_fit, wrap_optimal_fit, Penalties},
+    Options,
+};
 use tui::{style::Style, text::Span};
 use unicode_segmentation::UnicodeSegmentation;
 use unicode_width::UnicodeWidthStr;
 
+use crate::utils::emotes::PRIVATE_USE_UNICODE;
+
 /// Acquiring the horizontal position of the cursor so it can be rendered visually.
 pub fn get_cursor_position(line_buffer: &LineBuffer) -> usize {
     line_buffer
@@ -16,6 +25,261 @@ pub fn get_cursor_position(line_buffer: &LineBuffer) -> usize {
         .sum()
 }
 
+/// Columns that text takes up on screen, counting each grapheme as it is shown.
+///
+/// Emotes shown as images are made of placeholders that take up one cell each.
+pub fn display_width(text: &str) -> usize {
+    text.graphemes(true)
+        .map(
+            |grapheme| match grapheme.matches(PRIVATE_USE_UNICODE).count() {
+                0 => grapheme.width(),
+                cells => cells,
+            },
+        )
+        .sum()
+}
+
+/// Pad text with spaces on the left, so that it ends at the right edge of `width` columns.
+pub fn align_right(text: &str, width: usize) -> String {
+    format!(
+        "{}{text}",
+        " ".repeat(width.saturating_sub(display_width(text)))
+    )
+}
+
+/// The links in text, such as `https://example.com/page`, without the punctuation around them.
+pub fn extract_urls(text: &str) -> Vec<&str> {
+    text.split_whitespace()
+        .map(|word| {
+            word.trim_matches(|c: char| {
+                matches!(c, '(' | ')' | '<' | '>' | '"' | '\'' | ',' | '.' | '!')
+            })
+        })
+        .filter(|word| {
+            word.strip_prefix("https://")
+                .or_else(|| word.strip_prefix("http://"))
+                .or_else(|| word.strip_prefix("www."))
+                .is_some_and(|rest| {
+                    rest.split(['/', '?', '#'])
+                        .next()
+                        .is_some_and(|host| host.contains('.'))
+                })
+        })
+        .collect()
+}
+
+/// A number with commas between each group of three digits, such as `12,345`.
+pub fn format_thousands(number: u64) -> String {
+    let digits = number.to_string();
+
+    digits
+        .char_indices()
+        .fold(String::new(), |mut formatted, (i, digit)| {
+            if i > 0 && (digits.len() - i) % 3 == 0 {
+                formatted.push(',');
+            }
+            formatted.push(digit);
+
+            formatted
+        })
+}
+
+/// The longest start of text that fits in `width` columns, without cutting a grapheme.
+pub fn truncate_to_width(text: &str, width: usize) -> &str {
+    let mut used = 0;
+
+    let end = text
+        .grapheme_indices(true)
+        .find_map(|(i, grapheme)| {
+            used += display_width(grapheme);
+
+            (used > width).then_some(i)
+        })
+        .unwrap_or(text.len());
+
+    &text[..end]
+}
+
+/// A word of text to be wrapped, measured by [`display_width`].
+#[derive(Debug)]
+struct Token<'a> {
+    word: &'a str,
+    whitespace: &'a str,
+    penalty: &'a str,
+    width: usize,
+}
+
+impl<'a> Token<'a> {
+    fn new(word: &'a str, whitespace: &'a str, penalty: &'a str) -> Self {
+        Self {
+            word,
+            whitespace,
+            penalty,
+            width: display_width(word),
+        }
+    }
+
+    /// Split the token into pieces that fit in `width` columns, cutting between graphemes.
+    fn break_apart(self, width: usize, tokens: &mut Vec<Self>) {
+        if self.width <= width {
+            tokens.push(self);
+            return;
+        }
+
+        let mut start = 0;
+        let mut used = 0;
+
+        for (i, grapheme) in self.word.grapheme_indices(true) {
+            let grapheme_width = display_width(grapheme);
+
+            if used + grapheme_width > width && i > start {
+                tokens.push(Self::new(&self.word[start..i], "", ""));
+                start = i;
+                used = 0;
+            }
+
+            used += grapheme_width;
+        }
+
+        tokens.push(Self::new(
+            &self.word[start..],
+            self.whitespace,
+            self.penalty,
+        ));
+    }
+}
+
+impl Fragment for Token<'_> {
+    fn width(&self) -> f64 {
+        self.width as f64
+    }
+
+    fn whitespace_width(&self) -> f64 {
+        display_width(self.whitespace) as f64
+    }
+
+    fn penalty_width(&self) -> f64 {
+        display_width(self.penalty) as f64
+    }
+}
+
+/// Shorten words that are wider than `max_width` to fit in it, ending them with an ellipsis,
+/// where words are never shortened if it is 0.
+fn cut_long_words(line: &str, max_width: usize) -> Cow<'_, str> {
+    if max_width == 0 || line.split(' ').all(|word| display_width(word) <= max_width) {
+        return Cow::Borrowed(line);
+    }
+
+    Cow::Owned(
+        line.split(' ')
+            .map(|word| {
+                if display_width(word) <= max_width {
+                    Cow::Borrowed(word)
+                } else {
+                    Cow::Owned(format!(
+                        "{}…",
+                        truncate_to_width(word, max_width.saturating_sub(1))
+                    ))
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" "),
+    )
+}
+
+/// Wrap text into lines of at most `width` columns, with the first line starting with `indent`.
+///
+/// This wraps like [`textwrap::wrap`], except that every word is measured by [`display_width`],
+/// so that lines with emotes do not overflow. Words that are wider than a line are broken
+/// across as many lines as they need, up to `word_lines` of them if it is not 0, after which
+/// the rest of the word is cut off.
+pub fn wrap_tokens<'a>(
+    text: &'a str,
+    width: usize,
+    indent: &str,
+    word_lines: usize,
+) -> Vec<Cow<'a, str>> {
+    let mut lines = vec![];
+
+    for line in text.split('\n').map(|line| line.trim_end_matches('\r')) {
+        let indent = if lines.is_empty() { indent } else { "" };
+
+        match cut_long_words(line, width * word_lines) {
+            Cow::Borrowed(line) => lines.extend(wrap_line(line, width, indent)),
+            Cow::Owned(line) => lines.extend(
+                wrap_line(&line, width, indent)
+                    .into_iter()
+                    .map(|row| Cow::Owned(row.into_owned())),
+            ),
+        }
+    }
+
+    lines
+}
+
+/// Wrap a line without line breaks, see [`wrap_tokens`].
+fn wrap_line<'a>(line: &'a str, width: usize, indent: &str) -> Vec<Cow<'a, str>> {
+    let options = Options::new(width);
+    let mut lines = vec![];
+    let indented = !indent.is_empty();
+
+    let line_widths = if indented {
+        vec![
+            width.saturating_sub(display_width(indent)) as f64,
+            width as f64,
+        ]
+    } else {
+        vec![width as f64]
+    };
+
+    let mut tokens = vec![];
+
+    // Without this, the first word would always go on the indented line, even if it does not fit.
+    if indented {
+        tokens.push(Token::new("", "", ""));
+    }
+
+    for word in split_words(
+        options.word_separator.find_words(line),
+        &options.word_splitter,
+    ) {
+        Token::new(word.word, word.whitespace, word.penalty).break_apart(width.max(1), &mut tokens);
+    }
+
+    let wrapped = wrap_optimal_fit(&tokens, &line_widths, &Penalties::new())
+        .unwrap_or_else(|_| wrap_first_fit(&tokens, &line_widths));
+
+    // The tokens follow each other in the line, so each row is the slice that they cover.
+    let mut start = 0;
+
+    for row in wrapped {
+        let Some(last) = row.last() else {
+            lines.push(Cow::Borrowed(""));
+            continue;
+        };
+
+        let len = row
+            .iter()
+            .map(|token| token.word.len() + token.whitespace.len())
+            .sum::<usize>();
+
+        let mut result = Cow::Borrowed(&line[start..start + len - last.whitespace.len()]);
+        start += len;
+
+        if !last.penalty.is_empty() {
+            result.to_mut().push_str(last.penalty);
+        }
+
+        if lines.is_empty() && indented {
+            result = Cow::Owned(format!("{indent}{result}"));
+        }
+
+        lines.push(result);
+    }
+
+    lines
+}
+
 pub fn split_cow_in_place<'a>(cow: &mut Cow<'a, str>, mid: usize) -> Cow<'a, str> {
     match *cow {
         Cow::Owned(ref mut s) => {
@@ -33,6 +297,8 @@ pub fn split_cow_in_place<'a>(cow: &mut Cow<'a, str>, mid: usize) -> Cow<'a, str
 
 pub enum TitleStyle<'a> {
     Combined(&'a str, &'a str),
+    /// Like [`TitleStyle::Combined`], with a value that has its own style.
+    CombinedStyled(&'a str, Span<'a>),
     Single(&'a str),
     Custom(Span<'a>),
 }
@@ -49,6 +315,13 @@ pub fn title_line<'a>(contents: &'a [TitleStyle<'a>], style: Style) -> Vec<Span<
                 Span::styled((*title).to_string(), style),
                 Span::raw(format!(": {value} ]")),
             ],
+            TitleStyle::CombinedStyled(title, value) => vec![
+                first_bracket,
+                Span::styled((*title).to_string(), style),
+                Span::raw(": "),
+                value.clone(),
+                Span::raw(" ]"),
+            ],
             TitleStyle::Single(value) => vec![
                 first_bracket,
                 Span::styled((*value).to_string(), style),
@@ -61,6 +334,110 @@ pub fn title_line<'a>(contents: &'a [TitleStyle<'a>], style: Style) -> Vec<Span<
     complete
 }
 
+/// What every matched character of a fuzzy search scores.
+const FUZZY_MATCH: i64 = 16;
+/// Extra for a character that follows right after the previous match.
+const FUZZY_CONSECUTIVE: i64 = 16;
+/// Extra for a character that starts a word, after a separator or as a capital in camel case.
+const FUZZY_BOUNDARY: i64 = 12;
+/// Extra for matching the very first character.
+const FUZZY_FIRST: i64 = 8;
+/// Taken off for every character skipped between two matches.
+const FUZZY_GAP: i64 = 2;
+/// Taken off for every character skipped before the first match.
+const FUZZY_LEADING: i64 = 1;
+
+fn fuzzy_fold(c: char) -> char {
+    c.to_lowercase().next().unwrap_or(c)
+}
+
+/// The penalty for skipping `skipped` characters.
+fn fuzzy_penalty(skipped: usize, per_char: i64) -> i64 {
+    i64::try_from(skipped)
+        .unwrap_or(i64::MAX)
+        .saturating_mul(per_char)
+}
+
+/// The score of the best fuzzy match of a query in a candidate, and the indices of the characters
+/// that it matched, where the query has to be a subsequence of the candidate, ignoring case.
+/// Matches that are contiguous or start words score higher. An empty query matches anything.
+pub fn fuzzy_indices(candidate: &str, query: &str) -> Option<(i64, Vec<usize>)> {
+    let candidate = candidate.chars().collect::<Vec<_>>();
+    let query = query.chars().map(fuzzy_fold).collect::<Vec<_>>();
+
+    if query.is_empty() {
+        return Some((0, vec![]));
+    }
+
+    if query.len() > candidate.len() {
+        return None;
+    }
+
+    let bonus = |i: usize| {
+        let boundary = i == 0
+            || !candidate[i - 1].is_alphanumeric()
+            || (candidate[i - 1].is_lowercase() && candidate[i].is_uppercase());
+
+        FUZZY_MATCH
+            + if boundary { FUZZY_BOUNDARY } else { 0 }
+            + if i == 0 { FUZZY_FIRST } else { 0 }
+    };
+
+    // The best score of matching the query up to each of its characters, with that character
+    // matched at each index of the candidate, and the index that the one before it was matched at.
+    let mut scores: Vec<Vec<Option<(i64, usize)>>> = vec![vec![None; candidate.len()]; query.len()];
+
+    for (j, &q) in query.iter().enumerate() {
+        for i in j..candidate.len() {
+            if fuzzy_fold(candidate[i]) != q {
+                continue;
+            }
+
+            scores[j][i] = if j == 0 {
+                Some((bonus(i) - fuzzy_penalty(i, FUZZY_LEADING), i))
+            } else {
+                scores[j - 1][..i]
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(k, score)| {
+                        let (score, _) = (*score)?;
+
+                        let between = if k + 1 == i {
+                            FUZZY_CONSECUTIVE
+                        } else {
+                            -fuzzy_penalty(i - k - 1, FUZZY_GAP)
+                        };
+
+                        Some((score + between + bonus(i), k))
+                    })
+                    .max_by_key(|(score, _)| *score)
+            };
+        }
+    }
+
+    let last = query.len() - 1;
+
+    let (mut index, (score, _)) = scores[last]
+        .iter()
+        .enumerate()
+        .filter_map(|(i, score)| score.map(|score| (i, score)))
+        .max_by_key(|(_, (score, _))| *score)?;
+
+    let mut indices = vec![index; query.len()];
+
+    for j in (0..last).rev() {
+        index = scores[j + 1][index].map(|(_, previous)| previous)?;
+        indices[j] = index;
+    }
+
+    Some((score, indices))
+}
+
+/// The score of the best fuzzy match of a query in a candidate, see [`fuzzy_indices`].
+pub fn fuzzy_score(candidate: &str, query: &str) -> Option<i64> {
+    fuzzy_indices(candidate, query).map(|(score, _)| score)
+}
+
 /// Within an array of strings, find the first partial or full match, if any.
 pub fn first_similarity(possibilities: &[String], search: &str) -> Option<String> {
     first_similarity_iter(possibilities.iter(), search)
@@ -147,6 +524,162 @@ pub fn clean_message(msg: &str) -> (String, bool) {
     (String::from_utf8(output).unwrap(), action)
 }
 
+/// Turn censored terms into case-insensitive patterns, which [`censor`] only lets match whole words.
+///
+/// Terms are treated as regex, and are matched literally if they are not valid regex.
+pub fn censor_patterns(terms: &[String]) -> Vec<Regex> {
+    terms
+        .iter()
+        .filter(|term| !term.is_empty())
+        .filter_map(|term| {
+            Regex::new(&format!("(?i)(?:{term})"))
+                .or_else(|_| Regex::new(&format!("(?i){}", escape(term))))
+                .ok()
+        })
+        .collect()
+}
+
+fn is_word_char(c: Option<char>) -> bool {
+    c.is_some_and(|c| c.is_alphanumeric() || c == '_')
+}
+
+/// If the text from `start` to `end` is a whole word. Word boundaries only matter at the edges
+/// that are word characters, so that terms like `@foo` still match.
+fn is_whole_word(text: &str, start: usize, end: usize) -> bool {
+    let matched = &text[start..end];
+
+    let starts_word =
+        !is_word_char(matched.chars().next()) || !is_word_char(text[..start].chars().next_back());
+    let ends_word =
+        !is_word_char(matched.chars().next_back()) || !is_word_char(text[end..].chars().next());
+
+    starts_word && ends_word
+}
+
+/// Replace every character matched by any of the patterns with an asterisk,
+/// keeping the amount of characters in the text the same.
+pub fn censor(text: &str, terms: &[Regex]) -> String {
+    let mut masked = vec![false; text.len()];
+    let mut any_match = false;
+
+    for term in terms {
+        let mut start = 0;
+
+        while let Some(m) = term.find_at(text, start) {
+            if !m.is_empty() && is_whole_word(text, m.start(), m.end()) {
+                masked[m.range()].fill(true);
+                any_match = true;
+
+                start = m.end();
+            } else {
+                // A match inside of a word can hide a whole word that starts later in it.
+                start = m.start() + text[m.start()..].chars().next().map_or(1, char::len_utf8);
+
+                if start > text.len() {
+                    break;
+                }
+            }
+        }
+    }
+
+    if !any_match {
+        return text.to_string();
+    }
+
+    text.char_indices()
+        .map(|(i, c)| if masked[i] { '*' } else { c })
+        .collect()
+}
+
+/// The abbreviation of an invisible character that can hide text or reorder it, which are
+/// zero-width spaces and joiners that separate nothing, and the marks and overrides of
+/// bidirectional text. The zero-width joiner and non-joiner are left out, since emoji and
+/// some scripts need them, and so are combining marks.
+const fn deceptive_control(c: char) -> Option<&'static str> {
+    Some(match c {
+        '\u{200B}' => "ZWSP",
+        '\u{2060}' => "WJ",
+        '\u{FEFF}' => "ZWNBSP",
+        '\u{180E}' => "MVS",
+        '\u{061C}' => "ALM",
+        '\u{200E}' => "LRM",
+        '\u{200F}' => "RLM",
+        '\u{202A}' => "LRE",
+        '\u{202B}' => "RLE",
+        '\u{202C}' => "PDF",
+        '\u{202D}' => "LRO",
+        '\u{202E}' => "RLO",
+        '\u{2066}' => "LRI",
+        '\u{2067}' => "RLI",
+        '\u{2068}' => "FSI",
+        '\u{2069}' => "PDI",
+        _ => return None,
+    })
+}
+
+/// Text without the invisible characters that could hide parts of it or show it in another order.
+pub fn sanitize_display(text: &str) -> String {
+    text.chars()
+        .filter(|&c| deceptive_control(c).is_none())
+        .collect()
+}
+
+/// Text with the invisible characters that could hide parts of it or show it in another order
+/// shown by their abbreviations, such as `<RLO>`.
+pub fn mark_display(text: &str) -> String {
+    let mut marked = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        match deceptive_control(c) {
+            Some(name) => {
+                marked.push('<');
+                marked.push_str(name);
+                marked.push('>');
+            }
+            None => marked.push(c),
+        }
+    }
+
+    marked
+}
+
+/// Messages with fewer visible characters than this are never treated as ASCII art.
+const ASCII_ART_MIN_LENGTH: usize = 60;
+
+/// If a character is commonly used to draw pictures in chat,
+/// like box-drawing characters, block elements, geometric shapes and braille patterns.
+const fn is_art_char(c: char) -> bool {
+    matches!(c, '\u{2500}'..='\u{25FF}' | '\u{2800}'..='\u{28FF}')
+}
+
+/// If a long message is mostly made of drawing characters, where `ratio` is the
+/// fraction of its visible characters that have to be drawing characters.
+pub fn is_ascii_art(text: &str, ratio: f32) -> bool {
+    let (visible, art) = text
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .fold((0_usize, 0_usize), |(visible, art), c| {
+            (visible + 1, art + usize::from(is_art_char(c)))
+        });
+
+    #[allow(clippy::cast_precision_loss)]
+    let art_ratio = art as f32 / visible.max(1) as f32;
+
+    visible >= ASCII_ART_MIN_LENGTH && art_ratio >= ratio
+}
+
+/// Fill in the placeholders of a canned response.
+///
+/// `{user}` is replaced when a user is given, otherwise it is left in place to be filled in by hand.
+pub fn fill_template(template: &str, user: Option<&str>) -> String {
+    const USER_PLACEHOLDER: &str = "{user}";
+
+    user.map_or_else(
+        || template.to_string(),
+        |user| template.replace(USER_PLACEHOLDER, user),
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use tui::{
@@ -155,6 +688,67 @@ mod tests {
     };
 
     use super::*;
+    use crate::utils::emotes::ZERO_WIDTH_SPACE;
+
+    const BRAILLE_ART: &str = "⠀⠀⠀⠀⣀⣤⣴⣶⣶⣶⣶⣦⣤⣀⠀⠀⠀⠀ ⠀⠀⣠⣾⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣷⣄⠀⠀ ⠀⣼⣿⣿⠟⠋⠉⠀⠀⠉⠙⠻⣿⣿⣿⣧⠀ ⢸⣿⣿⠃⠀⠀⠀⠀⠀⠀⠀⠀⠘⣿⣿⣿⡇ ⠈⢿⣿⣷⣤⣀⠀⠀⠀⢀⣠⣴⣿⣿⡿⠁";
+
+    const BLOCK_ART: &str = "██████╗ ██████╗  ██████╗ ╔══════╗ ██╔══██╗██╔══██╗██╔═══██╗ ║ GG ║ ██████╔╝██║  ██║██║   ██║ ╚══════╝ ██╔═══╝ ██║  ██║██║   ██║";
+
+    #[test]
+    fn test_is_ascii_art() {
+        assert!(is_ascii_art(BRAILLE_ART, 0.5));
+        assert!(is_ascii_art(BLOCK_ART, 0.5));
+    }
+
+    #[test]
+    fn test_prose_is_not_ascii_art() {
+        let prose = "This is a pretty long message about how the streamer should have gone left instead of right at the last fork, since the boss room was clearly over there and everyone in chat said so.";
+
+        assert!(!is_ascii_art(prose, 0.5));
+        assert!(!is_ascii_art("⣿⣿⣿ short ⣿⣿⣿", 0.1));
+        assert!(!is_ascii_art(&format!("{prose} ━━━━━━━━━━"), 0.5));
+    }
+
+    #[test]
+    fn test_ascii_art_ratio() {
+        let mixed = format!("{} {BLOCK_ART}", "words in between the art ".repeat(6));
+
+        assert!(is_ascii_art(&mixed, 0.3));
+        assert!(!is_ascii_art(&mixed, 0.6));
+    }
+
+    #[test]
+    fn test_align_right() {
+        assert_eq!(align_right("12:00", 8), "   12:00");
+        assert_eq!(align_right("12:00", 3), "12:00");
+        // Each of these takes up two columns.
+        assert_eq!(align_right("绝对", 6), "  绝对");
+        // A combining character does not take up a column of its own.
+        assert_eq!(align_right("e\u{301}", 3), "  e\u{301}");
+    }
+
+    #[test]
+    fn test_extract_urls() {
+        assert_eq!(
+            extract_urls("look (https://example.com/a?b=1), www.twitch.tv and http://example.org/"),
+            vec![
+                "https://example.com/a?b=1",
+                "www.twitch.tv",
+                "http://example.org/"
+            ]
+        );
+        assert!(extract_urls("https:// no.links here www. or https://nodot").is_empty());
+    }
+
+    #[test]
+    fn test_format_thousands() {
+        assert_eq!(format_thousands(0), "0");
+        assert_eq!(format_thousands(999), "999");
+        assert_eq!(format_thousands(1000), "1,000");
+        assert_eq!(format_thousands(123_456), "123,456");
+        assert_eq!(format_thousands(1_234_567), "1,234,567");
+        assert_eq!(format_thousands(u64::MAX), "18,446,744,073,709,551,615");
+    }
 
     #[test]
     fn test_get_cursor_position_with_single_byte_graphemes() {
@@ -273,4 +867,273 @@ mod tests {
         assert_eq!(output, "foobar  baz");
         assert!(highlight);
     }
+
+    fn censor_terms(terms: &[&str]) -> Vec<Regex> {
+        censor_patterns(&terms.iter().map(ToString::to_string).collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn test_censor_case_insensitive() {
+        let terms = censor_terms(&["spoiler"]);
+
+        assert_eq!(
+            censor("Big SPOILER: the spoiler is Spoiler", &terms),
+            "Big *******: the ******* is *******"
+        );
+    }
+
+    #[test]
+    fn test_censor_word_boundaries() {
+        let terms = censor_terms(&["ass"]);
+
+        assert_eq!(
+            censor("ass class assassin ass.", &terms),
+            "*** class assassin ***."
+        );
+    }
+
+    #[test]
+    fn test_censor_terms_with_symbols() {
+        let terms = censor_terms(&["@foo", "#ad", "(?:spoil|leak)s?"]);
+
+        assert_eq!(
+            censor("hi @foo, @foobar posted #ad but not #adventure", &terms),
+            "hi ****, @foobar posted *** but not #adventure"
+        );
+        assert_eq!(
+            censor("no leaks, spoils or spoilers", &terms),
+            "no *****, ****** or spoilers"
+        );
+    }
+
+    #[test]
+    fn test_censor_overlapping_terms() {
+        let terms = censor_terms(&["snape kills", "kills dumbledore"]);
+
+        assert_eq!(
+            censor("so snape kills dumbledore huh", &terms),
+            "so ********************** huh"
+        );
+    }
+
+    #[test]
+    fn test_censor_regex_and_literal_terms() {
+        // "f(" is not valid regex, so it is matched literally.
+        let terms = censor_terms(&["fo+", "f("]);
+
+        assert_eq!(censor("foooo fo f", &terms), "***** ** f");
+        assert_eq!(censor("f(x) if(x)", &terms), "**x) if(x)");
+    }
+
+    #[test]
+    fn test_censor_keeps_character_count() {
+        let terms = censor_terms(&["绝对"]);
+
+        assert_eq!(censor("我 绝对 不会", &terms), "我 ** 不会");
+    }
+
+    #[test]
+    fn test_censor_no_terms() {
+        assert_eq!(censor("nothing to see", &[]), "nothing to see");
+    }
+
+    #[test]
+    fn test_fill_template_present_placeholder() {
+        assert_eq!(
+            fill_template(
+                "@{user} please keep chat on-topic, {user}",
+                Some("xithrius")
+            ),
+            "@xithrius please keep chat on-topic, xithrius"
+        );
+    }
+
+    #[test]
+    fn test_fill_template_absent_user() {
+        assert_eq!(
+            fill_template("@{user} please keep chat on-topic", None),
+            "@{user} please keep chat on-topic"
+        );
+    }
+
+    #[test]
+    fn test_fill_template_no_placeholder() {
+        assert_eq!(
+            fill_template("please keep chat on-topic", Some("xithrius")),
+            "please keep chat on-topic"
+        );
+    }
+
+    #[test]
+    fn test_display_width_text() {
+        assert_eq!(display_width("hello"), 5);
+        assert_eq!(display_width("e\u{301}"), 1);
+        assert_eq!(display_width(""), 0);
+    }
+
+    #[test]
+    fn test_display_width_wide() {
+        assert_eq!(display_width("日本語"), 6);
+        assert_eq!(display_width("a日b"), 4);
+    }
+
+    #[test]
+    fn test_display_width_emote() {
+        let emote = PRIVATE_USE_UNICODE.to_string().repeat(3);
+
+        assert_eq!(display_width(&emote), 3);
+        assert_eq!(
+            display_width(&format!(
+                "foo{ZERO_WIDTH_SPACE}{emote}{ZERO_WIDTH_SPACE}bar"
+            )),
+            9
+        );
+    }
+
+    #[test]
+    fn test_wrap_tokens_fits_width() {
+        let emote = PRIVATE_USE_UNICODE.to_string().repeat(4);
+        let text = format!("日本語 のテキスト {emote} and some more words here");
+
+        let lines = wrap_tokens(&text, 10, "> ", 0);
+
+        assert!(lines[0].starts_with("> 日本語"));
+        assert!(lines.iter().all(|line| display_width(line) <= 10));
+        assert_eq!(
+            lines.concat().replace("> ", "").replace(' ', ""),
+            text.replace(' ', "")
+        );
+        assert_eq!(wrap_tokens("short", 10, "", 0), vec!["short"]);
+        assert_eq!(wrap_tokens("", 10, " ", 0), vec![" "]);
+    }
+
+    #[test]
+    fn test_wrap_tokens_long_word() {
+        let word = "a".repeat(35);
+        let text = format!("look {word} ok");
+
+        let lines = wrap_tokens(&text, 10, "", 0);
+
+        assert_eq!(
+            lines,
+            vec![
+                "look".to_string(),
+                "a".repeat(10),
+                "a".repeat(10),
+                "a".repeat(10),
+                "aaaaa ok".to_string(),
+            ]
+        );
+
+        // Only the first lines of the word are kept, when it would take up more.
+        let lines = wrap_tokens(&text, 10, "", 2);
+
+        assert_eq!(
+            lines,
+            vec![
+                "look".to_string(),
+                "a".repeat(10),
+                format!("{}…", "a".repeat(9)),
+                "ok".to_string(),
+            ]
+        );
+        assert!(matches!(
+            wrap_tokens("short words", 10, "", 2)[0],
+            Cow::Borrowed(_)
+        ));
+    }
+
+    #[test]
+    fn test_truncate_to_width() {
+        assert_eq!(truncate_to_width("hello world", 5), "hello");
+        assert_eq!(truncate_to_width("hello", 10), "hello");
+        // Wide and combined graphemes are kept whole.
+        assert_eq!(truncate_to_width("日本語", 5), "日本");
+        assert_eq!(truncate_to_width("e\u{301}e\u{301}", 1), "e\u{301}");
+    }
+
+    #[test]
+    fn test_fuzzy_prefix_beats_scattered() {
+        let prefix = fuzzy_score("kappa", "kap").unwrap();
+        let scattered = fuzzy_score("kxaxpxx", "kap").unwrap();
+
+        assert!(prefix > scattered);
+
+        // Contiguous matches score higher wherever they are.
+        assert!(fuzzy_score("xxkapxx", "kap") > fuzzy_score("xkxaxpx", "kap"));
+    }
+
+    #[test]
+    fn test_fuzzy_word_boundary() {
+        assert!(fuzzy_score("PogChamp", "pc") > fuzzy_score("pacman", "pc"));
+        assert!(fuzzy_score("toggle_focus", "tf") > fuzzy_score("toffee", "tf"));
+        assert!(fuzzy_score("Reverse filter", "f") > fuzzy_score("Left", "f"));
+    }
+
+    #[test]
+    fn test_fuzzy_case_insensitive() {
+        assert_eq!(fuzzy_score("Kappa", "KAP"), fuzzy_score("kappa", "kap"));
+        assert!(fuzzy_score("XITHRIUS", "xith").is_some());
+    }
+
+    #[test]
+    fn test_fuzzy_no_match() {
+        assert_eq!(fuzzy_score("kappa", "z"), None);
+        assert_eq!(fuzzy_score("kappa", "ppak"), None);
+        assert_eq!(fuzzy_score("kap", "kappa"), None);
+        assert_eq!(fuzzy_score("", "a"), None);
+        assert_eq!(fuzzy_score("kappa", ""), Some(0));
+    }
+
+    #[test]
+    fn test_fuzzy_indices() {
+        assert_eq!(
+            fuzzy_indices("xithrius", "xir"),
+            Some((fuzzy_score("xithrius", "xir").unwrap(), vec![0, 1, 4]))
+        );
+
+        // The best match is picked over the first one.
+        let (_, indices) = fuzzy_indices("some_thing_focus", "fo").unwrap();
+        assert_eq!(indices, vec![11, 12]);
+
+        let (_, indices) = fuzzy_indices("Toggle focus", "toggle focus").unwrap();
+        assert_eq!(indices, (0..12).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_sanitize_display_overrides() {
+        // Shown as "admin" ending in "gpj.exe", reversed by the override.
+        let spoofed = "admin\u{202E}exe.jpg\u{202C} and \u{202D}ltr\u{202C}";
+
+        assert_eq!(sanitize_display(spoofed), "adminexe.jpg and ltr");
+        assert_eq!(
+            sanitize_display("\u{2066}a\u{2069}\u{200F}b\u{200E}\u{061C}"),
+            "ab"
+        );
+    }
+
+    #[test]
+    fn test_sanitize_display_zero_width() {
+        assert_eq!(
+            sanitize_display("x\u{200B}ithrius\u{FEFF} \u{2060}twitch.tv"),
+            "xithrius twitch.tv"
+        );
+    }
+
+    #[test]
+    fn test_sanitize_display_keeps_legitimate_marks() {
+        // Combining accents, an emoji sequence with zero-width joiners, and a non-joiner in Persian.
+        let text = "cafe\u{301} nai\u{308}ve 👨\u{200D}👩\u{200D}👧 می\u{200C}خواهم";
+
+        assert_eq!(sanitize_display(text), text);
+        assert_eq!(mark_display(text), text);
+    }
+
+    #[test]
+    fn test_mark_display() {
+        assert_eq!(
+            mark_display("admin\u{202E}exe.jpg\u{200B}"),
+            "admin<RLO>exe.jpg<ZWSP>"
+        );
+    }
 }